@@ -0,0 +1,65 @@
+//! Regenerates `src/ids` from saved game data and prints changed ids.
+//!
+//! Save data of the installed (or given) game version:
+//! `cargo run --example generate-ids -- dump data.bin [sc2_version]`
+//!
+//! Generate ids from it:
+//! `cargo run --example generate-ids -- data.bin [path/to/src/ids]`
+use rust_sc2::{
+	codegen::{load_data, save_data, IdTables},
+	prelude::*,
+};
+use std::env::args;
+
+#[bot]
+#[derive(Default)]
+struct DataDumper {
+	path: String,
+}
+impl DataDumper {
+	fn new(path: String) -> Self {
+		Self {
+			path,
+			..Default::default()
+		}
+	}
+}
+impl Player for DataDumper {
+	fn get_player_settings(&self) -> PlayerSettings {
+		PlayerSettings::new(Race::Random)
+	}
+	fn on_start(&mut self) -> SC2Result<()> {
+		save_data(self.api()?, &self.path)?;
+		self.leave()
+	}
+}
+
+fn main() -> SC2Result<()> {
+	let args = args().skip(1).collect::<Vec<_>>();
+	match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+		["dump", path, ref version @ ..] => {
+			let mut bot = DataDumper::new(path.to_string());
+			run_vs_computer(
+				&mut bot,
+				Computer::new(Race::Random, Difficulty::VeryEasy, None),
+				"EternalEmpireLE",
+				LaunchOptions {
+					sc2_version: version.first().copied(),
+					..Default::default()
+				},
+			)
+		}
+		[path, ref dir @ ..] => {
+			let dir = dir.first().copied().unwrap_or("src/ids");
+			let new = IdTables::from_data(&load_data(path)?);
+			let diff = IdTables::parse_dir(dir)?.diff(&new);
+			if diff.is_empty() {
+				println!("No changes");
+			} else {
+				print!("{}", diff);
+			}
+			new.write_dir(dir)
+		}
+		_ => Err("Usage: generate-ids dump <output> [sc2_version] | generate-ids <data> [ids_dir]".into()),
+	}
+}
@@ -8,6 +8,8 @@ impl Player for EmptyBot {
 		PlayerSettings::new(Race::Random)
 	}
 }
+// Bot can observe replays too
+impl ReplayObserver for EmptyBot {}
 
 // Example of how to use runner
 fn main() -> SC2Result<()> {
@@ -61,6 +63,22 @@ fn main() -> SC2Result<()> {
 	other.run_game()?;
 	other.close();
 
+	// Bot vs Bot
+	let mut opponent = EmptyBot::default();
+	let mut runner = RunnerBotVsBot::new(&mut bot, &mut opponent, "EternalEmpireLE", None);
+	runner.launch()?;
+	let (result, opponent_result) = runner.run_game()?;
+	println!("Bot: {:?}, opponent: {:?}", result, opponent_result);
+	runner.close();
+
+	// Replay
+	let mut runner = ReplayRunner::new(&mut bot);
+	runner.observed_player = 2; // Default: 1
+	runner.disable_fog = true; // Default: false
+	let result = runner.run_replay("path/to/replay/MyReplay.SC2Replay")?;
+	println!("Observed player: {:?}", result);
+	runner.close();
+
 	// Human vs Bot
 	// 1. Initialize runner
 	let mut runner = RunnerMulti::new(
@@ -495,6 +495,7 @@ fn main() -> SC2Result<()> {
 				sc2_version: sub.value_of("sc2_version"),
 				realtime: sub.is_present("realtime"),
 				save_replay_as: sub.value_of("save_replay"),
+				record_as: None,
 			},
 		),
 		("human", Some(sub)) => run_vs_human(
@@ -514,6 +515,7 @@ fn main() -> SC2Result<()> {
 				sc2_version: sub.value_of("sc2_version"),
 				realtime: true,
 				save_replay_as: sub.value_of("save_replay"),
+				record_as: None,
 			},
 		),
 		_ => run_ladder_game(
@@ -2,16 +2,28 @@
 
 use crate::{
 	geometry::{Point2, Point3},
-	ids::AbilityId,
+	ids::{AbilityId, DecodeId},
+	version::VersionData,
 	FromProto, IntoProto,
 };
-use num_traits::{FromPrimitive, ToPrimitive};
 use rustc_hash::FxHashMap;
 use sc2_proto::{
+	common::{PointI, RectangleI},
 	error::ActionResult as ProtoActionResult,
 	raw::{ActionRawUnitCommand_oneof_target as ProtoTarget, ActionRaw_oneof_action as ProtoRawAction},
 	sc2api::{Action as ProtoAction, ActionChat_Channel, ActionError as ProtoActionError},
+	spatial::{
+		ActionSpatial, ActionSpatialUnitCommand_oneof_target as ProtoSpatialTarget,
+		ActionSpatialUnitSelectionPoint_Type as ProtoSelectPointType,
+		ActionSpatial_oneof_action as ProtoSpatialAction,
+	},
+	ui::{
+		ActionControlGroup_ControlGroupAction as ProtoControlGroupAction, ActionSelectIdleWorker_Type,
+		ActionUI, ActionUI_oneof_action as ProtoUIAction,
+	},
 };
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 // pub(crate) type Command = (u64, (AbilityId, Target, bool));
 
@@ -23,6 +35,7 @@ pub(crate) struct Commander {
 
 /// Target of ability used by unit.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Target {
 	/// Ability target is position (move, build, ...).
 	Pos(Point2),
@@ -33,17 +46,22 @@ pub enum Target {
 }
 
 #[doc(hidden)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Action {
 	UnitCommand(AbilityId, Target, Vec<u64>, bool),
 	CameraMove(Point3),
 	ToggleAutocast(AbilityId, Vec<u64>),
 	Chat(String, bool),
+	FeatureLayer(SpatialAction),
+	Render(SpatialAction),
+	UI(UIAction),
 }
-impl IntoProto<ProtoAction> for &Action {
+impl IntoProto<ProtoAction> for (&Action, &VersionData) {
 	fn into_proto(self) -> ProtoAction {
+		let (source, version) = self;
 		let mut action = ProtoAction::new();
-		match self {
+		match source {
 			Action::Chat(message, team_only) => {
 				let chat_action = action.mut_action_chat();
 				chat_action.set_channel({
@@ -57,7 +75,7 @@ impl IntoProto<ProtoAction> for &Action {
 			}
 			Action::UnitCommand(ability, target, units, queue) => {
 				let unit_command = action.mut_action_raw().mut_unit_command();
-				unit_command.set_ability_id(ability.to_i32().unwrap());
+				unit_command.set_ability_id(ability.encode(version) as i32);
 				match target {
 					Target::Pos(pos) => unit_command.set_target_world_space_pos(pos.into_proto()),
 					Target::Tag(tag) => unit_command.set_target_unit_tag(*tag),
@@ -72,24 +90,23 @@ impl IntoProto<ProtoAction> for &Action {
 			}
 			Action::ToggleAutocast(ability, units) => {
 				let toggle_autocast = action.mut_action_raw().mut_toggle_autocast();
-				toggle_autocast.set_ability_id(ability.to_i32().unwrap());
+				toggle_autocast.set_ability_id(ability.encode(version) as i32);
 				toggle_autocast.set_unit_tags(units.to_vec());
 			}
+			Action::FeatureLayer(spatial) => action.set_action_feature_layer((spatial, version).into_proto()),
+			Action::Render(spatial) => action.set_action_render((spatial, version).into_proto()),
+			Action::UI(ui) => action.set_action_ui(ui.into_proto()),
 		}
 		action
 	}
 }
-impl FromProto<&ProtoAction> for Option<Action> {
-	fn from_proto(action: &ProtoAction) -> Self {
+impl FromProto<(&ProtoAction, &VersionData)> for Option<Action> {
+	fn from_proto((action, version): (&ProtoAction, &VersionData)) -> Self {
 		// let game_loop: u32 = action.get_game_loop();
 		if action.has_action_raw() {
 			match &action.get_action_raw().action {
 				Some(ProtoRawAction::unit_command(unit_command)) => Some(Action::UnitCommand(
-					{
-						let id = unit_command.get_ability_id();
-						AbilityId::from_i32(id)
-							.unwrap_or_else(|| panic!("There's no `AbilityId` with value {}", id))
-					},
+					AbilityId::decode_lossy(unit_command.get_ability_id() as u64, version)?,
 					match &unit_command.target {
 						Some(ProtoTarget::target_world_space_pos(pos)) => {
 							Target::Pos(Point2::from_proto(pos))
@@ -104,15 +121,18 @@ impl FromProto<&ProtoAction> for Option<Action> {
 					Point3::from_proto(camera_move.get_center_world_space()),
 				)),
 				Some(ProtoRawAction::toggle_autocast(toggle_autocast)) => Some(Action::ToggleAutocast(
-					{
-						let id = toggle_autocast.get_ability_id();
-						AbilityId::from_i32(id)
-							.unwrap_or_else(|| panic!("There's no `AbilityId` with value {}", id))
-					},
+					AbilityId::decode_lossy(toggle_autocast.get_ability_id() as u64, version)?,
 					toggle_autocast.get_unit_tags().to_vec(),
 				)),
 				None => unreachable!(),
 			}
+		} else if action.has_action_feature_layer() {
+			Option::<SpatialAction>::from_proto((action.get_action_feature_layer(), version))
+				.map(Action::FeatureLayer)
+		} else if action.has_action_render() {
+			Option::<SpatialAction>::from_proto((action.get_action_render(), version)).map(Action::Render)
+		} else if action.has_action_ui() {
+			Option::<UIAction>::from_proto(action.get_action_ui()).map(Action::UI)
 		} else if action.has_action_chat() {
 			let chat = action.get_action_chat();
 			Some(Action::Chat(chat.get_message().to_string(), {
@@ -127,9 +147,296 @@ impl FromProto<&ProtoAction> for Option<Action> {
 	}
 }
 
+/// Pixel coordinates (x, y) on screen or minimap of feature layer or rendered interface.
+pub type PixelPoint = (i32, i32);
+
+fn pixel_into_proto((x, y): PixelPoint) -> PointI {
+	let mut point = PointI::new();
+	point.set_x(x);
+	point.set_y(y);
+	point
+}
+fn pixel_from_proto(point: &PointI) -> PixelPoint {
+	(point.get_x(), point.get_y())
+}
+
+/// Action of feature layer or rendered interface, given through screen and minimap.
+///
+/// Can be sent with [`feature_layer_action`] or [`render_action`].
+///
+/// [`feature_layer_action`]: crate::bot::Bot::feature_layer_action
+/// [`render_action`]: crate::bot::Bot::render_action
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SpatialAction {
+	/// Selected units use ability on given target, the flag is set to queue the command.
+	UnitCommand(AbilityId, SpatialTarget, bool),
+	/// Move camera to given position on minimap.
+	CameraMove(PixelPoint),
+	/// Select units at given position on screen.
+	SelectPoint(PixelPoint, SelectPointType),
+	/// Select units in given rectangles on screen, stored in (corner, opposite corner) pairs.
+	/// The flag is set to add units to current selection.
+	SelectRect(Vec<(PixelPoint, PixelPoint)>, bool),
+}
+impl IntoProto<ActionSpatial> for (&SpatialAction, &VersionData) {
+	fn into_proto(self) -> ActionSpatial {
+		let (source, version) = self;
+		let mut action = ActionSpatial::new();
+		match source {
+			SpatialAction::UnitCommand(ability, target, queue) => {
+				let unit_command = action.mut_unit_command();
+				unit_command.set_ability_id(ability.encode(version) as i32);
+				match target {
+					SpatialTarget::Screen(pos) => {
+						unit_command.set_target_screen_coord(pixel_into_proto(*pos))
+					}
+					SpatialTarget::Minimap(pos) => {
+						unit_command.set_target_minimap_coord(pixel_into_proto(*pos))
+					}
+					SpatialTarget::None => {}
+				}
+				unit_command.set_queue_command(*queue);
+			}
+			SpatialAction::CameraMove(pos) => {
+				action
+					.mut_camera_move()
+					.set_center_minimap(pixel_into_proto(*pos));
+			}
+			SpatialAction::SelectPoint(pos, kind) => {
+				let select_point = action.mut_unit_selection_point();
+				select_point.set_selection_screen_coord(pixel_into_proto(*pos));
+				select_point.set_field_type(kind.into_proto());
+			}
+			SpatialAction::SelectRect(rects, add) => {
+				let select_rect = action.mut_unit_selection_rect();
+				for (p0, p1) in rects {
+					let mut rect = RectangleI::new();
+					rect.set_p0(pixel_into_proto(*p0));
+					rect.set_p1(pixel_into_proto(*p1));
+					select_rect.mut_selection_screen_coord().push(rect);
+				}
+				select_rect.set_selection_add(*add);
+			}
+		}
+		action
+	}
+}
+impl FromProto<(&ActionSpatial, &VersionData)> for Option<SpatialAction> {
+	fn from_proto((action, version): (&ActionSpatial, &VersionData)) -> Self {
+		match &action.action {
+			Some(ProtoSpatialAction::unit_command(unit_command)) => Some(SpatialAction::UnitCommand(
+				AbilityId::decode_lossy(unit_command.get_ability_id() as u64, version)?,
+				match &unit_command.target {
+					Some(ProtoSpatialTarget::target_screen_coord(pos)) => {
+						SpatialTarget::Screen(pixel_from_proto(pos))
+					}
+					Some(ProtoSpatialTarget::target_minimap_coord(pos)) => {
+						SpatialTarget::Minimap(pixel_from_proto(pos))
+					}
+					None => SpatialTarget::None,
+				},
+				unit_command.get_queue_command(),
+			)),
+			Some(ProtoSpatialAction::camera_move(camera_move)) => Some(SpatialAction::CameraMove(
+				pixel_from_proto(camera_move.get_center_minimap()),
+			)),
+			Some(ProtoSpatialAction::unit_selection_point(select_point)) => Some(SpatialAction::SelectPoint(
+				pixel_from_proto(select_point.get_selection_screen_coord()),
+				SelectPointType::from_proto(select_point.get_field_type()),
+			)),
+			Some(ProtoSpatialAction::unit_selection_rect(select_rect)) => Some(SpatialAction::SelectRect(
+				select_rect
+					.get_selection_screen_coord()
+					.iter()
+					.map(|rect| (pixel_from_proto(rect.get_p0()), pixel_from_proto(rect.get_p1())))
+					.collect(),
+				select_rect.get_selection_add(),
+			)),
+			None => None,
+		}
+	}
+}
+
+/// Target of ability used through screen or minimap.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SpatialTarget {
+	/// Ability target is position on screen.
+	Screen(PixelPoint),
+	/// Ability target is position on minimap.
+	Minimap(PixelPoint),
+	/// Ability don't require target.
+	None,
+}
+
+/// How units are selected with [`SelectPoint`](SpatialAction::SelectPoint).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SelectPointType {
+	/// Select only unit at the point (left click).
+	Select,
+	/// Add unit to selection or remove it if it's already selected (shift + left click).
+	Toggle,
+	/// Select all units of the same type on screen (double click).
+	AllType,
+	/// Add all units of the same type on screen to selection (shift + double click).
+	AddAllType,
+}
+impl FromProto<ProtoSelectPointType> for SelectPointType {
+	fn from_proto(kind: ProtoSelectPointType) -> Self {
+		match kind {
+			ProtoSelectPointType::Select => SelectPointType::Select,
+			ProtoSelectPointType::Toggle => SelectPointType::Toggle,
+			ProtoSelectPointType::AllType => SelectPointType::AllType,
+			ProtoSelectPointType::AddAllType => SelectPointType::AddAllType,
+		}
+	}
+}
+impl IntoProto<ProtoSelectPointType> for SelectPointType {
+	fn into_proto(self) -> ProtoSelectPointType {
+		match self {
+			SelectPointType::Select => ProtoSelectPointType::Select,
+			SelectPointType::Toggle => ProtoSelectPointType::Toggle,
+			SelectPointType::AllType => ProtoSelectPointType::AllType,
+			SelectPointType::AddAllType => ProtoSelectPointType::AddAllType,
+		}
+	}
+}
+
+/// Action of game UI. Can be sent with [`ui_action`](crate::bot::Bot::ui_action).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum UIAction {
+	/// Control group action with control group of given index (0-9).
+	ControlGroup(ControlGroupAction, u32),
+	/// Select all army units, the flag is set to add them to current selection.
+	SelectArmy(bool),
+	/// Select idle workers.
+	SelectIdleWorker(SelectIdleWorkerType),
+}
+impl IntoProto<ActionUI> for &UIAction {
+	fn into_proto(self) -> ActionUI {
+		let mut action = ActionUI::new();
+		match *self {
+			UIAction::ControlGroup(kind, index) => {
+				let control_group = action.mut_control_group();
+				control_group.set_action(kind.into_proto());
+				control_group.set_control_group_index(index);
+			}
+			UIAction::SelectArmy(add) => action.mut_select_army().set_selection_add(add),
+			UIAction::SelectIdleWorker(kind) => {
+				action.mut_select_idle_worker().set_field_type(kind.into_proto())
+			}
+		}
+		action
+	}
+}
+impl FromProto<&ActionUI> for Option<UIAction> {
+	fn from_proto(action: &ActionUI) -> Self {
+		match &action.action {
+			Some(ProtoUIAction::control_group(control_group)) => Some(UIAction::ControlGroup(
+				ControlGroupAction::from_proto(control_group.get_action()),
+				control_group.get_control_group_index(),
+			)),
+			Some(ProtoUIAction::select_army(select_army)) => {
+				Some(UIAction::SelectArmy(select_army.get_selection_add()))
+			}
+			Some(ProtoUIAction::select_idle_worker(select_idle_worker)) => Some(UIAction::SelectIdleWorker(
+				SelectIdleWorkerType::from_proto(select_idle_worker.get_field_type()),
+			)),
+			Some(
+				ProtoUIAction::select_warp_gates(_)
+				| ProtoUIAction::select_larva(_)
+				| ProtoUIAction::multi_panel(_)
+				| ProtoUIAction::cargo_panel(_)
+				| ProtoUIAction::production_panel(_)
+				| ProtoUIAction::toggle_autocast(_),
+			) => {
+				debug!("Skipping unsupported UI action: {:?}", action);
+				None
+			}
+			None => None,
+		}
+	}
+}
+
+/// Action with control group.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ControlGroupAction {
+	/// Select units of control group (1-0).
+	Recall,
+	/// Set control group to current selection (ctrl + 1-0).
+	Set,
+	/// Add current selection to control group (shift + 1-0).
+	Append,
+	/// Set control group to current selection and remove units from other groups (ctrl + alt + 1-0).
+	SetAndSteal,
+	/// Add current selection to control group and remove units from other groups (shift + alt + 1-0).
+	AppendAndSteal,
+}
+impl FromProto<ProtoControlGroupAction> for ControlGroupAction {
+	fn from_proto(action: ProtoControlGroupAction) -> Self {
+		match action {
+			ProtoControlGroupAction::Recall => ControlGroupAction::Recall,
+			ProtoControlGroupAction::Set => ControlGroupAction::Set,
+			ProtoControlGroupAction::Append => ControlGroupAction::Append,
+			ProtoControlGroupAction::SetAndSteal => ControlGroupAction::SetAndSteal,
+			ProtoControlGroupAction::AppendAndSteal => ControlGroupAction::AppendAndSteal,
+		}
+	}
+}
+impl IntoProto<ProtoControlGroupAction> for ControlGroupAction {
+	fn into_proto(self) -> ProtoControlGroupAction {
+		match self {
+			ControlGroupAction::Recall => ProtoControlGroupAction::Recall,
+			ControlGroupAction::Set => ProtoControlGroupAction::Set,
+			ControlGroupAction::Append => ProtoControlGroupAction::Append,
+			ControlGroupAction::SetAndSteal => ProtoControlGroupAction::SetAndSteal,
+			ControlGroupAction::AppendAndSteal => ProtoControlGroupAction::AppendAndSteal,
+		}
+	}
+}
+
+/// How idle workers are selected with [`SelectIdleWorker`](UIAction::SelectIdleWorker).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SelectIdleWorkerType {
+	/// Select one idle worker (click).
+	Set,
+	/// Add one idle worker to selection (shift + click).
+	Add,
+	/// Select all idle workers (ctrl + click).
+	All,
+	/// Add all idle workers to selection (shift + ctrl + click).
+	AddAll,
+}
+impl FromProto<ActionSelectIdleWorker_Type> for SelectIdleWorkerType {
+	fn from_proto(kind: ActionSelectIdleWorker_Type) -> Self {
+		match kind {
+			ActionSelectIdleWorker_Type::Set => SelectIdleWorkerType::Set,
+			ActionSelectIdleWorker_Type::Add => SelectIdleWorkerType::Add,
+			ActionSelectIdleWorker_Type::All => SelectIdleWorkerType::All,
+			ActionSelectIdleWorker_Type::AddAll => SelectIdleWorkerType::AddAll,
+		}
+	}
+}
+impl IntoProto<ActionSelectIdleWorker_Type> for SelectIdleWorkerType {
+	fn into_proto(self) -> ActionSelectIdleWorker_Type {
+		match self {
+			SelectIdleWorkerType::Set => ActionSelectIdleWorker_Type::Set,
+			SelectIdleWorkerType::Add => ActionSelectIdleWorker_Type::Add,
+			SelectIdleWorkerType::All => ActionSelectIdleWorker_Type::All,
+			SelectIdleWorkerType::AddAll => ActionSelectIdleWorker_Type::AddAll,
+		}
+	}
+}
+
 /// Structure used to analyze actions failed on previous game step.
 /// Stored in [`state.action_errors`](crate::game_state::GameState::action_errors).
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ActionError {
 	/// Tag of unit that was executing action.
 	pub unit: u64,
@@ -138,14 +445,11 @@ pub struct ActionError {
 	/// Result of executed action.
 	pub result: ActionResult,
 }
-impl FromProto<&ProtoActionError> for ActionError {
-	fn from_proto(e: &ProtoActionError) -> Self {
+impl FromProto<(&ProtoActionError, &VersionData)> for ActionError {
+	fn from_proto((e, version): (&ProtoActionError, &VersionData)) -> Self {
 		Self {
 			unit: e.get_unit_tag(),
-			ability: {
-				let id = e.get_ability_id();
-				AbilityId::from_u64(id).unwrap_or_else(|| panic!("There's no `AbilityId` with value {}", id))
-			},
+			ability: AbilityId::decode_or_fallback(e.get_ability_id(), version),
 			result: ActionResult::from_proto(e.get_result()),
 		}
 	}
@@ -154,6 +458,7 @@ impl FromProto<&ProtoActionError> for ActionError {
 /// Result of executed action.
 #[allow(missing_docs)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ActionResult {
 	Success,
 	NotSupported,
@@ -604,3 +909,80 @@ impl FromProto<ProtoActionResult> for ActionResult {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::version;
+
+	fn round_trip(action: &Action, version: &VersionData) -> Option<Action> {
+		let proto: ProtoAction = (action, version).into_proto();
+		Option::<Action>::from_proto((&proto, version))
+	}
+
+	#[test]
+	fn spatial_actions_round_trip() {
+		let actions = [
+			SpatialAction::UnitCommand(AbilityId::Attack, SpatialTarget::Screen((10, 20)), true),
+			SpatialAction::UnitCommand(AbilityId::MoveMove, SpatialTarget::Minimap((1, 2)), false),
+			SpatialAction::UnitCommand(AbilityId::StopStop, SpatialTarget::None, false),
+			SpatialAction::CameraMove((30, 40)),
+			SpatialAction::SelectPoint((5, 6), SelectPointType::AddAllType),
+			SpatialAction::SelectRect(vec![((0, 0), (10, 10)), ((20, 20), (25, 30))], true),
+		];
+		for version in version::versions() {
+			for spatial in &actions {
+				for action in [
+					Action::FeatureLayer(spatial.clone()),
+					Action::Render(spatial.clone()),
+				] {
+					assert_eq!(round_trip(&action, version), Some(action), "{}", version.name);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn spatial_abilities_are_versioned() {
+		let action = SpatialAction::UnitCommand(
+			AbilityId::BatteryOverchargeBatteryOvercharge,
+			SpatialTarget::None,
+			false,
+		);
+		for (name, game_id) in [("4.10", 3815), ("latest", 4107)] {
+			let version = version::by_name(name);
+			let proto: ActionSpatial = (&action, version).into_proto();
+			assert_eq!(proto.get_unit_command().get_ability_id(), game_id);
+			assert_eq!(
+				Option::<SpatialAction>::from_proto((&proto, version)),
+				Some(action.clone())
+			);
+		}
+	}
+
+	#[test]
+	fn ui_actions_round_trip() {
+		let actions = [
+			UIAction::ControlGroup(ControlGroupAction::Recall, 0),
+			UIAction::ControlGroup(ControlGroupAction::AppendAndSteal, 9),
+			UIAction::SelectArmy(true),
+			UIAction::SelectArmy(false),
+			UIAction::SelectIdleWorker(SelectIdleWorkerType::All),
+		];
+		for version in version::versions() {
+			for ui in actions {
+				assert_eq!(round_trip(&Action::UI(ui), version), Some(Action::UI(ui)));
+			}
+		}
+	}
+
+	#[test]
+	fn unsupported_ui_actions_are_skipped() {
+		let mut action = ActionUI::new();
+		action.mut_select_larva();
+		assert_eq!(Option::<UIAction>::from_proto(&action), None);
+		action.mut_multi_panel().set_unit_index(1);
+		assert_eq!(Option::<UIAction>::from_proto(&action), None);
+		assert_eq!(Option::<UIAction>::from_proto(&ActionUI::new()), None);
+	}
+}
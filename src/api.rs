@@ -3,36 +3,73 @@
 use crate::{
 	bot::{Locked, Rl},
 	client::{SC2Result, WS},
+	error::{Error, ProtoError},
 };
 use protobuf::Message;
-use sc2_proto::sc2api::{Request, Response};
+use sc2_proto::{
+	error::ActionResult as ProtoActionResult,
+	sc2api::{Request, Request_oneof_request, Response, Status},
+};
+use std::{
+	collections::VecDeque,
+	fs::File,
+	io::{BufReader, BufWriter, ErrorKind, Read, Write},
+	mem::discriminant,
+	path::Path,
+};
 use tungstenite::Message::Binary;
 
+#[cfg(feature = "async")]
+use futures_util::{
+	stream::{SplitSink, SplitStream},
+	SinkExt, StreamExt,
+};
+#[cfg(feature = "async")]
+use tokio::{
+	fs::File as AsyncFile,
+	io::{AsyncWriteExt, BufWriter as AsyncBufWriter},
+	net::TcpStream,
+	sync::Mutex as AsyncMutex,
+};
+#[cfg(feature = "async")]
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+#[cfg(feature = "async")]
+use tungstenite::Message as WsMessage;
+
 /// SC2 API. Can be accessed through [`self.api()`](crate::bot::Bot::api).
-pub struct API(Rl<WS>);
+pub struct API {
+	transport: Rl<Transport>,
+	recorder: Rl<Option<Recorder>>,
+}
 impl API {
 	pub(crate) fn new(ws: WS) -> API {
-		API(Rl::new(ws))
+		API {
+			transport: Rl::new(Transport::Socket(ws)),
+			recorder: Rl::new(None),
+		}
+	}
+	pub(crate) fn playback<P: AsRef<Path>>(path: P) -> SC2Result<API> {
+		Ok(API {
+			transport: Rl::new(Transport::Playback(Playback::open(path)?)),
+			recorder: Rl::new(None),
+		})
 	}
 
 	/// Sends request and returns a response.
+	///
+	/// Returns [`Error::Proto`] if SC2 couldn't handle the request at all (i.e. response has errors
+	/// and no result) or [`Error::GameEnded`] if the game is already over.
+	/// Errors given along with the result are only logged,
+	/// so they can be inspected in the returned response.
 	pub fn send(&self, req: Request) -> SC2Result<Response> {
-		let mut ws = self.0.write_lock();
-
-		ws.write_message(Binary(req.write_to_bytes()?))?;
-
-		let msg = ws.read_message()?;
-
-		let mut res = Response::new();
-		res.merge_from_bytes(msg.into_data().as_slice())?;
-		Ok(res)
+		let res = self.transport.write_lock().send(&req)?;
+		self.record(&req, &res)?;
+		check_response(res)
 	}
 
 	/// Sends request, waits for the response, but ignores it (useful when response is empty).
 	pub fn send_request(&self, req: Request) -> SC2Result<()> {
-		let mut ws = self.0.write_lock();
-		ws.write_message(Binary(req.write_to_bytes()?))?;
-		let _ = ws.read_message()?;
+		self.send(req)?;
 		Ok(())
 	}
 
@@ -42,17 +79,403 @@ impl API {
 	/// [`send`]: Self::send
 	/// [`send_request`]: Self::send_request
 	pub fn send_only(&self, req: Request) -> SC2Result<()> {
-		self.0.write_lock().write_message(Binary(req.write_to_bytes()?))?;
+		self.transport.write_lock().send_only(&req)?;
+		if let Some(recorder) = &mut *self.recorder.write_lock() {
+			recorder.pending.push_back(req);
+		}
 		Ok(())
 	}
+	/// Sends requests one after another without waiting for responses,
+	/// then receives all of them in the same order.
+	///
+	/// All responses are received even if some of them are errors,
+	/// so the next request gets its own response. The first error is returned then.
+	pub fn send_batch(&self, reqs: Vec<Request>) -> SC2Result<Vec<Response>> {
+		let n = reqs.len();
+		for req in reqs {
+			self.send_only(req)?;
+		}
+		let responses = (0..n).map(|_| self.wait_response()).collect::<Vec<_>>();
+		responses.into_iter().collect()
+	}
 	/// Waits for a response (useful only after [`send_only`]).
 	///
 	/// [`send_only`]: Self::send_only
 	pub fn wait_response(&self) -> SC2Result<Response> {
-		let msg = self.0.write_lock().read_message()?;
+		let res = self.transport.write_lock().wait_response()?;
+		if let Some(recorder) = &mut *self.recorder.write_lock() {
+			if let Some(req) = recorder.pending.pop_front() {
+				recorder.write(&req, &res)?;
+			}
+		}
+		check_response(res)
+	}
+
+	/// Starts writing every request and response to a file in given path.
+	/// Recorded game can be played again without SC2 using
+	/// [`RunnerReplayStream`](crate::client::RunnerReplayStream).
+	///
+	/// File consists of (request, response) pairs,
+	/// every message is stored as protobuf bytes prefixed with its length (little-endian `u32`).
+	pub fn start_recording<P: AsRef<Path>>(&self, path: P) -> SC2Result<()> {
+		*self.recorder.write_lock() = Some(Recorder::create(path)?);
+		Ok(())
+	}
+	/// Stops recording started with [`start_recording`](Self::start_recording).
+	pub fn stop_recording(&self) -> SC2Result<()> {
+		if let Some(mut recorder) = self.recorder.write_lock().take() {
+			recorder.file.flush()?;
+		}
+		Ok(())
+	}
+	/// Checks if requests are being recorded now.
+	pub fn is_recording(&self) -> bool {
+		self.recorder.read_lock().is_some()
+	}
+
+	fn record(&self, req: &Request, res: &Response) -> SC2Result<()> {
+		if let Some(recorder) = &mut *self.recorder.write_lock() {
+			recorder.write(req, res)?;
+		}
+		Ok(())
+	}
+
+	/// Returns actions sent by bot while playing recorded game,
+	/// stored in (game loop, action request) pairs.
+	pub(crate) fn playback_actions(&self) -> Vec<(u32, Request)> {
+		match &*self.transport.read_lock() {
+			Transport::Playback(playback) => playback.actions.clone(),
+			Transport::Socket(_) => Vec::new(),
+		}
+	}
+}
+
+enum Transport {
+	Socket(WS),
+	Playback(Playback),
+}
+impl Transport {
+	fn send(&mut self, req: &Request) -> SC2Result<Response> {
+		match self {
+			Transport::Socket(ws) => {
+				ws.write_message(Binary(req.write_to_bytes()?))?;
+				read_response(ws)
+			}
+			Transport::Playback(playback) => playback.answer(req),
+		}
+	}
+	fn send_only(&mut self, req: &Request) -> SC2Result<()> {
+		match self {
+			Transport::Socket(ws) => ws.write_message(Binary(req.write_to_bytes()?))?,
+			Transport::Playback(playback) => {
+				let res = playback.answer(req)?;
+				playback.pending.push_back(res);
+			}
+		}
+		Ok(())
+	}
+	fn wait_response(&mut self) -> SC2Result<Response> {
+		match self {
+			Transport::Socket(ws) => read_response(ws),
+			Transport::Playback(playback) => Ok(playback
+				.pending
+				.pop_front()
+				.ok_or("There's no request waiting for response")?),
+		}
+	}
+}
+
+fn check_response(res: Response) -> SC2Result<Response> {
+	if res.get_error().is_empty() {
+		Ok(res)
+	} else if res.response.is_some() {
+		warn!("SC2 API returned errors: {}", res.get_error().join("; "));
+		Ok(res)
+	} else if matches!(res.get_status(), Status::ended) {
+		Err(Error::GameEnded)
+	} else {
+		let err = ProtoError {
+			error: res.get_error().join("; "),
+			details: String::new(),
+		};
+		error!("{}", err);
+		Err(err.into())
+	}
+}
+
+fn read_response(ws: &mut WS) -> SC2Result<Response> {
+	let msg = ws.read_message()?;
+
+	let mut res = Response::new();
+	res.merge_from_bytes(msg.into_data().as_slice())?;
+	Ok(res)
+}
+
+struct Recorder {
+	file: BufWriter<File>,
+	pending: VecDeque<Request>,
+}
+impl Recorder {
+	fn create<P: AsRef<Path>>(path: P) -> SC2Result<Self> {
+		Ok(Self {
+			file: BufWriter::new(File::create(path)?),
+			pending: VecDeque::new(),
+		})
+	}
+	fn write(&mut self, req: &Request, res: &Response) -> SC2Result<()> {
+		for bytes in [req.write_to_bytes()?, res.write_to_bytes()?] {
+			self.file.write_all(&(bytes.len() as u32).to_le_bytes())?;
+			self.file.write_all(&bytes)?;
+		}
+		Ok(())
+	}
+}
+
+/// Version of [`Recorder`] used by [`AsyncAPI`], which writes to file without blocking the runtime.
+#[cfg(feature = "async")]
+struct AsyncRecorder {
+	file: AsyncBufWriter<AsyncFile>,
+	pending: VecDeque<Request>,
+}
+#[cfg(feature = "async")]
+impl AsyncRecorder {
+	async fn create<P: AsRef<Path>>(path: P) -> SC2Result<Self> {
+		Ok(Self {
+			file: AsyncBufWriter::new(AsyncFile::create(path).await?),
+			pending: VecDeque::new(),
+		})
+	}
+	async fn write(&mut self, req: &Request, res: &Response) -> SC2Result<()> {
+		for bytes in [req.write_to_bytes()?, res.write_to_bytes()?] {
+			self.file.write_all(&(bytes.len() as u32).to_le_bytes()).await?;
+			self.file.write_all(&bytes).await?;
+		}
+		Ok(())
+	}
+}
 
+/// Reads all (request, response) pairs from recorded file.
+pub(crate) fn read_recording<P: AsRef<Path>>(path: P) -> SC2Result<Vec<(Request, Response)>> {
+	fn read_bytes<R: Read>(reader: &mut R) -> SC2Result<Option<Vec<u8>>> {
+		let mut len = [0; 4];
+		if let Err(e) = reader.read_exact(&mut len) {
+			return if e.kind() == ErrorKind::UnexpectedEof {
+				Ok(None)
+			} else {
+				Err(e.into())
+			};
+		}
+		let mut bytes = vec![0; u32::from_le_bytes(len) as usize];
+		reader.read_exact(&mut bytes)?;
+		Ok(Some(bytes))
+	}
+
+	let mut reader = BufReader::new(File::open(path)?);
+	let mut frames = Vec::new();
+	while let Some(req_bytes) = read_bytes(&mut reader)? {
+		let res_bytes = read_bytes(&mut reader)?.ok_or("Recording is truncated")?;
+
+		let mut req = Request::new();
+		req.merge_from_bytes(&req_bytes)?;
 		let mut res = Response::new();
-		res.merge_from_bytes(msg.into_data().as_slice())?;
+		res.merge_from_bytes(&res_bytes)?;
+		frames.push((req, res));
+	}
+	Ok(frames)
+}
+
+/// Answers requests with responses from recorded game.
+///
+/// Observations are given in recorded order, other requests are matched with the recorded ones
+/// made between the same observations. Actions, debug commands and steps are not sent anywhere,
+/// but actions are saved to compare bot's decisions with the recorded ones.
+struct Playback {
+	frames: Vec<(Request, Response)>,
+	used: Vec<bool>,
+	pos: usize,
+	game_loop: u32,
+	pending: VecDeque<Response>,
+	actions: Vec<(u32, Request)>,
+}
+impl Playback {
+	fn open<P: AsRef<Path>>(path: P) -> SC2Result<Self> {
+		let frames = read_recording(path)?;
+		Ok(Self {
+			used: vec![false; frames.len()],
+			frames,
+			pos: 0,
+			game_loop: 0,
+			pending: VecDeque::new(),
+			actions: Vec::new(),
+		})
+	}
+	fn answer(&mut self, req: &Request) -> SC2Result<Response> {
+		let mut res = Response::new();
+		res.set_status(Status::in_game);
+		match &req.request {
+			Some(Request_oneof_request::observation(_)) => {
+				let offset = self.frames[self.pos..]
+					.iter()
+					.position(|(req, _)| req.has_observation())
+					.ok_or(Error::GameEnded)?;
+				let (_, res) = &self.frames[self.pos + offset];
+				self.pos += offset + 1;
+				self.game_loop = res.get_observation().get_observation().get_game_loop();
+				return Ok(res.clone());
+			}
+			Some(Request_oneof_request::action(req_action)) => {
+				self.actions.push((self.game_loop, req.clone()));
+				res.mut_action()
+					.set_result(vec![ProtoActionResult::Success; req_action.get_actions().len()]);
+			}
+			Some(Request_oneof_request::step(_)) => res.mut_step().set_simulation_loop(self.game_loop),
+			Some(Request_oneof_request::debug(_)) => {
+				res.mut_debug();
+			}
+			Some(Request_oneof_request::leave_game(_)) => {
+				res.mut_leave_game();
+			}
+			Some(Request_oneof_request::quit(_)) => {
+				res.mut_quit();
+			}
+			Some(Request_oneof_request::save_replay(_)) => {
+				res.mut_save_replay();
+			}
+			Some(kind) => {
+				let end = self.frames[self.pos..]
+					.iter()
+					.position(|(req, _)| req.has_observation())
+					.map_or(self.frames.len(), |offset| self.pos + offset);
+				let is_query = req.has_query();
+				let found = (self.pos..end)
+					.find(|i| !self.used[*i] && self.frames[*i].0 == *req)
+					.or_else(|| {
+						if is_query {
+							None
+						} else {
+							(self.pos..end).find(|i| {
+								!self.used[*i]
+									&& matches!(
+										&self.frames[*i].0.request,
+										Some(r) if discriminant(r) == discriminant(kind)
+									)
+							})
+						}
+					})
+					.ok_or_else(|| format!("Request is missing in recording: {:?}", req))?;
+				self.used[found] = true;
+				return Ok(self.frames[found].1.clone());
+			}
+			None => res.mut_error().push("Empty request".to_string()),
+		}
 		Ok(res)
 	}
 }
+
+#[cfg(feature = "async")]
+pub(crate) type AsyncWS = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Asynchronous SC2 API, available with `"async"` feature.
+/// Can be accessed through [`self.async_api()`](crate::bot::Bot::async_api)
+/// in bots implementing [`AsyncPlayer`](crate::AsyncPlayer).
+///
+/// New requests can be sent while waiting for responses on the previous ones,
+/// responses are received in the same order as requests were sent.
+#[cfg(feature = "async")]
+pub struct AsyncAPI {
+	sink: AsyncMutex<SplitSink<AsyncWS, WsMessage>>,
+	stream: AsyncMutex<SplitStream<AsyncWS>>,
+	recorder: AsyncMutex<Option<AsyncRecorder>>,
+}
+#[cfg(feature = "async")]
+impl AsyncAPI {
+	pub(crate) fn new(ws: AsyncWS) -> AsyncAPI {
+		let (sink, stream) = ws.split();
+		AsyncAPI {
+			sink: AsyncMutex::new(sink),
+			stream: AsyncMutex::new(stream),
+			recorder: AsyncMutex::new(None),
+		}
+	}
+
+	/// Sends request and returns a response.
+	/// Errors are returned in the same cases as in [`API::send`].
+	pub async fn send(&self, req: Request) -> SC2Result<Response> {
+		let mut stream = {
+			let mut sink = self.sink.lock().await;
+			sink.send(Binary(req.write_to_bytes()?)).await?;
+			// Locking stream before the next request is sent keeps responses in order
+			self.stream.lock().await
+		};
+		let res = read_response_async(&mut stream).await?;
+		if let Some(recorder) = &mut *self.recorder.lock().await {
+			recorder.write(&req, &res).await?;
+		}
+		check_response(res)
+	}
+
+	/// Sends request, waits for the response, but ignores it (useful when response is empty).
+	pub async fn send_request(&self, req: Request) -> SC2Result<()> {
+		self.send(req).await?;
+		Ok(())
+	}
+
+	/// Sends request, but doesn't wait for the response.
+	/// Responses must be received with [`wait_response`](Self::wait_response) in the same order.
+	pub async fn send_only(&self, req: Request) -> SC2Result<()> {
+		self.sink.lock().await.send(Binary(req.write_to_bytes()?)).await?;
+		if let Some(recorder) = &mut *self.recorder.lock().await {
+			recorder.pending.push_back(req);
+		}
+		Ok(())
+	}
+	/// Sends requests one after another without waiting for responses,
+	/// then receives all of them in the same order. See [`API::send_batch`].
+	pub async fn send_batch(&self, reqs: Vec<Request>) -> SC2Result<Vec<Response>> {
+		let n = reqs.len();
+		for req in reqs {
+			self.send_only(req).await?;
+		}
+		let mut responses = Vec::with_capacity(n);
+		for _ in 0..n {
+			responses.push(self.wait_response().await);
+		}
+		responses.into_iter().collect()
+	}
+	/// Waits for a response (useful only after [`send_only`](Self::send_only)).
+	pub async fn wait_response(&self) -> SC2Result<Response> {
+		let res = read_response_async(&mut *self.stream.lock().await).await?;
+		if let Some(recorder) = &mut *self.recorder.lock().await {
+			if let Some(req) = recorder.pending.pop_front() {
+				recorder.write(&req, &res).await?;
+			}
+		}
+		check_response(res)
+	}
+
+	/// Starts writing every request and response to a file in given path.
+	/// See [`API::start_recording`].
+	pub async fn start_recording<P: AsRef<Path>>(&self, path: P) -> SC2Result<()> {
+		*self.recorder.lock().await = Some(AsyncRecorder::create(path).await?);
+		Ok(())
+	}
+	/// Stops recording started with [`start_recording`](Self::start_recording).
+	pub async fn stop_recording(&self) -> SC2Result<()> {
+		if let Some(mut recorder) = self.recorder.lock().await.take() {
+			recorder.file.flush().await?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(feature = "async")]
+async fn read_response_async(stream: &mut SplitStream<AsyncWS>) -> SC2Result<Response> {
+	let msg = stream
+		.next()
+		.await
+		.ok_or(tungstenite::Error::ConnectionClosed)??;
+
+	let mut res = Response::new();
+	res.merge_from_bytes(msg.into_data().as_slice())?;
+	Ok(res)
+}
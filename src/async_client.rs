@@ -0,0 +1,355 @@
+//! Runners for bots implementing [`AsyncPlayer`], available with `"async"` feature.
+//!
+//! Games are played on the caller's tokio runtime, so bot can overlap it's own computations
+//! with requests to the API, and several games can be played in one process
+//! (e.g. by joining futures of multiple runners).
+//!
+//! Requests can be made with [`async_api`](Bot::async_api) or async versions of query methods
+//! (e.g. [`query_pathing_async`](Bot::query_pathing_async)).
+
+#[cfg(any(test, feature = "mock"))]
+use crate::mock::MockServer;
+use crate::{
+	api::{AsyncAPI, AsyncWS},
+	bot::{Bot, Rs},
+	client::{
+		create_computer_setup, create_player_setup, game_data_request, get_unused_port, join_game_request,
+		launch_client, no_observation_request, player_id_from_response, write_replay, GameLoop,
+		LaunchOptions, Ports, HOST,
+	},
+	error::ProtoError,
+	game_state::{abilities_request, set_abilities, update_observation},
+	paths::*,
+	player::Computer,
+	version, AsyncPlayer, Event, IntoSC2, SC2Result,
+};
+use sc2_proto::sc2api::{Request, Response, ResponseObservation};
+use std::{
+	ops::{Deref, DerefMut},
+	time::Duration,
+};
+use tokio::time::sleep;
+use tokio_tungstenite::{connect_async, MaybeTlsStream};
+
+/// Async version of [`run_vs_computer`](crate::client::run_vs_computer).
+///
+/// SC2 client is closed after the game.
+pub async fn run_vs_computer<B>(
+	bot: &mut B,
+	computer: Computer,
+	map_name: &str,
+	options: LaunchOptions<'_>,
+) -> SC2Result<()>
+where
+	B: AsyncPlayer + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	debug!("Starting game vs computer");
+	let sc2_path = get_path_to_sc2();
+	let map_path = get_map_path(&sc2_path, map_name)?;
+
+	let port = get_unused_port()?;
+	debug!("Launching SC2 process");
+	bot.process = Some(launch_client(&sc2_path, port, options.sc2_version)?);
+	debug!("Connecting to websocket");
+	bot.async_api = Some(AsyncAPI::new(connect_to_websocket(HOST, port).await?));
+
+	let result = play_vs_computer(bot, computer, map_path, &options).await;
+	close_client(bot).await;
+	result
+}
+
+async fn play_vs_computer<B>(
+	bot: &mut B,
+	computer: Computer,
+	map_path: String,
+	options: &LaunchOptions<'_>,
+) -> SC2Result<()>
+where
+	B: AsyncPlayer + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	let settings = bot.get_player_settings();
+	let api = bot.async_api()?;
+	if let Some(path) = options.record_as {
+		api.start_recording(path).await?;
+	}
+
+	debug!("Sending CreateGame request");
+	let mut req = Request::new();
+	let req_create_game = req.mut_create_game();
+
+	req_create_game.mut_local_map().set_map_path(map_path);
+	create_player_setup(&settings, req_create_game);
+	create_computer_setup(&computer, req_create_game);
+
+	req_create_game.set_realtime(options.realtime);
+
+	let res = api.send(req).await?;
+	let res_create_game = res.get_create_game();
+	if res_create_game.has_error() {
+		let err = ProtoError::new(res_create_game.get_error(), res_create_game.get_error_details());
+		error!("{}", err);
+		return Err(err.into());
+	}
+
+	debug!("Sending JoinGame request");
+	let res = api.send(join_game_request(&settings, None)).await?;
+	bot.player_id = player_id_from_response(&res)?;
+
+	set_static_data(bot).await?;
+
+	debug!("Entered main loop");
+	play_game(bot, options.realtime).await?;
+	debug!("Game finished");
+
+	let api = bot.async_api()?;
+	if options.record_as.is_some() {
+		api.stop_recording().await?;
+	}
+	if let Some(path) = options.save_replay_as {
+		let mut req = Request::new();
+		req.mut_save_replay();
+		write_replay(&api.send(req).await?, path)?;
+	}
+	Ok(())
+}
+
+/// Async version of [`run_ladder_game`](crate::client::run_ladder_game).
+pub async fn run_ladder_game<B>(
+	bot: &mut B,
+	host: &str,
+	port: i32,
+	player_port: i32,
+	opponent_id: Option<&str>,
+) -> SC2Result<()>
+where
+	B: AsyncPlayer + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	debug!("Starting ladder game");
+
+	debug!("Connecting to websocket");
+	bot.async_api = Some(AsyncAPI::new(connect_to_websocket(host, port).await?));
+
+	debug!("Sending JoinGame request");
+
+	if let Some(id) = opponent_id {
+		bot.opponent_id = id.to_string();
+	}
+
+	let req = join_game_request(
+		&bot.get_player_settings(),
+		Some(&Ports {
+			server: (player_port + 2, player_port + 3),
+			client: vec![(player_port + 4, player_port + 5)],
+		}),
+	);
+	let res = bot.async_api()?.send(req).await?;
+	bot.player_id = player_id_from_response(&res)?;
+
+	set_static_data(bot).await?;
+
+	debug!("Entered main loop");
+	play_game(bot, false).await?;
+	debug!("Game finished");
+
+	Ok(())
+}
+
+/// Async version of [`run_mock_game`](crate::client::run_mock_game).
+#[cfg(any(test, feature = "mock"))]
+pub async fn run_mock_game<B>(bot: &mut B, server: &MockServer) -> SC2Result<()>
+where
+	B: AsyncPlayer + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	run_mock(bot, server, None).await
+}
+/// Async version of [`record_mock_game`](crate::client::record_mock_game).
+#[cfg(any(test, feature = "mock"))]
+pub async fn record_mock_game<B>(bot: &mut B, server: &MockServer, record_as: &str) -> SC2Result<()>
+where
+	B: AsyncPlayer + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	run_mock(bot, server, Some(record_as)).await
+}
+#[cfg(any(test, feature = "mock"))]
+async fn run_mock<B>(bot: &mut B, server: &MockServer, record_as: Option<&str>) -> SC2Result<()>
+where
+	B: AsyncPlayer + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	debug!("Starting mock game");
+
+	debug!("Connecting to websocket");
+	bot.async_api = Some(AsyncAPI::new(
+		connect_to_websocket(HOST, server.port() as i32).await?,
+	));
+
+	// Client is closed even if bot fails, this also finishes recording
+	let result = play_mock_game(bot, record_as).await;
+	close_client(bot).await;
+	result
+}
+#[cfg(any(test, feature = "mock"))]
+async fn play_mock_game<B>(bot: &mut B, record_as: Option<&str>) -> SC2Result<()>
+where
+	B: AsyncPlayer + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	if let Some(path) = record_as {
+		bot.async_api()?.start_recording(path).await?;
+	}
+
+	debug!("Sending JoinGame request");
+	let res = bot
+		.async_api()?
+		.send(join_game_request(&bot.get_player_settings(), None))
+		.await?;
+	bot.player_id = player_id_from_response(&res)?;
+
+	set_static_data(bot).await?;
+
+	debug!("Entered main loop");
+	let result = play_game(bot, false).await;
+	debug!("Game finished");
+	result
+}
+
+async fn connect_to_websocket(host: &str, port: i32) -> SC2Result<AsyncWS> {
+	let url = format!("ws://{}:{}/sc2api", host, port);
+	loop {
+		if let Ok((ws, _rs)) = connect_async(&url).await {
+			if let MaybeTlsStream::Plain(stream) = ws.get_ref() {
+				stream.set_nodelay(true)?;
+			}
+			break Ok(ws);
+		}
+		sleep(Duration::from_millis(100)).await;
+	}
+}
+
+async fn close_client(bot: &mut Bot) {
+	if let Some(api) = bot.async_api.take() {
+		// Buffered frames aren't written when recorder is dropped
+		if let Err(e) = api.stop_recording().await {
+			error!("Can't finish recording: {}", e);
+		}
+
+		let mut req = Request::new();
+		req.mut_leave_game();
+		if let Err(e) = api.send_request(req).await {
+			error!("Request LeaveGame failed: {}", e);
+		}
+
+		let mut req = Request::new();
+		req.mut_quit();
+		if let Err(e) = api.send_request(req).await {
+			error!("Request QuitGame failed: {}", e);
+		}
+	}
+
+	if let Some(mut process) = bot.process.take() {
+		if let Err(e) = process.kill() {
+			error!("Can't kill SC2 process: {}", e);
+		}
+	}
+}
+
+async fn set_static_data(bot: &mut Bot) -> SC2Result<()> {
+	let api = bot.async_api()?;
+
+	debug!("Requesting Ping, GameInfo and GameData");
+	let mut req = Request::new();
+	req.mut_ping();
+	api.send_only(req).await?;
+	let mut req = Request::new();
+	req.mut_game_info();
+	api.send_only(req).await?;
+	api.send_only(game_data_request()).await?;
+
+	// Version must be selected before ids in game data are decoded
+	let version = version::select(api.wait_response().await?.get_ping().get_base_build());
+
+	let game_info = api.wait_response().await?.take_game_info().into_sc2();
+	let game_data = Rs::new((api.wait_response().await?.take_data(), version).into_sc2());
+
+	bot.version = Rs::new(version.clone());
+	bot.game_info = game_info;
+	bot.game_data = game_data;
+
+	Ok(())
+}
+
+async fn update_state(bot: &mut Bot, response_observation: &ResponseObservation) -> SC2Result<Vec<Event>> {
+	// Abilities are queried while observation is processed
+	bot.async_api()?
+		.send_only(abilities_request(response_observation))
+		.await?;
+	let events = update_observation(bot, response_observation)?;
+	let res = bot.async_api()?.wait_response().await?;
+	set_abilities(bot, &res);
+	Ok(events)
+}
+
+/// Async version of the game loop, see [`GameLoop`].
+///
+/// In [`pipelined`](crate::player::PlayerSettings::pipelined) mode requests of the step
+/// are sent at once and responses are received after that.
+async fn play_game<B>(bot: &mut B, realtime: bool) -> SC2Result<()>
+where
+	B: AsyncPlayer + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	let pipelined = bot.get_player_settings().pipelined;
+	let mut game = GameLoop::new(realtime);
+
+	let mut res = bot
+		.async_api()?
+		.send(GameLoop::first_observation_request())
+		.await?;
+	loop {
+		if step(&mut game, bot, &res).await? {
+			return Ok(());
+		}
+		let mut requests = game.requests(bot);
+		res = if pipelined {
+			bot.async_api()?
+				.send_batch(requests)
+				.await?
+				.pop()
+				.ok_or_else(no_observation_request)?
+		} else {
+			let req = requests.pop().ok_or_else(no_observation_request)?;
+			for req in requests {
+				bot.async_api()?.send_request(req).await?;
+			}
+			bot.async_api()?.send(req).await?
+		};
+	}
+}
+
+/// Async version of [`GameLoop::step`], returns `true` if the game has ended.
+async fn step<B>(game: &mut GameLoop, bot: &mut B, res: &Response) -> SC2Result<bool>
+where
+	B: AsyncPlayer + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	if let Some(result) = game.begin_step(bot, res)? {
+		bot.on_end(result).await?;
+		return Ok(true);
+	}
+
+	if game.is_first_step() {
+		bot.init_data_for_unit()?;
+	}
+	let events = update_state(bot, res.get_observation()).await?;
+	if game.is_first_step() {
+		bot.prepare_start_async().await?;
+	}
+	bot.prepare_step();
+	game.state_updated();
+
+	for e in events {
+		bot.on_event(e).await?;
+	}
+	match game.iteration() {
+		Some(iteration) => bot.on_step(iteration).await?,
+		None => bot.on_start().await?,
+	}
+	game.end_step();
+	Ok(false)
+}
@@ -1,35 +1,46 @@
 //! [`Bot`] struct and it's helpers.
 
 use crate::{
-	action::{Action, ActionResult, Commander, Target},
+	action::{Action, ActionResult, Commander, SpatialAction, Target, UIAction},
 	api::API,
-	client::SC2Result,
-	consts::{RaceValues, FRAMES_PER_SECOND, INHIBITOR_IDS, RACE_VALUES, TECH_ALIAS, UNIT_ALIAS},
+	client::{SC2Result, StepLatency},
+	consts::{RaceValues, FRAMES_PER_SECOND, RACE_VALUES, TECH_ALIAS, UNIT_ALIAS},
 	debug::{DebugCommand, Debugger},
 	distance::*,
+	error::Error,
 	game_data::{Cost, GameData},
 	game_info::GameInfo,
 	game_state::Effect,
 	game_state::{Alliance, GameState},
-	geometry::Point2,
-	ids::{AbilityId, EffectId, UnitTypeId, UpgradeId},
+	geometry::{Point2, Rect},
+	ids::{AbilityId, DecodeId, EffectId, UnitTypeId, UpgradeId},
+	influence::InfluenceMap,
+	pathfinding::{footprint, placement_footprint, PathGrid, PathMode},
+	pixel_map::{Pixel, PixelMap},
 	player::Race,
 	ramp::{Ramp, Ramps},
+	terrain::Terrain,
 	unit::{DataForUnit, SharedUnitData, Unit},
 	units::{AllUnits, Units},
 	utils::{dbscan, range_query},
+	version::VersionData,
 	FromProto, IntoProto,
 };
 use indexmap::IndexSet;
-use num_traits::ToPrimitive;
+use ndarray::{s, Ix2, SliceInfo, SliceInfoElem};
 use rand::prelude::*;
 use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
 use sc2_proto::{
 	query::{RequestQueryBuildingPlacement, RequestQueryPathing},
-	sc2api::Request,
+	sc2api::{Request, Response},
 };
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::{fmt, hash::BuildHasherDefault, process::Child};
 
+#[cfg(feature = "async")]
+use crate::api::AsyncAPI;
+
 type FxIndexSet<T> = IndexSet<T, BuildHasherDefault<FxHasher>>;
 
 #[cfg(feature = "enemies_cache")]
@@ -37,44 +48,45 @@ use crate::{consts::BURROWED_IDS, unit::DisplayType};
 
 #[cfg(feature = "parking_lot")]
 use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
-#[cfg(all(not(feature = "parking_lot"), feature = "rayon"))]
+#[cfg(all(not(feature = "parking_lot"), any(feature = "rayon", feature = "async")))]
 use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-#[cfg(feature = "rayon")]
+#[cfg(any(feature = "rayon", feature = "async"))]
 use std::sync::{
 	atomic::{AtomicBool, AtomicU32, Ordering},
 	Arc,
 };
 
-#[cfg(not(feature = "rayon"))]
+#[cfg(not(any(feature = "rayon", feature = "async")))]
 use std::{
 	cell::{Cell, Ref, RefCell, RefMut},
 	rc::Rc,
 };
 
-#[cfg(feature = "rayon")]
+// Shared state is thread-safe when bot is run in parallel or can be moved between threads of async runtime
+#[cfg(any(feature = "rayon", feature = "async"))]
 pub(crate) type Rs<T> = Arc<T>;
-#[cfg(not(feature = "rayon"))]
+#[cfg(not(any(feature = "rayon", feature = "async")))]
 pub(crate) type Rs<T> = Rc<T>;
 
-#[cfg(feature = "rayon")]
+#[cfg(any(feature = "rayon", feature = "async"))]
 pub(crate) type Rl<T> = RwLock<T>;
-#[cfg(not(feature = "rayon"))]
+#[cfg(not(any(feature = "rayon", feature = "async")))]
 pub(crate) type Rl<T> = RefCell<T>;
 
-#[cfg(feature = "rayon")]
+#[cfg(any(feature = "rayon", feature = "async"))]
 pub(crate) type Rw<T> = Arc<RwLock<T>>;
-#[cfg(not(feature = "rayon"))]
+#[cfg(not(any(feature = "rayon", feature = "async")))]
 pub(crate) type Rw<T> = Rc<RefCell<T>>;
 
-#[cfg(feature = "rayon")]
+#[cfg(any(feature = "rayon", feature = "async"))]
 pub(crate) type Reader<'a, T> = RwLockReadGuard<'a, T>;
-#[cfg(not(feature = "rayon"))]
+#[cfg(not(any(feature = "rayon", feature = "async")))]
 pub(crate) type Reader<'a, T> = Ref<'a, T>;
 
-#[cfg(feature = "rayon")]
+#[cfg(any(feature = "rayon", feature = "async"))]
 pub(crate) type Writer<'a, T> = RwLockWriteGuard<'a, T>;
-#[cfg(not(feature = "rayon"))]
+#[cfg(not(any(feature = "rayon", feature = "async")))]
 pub(crate) type Writer<'a, T> = RefMut<'a, T>;
 
 pub(crate) trait Locked<T> {
@@ -83,7 +95,7 @@ pub(crate) trait Locked<T> {
 }
 impl<T> Locked<T> for Rl<T> {
 	fn read_lock(&self) -> Reader<T> {
-		#[cfg(feature = "rayon")]
+		#[cfg(any(feature = "rayon", feature = "async"))]
 		{
 			#[cfg(feature = "parking_lot")]
 			{
@@ -94,13 +106,13 @@ impl<T> Locked<T> for Rl<T> {
 				self.read().unwrap()
 			}
 		}
-		#[cfg(not(feature = "rayon"))]
+		#[cfg(not(any(feature = "rayon", feature = "async")))]
 		{
 			self.borrow()
 		}
 	}
 	fn write_lock(&self) -> Writer<T> {
-		#[cfg(feature = "rayon")]
+		#[cfg(any(feature = "rayon", feature = "async"))]
 		{
 			#[cfg(feature = "parking_lot")]
 			{
@@ -111,7 +123,7 @@ impl<T> Locked<T> for Rl<T> {
 				self.write().unwrap()
 			}
 		}
-		#[cfg(not(feature = "rayon"))]
+		#[cfg(not(any(feature = "rayon", feature = "async")))]
 		{
 			self.borrow_mut()
 		}
@@ -123,56 +135,57 @@ pub(crate) trait LockOwned<T> {
 	fn set_locked(&self, val: T);
 }
 
-#[cfg(feature = "rayon")]
+#[cfg(any(feature = "rayon", feature = "async"))]
 pub(crate) type LockBool = AtomicBool;
-#[cfg(not(feature = "rayon"))]
+#[cfg(not(any(feature = "rayon", feature = "async")))]
 pub(crate) type LockBool = Cell<bool>;
 
 impl LockOwned<bool> for LockBool {
 	fn get_locked(&self) -> bool {
-		#[cfg(feature = "rayon")]
+		#[cfg(any(feature = "rayon", feature = "async"))]
 		{
 			self.load(Ordering::Relaxed)
 		}
-		#[cfg(not(feature = "rayon"))]
+		#[cfg(not(any(feature = "rayon", feature = "async")))]
 		{
 			self.get()
 		}
 	}
 	fn set_locked(&self, val: bool) {
-		#[cfg(feature = "rayon")]
+		#[cfg(any(feature = "rayon", feature = "async"))]
 		self.store(val, Ordering::Relaxed);
-		#[cfg(not(feature = "rayon"))]
+		#[cfg(not(any(feature = "rayon", feature = "async")))]
 		self.set(val);
 	}
 }
 
-#[cfg(feature = "rayon")]
+#[cfg(any(feature = "rayon", feature = "async"))]
 pub(crate) type LockU32 = AtomicU32;
-#[cfg(not(feature = "rayon"))]
+#[cfg(not(any(feature = "rayon", feature = "async")))]
 pub(crate) type LockU32 = Cell<u32>;
 
 impl LockOwned<u32> for LockU32 {
 	fn get_locked(&self) -> u32 {
-		#[cfg(feature = "rayon")]
+		#[cfg(any(feature = "rayon", feature = "async"))]
 		{
 			self.load(Ordering::Relaxed)
 		}
-		#[cfg(not(feature = "rayon"))]
+		#[cfg(not(any(feature = "rayon", feature = "async")))]
 		{
 			self.get()
 		}
 	}
 	fn set_locked(&self, val: u32) {
-		#[cfg(feature = "rayon")]
+		#[cfg(any(feature = "rayon", feature = "async"))]
 		self.store(val, Ordering::Relaxed);
-		#[cfg(not(feature = "rayon"))]
+		#[cfg(not(any(feature = "rayon", feature = "async")))]
 		self.set(val);
 	}
 }
 
 /// Information about an expansion location.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Expansion {
 	/// Placement position for townhall.
 	pub loc: Point2,
@@ -188,6 +201,8 @@ pub struct Expansion {
 	pub alliance: Alliance,
 	/// Tag of townhall placed on the expansion. (Only for occupied ones)
 	pub base: Option<u64>,
+	/// Index of region in [`terrain`](Bot::terrain) the expansion is located in.
+	pub region: Option<usize>,
 }
 
 /// Additional options for [`find_placement`](Bot::find_placement).
@@ -389,6 +404,8 @@ impl Default for Completion {
 pub struct Bot {
 	pub(crate) process: Option<Child>,
 	pub(crate) api: Option<API>,
+	#[cfg(feature = "async")]
+	pub(crate) async_api: Option<AsyncAPI>,
 	pub(crate) game_step: Rs<LockU32>,
 	#[doc(hidden)]
 	pub disable_fog: bool,
@@ -410,6 +427,8 @@ pub struct Bot {
 	pub game_info: GameInfo,
 	/// Constant information about abilities, unit types, upgrades, buffs and effects.
 	pub game_data: Rs<GameData>,
+	/// Data of the game version being played, used to decode ids received from the game.
+	pub version: Rs<VersionData>,
 	/// Information about current state, updated each step.
 	pub state: GameState,
 	/// Values, which depend on bot's race
@@ -429,6 +448,8 @@ pub struct Bot {
 	pub(crate) saved_hallucinations: FxHashSet<u64>,
 	/// In-game time in seconds.
 	pub time: f32,
+	/// Time spent on previous step.
+	pub step_latency: StepLatency,
 	/// Amount of minerals bot has.
 	pub minerals: u32,
 	/// Amount of gas bot has.
@@ -461,6 +482,26 @@ pub struct Bot {
 	pub vision_blockers: Vec<Point2>,
 	/// Ramps on map.
 	pub ramps: Ramps,
+	/// Regions of the map and chokes between them.
+	pub terrain: Terrain,
+	/// Pathing grid updated every step with footprints of buildings, minerals, geysers and rocks.
+	///
+	/// Unlike static [`pathing_grid`](crate::game_info::GameInfo::pathing_grid) from the game start,
+	/// footprints are removed when buildings are destroyed or lifted off,
+	/// rocks are destroyed and mineral fields are mined out.
+	pub pathing_grid: PixelMap,
+	/// Placement grid updated every step with footprints of buildings, minerals, geysers and rocks.
+	///
+	/// Unlike static [`placement_grid`](crate::game_info::GameInfo::placement_grid) from the game start,
+	/// footprints are removed when obstacles are destroyed or removed the same way as in
+	/// [`pathing_grid`](Self::pathing_grid).
+	pub placement_grid: PixelMap,
+	/// Static pathing grid without footprints of obstacles, used to restore cells of removed ones.
+	base_pathing_grid: PixelMap,
+	/// Static placement grid without footprints of obstacles, used to restore cells of removed ones.
+	base_placement_grid: PixelMap,
+	obstacles: FxHashMap<u64, Rect>,
+	placement_obstacles: FxHashMap<u64, Rect>,
 	enemy_upgrades: Rw<FxHashSet<UpgradeId>>,
 	pub(crate) owned_tags: FxHashSet<u64>,
 	pub(crate) under_construction: FxHashSet<u64>,
@@ -469,9 +510,20 @@ pub struct Bot {
 
 impl Bot {
 	/// Interface for interacting with SC2 API through Request/Response.
+	///
+	/// Returns [`Error::ApiNotInitialized`] if bot isn't connected to the game.
+	#[inline]
+	pub fn api(&self) -> SC2Result<&API> {
+		self.api.as_ref().ok_or(Error::ApiNotInitialized)
+	}
+	/// Interface for interacting with SC2 API asynchronously,
+	/// initialized instead of [`api`](Self::api) when bot is run as [`AsyncPlayer`](crate::AsyncPlayer).
+	///
+	/// Returns [`Error::ApiNotInitialized`] if bot isn't connected to the game.
+	#[cfg(feature = "async")]
 	#[inline]
-	pub fn api(&self) -> &API {
-		self.api.as_ref().expect("API is not initialized")
+	pub fn async_api(&self) -> SC2Result<&AsyncAPI> {
+		self.async_api.as_ref().ok_or(Error::ApiNotInitialized)
 	}
 	/// Sets step between every [`on_step`] iteration
 	/// (e.g. on `1` [`on_step`] will be called every frame, on `2` every second frame, ...).
@@ -559,58 +611,11 @@ impl Bot {
 	}
 	/// Returns full cost of building given unit type, without any corrections.
 	pub fn get_unit_api_cost(&self, unit: UnitTypeId) -> Cost {
-		self.game_data
-			.units
-			.get(&unit)
-			.map_or_else(Cost::default, |data| data.cost())
+		self.game_data.unit_api_cost(unit)
 	}
 	/// Returns correct cost of building given unit type.
 	pub fn get_unit_cost(&self, unit: UnitTypeId) -> Cost {
-		let mut cost = self.get_unit_api_cost(unit);
-		match unit {
-			UnitTypeId::OverlordTransport => {
-				cost.minerals = 25;
-				cost.vespene = 25;
-			}
-			UnitTypeId::Zergling | UnitTypeId::ZerglingBurrowed => {
-				cost.minerals *= 2;
-				cost.supply *= 2.0;
-			}
-			_ => {
-				let pred = self.get_unit_api_cost(match unit {
-					UnitTypeId::Baneling | UnitTypeId::BanelingBurrowed => UnitTypeId::Zergling,
-					UnitTypeId::Ravager | UnitTypeId::RavagerBurrowed => UnitTypeId::Roach,
-					UnitTypeId::LurkerMP | UnitTypeId::LurkerMPBurrowed => UnitTypeId::Hydralisk,
-					UnitTypeId::Overseer | UnitTypeId::OverseerSiegeMode => UnitTypeId::Overlord,
-					UnitTypeId::BroodLord => UnitTypeId::Corruptor,
-					UnitTypeId::OrbitalCommand
-					| UnitTypeId::OrbitalCommandFlying
-					| UnitTypeId::PlanetaryFortress => UnitTypeId::CommandCenter,
-					UnitTypeId::Lair => UnitTypeId::Hatchery,
-					UnitTypeId::Hive => UnitTypeId::Lair,
-					UnitTypeId::GreaterSpire => UnitTypeId::Spire,
-					UnitTypeId::Hatchery
-					| UnitTypeId::SpineCrawler
-					| UnitTypeId::SporeCrawler
-					| UnitTypeId::Extractor
-					| UnitTypeId::SpawningPool
-					| UnitTypeId::EvolutionChamber
-					| UnitTypeId::RoachWarren
-					| UnitTypeId::BanelingNest
-					| UnitTypeId::HydraliskDen
-					| UnitTypeId::LurkerDenMP
-					| UnitTypeId::InfestationPit
-					| UnitTypeId::Spire
-					| UnitTypeId::NydusNetwork
-					| UnitTypeId::UltraliskCavern => UnitTypeId::Drone,
-					_ => return cost,
-				});
-				cost.minerals -= pred.minerals;
-				cost.vespene -= pred.vespene;
-				cost.supply = (cost.supply - pred.supply).max(0.0);
-			}
-		}
-		cost
+		self.game_data.unit_cost(unit)
 	}
 	/// Checks if bot has enough resources and supply to build given unit type.
 	pub fn can_afford(&self, unit: UnitTypeId, check_supply: bool) -> bool {
@@ -625,10 +630,7 @@ impl Bot {
 	}
 	/// Checks cost of making given upgrade.
 	pub fn get_upgrade_cost(&self, upgrade: UpgradeId) -> Cost {
-		self.game_data
-			.upgrades
-			.get(&upgrade)
-			.map_or_else(Default::default, |data| data.cost())
+		self.game_data.upgrade_cost(upgrade)
 	}
 	/// Checks if bot has enough resources to make given upgrade.
 	pub fn can_afford_upgrade(&self, upgrade: UpgradeId) -> bool {
@@ -720,6 +722,22 @@ impl Bot {
 	pub fn chat_ally(&mut self, message: &str) {
 		self.actions.push(Action::Chat(message.to_string(), true));
 	}
+	/// Sends action of feature layer interface, it should be enabled in [`PlayerSettings`].
+	///
+	/// [`PlayerSettings`]: crate::PlayerSettings
+	pub fn feature_layer_action(&mut self, action: SpatialAction) {
+		self.actions.push(Action::FeatureLayer(action));
+	}
+	/// Sends action of rendered interface, it should be enabled in [`PlayerSettings`].
+	///
+	/// [`PlayerSettings`]: crate::PlayerSettings
+	pub fn render_action(&mut self, action: SpatialAction) {
+		self.actions.push(Action::Render(action));
+	}
+	/// Sends action of game UI.
+	pub fn ui_action(&mut self, action: UIAction) {
+		self.actions.push(Action::UI(action));
+	}
 	/// Returns actual terrain height on given position in 3D space.
 	pub fn get_z_height<P: Into<(usize, usize)>>(&self, pos: P) -> f32 {
 		self.game_info
@@ -736,18 +754,20 @@ impl Bot {
 			.unwrap_or(0)
 	}
 	/// Checks if it's possible to build on given position.
+	///
+	/// Uses [`placement_grid`](Self::placement_grid) maintained by bot,
+	/// so footprints of current buildings, minerals and rocks aren't placeable.
 	pub fn is_placeable<P: Into<(usize, usize)>>(&self, pos: P) -> bool {
-		self.game_info
-			.placement_grid
+		self.placement_grid
 			.get(pos.into())
 			.map_or(false, |p| p.is_empty())
 	}
 	/// Checks if it's possible for ground units to walk through given position.
+	///
+	/// Uses [`pathing_grid`](Self::pathing_grid) maintained by bot,
+	/// so footprints of current buildings, minerals and rocks aren't pathable.
 	pub fn is_pathable<P: Into<(usize, usize)>>(&self, pos: P) -> bool {
-		self.game_info
-			.pathing_grid
-			.get(pos.into())
-			.map_or(false, |p| p.is_empty())
+		self.pathing_grid.get(pos.into()).map_or(false, |p| p.is_empty())
 	}
 	/// Checks if given position is hidden (wasn't explored before).
 	pub fn is_hidden<P: Into<(usize, usize)>>(&self, pos: P) -> bool {
@@ -805,18 +825,30 @@ impl Bot {
 			.get(pos.into())
 			.map_or(false, |p| p.is_empty())
 	}
-	pub(crate) fn init_data_for_unit(&mut self) {
-		self.race = self.game_info.players[&self.player_id].race_actual.unwrap();
+	pub(crate) fn init_data_for_unit(&mut self) -> SC2Result<()> {
+		let player = self
+			.game_info
+			.players
+			.get(&self.player_id)
+			.ok_or_else(|| Error::other(format!("There's no player {} in game info", self.player_id)))?;
+		self.race = player.race_actual.unwrap_or(player.race_requested);
 		if self.game_info.players.len() == 2 {
-			let enemy_player_id = 3 - self.player_id;
-			self.enemy_race = self.game_info.players[&enemy_player_id].race_requested;
-			self.enemy_player_id = enemy_player_id;
+			if let Some((id, enemy)) = self
+				.game_info
+				.players
+				.iter()
+				.find(|(id, _)| **id != self.player_id)
+			{
+				self.enemy_race = enemy.race_requested;
+				self.enemy_player_id = *id;
+			}
 		}
 		self.race_values = Rs::new(RACE_VALUES[&self.race].clone());
 
 		self.data_for_unit = Rs::new(DataForUnit {
 			commander: Rs::clone(&self.commander),
 			game_data: Rs::clone(&self.game_data),
+			version: Rs::clone(&self.version),
 			techlab_tags: Rs::clone(&self.techlab_tags),
 			reactor_tags: Rs::clone(&self.reactor_tags),
 			race_values: Rs::clone(&self.race_values),
@@ -830,8 +862,26 @@ impl Bot {
 			game_loop: Rs::clone(&self.state.observation.game_loop),
 			available_frames: Rs::clone(&self.available_frames),
 		});
+		Ok(())
+	}
+	pub(crate) fn prepare_start(&mut self) -> SC2Result<()> {
+		let expansions = self.find_expansions();
+		let start = Target::Pos(self.start_location);
+		let paths = self.query_pathing(expansions.iter().map(|exp| (start, exp.loc)).collect())?;
+		self.finish_start(expansions, paths);
+		Ok(())
+	}
+	#[cfg(feature = "async")]
+	pub(crate) async fn prepare_start_async(&mut self) -> SC2Result<()> {
+		let expansions = self.find_expansions();
+		let start = Target::Pos(self.start_location);
+		let paths = self
+			.query_pathing_async(expansions.iter().map(|exp| (start, exp.loc)).collect())
+			.await?;
+		self.finish_start(expansions, paths);
+		Ok(())
 	}
-	pub(crate) fn prepare_start(&mut self) {
+	fn find_expansions(&mut self) -> Vec<Expansion> {
 		if let Some(townhall) = self.units.my.townhalls.first() {
 			self.start_location = townhall.position();
 		}
@@ -880,7 +930,7 @@ impl Bot {
 			})
 			.collect::<Vec<(isize, isize)>>();
 
-		let mut expansions = resource_groups
+		resource_groups
 			.into_iter()
 			.map(|group| {
 				let resources = all_resources.find_tags(group.iter().map(|(_, tag)| tag));
@@ -900,7 +950,7 @@ impl Bot {
 						.iter()
 						.filter_map(|(x, y)| {
 							let pos = center.offset(*x as f32, *y as f32);
-							if self.is_placeable((pos.x as usize, pos.y as usize)) {
+							if is_free(&self.game_info.placement_grid, (pos.x as usize, pos.y as usize)) {
 								let mut distance_sum = 0_f32;
 								let far_enough = |r: &Unit| {
 									let dist = pos.distance_squared(r);
@@ -946,16 +996,13 @@ impl Bot {
 					geysers,
 					alliance,
 					base,
+					region: None,
 				}
 			})
-			.collect::<Vec<_>>();
-
+			.collect()
+	}
+	fn finish_start(&mut self, mut expansions: Vec<Expansion>, paths: Vec<Option<f32>>) {
 		// Sort expansions by distance to start location
-		let start = Target::Pos(self.start_location);
-		let paths = self
-			.query_pathing(expansions.iter().map(|exp| (start, exp.loc)).collect())
-			.unwrap();
-
 		let paths = expansions
 			.iter()
 			.zip(paths.into_iter())
@@ -966,12 +1013,17 @@ impl Bot {
 
 		self.expansions = expansions;
 
+		self.reset_grids(
+			self.game_info.pathing_grid.clone(),
+			self.game_info.placement_grid.clone(),
+		);
+
 		// Calclulating ramp locations
 		let mut ramp_points = FxHashSet::default();
 
 		let area = self.game_info.playable_area;
 		for pos in iproduct!(area.x0..area.x1, area.y0..area.y1) {
-			if !self.is_pathable(pos) || self.is_placeable(pos) {
+			if !self.is_pathable(pos) || is_free(&self.game_info.placement_grid, pos) {
 				continue;
 			}
 
@@ -1057,6 +1109,13 @@ impl Bot {
 		}
 
 		self.ramps.all = ramps;
+
+		self.terrain = Terrain::new(
+			&self.game_info.pathing_grid,
+			&self.game_info.terrain_height,
+			&self.ramps.all,
+			&mut self.expansions,
+		);
 	}
 	pub(crate) fn prepare_step(&mut self) {
 		let observation = &self.state.observation;
@@ -1105,6 +1164,49 @@ impl Bot {
 		}
 		self.current_units = current_units;
 		self.orders = orders;
+
+		self.update_grids();
+	}
+	fn update_grids(&mut self) {
+		let dead_units = &self.state.observation.raw.dead_units;
+		update_grid(
+			&mut self.pathing_grid,
+			&self.base_pathing_grid,
+			&mut self.obstacles,
+			dead_units,
+			&self.units.all,
+			footprint,
+		);
+		update_grid(
+			&mut self.placement_grid,
+			&self.base_placement_grid,
+			&mut self.placement_obstacles,
+			dead_units,
+			&self.units.all,
+			placement_footprint,
+		);
+	}
+	/// Replaces pathing and placement grids maintained by bot with given ones.
+	///
+	/// Base grids are taken from the static ones, where cells free in given grids
+	/// and footprints of current units are cleared, since these obstacles can be removed later.
+	pub(crate) fn reset_grids(&mut self, pathing_grid: PixelMap, placement_grid: PixelMap) {
+		self.base_pathing_grid = base_grid(
+			&self.game_info.pathing_grid,
+			&pathing_grid,
+			&self.units.all,
+			footprint,
+		);
+		self.base_placement_grid = base_grid(
+			&self.game_info.placement_grid,
+			&placement_grid,
+			&self.units.all,
+			placement_footprint,
+		);
+		self.pathing_grid = pathing_grid;
+		self.placement_grid = placement_grid;
+		self.obstacles.clear();
+		self.placement_obstacles.clear();
 	}
 	pub(crate) fn update_units(&mut self, all_units: Units) {
 		*self.last_units_health.write_lock() = self
@@ -1167,7 +1269,7 @@ impl Bot {
 						add_to!(units.resources);
 						add_to!(units.vespene_geysers);
 					}
-					id if INHIBITOR_IDS.contains(&id) => add_to!(units.inhibitor_zones),
+					id if self.version.inhibitor_ids.contains(&id) => add_to!(units.inhibitor_zones),
 
 					_ => add_to!(units.destructables),
 				},
@@ -1676,28 +1778,29 @@ impl Bot {
 	///
 	/// Returns `Vec` ordered by input values,
 	/// where element is distance of path from start to goal or `None` if there's no path.
+	/// Returns error if any start is [`Target::None`].
 	pub fn query_pathing(&self, paths: Vec<(Target, Point2)>) -> SC2Result<Vec<Option<f32>>> {
-		let mut req = Request::new();
-		let req_pathing = req.mut_query().mut_pathing();
-
-		for (start, goal) in paths {
-			let mut pathing = RequestQueryPathing::new();
-			match start {
-				Target::Tag(tag) => pathing.set_unit_tag(tag),
-				Target::Pos(pos) => pathing.set_start_pos(pos.into_proto()),
-				Target::None => panic!("start pos is not specified in query pathing request"),
-			}
-			pathing.set_end_pos(goal.into_proto());
-			req_pathing.push(pathing);
-		}
-
-		let res = self.api().send(req)?;
-		Ok(res
-			.get_query()
-			.get_pathing()
-			.iter()
-			.map(|result| result.distance)
-			.collect())
+		let res = self.api()?.send(pathing_request(paths)?)?;
+		Ok(pathing_results(&res))
+	}
+	/// Async version of [`query_pathing`](Self::query_pathing).
+	#[cfg(feature = "async")]
+	pub async fn query_pathing_async(&self, paths: Vec<(Target, Point2)>) -> SC2Result<Vec<Option<f32>>> {
+		let res = self.async_api()?.send(pathing_request(paths)?).await?;
+		Ok(pathing_results(&res))
+	}
+	/// Constructs grid for local pathfinding for given kind of units,
+	/// with current buildings, minerals and rocks marked as obstacles.
+	///
+	/// Unlike [`query_pathing`](Self::query_pathing) doesn't send requests to the game
+	/// and returns waypoints of paths. See [`pathfinding`](crate::pathfinding) for details.
+	pub fn path_grid(&self, mode: PathMode) -> PathGrid {
+		PathGrid::from_bot(self, mode)
+	}
+	/// Constructs map of threat from enemy units and effects, and enemy detection.
+	/// See [`influence`](crate::influence) for details.
+	pub fn influence_map(&self) -> InfluenceMap {
+		InfluenceMap::from_bot(self)
 	}
 	/// Sends placement requests to API.
 	/// Takes creep, psionic matrix, and other stuff into account.
@@ -1716,28 +1819,23 @@ impl Bot {
 		places: Vec<(AbilityId, Point2, Option<u64>)>,
 		check_resources: bool,
 	) -> SC2Result<Vec<ActionResult>> {
-		let mut req = Request::new();
-		let req_query = req.mut_query();
-		req_query.set_ignore_resource_requirements(!check_resources);
-		let req_placement = req_query.mut_placements();
-
-		for (ability, pos, builder) in places {
-			let mut placement = RequestQueryBuildingPlacement::new();
-			placement.set_ability_id(ability.to_i32().unwrap());
-			placement.set_target_pos(pos.into_proto());
-			if let Some(tag) = builder {
-				placement.set_placing_unit_tag(tag);
-			}
-			req_placement.push(placement);
-		}
-
-		let res = self.api().send(req)?;
-		Ok(res
-			.get_query()
-			.get_placements()
-			.iter()
-			.map(|result| ActionResult::from_proto(result.get_result()))
-			.collect())
+		let res = self
+			.api()?
+			.send(placement_request(places, check_resources, &self.version))?;
+		Ok(placement_results(&res))
+	}
+	/// Async version of [`query_placement`](Self::query_placement).
+	#[cfg(feature = "async")]
+	pub async fn query_placement_async(
+		&self,
+		places: Vec<(AbilityId, Point2, Option<u64>)>,
+		check_resources: bool,
+	) -> SC2Result<Vec<ActionResult>> {
+		let res = self
+			.async_api()?
+			.send(placement_request(places, check_resources, &self.version))
+			.await?;
+		Ok(placement_results(&res))
 	}
 
 	/// Leaves current game, which is counted as Defeat for bot.
@@ -1749,7 +1847,7 @@ impl Bot {
 	pub fn leave(&self) -> SC2Result<()> {
 		let mut req = Request::new();
 		req.mut_leave_game();
-		self.api().send_request(req)
+		self.api()?.send_request(req)
 	}
 
 	pub(crate) fn close_client(&mut self) {
@@ -1775,6 +1873,140 @@ impl Bot {
 	}
 }
 
+fn pathing_request(paths: Vec<(Target, Point2)>) -> SC2Result<Request> {
+	let mut req = Request::new();
+	let req_pathing = req.mut_query().mut_pathing();
+
+	for (start, goal) in paths {
+		let mut pathing = RequestQueryPathing::new();
+		match start {
+			Target::Tag(tag) => pathing.set_unit_tag(tag),
+			Target::Pos(pos) => pathing.set_start_pos(pos.into_proto()),
+			Target::None => return Err(Error::other("Start of pathing query must be unit or position")),
+		}
+		pathing.set_end_pos(goal.into_proto());
+		req_pathing.push(pathing);
+	}
+	Ok(req)
+}
+fn pathing_results(res: &Response) -> Vec<Option<f32>> {
+	res.get_query()
+		.get_pathing()
+		.iter()
+		.map(|result| result.distance)
+		.collect()
+}
+
+/// Cells of given area, clamped to the grid of given size.
+fn area_slice((w, h): (usize, usize), area: &Rect) -> SliceInfo<[SliceInfoElem; 2], Ix2, Ix2> {
+	s![area.x0.min(w)..area.x1.min(w), area.y0.min(h)..area.y1.min(h)]
+}
+fn is_free(grid: &PixelMap, pos: (usize, usize)) -> bool {
+	grid.get(pos).map_or(false, |p| p.is_empty())
+}
+fn fill_area(grid: &mut PixelMap, area: &Rect, pixel: Pixel) {
+	let cells = area_slice(grid.dim(), area);
+	grid.slice_mut(cells).fill(pixel);
+}
+
+/// Marks footprints of new obstacles on the grid and restores cells of removed ones from the base grid.
+fn update_grid(
+	grid: &mut PixelMap,
+	base: &PixelMap,
+	obstacles: &mut FxHashMap<u64, Rect>,
+	dead_units: &[u64],
+	units: &Units,
+	footprint: fn(&Unit) -> Option<Rect>,
+) {
+	let mut removed = Vec::new();
+	for tag in dead_units {
+		if let Some(area) = obstacles.remove(tag) {
+			removed.push(area);
+		}
+	}
+
+	// Buildings can be lifted off or landed somewhere else
+	let mut added = Vec::new();
+	for u in units {
+		let new = footprint(u);
+		let old = obstacles.get(&u.tag()).copied();
+		if old == new {
+			continue;
+		}
+		if let Some(old) = old {
+			removed.push(old);
+			obstacles.remove(&u.tag());
+		}
+		if let Some(new) = new {
+			added.push(new);
+			obstacles.insert(u.tag(), new);
+		}
+	}
+
+	for area in &removed {
+		let cells = area_slice(grid.dim(), area);
+		grid.slice_mut(cells).assign(&base.slice(cells));
+	}
+	// Restoring cells of obstacles, which overlapped with removed ones
+	if !removed.is_empty() {
+		for area in obstacles.values() {
+			if removed.iter().any(|r| r.intersects(area)) {
+				fill_area(grid, area, Pixel::Set);
+			}
+		}
+	}
+	for area in &added {
+		fill_area(grid, area, Pixel::Set);
+	}
+}
+/// Static grid, where cells free in given grid and footprints of given units are cleared.
+fn base_grid(
+	static_grid: &PixelMap,
+	grid: &PixelMap,
+	units: &Units,
+	footprint: fn(&Unit) -> Option<Rect>,
+) -> PixelMap {
+	let mut base = static_grid.clone();
+	base.zip_mut_with(grid, |base, pixel| {
+		if pixel.is_empty() {
+			*base = Pixel::Empty;
+		}
+	});
+	for area in units.iter().filter_map(footprint) {
+		fill_area(&mut base, &area, Pixel::Empty);
+	}
+	base
+}
+
+fn placement_request(
+	places: Vec<(AbilityId, Point2, Option<u64>)>,
+	check_resources: bool,
+	version: &VersionData,
+) -> Request {
+	let mut req = Request::new();
+	let req_query = req.mut_query();
+	req_query.set_ignore_resource_requirements(!check_resources);
+	let req_placement = req_query.mut_placements();
+
+	for (ability, pos, builder) in places {
+		let mut placement = RequestQueryBuildingPlacement::new();
+		placement.set_ability_id(ability.encode(version) as i32);
+		placement.set_target_pos(pos.into_proto());
+		if let Some(tag) = builder {
+			placement.set_placing_unit_tag(tag);
+		}
+		req_placement.push(placement);
+	}
+	req
+}
+fn placement_results(res: &Response) -> Vec<ActionResult> {
+	res.get_query()
+		.get_placements()
+		.iter()
+		.map(|result| ActionResult::from_proto(result.get_result()))
+		.collect()
+}
+
 impl Default for Bot {
 	fn default() -> Self {
 		Self {
@@ -1784,6 +2016,8 @@ impl Default for Bot {
 			enemy_race: Race::Random,
 			process: None,
 			api: Default::default(),
+			#[cfg(feature = "async")]
+			async_api: Default::default(),
 			player_id: Default::default(),
 			enemy_player_id: Default::default(),
 			opponent_id: Default::default(),
@@ -1792,6 +2026,7 @@ impl Default for Bot {
 			debug: Default::default(),
 			game_info: Default::default(),
 			game_data: Default::default(),
+			version: Default::default(),
 			state: Default::default(),
 			race_values: Default::default(),
 			data_for_unit: Default::default(),
@@ -1800,6 +2035,7 @@ impl Default for Bot {
 			orders: Default::default(),
 			current_units: Default::default(),
 			time: Default::default(),
+			step_latency: Default::default(),
 			minerals: Default::default(),
 			vespene: Default::default(),
 			supply_army: Default::default(),
@@ -1818,6 +2054,13 @@ impl Default for Bot {
 			last_units_health: Default::default(),
 			vision_blockers: Default::default(),
 			ramps: Default::default(),
+			terrain: Default::default(),
+			pathing_grid: Default::default(),
+			placement_grid: Default::default(),
+			base_pathing_grid: Default::default(),
+			base_placement_grid: Default::default(),
+			obstacles: Default::default(),
+			placement_obstacles: Default::default(),
 			enemy_upgrades: Default::default(),
 			owned_tags: Default::default(),
 			under_construction: Default::default(),
@@ -0,0 +1,721 @@
+//! Build orders, described as a list of steps and executed one step after another.
+//!
+//! ```no_run
+//! use rust_sc2::{build_order::BuildOrder, prelude::*};
+//!
+//! #[bot]
+//! #[derive(Default)]
+//! struct MyBot {
+//!     build_order: BuildOrder,
+//! }
+//! impl Player for MyBot {
+//!     fn get_player_settings(&self) -> PlayerSettings {
+//!         PlayerSettings::new(Race::Terran)
+//!     }
+//!     fn on_start(&mut self) -> SC2Result<()> {
+//!         self.build_order = "14 SupplyDepot, 16 Barracks, 16 Refinery, 19 OrbitalCommand".parse()?;
+//!         if let Err(blocked) = self.build_order.validate(self.race) {
+//!             blocked.iter().for_each(|b| println!("{}", b));
+//!         }
+//!         Ok(())
+//!     }
+//!     fn on_step(&mut self, _iteration: usize) -> SC2Result<()> {
+//!         if let Some(blocked) = self.build_order.execute(&mut self._bot) {
+//!             println!("{}", blocked);
+//!         }
+//!         Ok(())
+//!     }
+//! }
+//! ```
+//!
+//! Each step consists of optional supply and name of unit or upgrade.
+//! Step starts only when all previous steps are started and bot's supply reaches given value,
+//! workers are trained automatically until then.
+//! Step is considered done when the bot has enough units of its type (including ones in progress),
+//! so units lost later are ordered again while build order isn't complete.
+
+use crate::{
+	action::Target,
+	bot::{Bot, PlacementOptions},
+	consts::{ALL_PRODUCERS, PRODUCERS, RACE_VALUES, RESEARCHERS, TECH_ALIAS, TECH_REQUIREMENTS},
+	distance::{Distance, DistanceIterator},
+	game_data::Attribute,
+	ids::{UnitTypeId, UpgradeId},
+	player::Race,
+	unit::Unit,
+	units::iter::UnitsIterator,
+	Error,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::{fmt, str::FromStr};
+
+/// Number of game loops, during which just ordered step is considered started,
+/// before its order appears in observation.
+const PENDING_LOOPS: u32 = 22;
+
+/// Unit or upgrade made by a step of build order.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BuildItem {
+	/// Unit or structure to train, build or morph.
+	Unit(UnitTypeId),
+	/// Upgrade to research.
+	Upgrade(UpgradeId),
+}
+impl From<UnitTypeId> for BuildItem {
+	fn from(unit: UnitTypeId) -> Self {
+		BuildItem::Unit(unit)
+	}
+}
+impl From<UpgradeId> for BuildItem {
+	fn from(upgrade: UpgradeId) -> Self {
+		BuildItem::Upgrade(upgrade)
+	}
+}
+impl FromStr for BuildItem {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		if let Ok(unit) = s.parse::<UnitTypeId>() {
+			Ok(BuildItem::Unit(unit))
+		} else if let Ok(upgrade) = s.parse::<UpgradeId>() {
+			Ok(BuildItem::Upgrade(upgrade))
+		} else {
+			Err(Error::other(format!("There's no unit or upgrade named `{}`", s)))
+		}
+	}
+}
+impl fmt::Display for BuildItem {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			BuildItem::Unit(unit) => write!(f, "{:?}", unit),
+			BuildItem::Upgrade(upgrade) => write!(f, "{:?}", upgrade),
+		}
+	}
+}
+
+/// Single step of build order.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BuildStep {
+	/// Supply used, at which step should be started. `None` means as soon as possible.
+	pub supply: Option<u32>,
+	/// What should be made.
+	pub item: BuildItem,
+}
+impl BuildStep {
+	/// Constructs step, which starts at given supply.
+	pub fn new<I: Into<BuildItem>>(supply: u32, item: I) -> Self {
+		Self {
+			supply: Some(supply),
+			item: item.into(),
+		}
+	}
+	/// Constructs step, which starts as soon as possible.
+	pub fn asap<I: Into<BuildItem>>(item: I) -> Self {
+		Self {
+			supply: None,
+			item: item.into(),
+		}
+	}
+}
+/// Parses step in format `"[supply] Name"`, e.g. `"16 Barracks"` or `"Stimpack"`.
+impl FromStr for BuildStep {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let invalid = || Error::other(format!("Invalid build order step `{}`", s));
+
+		let mut words = s.split_whitespace();
+		let first = words.next().ok_or_else(invalid)?;
+		let (supply, name) = match first.parse::<u32>() {
+			Ok(supply) => (Some(supply), words.next().ok_or_else(invalid)?),
+			Err(_) => (None, first),
+		};
+		if words.next().is_some() {
+			return Err(invalid());
+		}
+		Ok(Self {
+			supply,
+			item: name.parse()?,
+		})
+	}
+}
+impl fmt::Display for BuildStep {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if let Some(supply) = self.supply {
+			write!(f, "{} ", supply)?;
+		}
+		self.item.fmt(f)
+	}
+}
+
+/// Reason why step of build order can't be started.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BlockReason {
+	/// Required structure isn't complete.
+	MissingTech(UnitTypeId),
+	/// There're no complete units able to produce the item.
+	NoProducer(UnitTypeId),
+	/// All producers of the item are busy.
+	ProducersBusy(UnitTypeId),
+	/// Item can't be produced at all (e.g. it's a neutral unit).
+	Unproducible,
+	/// Not enough free supply.
+	SupplyBlocked,
+	/// Not enough minerals or vespene.
+	NotEnoughResources,
+	/// Supply used is lower than one given in the step.
+	WaitingSupply(u32),
+	/// Free position to place structure not found.
+	NoPlacement,
+}
+impl fmt::Display for BlockReason {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			BlockReason::MissingTech(unit) => write!(f, "missing tech {:?}", unit),
+			BlockReason::NoProducer(unit) => write!(f, "no producer {:?}", unit),
+			BlockReason::ProducersBusy(unit) => write!(f, "all {:?} are busy", unit),
+			BlockReason::Unproducible => write!(f, "can't be produced"),
+			BlockReason::SupplyBlocked => write!(f, "supply blocked"),
+			BlockReason::NotEnoughResources => write!(f, "not enough resources"),
+			BlockReason::WaitingSupply(supply) => write!(f, "waiting for {} supply", supply),
+			BlockReason::NoPlacement => write!(f, "no place for structure"),
+		}
+	}
+}
+
+/// Step of build order, which can't be started.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BlockedStep {
+	/// Index of the step in build order.
+	pub index: usize,
+	/// The step itself.
+	pub step: BuildStep,
+	/// Why it can't be started.
+	pub reason: BlockReason,
+}
+impl fmt::Display for BlockedStep {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"Step {} `{}` is blocked: {}",
+			self.index, self.step, self.reason
+		)
+	}
+}
+
+/// List of steps, executed in strict order.
+///
+/// Can be parsed from comma or newline separated steps,
+/// e.g. `"14 SupplyDepot, 16 Barracks, 16 Refinery, 19 OrbitalCommand"`.
+#[derive(Debug, Clone)]
+pub struct BuildOrder {
+	steps: Vec<BuildStep>,
+	/// Train workers while waiting for supply of the next step. [Default: `true`]
+	pub train_workers: bool,
+	baseline: Option<FxHashMap<UnitTypeId, usize>>,
+	pending: FxHashMap<usize, u32>,
+	current: usize,
+}
+impl Default for BuildOrder {
+	fn default() -> Self {
+		Self::new(Vec::new())
+	}
+}
+impl FromStr for BuildOrder {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		s.split(&[',', '\n'][..])
+			.map(|step| step.trim())
+			.filter(|step| !step.is_empty())
+			.map(|step| step.parse())
+			.collect::<Result<_, _>>()
+			.map(Self::new)
+	}
+}
+impl BuildOrder {
+	/// Constructs build order from given steps.
+	pub fn new(steps: Vec<BuildStep>) -> Self {
+		Self {
+			steps,
+			train_workers: true,
+			baseline: None,
+			pending: Default::default(),
+			current: 0,
+		}
+	}
+	/// Sets `train_workers` to a given value.
+	pub fn train_workers(mut self, val: bool) -> Self {
+		self.train_workers = val;
+		self
+	}
+	/// Returns steps of build order.
+	pub fn steps(&self) -> &[BuildStep] {
+		&self.steps
+	}
+	/// Returns index of the first step, which wasn't started on the last [`execute`](Self::execute).
+	pub fn current(&self) -> usize {
+		self.current
+	}
+	/// Checks if all steps were started.
+	pub fn is_complete(&self) -> bool {
+		self.current >= self.steps.len()
+	}
+	/// Forgets units counted on the first [`execute`](Self::execute),
+	/// so build order starts from the beginning with units bot has at the moment.
+	pub fn reset(&mut self) {
+		self.baseline = None;
+		self.pending.clear();
+		self.current = 0;
+	}
+
+	/// Checks that every step can be made by given race after the previous ones,
+	/// i.e. its tech requirement, producer and supply are provided by initial units or previous steps.
+	///
+	/// Returns all steps, which will be blocked.
+	/// Addons required by units (e.g. tech lab for Marauder) aren't checked,
+	/// tech requirements are taken from the latest game version.
+	pub fn validate(&self, race: Race) -> Result<(), Vec<BlockedStep>> {
+		let race_values = &RACE_VALUES[&race];
+		let mut available = FxHashSet::default();
+		available.insert(race_values.start_townhall);
+		available.insert(race_values.worker);
+		let mut supply_cap = supply_provided(race_values.start_townhall);
+		if race == Race::Zerg {
+			available.insert(UnitTypeId::Larva);
+			available.insert(UnitTypeId::Overlord);
+			supply_cap += supply_provided(UnitTypeId::Overlord);
+		}
+		let has = |available: &FxHashSet<UnitTypeId>, unit: UnitTypeId| {
+			available.contains(&unit)
+				|| TECH_ALIAS
+					.get(&unit)
+					.map_or(false, |alias| alias.iter().any(|a| available.contains(a)))
+		};
+
+		let mut blocked = Vec::new();
+		for (index, step) in self.steps.iter().enumerate() {
+			let reason = match step.item {
+				BuildItem::Unit(unit) => {
+					let missing_tech = TECH_REQUIREMENTS
+						.get(&unit)
+						.copied()
+						.filter(|requirement| !has(&available, *requirement));
+					match (ALL_PRODUCERS.get(&unit), missing_tech) {
+						(None, _) => Some(BlockReason::Unproducible),
+						(_, Some(requirement)) => Some(BlockReason::MissingTech(requirement)),
+						(Some(producers), None) if !producers.iter().any(|p| has(&available, *p)) => {
+							Some(BlockReason::NoProducer(PRODUCERS[&unit]))
+						}
+						_ => None,
+					}
+				}
+				BuildItem::Upgrade(upgrade) => match RESEARCHERS.get(&upgrade) {
+					None => Some(BlockReason::Unproducible),
+					Some(researcher) if !has(&available, *researcher) => {
+						Some(BlockReason::NoProducer(*researcher))
+					}
+					_ => None,
+				},
+			}
+			.or_else(|| {
+				step.supply
+					.filter(|supply| *supply > supply_cap)
+					.map(|_| BlockReason::SupplyBlocked)
+			});
+
+			if let Some(reason) = reason {
+				blocked.push(BlockedStep {
+					index,
+					step: *step,
+					reason,
+				});
+			}
+			if let BuildItem::Unit(unit) = step.item {
+				available.insert(unit);
+				supply_cap += supply_provided(unit);
+			}
+		}
+
+		if blocked.is_empty() {
+			Ok(())
+		} else {
+			Err(blocked)
+		}
+	}
+
+	/// Orders all steps, which can be started now, and subtracts their cost from bot's resources.
+	///
+	/// Returns the first step, which can't be started and the reason,
+	/// or `None` if build order is complete.
+	pub fn execute(&mut self, bot: &mut Bot) -> Option<BlockedStep> {
+		let game_loop = bot.state.observation.game_loop();
+		self.pending
+			.retain(|_, ordered| game_loop.saturating_sub(*ordered) < PENDING_LOOPS);
+
+		let steps = &self.steps;
+		let baseline = self.baseline.get_or_insert_with(|| {
+			steps
+				.iter()
+				.filter_map(|step| match step.item {
+					BuildItem::Unit(unit) => Some((unit, count_units(bot, unit))),
+					BuildItem::Upgrade(_) => None,
+				})
+				.collect()
+		});
+
+		let mut required = FxHashMap::<UnitTypeId, usize>::default();
+		let mut queued = FxHashMap::default();
+		for (index, step) in steps.iter().enumerate() {
+			let done = match step.item {
+				BuildItem::Unit(unit) => {
+					let required = required.entry(unit).or_default();
+					*required += 1;
+					count_units(bot, unit) >= baseline[&unit] + *required
+				}
+				BuildItem::Upgrade(upgrade) => bot.has_upgrade(upgrade) || bot.is_ordered_upgrade(upgrade),
+			};
+			if done || self.pending.contains_key(&index) {
+				continue;
+			}
+
+			self.current = index;
+			let result = match step.supply {
+				Some(supply) if bot.supply_used < supply => {
+					if self.train_workers {
+						train_workers(bot, supply);
+					}
+					if bot.supply_left == 0 {
+						Err(BlockReason::SupplyBlocked)
+					} else {
+						Err(BlockReason::WaitingSupply(supply))
+					}
+				}
+				_ => match step.item {
+					BuildItem::Unit(unit) => start_unit(bot, unit, &mut queued),
+					BuildItem::Upgrade(upgrade) => start_upgrade(bot, upgrade),
+				},
+			};
+			match result {
+				Ok(()) => {
+					self.pending.insert(index, game_loop);
+				}
+				Err(reason) => {
+					return Some(BlockedStep {
+						index,
+						step: *step,
+						reason,
+					})
+				}
+			}
+		}
+		self.current = steps.len();
+		None
+	}
+}
+
+/// Supply provided by unit, used to validate build order without game data.
+fn supply_provided(unit: UnitTypeId) -> u32 {
+	match unit {
+		UnitTypeId::CommandCenter | UnitTypeId::Nexus => 15,
+		UnitTypeId::Hatchery => 6,
+		UnitTypeId::SupplyDepot | UnitTypeId::Pylon | UnitTypeId::Overlord => 8,
+		_ => 0,
+	}
+}
+
+/// Counts complete and ordered units of given type with their tech alias.
+fn count_units(bot: &Bot, unit: UnitTypeId) -> usize {
+	if unit != bot.race_values.gas {
+		return bot.counter().all().tech().count(unit);
+	}
+
+	// Worker keeps the order while building gas, so geysers are counted once
+	let gas_buildings = &bot.units.my.gas_buildings;
+	let ability = bot.game_data.units[&unit].ability;
+	let ordered = bot
+		.units
+		.my
+		.workers
+		.iter()
+		.flat_map(|u| u.orders())
+		.filter(|order| Some(order.ability) == ability)
+		.filter_map(|order| match order.target {
+			Target::Tag(tag) => bot.units.vespene_geysers.get(tag),
+			_ => None,
+		})
+		.filter(|geyser| gas_buildings.iter().all(|g| !g.is_closer(1.0, *geyser)))
+		.map(|geyser| geyser.tag())
+		.collect::<FxHashSet<_>>();
+	gas_buildings.len() + ordered.len()
+}
+
+/// Trains workers from idle townhalls or larva until given supply is reached.
+fn train_workers(bot: &mut Bot, supply: u32) {
+	let worker = bot.race_values.worker;
+	let producers = if bot.race == Race::Zerg {
+		bot.units.my.larvas.clone()
+	} else {
+		bot.units
+			.my
+			.townhalls
+			.iter()
+			.ready()
+			.filter(|u| u.is_almost_idle())
+			.cloned()
+			.collect()
+	};
+	for producer in &producers {
+		if bot.supply_used >= supply || !bot.can_afford(worker, true) {
+			break;
+		}
+		producer.train(worker, false);
+		bot.subtract_resources(worker, true);
+	}
+}
+
+/// Orders given unit from suitable producer and subtracts its cost.
+///
+/// `queued` counts orders given to producers on this step, so reactors get two orders at once.
+pub(crate) fn start_unit(
+	bot: &mut Bot,
+	unit: UnitTypeId,
+	queued: &mut FxHashMap<u64, usize>,
+) -> Result<(), BlockReason> {
+	let producers = ALL_PRODUCERS.get(&unit).ok_or(BlockReason::Unproducible)?;
+	if let Some(&requirement) = bot.version.tech_requirements.get(&unit) {
+		if bot.counter().tech().count(requirement) == 0 {
+			return Err(BlockReason::MissingTech(requirement));
+		}
+	}
+
+	let cost = bot.get_unit_cost(unit);
+	if bot.minerals < cost.minerals || bot.vespene < cost.vespene {
+		return Err(BlockReason::NotEnoughResources);
+	}
+	if (bot.supply_left as f32) < cost.supply {
+		return Err(BlockReason::SupplyBlocked);
+	}
+
+	let main_producer = PRODUCERS[&unit];
+	if main_producer == bot.race_values.worker {
+		return build_structure(bot, unit);
+	}
+
+	let data = &bot.game_data.units[&unit];
+	let is_structure = data.attributes.contains(&Attribute::Structure);
+	let is_addon = matches!(
+		unit,
+		UnitTypeId::BarracksTechLab
+			| UnitTypeId::BarracksReactor
+			| UnitTypeId::FactoryTechLab
+			| UnitTypeId::FactoryReactor
+			| UnitTypeId::StarportTechLab
+			| UnitTypeId::StarportReactor
+	);
+	let require_techlab = data.require_attached;
+
+	let candidates = bot.units.my.all.of_types(producers).ready();
+	if candidates.is_empty() {
+		return Err(BlockReason::NoProducer(main_producer));
+	}
+	let producer = candidates
+		.iter()
+		.filter(|u| {
+			if is_addon {
+				u.is_idle() && !u.has_addon() && !u.is_sleeping()
+			} else if is_structure {
+				// Morphing structures can't train anything at the same time
+				u.is_idle() && !u.is_sleeping()
+			} else {
+				free_slots(u, queued) > 0 && !u.is_sleeping() && (!require_techlab || u.has_techlab())
+			}
+		})
+		.closest(bot.start_location)
+		.ok_or(BlockReason::ProducersBusy(main_producer))?;
+
+	producer.train(unit, false);
+	*queued.entry(producer.tag()).or_default() += 1;
+	// Orders appear in the next observation, so full producer is skipped for the rest of this step
+	if is_addon || is_structure || free_slots(producer, queued) == 0 {
+		producer.sleep(1);
+	}
+	bot.subtract_resources(unit, true);
+	Ok(())
+}
+
+/// Number of units, which producer can start now, reactor allows to train two units at once.
+fn free_slots(producer: &Unit, queued: &FxHashMap<u64, usize>) -> usize {
+	let capacity: usize = if producer.has_reactor() { 2 } else { 1 };
+	let busy = producer
+		.orders()
+		.iter()
+		.filter(|order| order.progress < 0.95)
+		.count();
+	capacity.saturating_sub(busy + queued.get(&producer.tag()).copied().unwrap_or(0))
+}
+
+/// Orders given upgrade from idle researcher and subtracts its cost.
+pub(crate) fn start_upgrade(bot: &mut Bot, upgrade: UpgradeId) -> Result<(), BlockReason> {
+	let researcher = *RESEARCHERS.get(&upgrade).ok_or(BlockReason::Unproducible)?;
+	if !bot.can_afford_upgrade(upgrade) {
+		return Err(BlockReason::NotEnoughResources);
+	}
+
+	let candidates = bot.units.my.structures.of_type(researcher).ready();
+	if candidates.is_empty() {
+		return Err(BlockReason::NoProducer(researcher));
+	}
+	let producer = candidates
+		.iter()
+		.find(|u| u.is_idle() && !u.is_sleeping())
+		.ok_or(BlockReason::ProducersBusy(researcher))?;
+
+	producer.research(upgrade, false);
+	producer.sleep(1);
+	bot.subtract_upgrade_cost(upgrade);
+	Ok(())
+}
+
+fn build_structure(bot: &mut Bot, unit: UnitTypeId) -> Result<(), BlockReason> {
+	let worker_type = bot.race_values.worker;
+	let builders = bot.units.my.workers.filter(|u| {
+		!(u.is_constructing() || u.is_returning() || u.is_carrying_resource() || u.is_sleeping())
+			&& (u.is_idle()
+				|| (u.is_gathering()
+					&& u.target_tag()
+						.map_or(false, |tag| bot.units.mineral_fields.contains_tag(tag))))
+	});
+	if builders.is_empty() {
+		return Err(BlockReason::ProducersBusy(worker_type));
+	}
+
+	if unit == bot.race_values.gas {
+		let geyser = bot
+			.units
+			.my
+			.townhalls
+			.iter()
+			.find_map(|townhall| bot.find_gas_placement(townhall.position()))
+			.ok_or(BlockReason::NoPlacement)?;
+		let builder = builders.closest(&geyser).unwrap();
+		builder.build_gas(geyser.tag(), false);
+		builder.sleep(1);
+	} else {
+		let location = if RACE_VALUES[&bot.race].townhalls.contains(&unit) {
+			bot.get_expansion().map(|exp| exp.loc)
+		} else {
+			let near = if bot.race == Race::Protoss && unit != UnitTypeId::Pylon {
+				bot.units
+					.my
+					.structures
+					.iter()
+					.of_type(UnitTypeId::Pylon)
+					.ready()
+					.closest(bot.start_location)
+					.map(|pylon| pylon.position())
+					.ok_or(BlockReason::MissingTech(UnitTypeId::Pylon))?
+			} else {
+				bot.start_location.towards(bot.game_info.map_center, 8.0)
+			};
+			let options = PlacementOptions {
+				addon: matches!(
+					unit,
+					UnitTypeId::Barracks | UnitTypeId::Factory | UnitTypeId::Starport
+				),
+				..Default::default()
+			};
+			bot.find_placement(unit, near, options)
+		}
+		.ok_or(BlockReason::NoPlacement)?;
+
+		let builder = builders.closest(location).unwrap();
+		builder.build(unit, location, false);
+		builder.sleep(1);
+	}
+
+	bot.subtract_resources(unit, false);
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn blocked(order: &str, race: Race) -> Vec<(usize, BlockReason)> {
+		let order = order.parse::<BuildOrder>().unwrap();
+		match order.validate(race) {
+			Ok(()) => Vec::new(),
+			Err(blocked) => blocked.iter().map(|b| (b.index, b.reason)).collect(),
+		}
+	}
+
+	#[test]
+	fn parses_steps() {
+		assert_eq!(
+			"16 Barracks".parse::<BuildStep>().unwrap(),
+			BuildStep::new(16, UnitTypeId::Barracks)
+		);
+		assert_eq!(
+			" Stimpack ".parse::<BuildStep>().unwrap(),
+			BuildStep::asap(UpgradeId::Stimpack)
+		);
+		for invalid in ["", "16", "16 Barracks Factory", "Barracks 16", "16 Barrack"] {
+			assert!(invalid.parse::<BuildStep>().is_err(), "`{}` is parsed", invalid);
+		}
+	}
+
+	#[test]
+	fn parses_build_order() {
+		let order = "14 SupplyDepot, 16 Barracks\n16 Refinery,,\n 19 OrbitalCommand"
+			.parse::<BuildOrder>()
+			.unwrap();
+		assert_eq!(
+			order.steps(),
+			[
+				BuildStep::new(14, UnitTypeId::SupplyDepot),
+				BuildStep::new(16, UnitTypeId::Barracks),
+				BuildStep::new(16, UnitTypeId::Refinery),
+				BuildStep::new(19, UnitTypeId::OrbitalCommand),
+			]
+		);
+		assert!("".parse::<BuildOrder>().unwrap().is_complete());
+		assert!("14 SupplyDepot, 16 Barack".parse::<BuildOrder>().is_err());
+	}
+
+	#[test]
+	fn validates_build_order() {
+		let order = "14 SupplyDepot, 16 Barracks, 16 Refinery, BarracksTechLab, Stimpack, 19 OrbitalCommand";
+		assert_eq!(blocked(order, Race::Terran), []);
+		assert_eq!(
+			blocked("13 Overlord, 17 SpawningPool, 17 Hatchery, Queen", Race::Zerg),
+			[]
+		);
+		assert_eq!(
+			blocked("14 Pylon, 16 Gateway, Stalker", Race::Protoss),
+			[(2, BlockReason::MissingTech(UnitTypeId::CyberneticsCore))]
+		);
+	}
+
+	#[test]
+	fn reports_blocked_steps() {
+		assert_eq!(
+			blocked("BarracksTechLab, 14 Barracks, Stimpack", Race::Terran),
+			[
+				(0, BlockReason::NoProducer(UnitTypeId::Barracks)),
+				(1, BlockReason::MissingTech(UnitTypeId::SupplyDepot)),
+			]
+		);
+		assert_eq!(
+			blocked("Zergling, SpawningPool, 20 Zergling", Race::Zerg),
+			[
+				(0, BlockReason::MissingTech(UnitTypeId::SpawningPool)),
+				(2, BlockReason::SupplyBlocked),
+			]
+		);
+		assert_eq!(
+			blocked("MineralField", Race::Terran),
+			[(0, BlockReason::Unproducible)]
+		);
+	}
+}
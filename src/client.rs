@@ -3,33 +3,41 @@
 //! Contains Runner structures for verbose configuration and multiple games,
 //! and simple runner functions for playing once.
 
+#[cfg(any(test, feature = "mock"))]
+use crate::mock::MockServer;
 use crate::{
-	api::API,
+	action::Action,
+	api::{read_recording, API},
 	bot::{Bot, LockOwned, Rs},
+	error::{Error, ProtoError},
 	game_state::update_state,
 	paths::*,
-	player::Computer,
-	IntoProto, IntoSC2, Player, PlayerSettings,
+	player::{Computer, GameResult},
+	version::{self, VersionData},
+	Event, FromProto, IntoProto, IntoSC2, Player, PlayerSettings, ReplayObserver,
+};
+use sc2_proto::sc2api::{
+	InterfaceOptions, PlayerSetup, PlayerType, PortSet, Request, RequestCreateGame, Response,
+	ResponseReplayInfo, Status,
 };
-use sc2_proto::sc2api::{PlayerSetup, PlayerType, PortSet, Request, RequestCreateGame, Status};
 use std::{
-	error::Error,
-	fmt,
 	fs::File,
-	io::Write,
+	io::{self, Write},
 	net::{TcpListener, TcpStream},
 	ops::{Deref, DerefMut},
+	path::Path,
 	process::{Child, Command},
+	time::{Duration, Instant},
 };
 use tungstenite::{connect, stream::MaybeTlsStream, WebSocket};
 
 pub(crate) type WS = WebSocket<MaybeTlsStream<TcpStream>>;
-pub type SC2Result<T> = Result<T, Box<dyn Error>>;
+pub type SC2Result<T> = Result<T, Error>;
 
 #[cfg(all(feature = "wine_sc2", not(target_os = "linux")))]
 compile_error!("Wine is only supported on linux");
 
-const HOST: &str = "127.0.0.1";
+pub(crate) const HOST: &str = "127.0.0.1";
 const SC2_BINARY: &str = {
 	#[cfg(any(target_os = "windows", feature = "wine_sc2"))]
 	{
@@ -77,11 +85,15 @@ where
 	sc2_version: Option<&'a str>,
 	/// Computer opponent configuration.
 	pub computer: Computer,
-	map_path: String,
+	map_name: String,
 	/// Play games in real time mode or not.
 	pub realtime: bool,
 	/// Save replay after the game in given path.
 	pub save_replay_as: Option<&'a str>,
+	/// Record all requests and responses of the game in given path.
+	/// See [`start_recording`](API::start_recording).
+	pub record_as: Option<&'a str>,
+	last_result: Option<GameResult>,
 }
 
 impl<'a, B> RunnerSingle<'a, B>
@@ -92,24 +104,25 @@ where
 	pub fn new(bot: &'a mut B, computer: Computer, map: &str, sc2_version: Option<&'a str>) -> Self {
 		debug!("Starting game vs computer");
 		let sc2_path = get_path_to_sc2();
-		let map_path = get_map_path(&sc2_path, map);
 
 		Self {
 			bot,
 			sc2_path,
 			sc2_version,
 			computer,
-			map_path,
+			map_name: map.to_string(),
 			save_replay_as: None,
+			record_as: None,
 			realtime: false,
+			last_result: None,
 		}
 	}
 
 	/// Launches SC2 client and connects bot to the API.
 	pub fn launch(&mut self) -> SC2Result<()> {
-		let port = get_unused_port();
+		let port = get_unused_port()?;
 		debug!("Launching SC2 process");
-		self.bot.process = Some(launch_client(&self.sc2_path, port, self.sc2_version));
+		self.bot.process = Some(launch_client(&self.sc2_path, port, self.sc2_version)?);
 		debug!("Connecting to websocket");
 		self.bot.api = Some(API::new(connect_to_websocket(HOST, port)?));
 		Ok(())
@@ -117,8 +130,23 @@ where
 
 	/// Runs requested game.
 	pub fn run_game(&mut self) -> SC2Result<()> {
+		self.start_game()?;
+
+		debug!("Entered main loop");
+		self.last_result = Some(play_game(self.bot, self.realtime)?);
+		debug!("Game finished");
+
+		self.finish_game()
+	}
+
+	/// Creates new game, joins it and requests static data, the game isn't stepped.
+	pub(crate) fn start_game(&mut self) -> SC2Result<()> {
+		self.last_result = None;
 		let settings = self.bot.get_player_settings();
-		let api = self.bot.api();
+		let api = self.bot.api()?;
+		if let Some(path) = self.record_as {
+			api.start_recording(path)?;
+		}
 
 		debug!("Sending CreateGame request");
 		let mut req = Request::new();
@@ -126,7 +154,7 @@ where
 
 		req_create_game
 			.mut_local_map()
-			.set_map_path(self.map_path.clone());
+			.set_map_path(get_map_path(&self.sc2_path, &self.map_name)?);
 		create_player_setup(&settings, req_create_game);
 		create_computer_setup(&self.computer, req_create_game);
 
@@ -135,41 +163,48 @@ where
 		let res = api.send(req)?;
 		let res_create_game = res.get_create_game();
 		if res_create_game.has_error() {
-			let err = format!(
-				"{:?}: {}",
-				res_create_game.get_error(),
-				res_create_game.get_error_details()
-			);
+			let err = ProtoError::new(res_create_game.get_error(), res_create_game.get_error_details());
 			error!("{}", err);
-			panic!("{}", err);
+			return Err(err.into());
 		}
 
 		debug!("Sending JoinGame request");
 		let player_id = join_game(&settings, api, None)?;
 		self.bot.player_id = player_id;
 
-		set_static_data(self.bot)?;
-
-		debug!("Entered main loop");
-		play_first_step(self.bot, self.realtime)?;
-		let mut iteration = 0;
-		while play_step(self.bot, iteration, self.realtime)? {
-			iteration += 1;
+		set_static_data(self.bot)
+	}
+	/// Stops recording and saves replay of the finished game.
+	pub(crate) fn finish_game(&mut self) -> SC2Result<()> {
+		if self.record_as.is_some() {
+			self.bot.api()?.stop_recording()?;
 		}
-		debug!("Game finished");
-
 		if let Some(path) = &self.save_replay_as {
-			save_replay(self.bot.api(), path)?;
+			save_replay(self.bot.api()?, path)?;
 		}
 		Ok(())
 	}
 
+	/// Returns result of the last finished game for bot.
+	pub fn last_result(&self) -> Option<GameResult> {
+		self.last_result
+	}
+	/// Returns the bot playing games.
+	pub fn bot(&self) -> &B {
+		self.bot
+	}
+	pub(crate) fn bot_mut(&mut self) -> &mut B {
+		self.bot
+	}
+	pub(crate) fn set_last_result(&mut self, result: GameResult) {
+		self.last_result = Some(result);
+	}
+
 	/// Changes map to play on.
 	///
-	/// # Panics
-	/// Panics if the map doesn't exist in maps directory.
+	/// Error is returned when the game is started if the map doesn't exist in maps directory.
 	pub fn set_map(&mut self, map: &str) {
-		self.map_path = get_map_path(&self.sc2_path, map);
+		self.map_name = map.to_string();
 	}
 
 	/// Manually closes SC2 client.
@@ -189,11 +224,14 @@ where
 	sc2_version: Option<&'a str>,
 	/// Configuration of human opponent.
 	pub human_settings: PlayerSettings<'a>,
-	map_path: String,
+	map_name: String,
 	/// Play games in real time mode or not.
 	pub realtime: bool,
 	/// Save replay after the game in given path.
 	pub save_replay_as: Option<&'a str>,
+	/// Record all requests and responses of the game in given path.
+	/// See [`start_recording`](API::start_recording).
+	pub record_as: Option<&'a str>,
 }
 
 impl<'a, B> RunnerMulti<'a, B>
@@ -209,7 +247,6 @@ where
 	) -> Self {
 		debug!("Starting human vs bot");
 		let sc2_path = get_path_to_sc2();
-		let map_path = get_map_path(&sc2_path, map);
 
 		Self {
 			bot,
@@ -217,8 +254,9 @@ where
 			sc2_path,
 			sc2_version,
 			human_settings,
-			map_path,
+			map_name: map.to_string(),
 			save_replay_as: None,
+			record_as: None,
 			realtime: false,
 		}
 	}
@@ -226,13 +264,13 @@ where
 	/// Launches SC2 clients and connects bot to the API.
 	pub fn launch(&mut self) -> SC2Result<()> {
 		// let (port_bot, port_human) = (PORT, PORT + 1);
-		let ports = get_unused_ports(2);
+		let ports = get_unused_ports(2)?;
 		let (port_bot, port_human) = (ports[0], ports[1]);
 
 		debug!("Launching host SC2 process");
-		self.human.process = Some(launch_client(&self.sc2_path, port_human, self.sc2_version));
+		self.human.process = Some(launch_client(&self.sc2_path, port_human, self.sc2_version)?);
 		debug!("Launching client SC2 process");
-		self.bot.process = Some(launch_client(&self.sc2_path, port_bot, self.sc2_version));
+		self.bot.process = Some(launch_client(&self.sc2_path, port_bot, self.sc2_version)?);
 
 		debug!("Connecting to host websocket");
 		self.human.api = Some(API::new(connect_to_websocket(HOST, port_human)?));
@@ -246,6 +284,9 @@ where
 	pub fn run_game(&mut self) -> SC2Result<()> {
 		let bot_settings = self.bot.get_player_settings();
 		let human_api = self.human.api.as_ref().unwrap();
+		if let Some(path) = self.record_as {
+			self.bot.api()?.start_recording(path)?;
+		}
 
 		debug!("Sending CreateGame request to host process");
 		let mut req = Request::new();
@@ -253,7 +294,7 @@ where
 
 		req_create_game
 			.mut_local_map()
-			.set_map_path(self.map_path.clone());
+			.set_map_path(get_map_path(&self.sc2_path, &self.map_name)?);
 		create_player_setup(&self.human_settings, req_create_game);
 		create_player_setup(&bot_settings, req_create_game);
 		req_create_game.set_realtime(self.realtime);
@@ -261,13 +302,9 @@ where
 		let res = human_api.send(req)?;
 		let res_create_game = res.get_create_game();
 		if res_create_game.has_error() {
-			let err = format!(
-				"{:?}: {}",
-				res_create_game.get_error(),
-				res_create_game.get_error_details()
-			);
+			let err = ProtoError::new(res_create_game.get_error(), res_create_game.get_error_details());
 			error!("{}", err);
-			panic!("{}", err);
+			return Err(err.into());
 		}
 
 		debug!("Sending JoinGame request to both processes");
@@ -276,38 +313,37 @@ where
 			server: (PORT + 3, PORT + 4),
 			client: vec![(PORT + 5, PORT + 6), (PORT + 7, PORT + 8)],
 		};*/
-		let ports = get_unused_ports(6);
+		let ports = get_unused_ports(6)?;
 		let ports = Ports {
 			server: (ports[0], ports[1]),
 			client: vec![(ports[2], ports[3]), (ports[4], ports[5])],
 		};
 		join_game2(&self.human_settings, human_api, Some(&ports))?;
-		join_game2(&bot_settings, self.bot.api(), Some(&ports))?;
+		join_game2(&bot_settings, self.bot.api()?, Some(&ports))?;
 		let _ = wait_join(human_api)?;
-		let player_id = wait_join(self.bot.api())?;
+		let player_id = wait_join(self.bot.api()?)?;
 		self.bot.player_id = player_id;
 
 		set_static_data(self.bot)?;
 
 		debug!("Entered main loop");
-		play_first_step(self.bot, self.realtime)?;
-		let mut iteration = 0;
-		while play_step(self.bot, iteration, self.realtime)? {
-			iteration += 1;
-		}
+		play_game(self.bot, self.realtime)?;
 		debug!("Game finished");
 
+		if self.record_as.is_some() {
+			self.bot.api()?.stop_recording()?;
+		}
 		if let Some(path) = &self.save_replay_as {
-			save_replay(self.bot.api(), path)?;
+			save_replay(self.bot.api()?, path)?;
 		}
 		Ok(())
 	}
 
 	/// Changes map to play on.
-	/// # Panics
-	/// Panics if the map doesn't exist in maps directory.
+	///
+	/// Error is returned when the game is started if the map doesn't exist in maps directory.
 	pub fn set_map(&mut self, map: &str) {
-		self.map_path = get_map_path(&self.sc2_path, map);
+		self.map_name = map.to_string();
 	}
 
 	/// Manually closes SC2 clients.
@@ -317,6 +353,461 @@ where
 	}
 }
 
+/// Runner for games between two bots in a single process.
+///
+/// Each bot gets its own SC2 client, bots are stepped in lockstep:
+/// requests of both bots are sent first and responses are received after that,
+/// as in [`pipelined`](PlayerSettings::pipelined) mode.
+pub struct RunnerBotVsBot<'a, B1, B2>
+where
+	B1: Player + DerefMut<Target = Bot> + Deref<Target = Bot>,
+	B2: Player + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	bot1: &'a mut B1,
+	bot2: &'a mut B2,
+	sc2_path: String,
+	sc2_version: Option<&'a str>,
+	map_name: String,
+	/// Play games in real time mode or not.
+	pub realtime: bool,
+	/// Save replay after the game in given path (replay is saved from the first bot's client).
+	pub save_replay_as: Option<&'a str>,
+	/// Record all requests and responses of both bots,
+	/// see [`recording_path`] for paths of their recordings.
+	pub record_as: Option<&'a str>,
+}
+
+impl<'a, B1, B2> RunnerBotVsBot<'a, B1, B2>
+where
+	B1: Player + DerefMut<Target = Bot> + Deref<Target = Bot>,
+	B2: Player + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	/// Constructs new bot vs bot runner.
+	pub fn new(bot1: &'a mut B1, bot2: &'a mut B2, map: &str, sc2_version: Option<&'a str>) -> Self {
+		debug!("Starting bot vs bot");
+		let sc2_path = get_path_to_sc2();
+
+		Self {
+			bot1,
+			bot2,
+			sc2_path,
+			sc2_version,
+			map_name: map.to_string(),
+			save_replay_as: None,
+			record_as: None,
+			realtime: false,
+		}
+	}
+
+	/// Launches SC2 clients and connects bots to the API.
+	pub fn launch(&mut self) -> SC2Result<()> {
+		let ports = get_unused_ports(2)?;
+		let (port1, port2) = (ports[0], ports[1]);
+
+		debug!("Launching host SC2 process");
+		self.bot1.process = Some(launch_client(&self.sc2_path, port1, self.sc2_version)?);
+		debug!("Launching client SC2 process");
+		self.bot2.process = Some(launch_client(&self.sc2_path, port2, self.sc2_version)?);
+
+		debug!("Connecting to host websocket");
+		self.bot1.api = Some(API::new(connect_to_websocket(HOST, port1)?));
+		debug!("Connecting to client websocket");
+		self.bot2.api = Some(API::new(connect_to_websocket(HOST, port2)?));
+
+		Ok(())
+	}
+
+	/// Runs requested game and returns results of both bots.
+	pub fn run_game(&mut self) -> SC2Result<(GameResult, GameResult)> {
+		let settings1 = self.bot1.get_player_settings();
+		let settings2 = self.bot2.get_player_settings();
+		self.start_recording()?;
+
+		debug!("Sending CreateGame request to host process");
+		let mut req = Request::new();
+		let req_create_game = req.mut_create_game();
+
+		req_create_game
+			.mut_local_map()
+			.set_map_path(get_map_path(&self.sc2_path, &self.map_name)?);
+		create_player_setup(&settings1, req_create_game);
+		create_player_setup(&settings2, req_create_game);
+		req_create_game.set_realtime(self.realtime);
+
+		let res = self.bot1.api()?.send(req)?;
+		let res_create_game = res.get_create_game();
+		if res_create_game.has_error() {
+			let err = ProtoError::new(res_create_game.get_error(), res_create_game.get_error_details());
+			error!("{}", err);
+			return Err(err.into());
+		}
+
+		let ports = get_unused_ports(6)?;
+		let ports = Ports {
+			server: (ports[0], ports[1]),
+			client: vec![(ports[2], ports[3]), (ports[4], ports[5])],
+		};
+		let results = self.join_and_play(Some(&ports))?;
+
+		if let Some(path) = &self.save_replay_as {
+			save_replay(self.bot1.api()?, path)?;
+		}
+		Ok(results)
+	}
+
+	fn start_recording(&self) -> SC2Result<()> {
+		if let Some(path) = self.record_as {
+			self.bot1.api()?.start_recording(recording_path(path, 1))?;
+			self.bot2.api()?.start_recording(recording_path(path, 2))?;
+		}
+		Ok(())
+	}
+
+	/// Joins both bots to the created game and plays it.
+	fn join_and_play(&mut self, ports: Option<&Ports>) -> SC2Result<(GameResult, GameResult)> {
+		debug!("Sending JoinGame request to both processes");
+		join_game2(&self.bot1.get_player_settings(), self.bot1.api()?, ports)?;
+		join_game2(&self.bot2.get_player_settings(), self.bot2.api()?, ports)?;
+		self.bot1.player_id = wait_join(self.bot1.api()?)?;
+		self.bot2.player_id = wait_join(self.bot2.api()?)?;
+
+		set_static_data(self.bot1)?;
+		set_static_data(self.bot2)?;
+
+		debug!("Entered main loop");
+		let results = self.play_game()?;
+		debug!("Game finished");
+
+		if self.record_as.is_some() {
+			self.bot1.api()?.stop_recording()?;
+			self.bot2.api()?.stop_recording()?;
+		}
+		Ok(results)
+	}
+
+	/// Steps both bots until the game is ended for each of them.
+	fn play_game(&mut self) -> SC2Result<(GameResult, GameResult)> {
+		let mut game1 = GameLoop::new(self.realtime);
+		let mut game2 = GameLoop::new(self.realtime);
+		let (mut result1, mut result2) = (None, None);
+
+		let mut requests1 = vec![GameLoop::first_observation_request()];
+		let mut requests2 = vec![GameLoop::first_observation_request()];
+		loop {
+			let (res1, res2) = send_lockstep(self.bot1.api()?, requests1, self.bot2.api()?, requests2)?;
+			if let Some(res) = res1 {
+				result1 = game1.step(self.bot1, &res)?;
+			}
+			if let Some(res) = res2 {
+				result2 = game2.step(self.bot2, &res)?;
+			}
+
+			if let (Some(result1), Some(result2)) = (result1, result2) {
+				return Ok((result1, result2));
+			}
+			// Bot which game has ended just waits for the other one
+			requests1 = match result1 {
+				Some(_) => Vec::new(),
+				None => game1.requests(self.bot1),
+			};
+			requests2 = match result2 {
+				Some(_) => Vec::new(),
+				None => game2.requests(self.bot2),
+			};
+		}
+	}
+
+	/// Changes map to play on.
+	///
+	/// Error is returned when the game is started if the map doesn't exist in maps directory.
+	pub fn set_map(&mut self, map: &str) {
+		self.map_name = map.to_string();
+	}
+
+	/// Manually closes SC2 clients.
+	pub fn close(&mut self) {
+		self.bot1.close_client();
+		self.bot2.close_client();
+	}
+}
+
+/// Sends requests of both bots, then waits for all responses.
+/// Returns responses on the last requests (i.e. observations), if any requests were sent.
+///
+/// Responses of both bots are received even if some of them are errors,
+/// so clients stay in sync when the first error is returned.
+fn send_lockstep(
+	api1: &API,
+	requests1: Vec<Request>,
+	api2: &API,
+	requests2: Vec<Request>,
+) -> SC2Result<(Option<Response>, Option<Response>)> {
+	let (n1, n2) = (requests1.len(), requests2.len());
+	for req in requests1 {
+		api1.send_only(req)?;
+	}
+	for req in requests2 {
+		api2.send_only(req)?;
+	}
+
+	let res1 = wait_last_response(api1, n1);
+	let res2 = wait_last_response(api2, n2);
+	Ok((res1?, res2?))
+}
+/// Receives `n` responses and returns the last one or the first error.
+fn wait_last_response(api: &API, n: usize) -> SC2Result<Option<Response>> {
+	let responses = (0..n).map(|_| api.wait_response()).collect::<Vec<_>>();
+	Ok(responses.into_iter().collect::<SC2Result<Vec<_>>>()?.pop())
+}
+
+/// Returns path of the recording of given bot (`1` or `2`) in bot vs bot game
+/// recorded with [`record_as`](RunnerBotVsBot::record_as).
+///
+/// Number of the bot is added to the file name (e.g. `game.1.bin` and `game.2.bin` for `game.bin`),
+/// each recording can be played again with [`RunnerReplayStream`].
+pub fn recording_path(record_as: &str, bot: u32) -> String {
+	let path = Path::new(record_as);
+	let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+	let name = match path.extension() {
+		Some(ext) => format!("{}.{}.{}", stem, bot, ext.to_string_lossy()),
+		None => format!("{}.{}", stem, bot),
+	};
+	path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+/// Runner which plays game recorded with [`record_as`](RunnerSingle::record_as) again,
+/// without launching SC2.
+///
+/// Recorded observations are passed through the same state update as in real game,
+/// so bot's decisions can be compared with the recorded ones to catch regressions.
+/// Recorded query answers are reused, so bot should make the same queries as when it was recorded.
+pub struct RunnerReplayStream<'a, B>
+where
+	B: Player + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	bot: &'a mut B,
+	path: String,
+}
+
+impl<'a, B> RunnerReplayStream<'a, B>
+where
+	B: Player + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	/// Constructs new runner for recording in given path.
+	pub fn new(bot: &'a mut B, path: &str) -> Self {
+		Self {
+			bot,
+			path: path.to_string(),
+		}
+	}
+
+	/// Plays recorded game and returns actions made by bot, stored in (game loop, actions) pairs.
+	pub fn run_game(&mut self) -> SC2Result<Vec<(u32, Vec<Action>)>> {
+		debug!("Starting recorded game");
+		self.bot.api = Some(API::playback(&self.path)?);
+
+		let player_id = join_game(&self.bot.get_player_settings(), self.bot.api()?, None)?;
+		self.bot.player_id = player_id;
+
+		set_static_data(self.bot)?;
+
+		debug!("Entered main loop");
+		play_game(self.bot, false)?;
+		debug!("Game finished");
+
+		let actions = decode_actions(self.bot.api()?.playback_actions(), &self.bot.version);
+		self.bot.api = None;
+		Ok(actions)
+	}
+
+	/// Returns actions made by bot when the game was recorded, stored in (game loop, actions) pairs.
+	pub fn recorded_actions(&self) -> SC2Result<Vec<(u32, Vec<Action>)>> {
+		let mut game_loop = 0;
+		let mut actions = Vec::new();
+		let mut version = version::latest();
+		for (req, res) in read_recording(&self.path)? {
+			if req.has_ping() {
+				version = version::select(res.get_ping().get_base_build());
+			} else if req.has_observation() {
+				game_loop = res.get_observation().get_observation().get_game_loop();
+			} else if req.has_action() {
+				actions.push((game_loop, req));
+			}
+		}
+		Ok(decode_actions(actions, version))
+	}
+}
+
+/// Runner which observes replays from perspective of one player.
+///
+/// Replays are played on SC2 version they were recorded on if it's installed,
+/// otherwise on the latest one.
+/// Observer's [`Bot`] is updated with replay observations the same way as in games,
+/// so `units`, `state` and `game_info` can be used to analyze the replay.
+pub struct ReplayRunner<'a, B>
+where
+	B: ReplayObserver + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	bot: &'a mut B,
+	sc2_path: String,
+	/// Base build of running SC2 client.
+	base_build: Option<u32>,
+	/// Id of the player to observe replay from.
+	pub observed_player: u32,
+	/// Observe replay without fog of war.
+	pub disable_fog: bool,
+	/// Crop raw data to playable area of the map.
+	pub raw_crop_to_playable_area: bool,
+	/// Play replays in real time mode or not.
+	pub realtime: bool,
+}
+
+impl<'a, B> ReplayRunner<'a, B>
+where
+	B: ReplayObserver + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	/// Constructs new replay runner, which observes the first player.
+	pub fn new(bot: &'a mut B) -> Self {
+		Self {
+			bot,
+			sc2_path: get_path_to_sc2(),
+			base_build: None,
+			observed_player: 1,
+			disable_fog: false,
+			raw_crop_to_playable_area: false,
+			realtime: false,
+		}
+	}
+
+	fn launch(&mut self, base_build: u32, data_hash: &str) -> SC2Result<()> {
+		self.close();
+		let port = get_unused_port()?;
+		debug!("Launching SC2 process with base build {}", base_build);
+		self.bot.process = Some(launch_client_build(&self.sc2_path, port, base_build, data_hash)?);
+		debug!("Connecting to websocket");
+		self.bot.api = Some(API::new(connect_to_websocket(HOST, port)?));
+		self.base_build = Some(base_build);
+		Ok(())
+	}
+
+	/// Observes replay in given path and returns result of the observed player.
+	///
+	/// Returns [`Error::Io`](crate::Error::Io) if the replay doesn't exist.
+	pub fn run_replay(&mut self, path: &str) -> SC2Result<GameResult> {
+		let replay_path = get_replay_path(path)?;
+		if self.base_build.is_none() {
+			self.launch(get_latest_base_version(&self.sc2_path), "")?;
+		}
+
+		let res_replay_info = replay_info(self.bot.api()?, &replay_path)?;
+		let base_build = res_replay_info.get_base_build();
+		if self.base_build != Some(base_build) {
+			if has_base_version(&self.sc2_path, base_build) {
+				self.launch(base_build, res_replay_info.get_data_version())?;
+			} else {
+				warn!(
+					"SC2 version with base build {} isn't installed, replay may fail to play",
+					base_build
+				);
+			}
+		}
+
+		debug!("Sending StartReplay request");
+		let mut req = Request::new();
+		let req_start_replay = req.mut_start_replay();
+		req_start_replay.set_replay_path(replay_path);
+		req_start_replay.set_observed_player_id(self.observed_player as i32);
+		req_start_replay.set_options(interface_options(false, self.raw_crop_to_playable_area));
+		req_start_replay.set_disable_fog(self.disable_fog);
+		req_start_replay.set_realtime(self.realtime);
+
+		start_replay(self.bot.api()?, req)?;
+		self.bot.player_id = self.observed_player;
+
+		set_static_data(self.bot)?;
+
+		debug!("Entered replay loop");
+		let result = play_replay(self.bot, self.realtime)?;
+		debug!("Replay finished");
+		Ok(result)
+	}
+
+	/// Manually closes SC2 client.
+	pub fn close(&mut self) {
+		self.bot.close_client();
+		self.base_build = None;
+	}
+}
+
+/// Requests info about replay in given path.
+pub(crate) fn replay_info(api: &API, replay_path: &str) -> SC2Result<ResponseReplayInfo> {
+	debug!("Requesting ReplayInfo");
+	let mut req = Request::new();
+	req.mut_replay_info().set_replay_path(replay_path.to_string());
+	let mut res = api.send(req)?;
+	let res_replay_info = res.take_replay_info();
+	if res_replay_info.has_error() {
+		let err = ProtoError::new(res_replay_info.get_error(), res_replay_info.get_error_details());
+		error!("{}", err);
+		return Err(err.into());
+	}
+	Ok(res_replay_info)
+}
+pub(crate) fn start_replay(api: &API, req: Request) -> SC2Result<()> {
+	let res = api.send(req)?;
+	let res_start_replay = res.get_start_replay();
+	if res_start_replay.has_error() {
+		let err = ProtoError::new(res_start_replay.get_error(), res_start_replay.get_error_details());
+		error!("{}", err);
+		return Err(err.into());
+	}
+	Ok(())
+}
+
+/// Observes replay until it's ended and returns result of the observed player.
+///
+/// Observers can't act, so actions and debug commands made by them are dropped.
+fn play_replay<B>(bot: &mut B, realtime: bool) -> SC2Result<GameResult>
+where
+	B: ReplayObserver + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	let mut game = GameLoop::new(realtime);
+	let mut res = bot.api()?.send(GameLoop::first_observation_request())?;
+	loop {
+		if let Some(result) = game.observe(bot, &res)? {
+			return Ok(result);
+		}
+
+		// Commands are taken from units before clearing, so they aren't sent on the next step
+		bot.get_actions();
+		bot.clear_actions();
+		bot.get_debug_commands();
+		bot.clear_debug_commands();
+		let mut requests = game.requests(bot);
+		let req = requests.pop().ok_or_else(no_observation_request)?;
+		for req in requests {
+			bot.api()?.send_request(req)?;
+		}
+		res = bot.api()?.send(req)?;
+	}
+}
+
+fn decode_actions(requests: Vec<(u32, Request)>, version: &VersionData) -> Vec<(u32, Vec<Action>)> {
+	requests
+		.into_iter()
+		.map(|(game_loop, req)| {
+			(
+				game_loop,
+				req.get_action()
+					.get_actions()
+					.iter()
+					.filter_map(|action| Option::<Action>::from_proto((action, version)))
+					.collect(),
+			)
+		})
+		.collect()
+}
+
 #[derive(Default)]
 struct Human {
 	process: Option<Child>,
@@ -351,27 +842,13 @@ impl Drop for Human {
 	}
 }
 
-#[derive(Debug)]
-struct ProtoError(String);
-impl ProtoError {
-	fn new<E: fmt::Debug>(error: E, details: &str) -> Self {
-		Self(format!("{:?}: {}", error, details))
-	}
-}
-impl fmt::Display for ProtoError {
-	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-		write!(f, "{}", self.0)
-	}
-}
-impl Error for ProtoError {}
-
-struct Ports {
+pub(crate) struct Ports {
 	// shared: i32,
-	server: (i32, i32),
-	client: Vec<(i32, i32)>,
+	pub(crate) server: (i32, i32),
+	pub(crate) client: Vec<(i32, i32)>,
 }
 
-/// Additional launch options for [`run_vs_computer`] and [`run_vs_human`].
+/// Additional launch options for [`run_vs_computer`], [`run_vs_human`] and [`run_bot_vs_bot`].
 #[derive(Default)]
 pub struct LaunchOptions<'a> {
 	/// SC2 version to play on, otherwise latest available will be used.
@@ -380,6 +857,9 @@ pub struct LaunchOptions<'a> {
 	pub save_replay_as: Option<&'a str>,
 	/// Play games in real time mode or not.
 	pub realtime: bool,
+	/// Record all requests and responses of the game in given path.
+	/// In bot vs bot games each bot is recorded to its own file, see [`recording_path`].
+	pub record_as: Option<&'a str>,
 }
 
 // Runners
@@ -398,6 +878,7 @@ where
 	runner.launch()?;
 	runner.realtime = options.realtime;
 	runner.save_replay_as = options.save_replay_as;
+	runner.record_as = options.record_as;
 	runner.run_game()?;
 	Ok(())
 }
@@ -426,7 +907,7 @@ where
 
 	let player_id = join_game(
 		&bot.get_player_settings(),
-		bot.api(),
+		bot.api()?,
 		Some(&Ports {
 			// shared: player_port + 1,
 			server: (player_port + 2, player_port + 3),
@@ -439,16 +920,113 @@ where
 
 	debug!("Entered main loop");
 	// Main loop
-	let mut iteration = 0;
-	play_first_step(bot, false)?;
-	while play_step(bot, iteration, false)? {
-		iteration += 1;
+	play_game(bot, false)?;
+	debug!("Game finished");
+
+	Ok(())
+}
+
+/// Runs game on [`MockServer`] instead of SC2 client, useful for testing bots without the game.
+///
+/// Client is closed after the game or when it fails, so server stops when this function returns.
+/// Available with `"mock"` feature.
+#[cfg(any(test, feature = "mock"))]
+pub fn run_mock_game<B>(bot: &mut B, server: &MockServer) -> SC2Result<()>
+where
+	B: Player + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	run_mock(bot, server, None)
+}
+/// Runs game on [`MockServer`] as [`run_mock_game`] and records it to a file in given path,
+/// so it can be played again with [`RunnerReplayStream`].
+#[cfg(any(test, feature = "mock"))]
+pub fn record_mock_game<B>(bot: &mut B, server: &MockServer, record_as: &str) -> SC2Result<()>
+where
+	B: Player + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	run_mock(bot, server, Some(record_as))
+}
+#[cfg(any(test, feature = "mock"))]
+fn run_mock<B>(bot: &mut B, server: &MockServer, record_as: Option<&str>) -> SC2Result<()>
+where
+	B: Player + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	debug!("Starting mock game");
+
+	debug!("Connecting to websocket");
+	bot.api = Some(API::new(connect_to_websocket(HOST, server.port() as i32)?));
+
+	// Client is closed even if bot fails, otherwise server waits for requests forever
+	let result = play_mock_game(bot, record_as);
+	bot.close_client();
+	bot.api = None;
+	result
+}
+#[cfg(any(test, feature = "mock"))]
+fn play_mock_game<B>(bot: &mut B, record_as: Option<&str>) -> SC2Result<()>
+where
+	B: Player + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	if let Some(path) = record_as {
+		bot.api()?.start_recording(path)?;
 	}
+
+	debug!("Sending JoinGame request");
+	let player_id = join_game(&bot.get_player_settings(), bot.api()?, None)?;
+	bot.player_id = player_id;
+
+	set_static_data(bot)?;
+
+	debug!("Entered main loop");
+	play_game(bot, false)?;
 	debug!("Game finished");
 
+	if record_as.is_some() {
+		bot.api()?.stop_recording()?;
+	}
 	Ok(())
 }
 
+/// Runs replay observer on [`MockServer`] instead of SC2 client,
+/// observations of the server are used as replay ones.
+///
+/// Client is closed after the replay or when it fails, so server stops when this function returns.
+#[cfg(any(test, feature = "mock"))]
+pub fn run_mock_replay<B>(bot: &mut B, server: &MockServer, observed_player: u32) -> SC2Result<GameResult>
+where
+	B: ReplayObserver + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	debug!("Starting mock replay");
+
+	debug!("Connecting to websocket");
+	bot.api = Some(API::new(connect_to_websocket(HOST, server.port() as i32)?));
+
+	// Client is closed even if observer fails, otherwise server waits for requests forever
+	let result = play_mock_replay(bot, observed_player);
+	bot.close_client();
+	bot.api = None;
+	result
+}
+#[cfg(any(test, feature = "mock"))]
+fn play_mock_replay<B>(bot: &mut B, observed_player: u32) -> SC2Result<GameResult>
+where
+	B: ReplayObserver + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	debug!("Sending StartReplay request");
+	let mut req = Request::new();
+	req.mut_start_replay()
+		.set_observed_player_id(observed_player as i32);
+	start_replay(bot.api()?, req)?;
+	bot.player_id = observed_player;
+
+	set_static_data(bot)?;
+
+	debug!("Entered replay loop");
+	let result = play_replay(bot, false)?;
+	debug!("Replay finished");
+	Ok(result)
+}
+
 /// Simple function to run game vs human.
 pub fn run_vs_human<B>(
 	bot: &mut B,
@@ -463,33 +1041,100 @@ where
 	runner.launch()?;
 	runner.realtime = options.realtime;
 	runner.save_replay_as = options.save_replay_as;
+	runner.record_as = options.record_as;
 	runner.run_game()?;
 	Ok(())
 }
 
+/// Simple function to run game between two bots, returns results of both bots.
+pub fn run_bot_vs_bot<B1, B2>(
+	bot1: &mut B1,
+	bot2: &mut B2,
+	map_name: &str,
+	options: LaunchOptions,
+) -> SC2Result<(GameResult, GameResult)>
+where
+	B1: Player + DerefMut<Target = Bot> + Deref<Target = Bot>,
+	B2: Player + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	let mut runner = RunnerBotVsBot::new(bot1, bot2, map_name, options.sc2_version);
+	runner.launch()?;
+	runner.realtime = options.realtime;
+	runner.save_replay_as = options.save_replay_as;
+	runner.record_as = options.record_as;
+	runner.run_game()
+}
+
+/// Runs game between two bots on [`MockServer`]s instead of SC2 clients, one server for each bot.
+///
+/// Game is recorded if `record_as` is given, as with [`record_as`](RunnerBotVsBot::record_as).
+/// Clients are closed after the game or when it fails, so servers stop when this function returns.
+#[cfg(any(test, feature = "mock"))]
+pub fn run_mock_bot_vs_bot<B1, B2>(
+	bot1: &mut B1,
+	bot2: &mut B2,
+	server1: &MockServer,
+	server2: &MockServer,
+	record_as: Option<&str>,
+) -> SC2Result<(GameResult, GameResult)>
+where
+	B1: Player + DerefMut<Target = Bot> + Deref<Target = Bot>,
+	B2: Player + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	debug!("Starting mock bot vs bot");
+
+	debug!("Connecting to websockets");
+	bot1.api = Some(API::new(connect_to_websocket(HOST, server1.port() as i32)?));
+	bot2.api = Some(API::new(connect_to_websocket(HOST, server2.port() as i32)?));
+
+	let mut runner = RunnerBotVsBot {
+		bot1,
+		bot2,
+		sc2_path: String::new(),
+		sc2_version: None,
+		map_name: String::new(),
+		realtime: false,
+		save_replay_as: None,
+		record_as,
+	};
+	// Clients are closed even if bots fail, otherwise servers wait for requests forever
+	let result = runner.start_recording().and_then(|_| runner.join_and_play(None));
+	runner.close();
+	runner.bot1.api = None;
+	runner.bot2.api = None;
+	result
+}
+
 // Portpicker
-fn get_unused_port() -> i32 {
-	(5000..65535)
-		.find(|port| TcpListener::bind((HOST, *port)).is_ok())
-		.unwrap() as i32
+pub(crate) fn get_unused_port() -> SC2Result<i32> {
+	Ok(get_unused_ports(1)?[0])
 }
 
-fn get_unused_ports(n: usize) -> Vec<i32> {
+fn get_unused_ports(n: usize) -> SC2Result<Vec<i32>> {
 	let mut ports = Vec::with_capacity(n);
 	for port in 5000..65535 {
 		if TcpListener::bind((HOST, port)).is_ok() {
 			ports.push(port as i32);
 			if ports.len() >= n {
-				break;
+				return Ok(ports);
 			}
 		}
 	}
-	ports
+	Err(io::Error::new(
+		io::ErrorKind::AddrNotAvailable,
+		format!("Can't find {} unused ports", n),
+	)
+	.into())
 }
 
 // Helpers
 fn set_static_data(bot: &mut Bot) -> SC2Result<()> {
-	let api = bot.api();
+	let api = bot.api()?;
+
+	debug!("Requesting Ping");
+	let mut req = Request::new();
+	req.mut_ping();
+	let version = version::select(api.send(req)?.get_ping().get_base_build());
 
 	debug!("Requesting GameInfo");
 	let mut req = Request::new();
@@ -498,6 +1143,16 @@ fn set_static_data(bot: &mut Bot) -> SC2Result<()> {
 	let game_info = res.take_game_info().into_sc2();
 
 	debug!("Requesting GameData");
+	let mut res = api.send(game_data_request())?;
+	let game_data = Rs::new((res.take_data(), version).into_sc2());
+
+	bot.version = Rs::new(version.clone());
+	bot.game_info = game_info;
+	bot.game_data = game_data;
+
+	Ok(())
+}
+pub(crate) fn game_data_request() -> Request {
 	let mut req = Request::new();
 	let req_game_data = req.mut_data();
 	req_game_data.set_ability_id(true);
@@ -505,16 +1160,10 @@ fn set_static_data(bot: &mut Bot) -> SC2Result<()> {
 	req_game_data.set_upgrade_id(true);
 	req_game_data.set_buff_id(true);
 	req_game_data.set_effect_id(true);
-	let mut res = api.send(req)?;
-	let game_data = Rs::new(res.take_data().into_sc2());
-
-	bot.game_info = game_info;
-	bot.game_data = game_data;
-
-	Ok(())
+	req
 }
 
-fn create_player_setup(settings: &PlayerSettings, req_create_game: &mut RequestCreateGame) {
+pub(crate) fn create_player_setup(settings: &PlayerSettings, req_create_game: &mut RequestCreateGame) {
 	let mut setup = PlayerSetup::new();
 
 	setup.set_race(settings.race.into_proto());
@@ -525,7 +1174,7 @@ fn create_player_setup(settings: &PlayerSettings, req_create_game: &mut RequestC
 	req_create_game.mut_player_setup().push(setup);
 }
 
-fn create_computer_setup(computer: &Computer, req_create_game: &mut RequestCreateGame) {
+pub(crate) fn create_computer_setup(computer: &Computer, req_create_game: &mut RequestCreateGame) {
 	let mut setup = PlayerSetup::new();
 
 	setup.set_race(computer.race.into_proto());
@@ -542,21 +1191,21 @@ fn join_game(settings: &PlayerSettings, api: &API, ports: Option<&Ports>) -> SC2
 	wait_join(api)
 }
 fn join_game2(settings: &PlayerSettings, api: &API, ports: Option<&Ports>) -> SC2Result<()> {
+	api.send_only(join_game_request(settings, ports))
+}
+pub(crate) fn join_game_request(settings: &PlayerSettings, ports: Option<&Ports>) -> Request {
 	let mut req = Request::new();
 	let req_join_game = req.mut_join_game();
 
 	req_join_game.set_race(settings.race.into_proto());
-
 	let options = req_join_game.mut_options();
-	options.set_raw(true);
-	options.set_score(true);
-	// options.mut_feature_layer()
-	// options.mut_render();
-	options.set_show_cloaked(true);
-	options.set_show_burrowed_shadows(true);
-	options.set_show_placeholders(true);
-	options.set_raw_affects_selection(settings.raw_affects_selection);
-	options.set_raw_crop_to_playable_area(settings.raw_crop_to_playable_area);
+	*options = interface_options(settings.raw_affects_selection, settings.raw_crop_to_playable_area);
+	if let Some(feature_layer) = settings.feature_layer {
+		options.set_feature_layer(feature_layer.into_proto());
+	}
+	if let Some(render) = settings.render {
+		options.set_render(render.into_proto());
+	}
 	if let Some(name) = &settings.name {
 		req_join_game.set_player_name(name.to_string());
 	}
@@ -576,119 +1225,289 @@ fn join_game2(settings: &PlayerSettings, api: &API, ports: Option<&Ports>) -> SC
 			client_ports.push(port_set);
 		}
 	}
-
-	api.send_only(req)?;
-	Ok(())
+	req
+}
+pub(crate) fn interface_options(
+	raw_affects_selection: bool,
+	raw_crop_to_playable_area: bool,
+) -> InterfaceOptions {
+	let mut options = InterfaceOptions::new();
+	options.set_raw(true);
+	options.set_score(true);
+	options.set_show_cloaked(true);
+	options.set_show_burrowed_shadows(true);
+	options.set_show_placeholders(true);
+	options.set_raw_affects_selection(raw_affects_selection);
+	options.set_raw_crop_to_playable_area(raw_crop_to_playable_area);
+	options
 }
 fn wait_join(api: &API) -> SC2Result<u32> {
-	let res = api.wait_response()?;
-
+	player_id_from_response(&api.wait_response()?)
+}
+pub(crate) fn player_id_from_response(res: &Response) -> SC2Result<u32> {
 	let res_join_game = res.get_join_game();
 	if res_join_game.has_error() {
 		let err = ProtoError::new(res_join_game.get_error(), res_join_game.get_error_details());
 		error!("{}", err);
-		Err(Box::new(err))
+		Err(err.into())
 	} else {
 		Ok(res_join_game.get_player_id())
 	}
 }
 
-fn play_first_step<B>(bot: &mut B, realtime: bool) -> SC2Result<()>
+/// Plays the game until it's ended and returns its result for bot.
+///
+/// In [`pipelined`](PlayerSettings::pipelined) mode actions, debug commands, step
+/// and request for the next observation are sent at once and responses are received after that.
+fn play_game<B>(bot: &mut B, realtime: bool) -> SC2Result<GameResult>
 where
 	B: Player + DerefMut<Target = Bot> + Deref<Target = Bot>,
 {
-	let mut req = Request::new();
-	req.mut_observation().set_disable_fog(true);
-	let res = bot.api().send(req)?;
-
-	bot.init_data_for_unit();
-	let events = update_state(bot, res.get_observation())?;
-	bot.prepare_start();
-	bot.prepare_step();
+	let pipelined = bot.get_player_settings().pipelined;
+	let mut game = GameLoop::new(realtime);
 
-	for e in events {
-		bot.on_event(e)?;
+	let mut res = bot.api()?.send(GameLoop::first_observation_request())?;
+	loop {
+		if let Some(result) = game.step(bot, &res)? {
+			return Ok(result);
+		}
+		let mut requests = game.requests(bot);
+		res = if pipelined {
+			bot.api()?
+				.send_batch(requests)?
+				.pop()
+				.ok_or_else(no_observation_request)?
+		} else {
+			let req = requests.pop().ok_or_else(no_observation_request)?;
+			for req in requests {
+				bot.api()?.send_request(req)?;
+			}
+			bot.api()?.send(req)?
+		};
 	}
-	bot.on_start()?;
+}
 
-	let bot_actions = bot.get_actions();
-	if !bot_actions.is_empty() {
-		let mut req = Request::new();
-		let actions = req.mut_action().mut_actions();
-		for a in bot_actions {
-			actions.push(a.into_proto());
+pub(crate) fn no_observation_request() -> Error {
+	Error::other("Request for the next observation is missing")
+}
+
+/// Game loop of a single bot, split into steps so several bots can be stepped together.
+///
+/// Async runner drives the same loop with [`begin_step`](Self::begin_step),
+/// [`state_updated`](Self::state_updated) and [`end_step`](Self::end_step),
+/// since it updates state and calls the bot asynchronously.
+pub(crate) struct GameLoop {
+	realtime: bool,
+	/// Iteration of the next step, `None` before the first step.
+	iteration: Option<usize>,
+	/// Start of the current step and time spent on state update in it.
+	current_step: (Instant, Duration),
+	/// Start of the last step and time spent on state update and by bot in it.
+	last_step: Option<(Instant, Duration, Duration)>,
+}
+impl GameLoop {
+	pub(crate) fn new(realtime: bool) -> Self {
+		Self {
+			realtime,
+			iteration: None,
+			current_step: (Instant::now(), Duration::ZERO),
+			last_step: None,
 		}
-		bot.clear_actions();
-		bot.api().send_request(req)?;
 	}
-	if !realtime {
+	pub(crate) fn first_observation_request() -> Request {
 		let mut req = Request::new();
-		req.mut_step().set_count(bot.game_step.get_locked());
-		bot.api().send_request(req)?;
+		req.mut_observation().set_disable_fog(true);
+		req
 	}
-	Ok(())
-}
+	/// Updates bot with received observation and runs its step.
+	/// Returns result of the game for bot if it's ended.
+	pub(crate) fn step<B>(&mut self, bot: &mut B, res: &Response) -> SC2Result<Option<GameResult>>
+	where
+		B: Player + DerefMut<Target = Bot> + Deref<Target = Bot>,
+	{
+		let callbacks = Callbacks {
+			on_start: <B as Player>::on_start,
+			on_step: <B as Player>::on_step,
+			on_end: <B as Player>::on_end,
+			on_event: <B as Player>::on_event,
+		};
+		self.run_step(bot, res, &callbacks)
+	}
+	/// Updates replay observer with received observation and runs its step.
+	/// Returns result of the observed player if replay is ended.
+	pub(crate) fn observe<B>(&mut self, bot: &mut B, res: &Response) -> SC2Result<Option<GameResult>>
+	where
+		B: ReplayObserver + DerefMut<Target = Bot> + Deref<Target = Bot>,
+	{
+		let callbacks = Callbacks {
+			on_start: <B as ReplayObserver>::on_start,
+			on_step: <B as ReplayObserver>::on_step,
+			on_end: <B as ReplayObserver>::on_end,
+			on_event: <B as ReplayObserver>::on_event,
+		};
+		self.run_step(bot, res, &callbacks)
+	}
+	fn run_step<B>(
+		&mut self,
+		bot: &mut B,
+		res: &Response,
+		callbacks: &Callbacks<B>,
+	) -> SC2Result<Option<GameResult>>
+	where
+		B: DerefMut<Target = Bot> + Deref<Target = Bot>,
+	{
+		if let Some(result) = self.begin_step(bot, res)? {
+			(callbacks.on_end)(bot, result)?;
+			return Ok(Some(result));
+		}
 
-fn play_step<B>(bot: &mut B, iteration: usize, realtime: bool) -> SC2Result<bool>
-where
-	B: Player + DerefMut<Target = Bot> + Deref<Target = Bot>,
-{
-	let mut req = Request::new();
-	req.mut_observation().set_disable_fog(bot.disable_fog);
-	let res = bot.api().send(req)?;
+		if self.is_first_step() {
+			bot.init_data_for_unit()?;
+		}
+		let events = update_state(bot, res.get_observation())?;
+		if self.is_first_step() {
+			bot.prepare_start()?;
+		}
+		bot.prepare_step();
+		self.state_updated();
 
-	if matches!(res.get_status(), Status::ended) {
-		let result = res.get_observation().get_player_result()[bot.player_id as usize - 1]
-			.get_result()
-			.into_sc2();
-		debug!("Result for bot: {:?}", result);
-		bot.on_end(result)?;
-		return Ok(false);
+		for e in events {
+			(callbacks.on_event)(bot, e)?;
+		}
+		match self.iteration() {
+			Some(iteration) => (callbacks.on_step)(bot, iteration)?,
+			None => (callbacks.on_start)(bot)?,
+		}
+		self.end_step();
+		Ok(None)
+	}
+	/// Sets latency of the last step and starts a new one.
+	/// Returns result of the game for bot if it's ended.
+	pub(crate) fn begin_step(&mut self, bot: &mut Bot, res: &Response) -> SC2Result<Option<GameResult>> {
+		if let Some((started, state, bot_time)) = self.last_step.take() {
+			bot.step_latency = StepLatency {
+				total: started.elapsed(),
+				state,
+				bot: bot_time,
+			};
+		}
+		let result = game_result(bot, res)?;
+		if let Some(result) = result {
+			debug!("Result for bot: {:?}", result);
+		}
+		self.current_step = (Instant::now(), Duration::ZERO);
+		Ok(result)
+	}
+	/// Checks if bot's [`on_start`](Player::on_start) should be called on this step.
+	pub(crate) fn is_first_step(&self) -> bool {
+		self.iteration.is_none()
+	}
+	/// Iteration passed to bot's [`on_step`](Player::on_step), `None` on the first step.
+	pub(crate) fn iteration(&self) -> Option<usize> {
+		self.iteration
+	}
+	/// Marks the end of state update in the current step.
+	pub(crate) fn state_updated(&mut self) {
+		let (started, _) = self.current_step;
+		self.current_step.1 = started.elapsed();
 	}
+	/// Marks the end of bot's step.
+	pub(crate) fn end_step(&mut self) {
+		let (started, state) = self.current_step;
+		self.last_step = Some((started, state, started.elapsed() - state));
+		self.iteration = Some(self.iteration.map_or(0, |i| i + 1));
+	}
+	/// Takes requests made on this step, the last one is request for the next observation.
+	pub(crate) fn requests(&self, bot: &mut Bot) -> Vec<Request> {
+		let mut requests = Vec::with_capacity(4);
+		requests.extend(actions_request(bot));
+		requests.extend(debug_request(bot));
+		if !self.realtime {
+			requests.push(step_request(bot));
+		}
+		let mut req = Request::new();
+		req.mut_observation().set_disable_fog(bot.disable_fog);
+		requests.push(req);
+		requests
+	}
+}
 
-	let events = update_state(bot, res.get_observation())?;
-	bot.prepare_step();
+/// Methods of [`Player`] or [`ReplayObserver`] called by [`GameLoop`].
+struct Callbacks<B> {
+	on_start: fn(&mut B) -> SC2Result<()>,
+	on_step: fn(&mut B, usize) -> SC2Result<()>,
+	on_end: fn(&B, GameResult) -> SC2Result<()>,
+	on_event: fn(&mut B, Event) -> SC2Result<()>,
+}
 
-	for e in events {
-		bot.on_event(e)?;
+/// Time spent on a single game step, available in [`step_latency`](Bot::step_latency).
+///
+/// Step starts when observation is received
+/// and ends when observation for the next step is received.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StepLatency {
+	/// Time of the whole step.
+	pub total: Duration,
+	/// Time spent updating bot's state with observation.
+	pub state: Duration,
+	/// Time spent in bot's [`on_step`](Player::on_step) and [`on_event`](Player::on_event).
+	pub bot: Duration,
+}
+impl StepLatency {
+	/// Time spent waiting for responses of SC2 API.
+	pub fn api(&self) -> Duration {
+		self.total.saturating_sub(self.state + self.bot)
 	}
-	bot.on_step(iteration)?;
+}
 
+/// Returns result of the game for bot if it's ended.
+///
+/// Returns [`Error::GameEnded`] if the game has ended, but there's no result for bot.
+pub(crate) fn game_result(bot: &Bot, res: &Response) -> SC2Result<Option<GameResult>> {
+	if !matches!(res.get_status(), Status::ended) {
+		return Ok(None);
+	}
+	res.get_observation()
+		.get_player_result()
+		.iter()
+		.find(|result| result.get_player_id() == bot.player_id)
+		.map(|result| Some(result.get_result().into_sc2()))
+		.ok_or(Error::GameEnded)
+}
+/// Takes actions made by bot on this step.
+pub(crate) fn actions_request(bot: &mut Bot) -> Option<Request> {
+	let version = Rs::clone(&bot.version);
 	let bot_actions = bot.get_actions();
-	if !bot_actions.is_empty() {
-		// println!("{:?}: {:?}", iteration, bot_actions);
-		let mut req = Request::new();
-		let actions = req.mut_action().mut_actions();
-		for a in bot_actions {
-			actions.push(a.into_proto());
-		}
-		bot.clear_actions();
-		bot.api().send_request(req)?;
-		/*
-		let res = api.send(req);
-		let results = res.get_action().get_result();
-		if !results.is_empty() {
-			println!("action_results: {:?}", results);
-		}
-		*/
+	if bot_actions.is_empty() {
+		return None;
 	}
-
+	let mut req = Request::new();
+	let actions = req.mut_action().mut_actions();
+	for a in bot_actions {
+		actions.push((a, &*version).into_proto());
+	}
+	bot.clear_actions();
+	Some(req)
+}
+/// Takes debug commands made by bot on this step.
+pub(crate) fn debug_request(bot: &mut Bot) -> Option<Request> {
+	let version = Rs::clone(&bot.version);
 	let bot_debug_commands = bot.get_debug_commands();
-	if !bot_debug_commands.is_empty() {
-		let mut req = Request::new();
-		let debug_commands = req.mut_debug().mut_debug();
-		for cmd in bot_debug_commands {
-			debug_commands.push(cmd.into_proto())
-		}
-		bot.clear_debug_commands();
-		bot.api().send_request(req)?;
+	if bot_debug_commands.is_empty() {
+		return None;
 	}
-	if !realtime {
-		let mut req = Request::new();
-		req.mut_step().set_count(bot.game_step.get_locked());
-		bot.api().send_request(req)?;
+	let mut req = Request::new();
+	let debug_commands = req.mut_debug().mut_debug();
+	for cmd in bot_debug_commands {
+		debug_commands.push((cmd, &*version).into_proto())
 	}
-	Ok(true)
+	bot.clear_debug_commands();
+	Some(req)
+}
+pub(crate) fn step_request(bot: &Bot) -> Request {
+	let mut req = Request::new();
+	req.mut_step().set_count(bot.game_step.get_locked());
+	req
 }
 
 fn save_replay(api: &API, path: &str) -> SC2Result<()> {
@@ -696,7 +1515,9 @@ fn save_replay(api: &API, path: &str) -> SC2Result<()> {
 	req.mut_save_replay();
 
 	let res = api.send(req)?;
-
+	write_replay(&res, path)
+}
+pub(crate) fn write_replay(res: &Response, path: &str) -> SC2Result<()> {
 	let mut path = path.to_string();
 	if !path.ends_with(".SC2Replay") {
 		path.push_str(".SC2Replay");
@@ -706,11 +1527,19 @@ fn save_replay(api: &API, path: &str) -> SC2Result<()> {
 	Ok(())
 }
 
-fn launch_client(sc2_path: &str, port: i32, sc2_version: Option<&str>) -> Child {
+pub(crate) fn launch_client(sc2_path: &str, port: i32, sc2_version: Option<&str>) -> SC2Result<Child> {
 	let (base_version, data_hash) = match sc2_version {
 		Some(ver) => get_version_info(ver),
 		None => (get_latest_base_version(sc2_path), ""),
 	};
+	launch_client_build(sc2_path, port, base_version, data_hash)
+}
+pub(crate) fn launch_client_build(
+	sc2_path: &str,
+	port: i32,
+	base_version: u32,
+	data_hash: &str,
+) -> SC2Result<Child> {
 	let sc2_full_path = format!("{}/Versions/Base{}/{}", sc2_path, base_version, SC2_BINARY);
 
 	let mut process = if cfg!(feature = "wine_sc2") {
@@ -750,15 +1579,49 @@ fn launch_client(sc2_path: &str, port: i32, sc2_version: Option<&str>) -> Child
 	if !data_hash.is_empty() {
 		process.arg("-dataVersion").arg(data_hash);
 	}
-	process.spawn().expect("Can't launch SC2 process.")
+	process.spawn().map_err(Error::Launch)
 }
 
-fn connect_to_websocket(host: &str, port: i32) -> SC2Result<WS> {
+pub(crate) fn connect_to_websocket(host: &str, port: i32) -> SC2Result<WS> {
 	let url = format!("ws://{}:{}/sc2api", host, port);
 	let (ws, _rs) = loop {
 		if let Ok(result) = connect(&url) {
 			break result;
 		}
 	};
+	// Small requests are sent one after another in pipelined mode, so they shouldn't be delayed
+	if let MaybeTlsStream::Plain(stream) = ws.get_ref() {
+		stream.set_nodelay(true)?;
+	}
 	Ok(ws)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc2_proto::sc2api::PlayerResult;
+
+	#[test]
+	fn game_result_is_found_by_player_id() {
+		let mut res = Response::new();
+		let mut bot = Bot::default();
+		bot.player_id = 2;
+		assert_eq!(game_result(&bot, &res).unwrap(), None);
+
+		res.set_status(Status::ended);
+		let mut result = PlayerResult::new();
+		result.set_player_id(2);
+		result.set_result(sc2_proto::sc2api::Result::Defeat);
+		res.mut_observation().mut_player_result().push(result);
+		assert_eq!(game_result(&bot, &res).unwrap(), Some(GameResult::Defeat));
+
+		bot.player_id = 1;
+		assert!(matches!(game_result(&bot, &res), Err(Error::GameEnded)));
+	}
+
+	#[test]
+	fn recording_paths_are_numbered() {
+		assert_eq!(recording_path("game.bin", 1), "game.1.bin");
+		assert_eq!(recording_path("game", 2), "game.2");
+	}
+}
@@ -0,0 +1,513 @@
+//! Generator of the [`ids`](crate::ids) module from game data.
+//!
+//! Ids are generated from [`ResponseData`] saved with [`save_data`],
+//! so they can be regenerated for any `sc2_version` without running the game again:
+//! ```no_run
+//! use rust_sc2::codegen::{load_data, IdTables};
+//!
+//! let data = load_data("data.bin")?;
+//! let old = IdTables::parse_dir("src/ids")?;
+//! let new = IdTables::from_data(&data);
+//! print!("{}", old.diff(&new));
+//! new.write_dir("src/ids")?;
+//! # Ok::<(), rust_sc2::Error>(())
+//! ```
+//! Same can be done with `generate-ids` example.
+
+use crate::{api::API, client::game_data_request, SC2Result};
+use indexmap::IndexMap;
+use protobuf::Message;
+use rustc_hash::FxHashMap;
+use sc2_proto::sc2api::ResponseData;
+use std::{fmt, fs, path::Path};
+
+const HEAD: &str = "\
+#![allow(deprecated)]
+
+#[cfg(feature = \"serde\")]
+use serde::{Serialize, Deserialize};
+";
+const DERIVES: &str = "\
+#[cfg_attr(feature = \"serde\", derive(Serialize, Deserialize))]
+#[derive(Debug, FromPrimitive, ToPrimitive, FromStr, Copy, Clone, PartialEq, Eq, Hash)]";
+const MOD_HEAD: &str = "\
+//! Auto generated with `codegen` module from game data
+//! ids of units, ablities, upgrades, buffs and effects.
+#![allow(missing_docs)]";
+const MOD_TAIL: &str = "\
+mod impls;
+
+mod decode;
+pub use decode::{unknown_ids, DecodeId};
+";
+
+/// Names of generated enums, in order of [`IdTables::tables`].
+pub const ENUM_NAMES: [&str; 5] = ["UnitTypeId", "AbilityId", "UpgradeId", "BuffId", "EffectId"];
+const FILE_NAMES: [&str; 5] = ["unit_typeid", "ability_id", "upgrade_id", "buff_id", "effect_id"];
+
+/// Deprecated variants and names they should be replaced with.
+const MIMICS: &[(&str, &str, &str)] = &[
+	("UnitTypeId", "Lurker", "LurkerMP"),
+	("UnitTypeId", "LurkerBurrowed", "LurkerMPBurrowed"),
+	("UnitTypeId", "LurkerDen", "LurkerDenMP"),
+	("UnitTypeId", "LurkerEgg", "LurkerMPEgg"),
+	(
+		"UpgradeId",
+		"TerranVehicleArmorsLevel1",
+		"TerranVehicleAndShipArmorsLevel1",
+	),
+	(
+		"UpgradeId",
+		"TerranVehicleArmorsLevel2",
+		"TerranVehicleAndShipArmorsLevel2",
+	),
+	(
+		"UpgradeId",
+		"TerranVehicleArmorsLevel3",
+		"TerranVehicleAndShipArmorsLevel3",
+	),
+	(
+		"UpgradeId",
+		"TerranShipArmorsLevel1",
+		"TerranVehicleAndShipArmorsLevel1",
+	),
+	(
+		"UpgradeId",
+		"TerranShipArmorsLevel2",
+		"TerranVehicleAndShipArmorsLevel2",
+	),
+	(
+		"UpgradeId",
+		"TerranShipArmorsLevel3",
+		"TerranVehicleAndShipArmorsLevel3",
+	),
+	("UpgradeId", "MarineStimpack", "Stimpack"),
+	("UpgradeId", "CombatShield", "ShieldWall"),
+	("UpgradeId", "JackhammerConcussionGrenades", "PunisherGrenades"),
+	("UpgradeId", "InfernalPreIgniters", "HighCapacityBarrels"),
+	(
+		"UpgradeId",
+		"HellionCampaignInfernalPreIgniter",
+		"HighCapacityBarrels",
+	),
+	("UpgradeId", "TransformationServos", "SmartServos"),
+	(
+		"UpgradeId",
+		"CycloneRapidFireLaunchers",
+		"CycloneLockOnDamageUpgrade",
+	),
+	("UpgradeId", "MagFieldLaunchers", "CycloneLockOnDamageUpgrade"),
+	("UpgradeId", "PermanentCloakGhost", "PersonalCloaking"),
+	("UpgradeId", "YamatoCannon", "BattlecruiserEnableSpecializations"),
+];
+
+/// Requests all game data from the API and saves it to the given file.
+pub fn save_data<P: AsRef<Path>>(api: &API, path: P) -> SC2Result<()> {
+	let data = api.send(game_data_request())?.take_data();
+	fs::write(path, data.write_to_bytes()?)?;
+	Ok(())
+}
+/// Loads game data saved with [`save_data`].
+pub fn load_data<P: AsRef<Path>>(path: P) -> SC2Result<ResponseData> {
+	Ok(ResponseData::parse_from_bytes(&fs::read(path)?)?)
+}
+
+/// Variant names and values of all id enums.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct IdTables {
+	/// Variants of [`UnitTypeId`](crate::ids::UnitTypeId).
+	pub units: IndexMap<String, u32>,
+	/// Variants of [`AbilityId`](crate::ids::AbilityId).
+	pub abilities: IndexMap<String, u32>,
+	/// Variants of [`UpgradeId`](crate::ids::UpgradeId).
+	pub upgrades: IndexMap<String, u32>,
+	/// Variants of [`BuffId`](crate::ids::BuffId).
+	pub buffs: IndexMap<String, u32>,
+	/// Variants of [`EffectId`](crate::ids::EffectId).
+	pub effects: IndexMap<String, u32>,
+}
+impl IdTables {
+	/// Builds tables from game data, naming variants the same way as existing ids.
+	pub fn from_data(data: &ResponseData) -> Self {
+		let mut abilities = IndexMap::new();
+		for a in data.get_abilities() {
+			let mut key = a.get_button_name();
+			if key.is_empty() {
+				if !a.has_remaps_to_ability_id() {
+					continue;
+				}
+				key = a.get_friendly_name();
+				if key.is_empty() {
+					warn!("Ability {} has no name", a.get_ability_id());
+					continue;
+				}
+			}
+
+			let mut key = format!("{}{}", clean_name(a.get_link_name()), clean_name(key));
+			if !a.get_friendly_name().is_empty() {
+				key = clean_name(a.get_friendly_name());
+			}
+			if key.starts_with(|c: char| c.is_ascii_digit()) {
+				key.insert(0, '_');
+			}
+			let key = capitalize(&key).replace("ResearchResearch", "Research");
+			abilities.insert(key, a.get_ability_id());
+		}
+		// fixes for wrong ids
+		abilities.insert("TerranBuildRefinery".to_string(), 320);
+
+		Self {
+			units: parse_simple(data.get_units().iter().map(|u| (u.get_name(), u.get_unit_id()))),
+			abilities,
+			upgrades: parse_simple(
+				data.get_upgrades()
+					.iter()
+					.map(|u| (u.get_name(), u.get_upgrade_id())),
+			),
+			buffs: parse_simple(data.get_buffs().iter().map(|b| (b.get_name(), b.get_buff_id()))),
+			effects: parse_simple(
+				data.get_effects()
+					.iter()
+					.map(|e| (e.get_name(), e.get_effect_id())),
+			),
+		}
+	}
+	/// Reads tables from previously generated `ids` directory.
+	pub fn parse_dir<P: AsRef<Path>>(dir: P) -> SC2Result<Self> {
+		let dir = dir.as_ref();
+		let mut tables = Self::default();
+		for (file, table) in FILE_NAMES.iter().zip(tables.tables_mut()) {
+			let source = fs::read_to_string(dir.join(format!("{}.rs", file)))?;
+			*table = parse_enum(&source);
+		}
+		Ok(tables)
+	}
+	/// Writes generated enums and `mod.rs` to the given directory.
+	pub fn write_dir<P: AsRef<Path>>(&self, dir: P) -> SC2Result<()> {
+		let dir = dir.as_ref();
+		for ((name, file), table) in ENUM_NAMES.iter().zip(FILE_NAMES).zip(self.tables()) {
+			fs::write(
+				dir.join(format!("{}.rs", file)),
+				format!("{}\n{}", HEAD, generate_enum(name, table)),
+			)?;
+		}
+
+		let mods = FILE_NAMES
+			.iter()
+			.map(|file| format!("mod {};", file))
+			.collect::<Vec<_>>()
+			.join("\n");
+		let uses = ENUM_NAMES
+			.iter()
+			.zip(FILE_NAMES)
+			.map(|(name, file)| format!("pub use {}::{};", file, name))
+			.collect::<Vec<_>>()
+			.join("\n");
+		fs::write(
+			dir.join("mod.rs"),
+			format!("{}\n\n{}\n\n{}\n\n{}", MOD_HEAD, mods, uses, MOD_TAIL),
+		)?;
+		Ok(())
+	}
+	/// Returns tables in the same order as [`ENUM_NAMES`].
+	pub fn tables(&self) -> [&IndexMap<String, u32>; 5] {
+		[
+			&self.units,
+			&self.abilities,
+			&self.upgrades,
+			&self.buffs,
+			&self.effects,
+		]
+	}
+	fn tables_mut(&mut self) -> [&mut IndexMap<String, u32>; 5] {
+		[
+			&mut self.units,
+			&mut self.abilities,
+			&mut self.upgrades,
+			&mut self.buffs,
+			&mut self.effects,
+		]
+	}
+	/// Compares these tables with newer ones.
+	///
+	/// Ids are matched by value, so variant with the same value but different name is reported as renamed.
+	pub fn diff(&self, new: &Self) -> IdDiff {
+		let mut diff = IdDiff::default();
+		for ((kind, old), new) in ENUM_NAMES.iter().zip(self.tables()).zip(new.tables()) {
+			let old_names = names_by_value(old);
+			let new_names = names_by_value(new);
+
+			for (value, names) in &old_names {
+				match new_names.get(value) {
+					None => diff.removed.extend(names.iter().map(|name| IdChange {
+						kind,
+						name: name.to_string(),
+						value: *value,
+					})),
+					Some(new_names) if new_names != names => diff.renamed.push(IdRename {
+						kind,
+						old_name: names.join(", "),
+						new_name: new_names.join(", "),
+						value: *value,
+					}),
+					_ => {}
+				}
+			}
+			for (value, names) in &new_names {
+				if !old_names.contains_key(value) {
+					diff.added.extend(names.iter().map(|name| IdChange {
+						kind,
+						name: name.to_string(),
+						value: *value,
+					}));
+				}
+			}
+		}
+		for changes in [&mut diff.added, &mut diff.removed] {
+			changes.sort_by_key(|c| (c.kind, c.value));
+		}
+		diff.renamed.sort_by_key(|c| (c.kind, c.value));
+		diff
+	}
+}
+
+/// Id added or removed between two [`IdTables`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdChange {
+	/// Name of the enum (e.g. `"AbilityId"`).
+	pub kind: &'static str,
+	pub name: String,
+	pub value: u32,
+}
+
+/// Id with the same value but different name in two [`IdTables`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdRename {
+	/// Name of the enum (e.g. `"AbilityId"`).
+	pub kind: &'static str,
+	pub old_name: String,
+	pub new_name: String,
+	pub value: u32,
+}
+
+/// Report of changes between two [`IdTables`], returned by [`IdTables::diff`].
+///
+/// Formatted with [`Display`](fmt::Display) one change per line.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct IdDiff {
+	pub added: Vec<IdChange>,
+	pub removed: Vec<IdChange>,
+	pub renamed: Vec<IdRename>,
+}
+impl IdDiff {
+	/// Checks if tables were the same.
+	pub fn is_empty(&self) -> bool {
+		self.added.is_empty() && self.removed.is_empty() && self.renamed.is_empty()
+	}
+}
+impl fmt::Display for IdDiff {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for c in &self.added {
+			writeln!(f, "+ {}::{} = {}", c.kind, c.name, c.value)?;
+		}
+		for c in &self.removed {
+			writeln!(f, "- {}::{} = {}", c.kind, c.name, c.value)?;
+		}
+		for c in &self.renamed {
+			writeln!(f, "~ {}::{} -> {} = {}", c.kind, c.old_name, c.new_name, c.value)?;
+		}
+		Ok(())
+	}
+}
+
+fn clean_name(name: &str) -> String {
+	name.replace([' ', '_', '@'], "")
+}
+
+fn capitalize(name: &str) -> String {
+	let mut chars = name.chars();
+	match chars.next() {
+		Some(c) => c.to_uppercase().chain(chars).collect(),
+		None => String::new(),
+	}
+}
+
+fn parse_simple<'a>(data: impl Iterator<Item = (&'a str, u32)>) -> IndexMap<String, u32> {
+	let mut table = IndexMap::new();
+	for (name, id) in data {
+		if name.is_empty() {
+			continue;
+		}
+
+		let mut key = clean_name(name);
+		if key.starts_with(|c: char| c.is_ascii_digit()) {
+			key.insert(0, '_');
+		}
+		if table.contains_key(&key) {
+			let mut index = 2;
+			while table.contains_key(&format!("{}{}", key, index)) {
+				index += 1;
+			}
+			key = format!("{}{}", key, index);
+		}
+		table.insert(capitalize(&key), id);
+	}
+	table
+}
+
+fn parse_enum(source: &str) -> IndexMap<String, u32> {
+	source
+		.lines()
+		.skip_while(|line| !line.starts_with("pub enum"))
+		.skip(1)
+		.take_while(|line| !line.starts_with('}'))
+		.filter_map(|line| {
+			let (name, value) = line.trim().trim_end_matches(',').split_once(" = ")?;
+			Some((name.to_string(), value.parse().ok()?))
+		})
+		.collect()
+}
+
+fn generate_enum(name: &str, table: &IndexMap<String, u32>) -> String {
+	let mut variants = table.iter().collect::<Vec<_>>();
+	variants.sort_by_key(|(_, value)| **value);
+
+	let mut out = format!("{}\npub enum {} {{\n", DERIVES, name);
+	for (variant, value) in variants {
+		if let Some((_, _, new)) = MIMICS.iter().find(|(e, old, _)| *e == name && old == variant) {
+			out.push_str(&format!(
+				"\t#[deprecated(note = \"Use `{}::{}` instead.\")]\n",
+				name, new
+			));
+		}
+		out.push_str(&format!("\t{} = {},\n", variant, value));
+	}
+	out.push_str("}\n");
+	out
+}
+
+fn names_by_value(table: &IndexMap<String, u32>) -> FxHashMap<u32, Vec<&str>> {
+	let mut names = FxHashMap::<u32, Vec<&str>>::default();
+	for (name, value) in table {
+		names.entry(*value).or_default().push(name);
+	}
+	names
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc2_proto::data::{AbilityData, UnitTypeData, UpgradeData};
+	use std::env::temp_dir;
+
+	fn table(ids: &[(&str, u32)]) -> IndexMap<String, u32> {
+		ids.iter()
+			.map(|(name, value)| (name.to_string(), *value))
+			.collect()
+	}
+
+	fn data() -> ResponseData {
+		let mut data = ResponseData::new();
+		for (name, id) in [
+			("Marine", 48),
+			("Siege Tank", 33),
+			("Marine", 1000),
+			("", 7),
+			("2Fast", 9),
+		] {
+			let mut unit = UnitTypeData::new();
+			unit.set_name(name.to_string());
+			unit.set_unit_id(id);
+			data.mut_units().push(unit);
+		}
+		for (link, button, friendly, id) in [
+			("Stop", "Stop", "Stop Stop", 4),
+			("Barracks Train", "Marine", "", 560),
+			// Abilities without buttons and remaps are skipped
+			("Hidden", "", "Hidden", 5),
+		] {
+			let mut ability = AbilityData::new();
+			ability.set_link_name(link.to_string());
+			ability.set_button_name(button.to_string());
+			ability.set_friendly_name(friendly.to_string());
+			ability.set_ability_id(id);
+			data.mut_abilities().push(ability);
+		}
+		let mut upgrade = UpgradeData::new();
+		upgrade.set_name("Stimpack".to_string());
+		upgrade.set_upgrade_id(15);
+		data.mut_upgrades().push(upgrade);
+		data
+	}
+
+	#[test]
+	fn names_are_generated_from_data() {
+		let tables = IdTables::from_data(&data());
+		assert_eq!(
+			tables.units,
+			table(&[
+				("Marine", 48),
+				("SiegeTank", 33),
+				("Marine2", 1000),
+				("_2Fast", 9)
+			])
+		);
+		assert_eq!(
+			tables.abilities,
+			table(&[
+				("StopStop", 4),
+				("BarracksTrainMarine", 560),
+				("TerranBuildRefinery", 320)
+			])
+		);
+		assert_eq!(tables.upgrades, table(&[("Stimpack", 15)]));
+		assert!(tables.buffs.is_empty() && tables.effects.is_empty());
+	}
+
+	#[test]
+	fn tables_round_trip_through_files() {
+		let dir = temp_dir().join("rust-sc2-codegen");
+		fs::create_dir_all(&dir).unwrap();
+
+		// Existing ids contain deprecated variants, which must be parsed too
+		for tables in [
+			IdTables::from_data(&data()),
+			IdTables::parse_dir("src/ids").unwrap(),
+		] {
+			tables.write_dir(&dir).unwrap();
+			let parsed = IdTables::parse_dir(&dir).unwrap();
+			assert_eq!(parsed, tables);
+			assert!(tables.diff(&parsed).is_empty());
+		}
+		assert!(IdTables::parse_dir("src/ids").unwrap().abilities.len() > 1000);
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn diff_is_grouped_by_change() {
+		let old = IdTables {
+			units: table(&[("Marine", 48), ("Tank", 33), ("Old", 5)]),
+			buffs: table(&[("Stim", 27)]),
+			..Default::default()
+		};
+		let new = IdTables {
+			units: table(&[("Marine", 48), ("SiegeTank", 33), ("New", 6), ("Newer", 2)]),
+			buffs: table(&[("Stim", 27), ("StimMarauder", 27)]),
+			..Default::default()
+		};
+		let diff = old.diff(&new);
+		assert_eq!(
+			diff.to_string(),
+			"\
++ UnitTypeId::Newer = 2
++ UnitTypeId::New = 6
+- UnitTypeId::Old = 5
+~ BuffId::Stim -> Stim, StimMarauder = 27
+~ UnitTypeId::Tank -> SiegeTank = 33
+"
+		);
+		assert!(!diff.is_empty());
+		assert!(new.diff(&new).is_empty());
+		assert_eq!(new.diff(&new).to_string(), "");
+	}
+}
@@ -0,0 +1,503 @@
+//! Simulator of fights between two groups of units, used to predict outcome of engagements.
+//!
+//! ```no_run
+//! # use rust_sc2::prelude::*;
+//! use rust_sc2::{bot::Bot, combat::{CombatSimulator, Winner}};
+//! # fn f(bot: &Bot) {
+//! let army = bot.units.my.units.filter(|u| !u.is_worker());
+//! let result = CombatSimulator::new().simulate(&army, &bot.units.enemy.all);
+//! if result.winner == Winner::My {
+//!     // Attack
+//! } else {
+//!     // Retreat
+//! }
+//! # }
+//! ```
+//!
+//! Fight is modeled as exchange of attacks, every unit attacks the closest target it can hit
+//! until that target dies. Attacks made at the same time are simultaneous, so units killed
+//! by them still make their own attacks. Damage is reduced by armor and shield armor of targets,
+//! excess damage of killing blows is lost. Units approach their targets with their speed,
+//! but abilities, splash damage, healing and terrain aren't taken into account.
+
+use crate::{
+	consts::{FRAMES_PER_SECOND, GAME_SPEED, MIN_DAMAGE},
+	distance::Distance,
+	game_data::TargetType,
+	geometry::Point2,
+	ids::UnitTypeId,
+	unit::Unit,
+	units::Units,
+};
+
+/// Extra armor given by guardian shield vs ranged attacks.
+const GUARDIAN_SHIELD_ARMOR: i32 = 2;
+
+/// Side which won simulated fight.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Winner {
+	/// First group (usually own units).
+	My,
+	/// Second group (usually enemy units).
+	Enemy,
+	/// Both groups died or ended with the same part of their health.
+	Draw,
+}
+
+/// Unit, which survived simulated fight.
+#[derive(Debug, Clone)]
+pub struct Survivor {
+	/// Tag of the unit.
+	pub tag: u64,
+	/// Remaining health.
+	pub health: f32,
+	/// Remaining shield.
+	pub shield: f32,
+}
+
+/// Outcome of simulated fight, returned by [`CombatSimulator::simulate`].
+#[derive(Debug, Clone)]
+pub struct CombatResult {
+	/// Predicted winner.
+	///
+	/// If both sides survived the time limit, the one with greater part of initial health and shield wins.
+	pub winner: Winner,
+	/// Surviving units of the first group.
+	pub my_survivors: Vec<Survivor>,
+	/// Surviving units of the second group.
+	pub enemy_survivors: Vec<Survivor>,
+	/// Summed remaining health and shield of the first group.
+	pub my_hits: f32,
+	/// Summed remaining health and shield of the second group.
+	pub enemy_hits: f32,
+	/// Duration of the fight in seconds.
+	pub duration: f32,
+}
+
+/// Settings of fight simulation.
+#[derive(Debug, Copy, Clone)]
+pub struct CombatSimulator {
+	/// Maximum duration of the fight in seconds.
+	pub time_limit: f32,
+	/// Take into account positions and speed of units.
+	///
+	/// If disabled, all units are considered in range of their targets from the start.
+	pub use_positions: bool,
+}
+impl Default for CombatSimulator {
+	fn default() -> Self {
+		Self {
+			time_limit: 60.0,
+			use_positions: true,
+		}
+	}
+}
+impl CombatSimulator {
+	/// Constructs simulator with default settings.
+	pub fn new() -> Self {
+		Self::default()
+	}
+	/// Sets maximum duration of the fight in seconds.
+	pub fn with_time_limit(mut self, seconds: f32) -> Self {
+		self.time_limit = seconds;
+		self
+	}
+	/// Sets `use_positions` to a given value.
+	pub fn use_positions(mut self, val: bool) -> Self {
+		self.use_positions = val;
+		self
+	}
+
+	/// Simulates fight between two groups of units and returns its outcome.
+	pub fn simulate(&self, my: &Units, enemy: &Units) -> CombatResult {
+		let mut fighters = my
+			.iter()
+			.map(|u| (u, 0))
+			.chain(enemy.iter().map(|u| (u, 1)))
+			.filter_map(|(u, side)| Fighter::new(u, side))
+			.collect::<Vec<_>>();
+		let initial_hits = side_hits(&fighters);
+
+		// Game data is in normal speed, while time limit is in real seconds on faster speed
+		let time_limit = self.time_limit * GAME_SPEED;
+
+		// The fight starts when the first unit engages
+		for i in 0..fighters.len() {
+			self.retarget(&mut fighters, i, 0.0);
+		}
+		let start = fighters
+			.iter()
+			.filter(|f| f.target.is_some())
+			.map(|f| f.next_attack)
+			.min_by(|a, b| a.partial_cmp(b).unwrap())
+			.unwrap_or(0.0);
+		for f in &mut fighters {
+			f.next_attack -= start;
+		}
+
+		let mut now = 0.0;
+		while let Some(i) = fighters
+			.iter()
+			.enumerate()
+			.filter(|(_, f)| f.can_attack())
+			.min_by(|(_, a), (_, b)| a.next_attack.partial_cmp(&b.next_attack).unwrap())
+			.map(|(i, _)| i)
+		{
+			let time = fighters[i].next_attack;
+			if time > time_limit {
+				break;
+			}
+			now = time;
+
+			let target = fighters[i].target.unwrap();
+			if !fighters[target].is_alive() {
+				self.retarget(&mut fighters, i, now);
+				continue;
+			}
+
+			let weapon = fighters[i].weapon;
+			let target = &mut fighters[target];
+			for _ in 0..weapon.attacks {
+				target.take_hit(weapon.damage as f32, weapon.range);
+			}
+			if !target.is_alive() && target.died_at.is_none() {
+				target.died_at = Some(time);
+			}
+			fighters[i].next_attack += weapon.cooldown;
+		}
+
+		let survivors = |side| {
+			fighters
+				.iter()
+				.filter(|f| f.side == side && f.is_alive())
+				.map(|f| Survivor {
+					tag: f.unit.tag(),
+					health: f.health,
+					shield: f.shield,
+				})
+				.collect::<Vec<_>>()
+		};
+		let my_survivors = survivors(0);
+		let enemy_survivors = survivors(1);
+		let [my_hits, enemy_hits] = side_hits(&fighters);
+
+		let winner = match (my_survivors.is_empty(), enemy_survivors.is_empty()) {
+			(false, true) => Winner::My,
+			(true, false) => Winner::Enemy,
+			(true, true) => Winner::Draw,
+			(false, false) => {
+				let my_part = my_hits / initial_hits[0];
+				let enemy_part = enemy_hits / initial_hits[1];
+				if my_part > enemy_part {
+					Winner::My
+				} else if enemy_part > my_part {
+					Winner::Enemy
+				} else {
+					Winner::Draw
+				}
+			}
+		};
+
+		CombatResult {
+			winner,
+			my_survivors,
+			enemy_survivors,
+			my_hits,
+			enemy_hits,
+			duration: now / GAME_SPEED,
+		}
+	}
+
+	/// Selects the closest target, which fighter can attack and reach.
+	fn retarget(&self, fighters: &mut [Fighter], i: usize, now: f32) {
+		let attacker = &fighters[i];
+		let mut best = None;
+		for (j, target) in fighters.iter().enumerate() {
+			if target.side == attacker.side || !target.is_alive() || !target.unit.can_be_attacked() {
+				continue;
+			}
+			let weapon = match attacker.weapon_vs(target) {
+				Some(weapon) => weapon,
+				None => continue,
+			};
+			let gap = if self.use_positions {
+				let gap = attacker.position.distance(target.unit.position())
+					- attacker.unit.radius()
+					- target.unit.radius()
+					- weapon.range;
+				if gap > 0.0 && attacker.speed <= 0.0 {
+					continue;
+				}
+				gap.max(0.0)
+			} else {
+				0.0
+			};
+			// Prefer targets, which can be killed faster
+			let time_to_kill = target.hits() / target.dps(&weapon);
+			if best.map_or(true, |(_, _, best)| (gap, time_to_kill) < best) {
+				best = Some((j, weapon, (gap, time_to_kill)));
+			}
+		}
+
+		let (j, weapon, (gap, _)) = match best {
+			Some(best) => best,
+			None => {
+				fighters[i].target = None;
+				return;
+			}
+		};
+		let target_position = fighters[j].unit.position();
+		let attacker = &mut fighters[i];
+		attacker.target = Some(j);
+		attacker.weapon = weapon;
+		attacker.next_attack = attacker.next_attack.max(now);
+		if gap > 0.0 {
+			// Unit stops as soon as target is in range
+			attacker.next_attack += gap / attacker.speed;
+			attacker.position = attacker.position.towards(target_position, gap);
+		}
+	}
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+struct Weapon {
+	damage: u32,
+	attacks: u32,
+	cooldown: f32,
+	range: f32,
+}
+
+struct Fighter<'a> {
+	unit: &'a Unit,
+	side: usize,
+	health: f32,
+	shield: f32,
+	armor: i32,
+	shield_armor: i32,
+	has_guardian_shield: bool,
+	position: Point2,
+	speed: f32,
+	target: Option<usize>,
+	weapon: Weapon,
+	next_attack: f32,
+	died_at: Option<f32>,
+}
+impl<'a> Fighter<'a> {
+	fn new(unit: &'a Unit, side: usize) -> Option<Self> {
+		let health = unit.health().or_else(|| unit.health_max())? as f32;
+		let shield = unit.shield().or_else(|| unit.shield_max()).unwrap_or(0) as f32;
+		let (armor, shield_armor, has_guardian_shield) = unit.real_armor(&unit.upgrades());
+		Some(Self {
+			unit,
+			side,
+			health,
+			shield,
+			armor,
+			shield_armor,
+			has_guardian_shield,
+			position: unit.position(),
+			speed: unit.real_speed(),
+			target: None,
+			weapon: Weapon::default(),
+			next_attack: unit
+				.weapon_cooldown()
+				.map_or(0.0, |cooldown| cooldown / FRAMES_PER_SECOND * GAME_SPEED),
+			died_at: None,
+		})
+	}
+	fn is_alive(&self) -> bool {
+		self.health > 0.0
+	}
+	/// Checks if fighter has a target and is alive or killed at the time of its next attack.
+	fn can_attack(&self) -> bool {
+		self.target.is_some() && (self.is_alive() || self.died_at == Some(self.next_attack))
+	}
+	fn hits(&self) -> f32 {
+		self.health.max(0.0) + self.shield
+	}
+
+	/// Returns weapon, which deals the most damage per second to given target.
+	fn weapon_vs(&self, target: &Fighter) -> Option<Weapon> {
+		let unit = target.unit;
+		let not_target = if unit.type_id() == UnitTypeId::Colossus {
+			TargetType::Any
+		} else if unit.is_flying() {
+			TargetType::Ground
+		} else {
+			TargetType::Air
+		};
+		self.unit
+			.weapon_hits(not_target, unit.attributes(), &self.unit.upgrades())
+			.into_iter()
+			.filter(|(_, attacks, cooldown, _)| *attacks > 0 && *cooldown > 0.0)
+			.map(|(damage, attacks, cooldown, range)| Weapon {
+				damage,
+				attacks,
+				cooldown,
+				range,
+			})
+			.max_by(|a, b| target.dps(a).partial_cmp(&target.dps(b)).unwrap())
+	}
+	/// Damage per second dealt to this fighter by given weapon, including armor.
+	fn dps(&self, weapon: &Weapon) -> f32 {
+		let armor = if self.shield > 0.0 {
+			self.shield_armor
+		} else {
+			self.armor
+		};
+		let damage = (weapon.damage as f32 - armor as f32).max(MIN_DAMAGE as f32);
+		damage * weapon.attacks as f32 / weapon.cooldown
+	}
+	/// Applies single attack, damage goes to shield first and the rest goes to health.
+	fn take_hit(&mut self, damage: f32, range: f32) {
+		if !self.is_alive() {
+			return;
+		}
+		let extra_armor = if self.has_guardian_shield && range >= 2.0 {
+			GUARDIAN_SHIELD_ARMOR
+		} else {
+			0
+		};
+
+		let mut damage = damage;
+		if self.shield > 0.0 {
+			let shield_damage = (damage - (self.shield_armor + extra_armor) as f32).max(MIN_DAMAGE as f32);
+			if shield_damage <= self.shield {
+				self.shield -= shield_damage;
+				return;
+			}
+			damage = shield_damage - self.shield;
+			self.shield = 0.0;
+			self.health -= (damage - (self.armor + extra_armor) as f32).max(0.0);
+		} else {
+			self.health -= (damage - (self.armor + extra_armor) as f32).max(MIN_DAMAGE as f32);
+		}
+	}
+}
+
+fn side_hits(fighters: &[Fighter]) -> [f32; 2] {
+	let mut hits = [0.0; 2];
+	for f in fighters.iter().filter(|f| f.is_alive()) {
+		hits[f.side] += f.hits();
+	}
+	hits
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		bot::{Rl, Rs, Rw},
+		game_data::GameData,
+		pixel_map::{Pixel, PixelMap, Visibility, VisibilityMap},
+		unit::DataForUnit,
+		version, FromProto,
+	};
+	use sc2_proto::{
+		data::{UnitTypeData, Weapon as ProtoWeapon, Weapon_TargetType},
+		raw::{Alliance as ProtoAlliance, DisplayType, Unit as ProtoUnit},
+		sc2api::ResponseData,
+	};
+
+	fn data() -> Rs<DataForUnit> {
+		let mut res = ResponseData::new();
+		for (type_id, armor) in [(UnitTypeId::Marine, 0.0), (UnitTypeId::Marauder, 1.0)] {
+			let mut unit = UnitTypeData::new();
+			unit.set_unit_id(type_id as u32);
+			unit.set_available(true);
+			unit.set_armor(armor);
+			unit.set_movement_speed(2.25);
+			let mut weapon = ProtoWeapon::new();
+			weapon.set_field_type(Weapon_TargetType::Any);
+			weapon.set_damage(6.0);
+			weapon.set_attacks(1);
+			weapon.set_range(5.0);
+			weapon.set_speed(0.61);
+			unit.mut_weapons().push(weapon);
+			res.mut_units().push(unit);
+		}
+		Rs::new(DataForUnit {
+			game_data: Rs::new(GameData::from_proto((res, version::latest()))),
+			creep: Rw::new(Rl::new(PixelMap::from_elem((32, 32), Pixel::Empty))),
+			..Default::default()
+		})
+	}
+
+	fn unit(data: &Rs<DataForUnit>, tag: u64, type_id: UnitTypeId, x: f32, shield: f32) -> Unit {
+		let mut u = ProtoUnit::new();
+		u.set_tag(tag);
+		u.set_unit_type(type_id as u32);
+		u.set_alliance(if tag < 100 {
+			ProtoAlliance::value_Self
+		} else {
+			ProtoAlliance::Enemy
+		});
+		u.set_display_type(DisplayType::Visible);
+		u.mut_pos().set_x(x);
+		u.mut_pos().set_y(16.0);
+		u.set_radius(0.375);
+		u.set_health(45.0);
+		u.set_health_max(45.0);
+		u.set_shield(shield);
+		u.set_shield_max(shield);
+		u.set_build_progress(1.0);
+		let visibility = VisibilityMap::from_elem((32, 32), Visibility::Visible);
+		Unit::from_proto(Rs::clone(data), &visibility, &u)
+	}
+
+	fn fight(my: &[(UnitTypeId, f32)], enemy: &[(UnitTypeId, f32)]) -> CombatResult {
+		let data = data();
+		let group = |units: &[(UnitTypeId, f32)], first_tag: u64, x: f32| {
+			units
+				.iter()
+				.enumerate()
+				.map(|(i, (type_id, shield))| unit(&data, first_tag + i as u64, *type_id, x, *shield))
+				.collect::<Units>()
+		};
+		CombatSimulator::new().simulate(&group(my, 1, 14.0), &group(enemy, 101, 18.0))
+	}
+
+	#[test]
+	fn symmetric_fight_is_draw() {
+		for n in 1..4 {
+			let marines = vec![(UnitTypeId::Marine, 0.0); n];
+			let result = fight(&marines, &marines);
+			assert_eq!(result.winner, Winner::Draw, "{} vs {}", n, n);
+			assert!(result.my_survivors.is_empty() && result.enemy_survivors.is_empty());
+		}
+	}
+
+	#[test]
+	fn armor_and_shield_decide_fight() {
+		let armored = fight(&[(UnitTypeId::Marine, 0.0)], &[(UnitTypeId::Marauder, 0.0)]);
+		assert_eq!(armored.winner, Winner::Enemy);
+		let shielded = fight(&[(UnitTypeId::Marine, 10.0)], &[(UnitTypeId::Marine, 0.0)]);
+		assert_eq!(shielded.winner, Winner::My);
+		assert_eq!(shielded.my_survivors[0].shield, 0.0);
+	}
+
+	#[test]
+	fn damage_goes_to_shield_before_armor() {
+		let data = data();
+		let u = unit(&data, 1, UnitTypeId::Marauder, 10.0, 10.0);
+		let mut fighter = Fighter::new(&u, 0).unwrap();
+
+		// Shield absorbs the whole hit, armor isn't applied
+		fighter.take_hit(6.0, 5.0);
+		assert_eq!((fighter.shield, fighter.health), (4.0, 45.0));
+		// Excess damage goes to health reduced by armor
+		fighter.take_hit(6.0, 5.0);
+		assert_eq!((fighter.shield, fighter.health), (0.0, 44.0));
+		fighter.take_hit(6.0, 5.0);
+		assert_eq!(fighter.health, 39.0);
+
+		// Guardian shield reduces ranged damage, but not below minimum
+		fighter.has_guardian_shield = true;
+		fighter.take_hit(6.0, 5.0);
+		assert_eq!(fighter.health, 36.0);
+		fighter.take_hit(1.0, 5.0);
+		assert_eq!(fighter.health, 36.0 - MIN_DAMAGE as f32);
+		fighter.take_hit(6.0, 0.1);
+		assert_eq!(fighter.health, 30.0);
+	}
+}
@@ -13,19 +13,19 @@ use std::collections::HashMap;
 pub const GAME_SPEED: f32 = 1.4;
 /// Frames per second, calculated by `16 (default frames per second) * 1.4 (game speed)`.
 pub const FRAMES_PER_SECOND: f32 = 22.4;
+/// Minimum damage dealt by single attack, when armor is greater than damage.
+pub const MIN_DAMAGE: i32 = 1;
 
 /// Units under effect of raven's anit-armor missile have this buff.
 /// It reduces armor and shield armor by 3 (armor can be negative at this point).
-// #[cfg(windows)]
+///
+/// Older game versions use different buff, see [`VersionData::anti_armor_buff`](crate::version::VersionData::anti_armor_buff).
 pub const ANTI_ARMOR_BUFF: BuffId = BuffId::RavenShredderMissileArmorReductionUISubtruct;
-// #[cfg(unix)]
-// pub const ANTI_ARMOR_BUFF: BuffId = BuffId::RavenShredderMissileArmorReduction;
 /// Unit targeted by raven's anit-armor missile have this buff.
 pub const ANTI_ARMOR_TARGET: BuffId = BuffId::RavenShredderMissileTint;
 /// Units disabled by raven's interference matrix have this buff.
 pub const INTERFERENCE_MATRIX_BUFF: BuffId = BuffId::RavenScramblerMissile;
 
-// #[cfg(windows)]
 pub(crate) const INHIBITOR_IDS: [UnitTypeId; 6] = [
 	UnitTypeId::InhibitorZoneSmall,
 	UnitTypeId::InhibitorZoneMedium,
@@ -34,12 +34,6 @@ pub(crate) const INHIBITOR_IDS: [UnitTypeId; 6] = [
 	UnitTypeId::InhibitorZoneFlyingMedium,
 	UnitTypeId::InhibitorZoneFlyingLarge,
 ];
-/*#[cfg(unix)]
-pub(crate) const INHIBITOR_IDS: [UnitTypeId; 3] = [
-	UnitTypeId::InhibitorZoneSmall,
-	UnitTypeId::InhibitorZoneMedium,
-	UnitTypeId::InhibitorZoneLarge,
-];*/
 
 /// Structured values, specific for each race.
 #[derive(Clone)]
@@ -70,8 +64,8 @@ impl Default for RaceValues {
 	}
 }
 
-type BonusesForTarget = HashMap<TargetType, BonusesByAttribute>;
-type BonusesByAttribute = (Option<u32>, HashMap<Attribute, u32>);
+pub(crate) type BonusesForTarget = HashMap<TargetType, BonusesByAttribute>;
+pub(crate) type BonusesByAttribute = (Option<u32>, HashMap<Attribute, u32>);
 
 lazy_static! {
 	/// [`RaceValues`] mapped to each race.
@@ -296,6 +290,9 @@ lazy_static! {
 	];
 	/// Tech requirements mapped to different units.
 	///
+	/// Matches the latest game version, requirements of the current game are in
+	/// [`VersionData::tech_requirements`](crate::version::VersionData::tech_requirements).
+	///
 	/// Basic usage:
 	/// ```
 	/// if let Some(requirment) = TECH_REQUIREMENTS.get(unit_type) {
@@ -378,6 +375,8 @@ lazy_static! {
 		UnitTypeId::BanelingNest => UnitTypeId::Drone,
 		UnitTypeId::Banshee => UnitTypeId::Starport,
 		UnitTypeId::Barracks => UnitTypeId::SCV,
+		UnitTypeId::BarracksReactor => UnitTypeId::Barracks,
+		UnitTypeId::BarracksTechLab => UnitTypeId::Barracks,
 		UnitTypeId::Battlecruiser => UnitTypeId::Starport,
 		UnitTypeId::BroodLord => UnitTypeId::Corruptor,
 		UnitTypeId::Bunker => UnitTypeId::SCV,
@@ -398,6 +397,8 @@ lazy_static! {
 		UnitTypeId::EvolutionChamber => UnitTypeId::Drone,
 		UnitTypeId::Extractor => UnitTypeId::Drone,
 		UnitTypeId::Factory => UnitTypeId::SCV,
+		UnitTypeId::FactoryReactor => UnitTypeId::Factory,
+		UnitTypeId::FactoryTechLab => UnitTypeId::Factory,
 		UnitTypeId::FleetBeacon => UnitTypeId::Probe,
 		UnitTypeId::Forge => UnitTypeId::Probe,
 		UnitTypeId::FusionCore => UnitTypeId::SCV,
@@ -462,6 +463,8 @@ lazy_static! {
 		UnitTypeId::Stalker => UnitTypeId::Gateway,
 		UnitTypeId::Stargate => UnitTypeId::Probe,
 		UnitTypeId::Starport => UnitTypeId::SCV,
+		UnitTypeId::StarportReactor => UnitTypeId::Starport,
+		UnitTypeId::StarportTechLab => UnitTypeId::Starport,
 		UnitTypeId::SupplyDepot => UnitTypeId::SCV,
 		UnitTypeId::SwarmHostMP => UnitTypeId::Larva,
 		UnitTypeId::Tempest => UnitTypeId::Stargate,
@@ -488,6 +491,8 @@ lazy_static! {
 		UnitTypeId::BanelingNest => vec![UnitTypeId::Drone],
 		UnitTypeId::Banshee => vec![UnitTypeId::Starport],
 		UnitTypeId::Barracks => vec![UnitTypeId::SCV],
+		UnitTypeId::BarracksReactor => vec![UnitTypeId::Barracks],
+		UnitTypeId::BarracksTechLab => vec![UnitTypeId::Barracks],
 		UnitTypeId::Battlecruiser => vec![UnitTypeId::Starport],
 		UnitTypeId::BroodLord => vec![UnitTypeId::Corruptor],
 		UnitTypeId::Bunker => vec![UnitTypeId::SCV],
@@ -513,6 +518,8 @@ lazy_static! {
 		UnitTypeId::EvolutionChamber => vec![UnitTypeId::Drone],
 		UnitTypeId::Extractor => vec![UnitTypeId::Drone],
 		UnitTypeId::Factory => vec![UnitTypeId::SCV],
+		UnitTypeId::FactoryReactor => vec![UnitTypeId::Factory],
+		UnitTypeId::FactoryTechLab => vec![UnitTypeId::Factory],
 		UnitTypeId::FleetBeacon => vec![UnitTypeId::Probe],
 		UnitTypeId::Forge => vec![UnitTypeId::Probe],
 		UnitTypeId::FusionCore => vec![UnitTypeId::SCV],
@@ -581,6 +588,8 @@ lazy_static! {
 		UnitTypeId::Stalker => vec![UnitTypeId::Gateway, UnitTypeId::WarpGate],
 		UnitTypeId::Stargate => vec![UnitTypeId::Probe],
 		UnitTypeId::Starport => vec![UnitTypeId::SCV],
+		UnitTypeId::StarportReactor => vec![UnitTypeId::Starport],
+		UnitTypeId::StarportTechLab => vec![UnitTypeId::Starport],
 		UnitTypeId::SupplyDepot => vec![UnitTypeId::SCV],
 		UnitTypeId::SwarmHostMP => vec![UnitTypeId::Larva],
 		UnitTypeId::Tempest => vec![UnitTypeId::Stargate],
@@ -2,10 +2,10 @@
 
 use crate::{
 	geometry::{Point2, Point3},
-	ids::UnitTypeId,
+	ids::{DecodeId, UnitTypeId},
+	version::VersionData,
 	IntoProto,
 };
-use num_traits::ToPrimitive;
 use rustc_hash::FxHashSet;
 use sc2_proto::debug::{
 	DebugBox, DebugCommand as ProtoDebugCommand, DebugDraw as ProtoDebugDraw, DebugEndGame_EndResult,
@@ -191,15 +191,16 @@ pub(crate) enum DebugCommand {
 	EndGame(bool),
 	SetUnitValue(u64, UnitValue, u32),
 }
-impl IntoProto<ProtoDebugCommand> for &DebugCommand {
+impl IntoProto<ProtoDebugCommand> for (&DebugCommand, &VersionData) {
 	fn into_proto(self) -> ProtoDebugCommand {
+		let (command, version) = self;
 		let mut proto = ProtoDebugCommand::new();
-		match self {
+		match command {
 			DebugCommand::Draw(cmds) => proto.set_draw(cmds.into_proto()),
 			DebugCommand::GameState(cmd) => proto.set_game_state(cmd.into_proto()),
 			DebugCommand::CreateUnit(type_id, owner, pos, count) => {
 				let unit = proto.mut_create_unit();
-				unit.set_unit_type(type_id.to_u32().unwrap());
+				unit.set_unit_type(type_id.encode(version));
 				if let Some(owner) = owner {
 					unit.set_owner(*owner as i32);
 				}
@@ -0,0 +1,734 @@
+//! Environment for training agents against built-in AI, in the style of OpenAI Gym.
+//!
+//! Agent controls the game step by step with [`reset`](Env::reset) and [`step`](Env::step),
+//! while observations and rewards are computed from bot's state by configurable encoders.
+//! ```no_run
+//! use rust_sc2::{
+//!     env::{DiscreteActions, Env, GridEncoder, GridLayer, ScoreReward, UnitEncoder},
+//!     geometry::Size,
+//!     prelude::*,
+//! };
+//!
+//! #[bot]
+//! #[derive(Default)]
+//! struct Agent;
+//! impl Player for Agent {
+//!     fn get_player_settings(&self) -> PlayerSettings {
+//!         PlayerSettings::new(Race::Terran)
+//!     }
+//! }
+//!
+//! let mut bot = Agent::default();
+//! let runner = RunnerSingle::new(
+//!     &mut bot,
+//!     Computer::new(Race::Zerg, Difficulty::Easy, None),
+//!     "EternalEmpireLE",
+//!     None,
+//! );
+//! let mut env = Env::new(
+//!     runner,
+//!     (UnitEncoder::new(64), GridEncoder::new(vec![GridLayer::Pathing, GridLayer::Creep])),
+//!     DiscreteActions::new(vec![AbilityId::Attack, AbilityId::MoveMove], Size::new(16, 16)),
+//!     ScoreReward::new(|score| score.killed_value_units),
+//! );
+//!
+//! let (mut obs, _, mut done) = env.reset()?;
+//! while !done {
+//!     let actions = vec![/* (unit tag, action index) pairs chosen by agent from `obs` */];
+//!     let (next, reward, finished) = env.step(&actions)?;
+//!     obs = next;
+//!     done = finished;
+//! }
+//! # Ok::<(), rust_sc2::Error>(())
+//! ```
+//!
+//! Bot's own [`on_start`](Player::on_start) and [`on_step`](Player::on_step) are still called
+//! on every step, so scripted logic can be mixed with agent's actions.
+
+use crate::{
+	action::Target,
+	bot::Bot,
+	client::{no_observation_request, GameLoop, RunnerSingle, SC2Result},
+	game_data::AbilityTarget,
+	game_state::Alliance,
+	geometry::{Point2, Size},
+	ids::AbilityId,
+	player::GameResult,
+	score::Score,
+	Error, Player,
+};
+use ndarray::Array3;
+use sc2_proto::sc2api::Request;
+use std::ops::{Deref, DerefMut};
+
+/// Step of environment returned to agent: (observation, reward, done).
+pub type EnvStep<T> = (T, f32, bool);
+
+/// Environment, which plays games vs built-in AI with [`RunnerSingle`] step by step.
+pub struct Env<'a, B, O, A, R>
+where
+	B: Player + DerefMut<Target = Bot> + Deref<Target = Bot>,
+{
+	runner: RunnerSingle<'a, B>,
+	/// Encoder of observations given to agent.
+	pub observation: O,
+	/// Decoder of actions given by agent.
+	pub actions: A,
+	/// Function computing reward of every step.
+	pub reward: R,
+	game: Option<GameLoop>,
+	result: Option<GameResult>,
+}
+
+impl<'a, B, O, A, R> Env<'a, B, O, A, R>
+where
+	B: Player + DerefMut<Target = Bot> + Deref<Target = Bot>,
+	O: ObservationEncoder,
+	A: ActionEncoder,
+	R: RewardFunction,
+{
+	/// Constructs new environment, which plays games configured in given runner.
+	pub fn new(runner: RunnerSingle<'a, B>, observation: O, actions: A, reward: R) -> Self {
+		Self {
+			runner,
+			observation,
+			actions,
+			reward,
+			game: None,
+			result: None,
+		}
+	}
+
+	/// Starts new game and returns its first step, SC2 is launched if needed.
+	/// Game in progress is left.
+	pub fn reset(&mut self) -> SC2Result<EnvStep<O::Observation>> {
+		if self.runner.bot().api.is_none() {
+			self.runner.launch()?;
+		}
+		if self.game.take().is_some() && self.result.is_none() {
+			debug!("Leaving game in progress");
+			let mut req = Request::new();
+			req.mut_leave_game();
+			self.runner.bot().api()?.send(req)?;
+		}
+		self.result = None;
+
+		self.runner.start_game()?;
+		let realtime = self.runner.realtime;
+		let bot = self.runner.bot_mut();
+		let mut game = GameLoop::new(realtime);
+		let res = bot.api()?.send(GameLoop::first_observation_request())?;
+		self.result = game.step(bot, &res)?;
+		self.game = Some(game);
+		if let Some(result) = self.result {
+			self.finish(result)?;
+		}
+
+		let bot = self.runner.bot();
+		self.reward.reset(bot);
+		Ok((self.observation.encode(bot), 0.0, self.result.is_some()))
+	}
+
+	/// Gives actions to units and steps the game.
+	/// Returns [`GameEnded`](Error::GameEnded) if the game isn't started or already ended.
+	pub fn step(&mut self, actions: &[A::Action]) -> SC2Result<EnvStep<O::Observation>> {
+		if self.result.is_some() {
+			return Err(Error::GameEnded);
+		}
+		let game = self.game.as_mut().ok_or(Error::GameEnded)?;
+		let bot = self.runner.bot_mut();
+
+		for action in actions {
+			match self.actions.decode(bot, action) {
+				Some(action) => match bot.units.my.all.get(action.unit) {
+					Some(unit) => unit.command(action.ability, action.target, action.queue),
+					None => debug!("Unit {} given in action doesn't exist", action.unit),
+				},
+				None => debug!("Action can't be decoded"),
+			}
+		}
+
+		let mut requests = game.requests(bot);
+		let req = requests.pop().ok_or_else(no_observation_request)?;
+		for req in requests {
+			bot.api()?.send_request(req)?;
+		}
+		let res = bot.api()?.send(req)?;
+		self.result = game.step(bot, &res)?;
+		if let Some(result) = self.result {
+			self.finish(result)?;
+		}
+
+		let bot = self.runner.bot();
+		let reward = self.reward.reward(bot, self.result);
+		Ok((self.observation.encode(bot), reward, self.result.is_some()))
+	}
+
+	fn finish(&mut self, result: GameResult) -> SC2Result<()> {
+		debug!("Game finished");
+		self.runner.set_last_result(result);
+		self.runner.finish_game()
+	}
+
+	/// Returns result of the game for bot, `None` while it's in progress.
+	pub fn result(&self) -> Option<GameResult> {
+		self.result
+	}
+	/// Returns the bot playing games.
+	pub fn bot(&self) -> &B {
+		self.runner.bot()
+	}
+	/// Returns runner used to play games, it can be used to change map or opponent between games.
+	pub fn runner(&mut self) -> &mut RunnerSingle<'a, B> {
+		&mut self.runner
+	}
+
+	/// Manually closes SC2 client.
+	pub fn close(&mut self) {
+		self.runner.close();
+		self.game = None;
+	}
+}
+
+/// Encodes state of the bot into observation for agent.
+///
+/// Encoders can be combined in tuples, then observation is a tuple of their observations.
+pub trait ObservationEncoder {
+	/// Type of encoded observation.
+	type Observation;
+	/// Encodes current state of the bot.
+	fn encode(&mut self, bot: &Bot) -> Self::Observation;
+}
+impl<E1: ObservationEncoder, E2: ObservationEncoder> ObservationEncoder for (E1, E2) {
+	type Observation = (E1::Observation, E2::Observation);
+
+	fn encode(&mut self, bot: &Bot) -> Self::Observation {
+		(self.0.encode(bot), self.1.encode(bot))
+	}
+}
+impl<E1: ObservationEncoder, E2: ObservationEncoder, E3: ObservationEncoder> ObservationEncoder
+	for (E1, E2, E3)
+{
+	type Observation = (E1::Observation, E2::Observation, E3::Observation);
+
+	fn encode(&mut self, bot: &Bot) -> Self::Observation {
+		(self.0.encode(bot), self.1.encode(bot), self.2.encode(bot))
+	}
+}
+
+/// Number of features of every unit in [`UnitEncoder`].
+pub const UNIT_FEATURES: usize = 8;
+
+/// Encodes visible units into list of features, which are:
+/// type id, alliance (1 - own, 2 - ally, 3 - neutral, 4 - enemy), x, y,
+/// health percentage, shield percentage, energy and build progress.
+pub struct UnitEncoder {
+	/// Maximum number of encoded units, the rest units are skipped.
+	pub max_units: usize,
+}
+impl UnitEncoder {
+	/// Constructs new encoder of at most given number of units.
+	pub fn new(max_units: usize) -> Self {
+		Self { max_units }
+	}
+}
+impl ObservationEncoder for UnitEncoder {
+	type Observation = Vec<[f32; UNIT_FEATURES]>;
+
+	fn encode(&mut self, bot: &Bot) -> Self::Observation {
+		bot.units
+			.all
+			.iter()
+			.take(self.max_units)
+			.map(|u| {
+				let pos = u.position();
+				[
+					u.type_id() as u32 as f32,
+					match u.alliance() {
+						Alliance::Own => 1.0,
+						Alliance::Ally => 2.0,
+						Alliance::Neutral => 3.0,
+						Alliance::Enemy => 4.0,
+					},
+					pos.x,
+					pos.y,
+					u.health_percentage().unwrap_or(0.0),
+					u.shield_percentage().unwrap_or(0.0),
+					u.energy().unwrap_or(0) as f32,
+					u.build_progress(),
+				]
+			})
+			.collect()
+	}
+}
+
+/// Grid of the map, which can be encoded with [`GridEncoder`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GridLayer {
+	/// `1` where ground units can walk.
+	Pathing,
+	/// `1` where structures can be built.
+	Placement,
+	/// Terrain height from `0` to `1`.
+	TerrainHeight,
+	/// `1` where creep is.
+	Creep,
+	/// `0` for hidden, `0.5` for fogged and `1` for visible positions.
+	Visibility,
+}
+
+/// Encodes grids of the map into array with shape (layers, x, y).
+pub struct GridEncoder {
+	/// Encoded grids, in order of the first axis.
+	pub layers: Vec<GridLayer>,
+}
+impl GridEncoder {
+	/// Constructs new encoder of given grids.
+	pub fn new(layers: Vec<GridLayer>) -> Self {
+		Self { layers }
+	}
+}
+impl ObservationEncoder for GridEncoder {
+	type Observation = Array3<f32>;
+
+	fn encode(&mut self, bot: &Bot) -> Self::Observation {
+		let size = bot.game_info.map_size;
+		let height = &bot.game_info.terrain_height;
+		Array3::from_shape_fn((self.layers.len(), size.x, size.y), |(layer, x, y)| {
+			let pos = (x, y);
+			let flag = |value: bool| if value { 1.0 } else { 0.0 };
+			match self.layers[layer] {
+				GridLayer::Pathing => flag(bot.is_pathable(pos)),
+				GridLayer::Placement => flag(bot.is_placeable(pos)),
+				GridLayer::TerrainHeight => height.get(pos).map_or(0.0, |h| *h as f32 / 255.0),
+				GridLayer::Creep => flag(bot.has_creep(pos)),
+				GridLayer::Visibility => {
+					if bot.is_visible(pos) {
+						1.0
+					} else if bot.is_fogged(pos) {
+						0.5
+					} else {
+						0.0
+					}
+				}
+			}
+		})
+	}
+}
+
+/// Computes reward of every step.
+///
+/// Closures taking bot and result of the game (`None` while it's in progress) can be used too,
+/// rewards of functions combined in tuples are summed.
+pub trait RewardFunction {
+	/// Called on the first step of every game.
+	fn reset(&mut self, _bot: &Bot) {}
+	/// Returns reward of the last step.
+	fn reward(&mut self, bot: &Bot, result: Option<GameResult>) -> f32;
+}
+impl<F: FnMut(&Bot, Option<GameResult>) -> f32> RewardFunction for F {
+	fn reward(&mut self, bot: &Bot, result: Option<GameResult>) -> f32 {
+		self(bot, result)
+	}
+}
+impl<R1: RewardFunction, R2: RewardFunction> RewardFunction for (R1, R2) {
+	fn reset(&mut self, bot: &Bot) {
+		self.0.reset(bot);
+		self.1.reset(bot);
+	}
+	fn reward(&mut self, bot: &Bot, result: Option<GameResult>) -> f32 {
+		self.0.reward(bot, result) + self.1.reward(bot, result)
+	}
+}
+
+/// Rewards agent with change of given score value since the last step
+/// and with fixed values for the result of the game.
+pub struct ScoreReward {
+	score: fn(&Score) -> f32,
+	last: f32,
+	/// Reward given for victory.
+	pub victory: f32,
+	/// Reward given for defeat.
+	pub defeat: f32,
+}
+impl ScoreReward {
+	/// Constructs new reward from given score value, game results aren't rewarded.
+	pub fn new(score: fn(&Score) -> f32) -> Self {
+		Self {
+			score,
+			last: 0.0,
+			victory: 0.0,
+			defeat: 0.0,
+		}
+	}
+	/// Constructs new reward from total score.
+	pub fn total() -> Self {
+		Self::new(|score| score.total_score as f32)
+	}
+	/// Sets rewards given for victory and defeat.
+	pub fn with_result(mut self, victory: f32, defeat: f32) -> Self {
+		self.victory = victory;
+		self.defeat = defeat;
+		self
+	}
+}
+impl RewardFunction for ScoreReward {
+	fn reset(&mut self, bot: &Bot) {
+		self.last = (self.score)(&bot.state.observation.score);
+	}
+	fn reward(&mut self, bot: &Bot, result: Option<GameResult>) -> f32 {
+		let current = (self.score)(&bot.state.observation.score);
+		let delta = current - self.last;
+		self.last = current;
+		delta
+			+ match result {
+				Some(GameResult::Victory) => self.victory,
+				Some(GameResult::Defeat) => self.defeat,
+				_ => 0.0,
+			}
+	}
+}
+
+/// Command given to unit by agent.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct UnitAction {
+	/// Tag of the unit.
+	pub unit: u64,
+	/// Ability used by the unit.
+	pub ability: AbilityId,
+	/// Target of the ability.
+	pub target: Target,
+	/// Queue command or not.
+	pub queue: bool,
+}
+
+/// Decodes actions given by agent into commands of own units.
+pub trait ActionEncoder {
+	/// Type of action given by agent.
+	type Action;
+	/// Decodes action, `None` if it's invalid.
+	fn decode(&self, bot: &Bot, action: &Self::Action) -> Option<UnitAction>;
+}
+
+/// Actions given as commands directly.
+pub struct RawActions;
+impl ActionEncoder for RawActions {
+	type Action = UnitAction;
+
+	fn decode(&self, _bot: &Bot, action: &Self::Action) -> Option<UnitAction> {
+		Some(*action)
+	}
+}
+
+/// Discrete actions, given in (unit tag, action index) pairs.
+///
+/// Index selects ability and its target, where targets of every ability are:
+/// no target, centers of cells in grid of given resolution over playable area
+/// and the first [`unit_targets`](Self::unit_targets) units in the order of [`UnitEncoder`].
+/// So index is `ability_index * (1 + x * y + unit_targets) + target` where target `0` is no target,
+/// target `1 + cell_x + cell_y * x` is cell of the grid and target `1 + x * y + i` is the `i`-th unit.
+///
+/// Actions with target not suitable for the ability are invalid.
+pub struct DiscreteActions {
+	/// Abilities available to agent.
+	pub abilities: Vec<AbilityId>,
+	/// Resolution of the grid of target positions.
+	pub resolution: Size,
+	/// Number of units, which can be targeted. [Default: `0`]
+	pub unit_targets: usize,
+}
+impl DiscreteActions {
+	/// Constructs new action space over given abilities and grid of target positions.
+	pub fn new(abilities: Vec<AbilityId>, resolution: Size) -> Self {
+		Self {
+			abilities,
+			resolution,
+			unit_targets: 0,
+		}
+	}
+	/// Sets number of units, which can be targeted (usually `max_units` of [`UnitEncoder`]).
+	pub fn with_unit_targets(mut self, units: usize) -> Self {
+		self.unit_targets = units;
+		self
+	}
+	fn cells(&self) -> usize {
+		self.resolution.x * self.resolution.y
+	}
+	fn targets(&self) -> usize {
+		1 + self.cells() + self.unit_targets
+	}
+	/// Returns number of all possible actions.
+	pub fn size(&self) -> usize {
+		self.abilities.len() * self.targets()
+	}
+}
+impl ActionEncoder for DiscreteActions {
+	type Action = (u64, usize);
+
+	fn decode(&self, bot: &Bot, &(unit, index): &Self::Action) -> Option<UnitAction> {
+		let ability = *self.abilities.get(index / self.targets())?;
+		let target = match index % self.targets() {
+			0 => Target::None,
+			cell if cell <= self.cells() => {
+				let cell = cell - 1;
+				Target::Pos(playable_pos(
+					bot,
+					(cell % self.resolution.x) as f32 + 0.5,
+					(cell / self.resolution.x) as f32 + 0.5,
+					self.resolution,
+				))
+			}
+			target => Target::Tag(bot.units.all.iter().nth(target - 1 - self.cells())?.tag()),
+		};
+		if !is_valid_target(bot, ability, &target) {
+			return None;
+		}
+		Some(UnitAction {
+			unit,
+			ability,
+			target,
+			queue: false,
+		})
+	}
+}
+
+/// Continuous actions, given in (unit tag, ability index, position) tuples,
+/// where position is from `(0, 0)` to `(1, 1)` over playable area.
+///
+/// Position is ignored for abilities without target,
+/// abilities which target only units are used on the closest unit to the position.
+pub struct ContinuousActions {
+	/// Abilities available to agent.
+	pub abilities: Vec<AbilityId>,
+}
+impl ContinuousActions {
+	/// Constructs new action space over given abilities.
+	pub fn new(abilities: Vec<AbilityId>) -> Self {
+		Self { abilities }
+	}
+}
+impl ActionEncoder for ContinuousActions {
+	type Action = (u64, usize, Point2);
+
+	fn decode(&self, bot: &Bot, &(unit, index, pos): &Self::Action) -> Option<UnitAction> {
+		let ability = *self.abilities.get(index)?;
+		let pos = playable_pos(bot, pos.x.clamp(0.0, 1.0), pos.y.clamp(0.0, 1.0), Size::new(1, 1));
+		let target = match bot.game_data.abilities.get(&ability).map(|data| data.target) {
+			Some(AbilityTarget::None) => Target::None,
+			Some(AbilityTarget::Unit) => Target::Tag(bot.units.all.closest(pos)?.tag()),
+			_ => Target::Pos(pos),
+		};
+		Some(UnitAction {
+			unit,
+			ability,
+			target,
+			queue: false,
+		})
+	}
+}
+
+/// Checks if target is suitable for the ability, any target is valid if ability has no data.
+fn is_valid_target(bot: &Bot, ability: AbilityId, target: &Target) -> bool {
+	let kind = match bot.game_data.abilities.get(&ability) {
+		Some(data) => data.target,
+		None => return true,
+	};
+	matches!(
+		(kind, target),
+		(AbilityTarget::None, Target::None)
+			| (AbilityTarget::Point, Target::Pos(_))
+			| (AbilityTarget::Unit, Target::Tag(_))
+			| (AbilityTarget::PointOrUnit, Target::Pos(_) | Target::Tag(_))
+			| (AbilityTarget::PointOrNone, Target::None | Target::Pos(_))
+	)
+}
+
+/// Maps position on grid of given resolution to position in playable area.
+fn playable_pos(bot: &Bot, x: f32, y: f32, resolution: Size) -> Point2 {
+	let area = bot.game_info.playable_area;
+	Point2::new(
+		area.x0 as f32 + x * (area.x1 - area.x0) as f32 / resolution.x as f32,
+		area.y0 as f32 + y * (area.y1 - area.y0) as f32 / resolution.y as f32,
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		bot::{Rl, Rs, Rw},
+		game_data::GameData,
+		geometry::Rect,
+		ids::UnitTypeId,
+		pixel_map::{ByteMap, Pixel, PixelMap, Visibility, VisibilityMap},
+		unit::Unit,
+		units::Units,
+		version, FromProto,
+	};
+	use sc2_proto::{
+		data::{AbilityData as ProtoAbilityData, AbilityData_Target},
+		raw::{Alliance as ProtoAlliance, DisplayType, Unit as ProtoUnit},
+		sc2api::ResponseData,
+	};
+
+	fn unit(bot: &Bot, tag: u64, alliance: ProtoAlliance, x: f32, y: f32) -> Unit {
+		let mut u = ProtoUnit::new();
+		u.set_tag(tag);
+		u.set_unit_type(UnitTypeId::Marine as u32);
+		u.set_alliance(alliance);
+		u.set_display_type(DisplayType::Visible);
+		u.mut_pos().set_x(x);
+		u.mut_pos().set_y(y);
+		u.set_radius(0.375);
+		u.set_health(30.0);
+		u.set_health_max(40.0);
+		u.set_energy(50.0);
+		u.set_build_progress(1.0);
+		let visibility = VisibilityMap::from_elem((8, 8), Visibility::Visible);
+		Unit::from_proto(Rs::clone(&bot.data_for_unit), &visibility, &u)
+	}
+
+	// 8x8 map with playable area from (2, 2) to (6, 6) and 3 units
+	fn bot() -> Bot {
+		let mut res = ResponseData::new();
+		for (ability, target) in [
+			(AbilityId::Attack, AbilityData_Target::PointOrUnit),
+			(AbilityId::Stop, AbilityData_Target::None),
+			(AbilityId::MedivacHealHeal, AbilityData_Target::Unit),
+		] {
+			let mut data = ProtoAbilityData::new();
+			data.set_ability_id(ability as u32);
+			data.set_available(true);
+			data.set_target(target);
+			res.mut_abilities().push(data);
+		}
+
+		let mut bot = Bot::default();
+		bot.game_data = Rs::new(GameData::from_proto((res, version::latest())));
+		bot.game_info.map_size = Size::new(8, 8);
+		bot.game_info.playable_area = Rect::new(2, 2, 6, 6);
+		let units = [
+			unit(&bot, 1, ProtoAlliance::value_Self, 2.5, 2.5),
+			unit(&bot, 2, ProtoAlliance::Neutral, 4.0, 5.0),
+			unit(&bot, 3, ProtoAlliance::Enemy, 5.5, 5.5),
+		]
+		.into_iter()
+		.collect::<Units>();
+		bot.update_units(units);
+		bot
+	}
+
+	#[test]
+	fn discrete_actions_decode_index() {
+		let bot = bot();
+		let actions = DiscreteActions::new(vec![AbilityId::Attack, AbilityId::Stop], Size::new(2, 2))
+			.with_unit_targets(2);
+		assert_eq!(actions.size(), 2 * (1 + 4 + 2));
+
+		let target = |index| actions.decode(&bot, &(1, index)).map(|a| (a.ability, a.target));
+		let attack = |target| Some((AbilityId::Attack, target));
+		// Attack can't be used without target
+		assert_eq!(target(0), None);
+		assert_eq!(target(1), attack(Target::Pos(Point2::new(3.0, 3.0))));
+		assert_eq!(target(2), attack(Target::Pos(Point2::new(5.0, 3.0))));
+		assert_eq!(target(3), attack(Target::Pos(Point2::new(3.0, 5.0))));
+		assert_eq!(target(4), attack(Target::Pos(Point2::new(5.0, 5.0))));
+		assert_eq!(target(5), attack(Target::Tag(1)));
+		assert_eq!(target(6), attack(Target::Tag(2)));
+		// Stop is used only without target
+		assert_eq!(target(7), Some((AbilityId::Stop, Target::None)));
+		for index in 8..14 {
+			assert_eq!(target(index), None, "index {}", index);
+		}
+		assert_eq!(target(actions.size()), None);
+
+		let action = actions.decode(&bot, &(1, 5)).unwrap();
+		assert_eq!(action.unit, 1);
+		assert!(!action.queue);
+	}
+
+	#[test]
+	fn discrete_actions_skip_missing_units() {
+		let bot = bot();
+		let actions =
+			DiscreteActions::new(vec![AbilityId::MedivacHealHeal], Size::new(1, 1)).with_unit_targets(4);
+		let target = |index| actions.decode(&bot, &(1, index)).map(|a| a.target);
+		assert_eq!(target(0), None);
+		assert_eq!(target(1), None);
+		assert_eq!(target(4), Some(Target::Tag(3)));
+		assert_eq!(target(5), None);
+	}
+
+	#[test]
+	fn continuous_actions_follow_ability_target() {
+		let bot = bot();
+		let actions = ContinuousActions::new(vec![
+			AbilityId::Attack,
+			AbilityId::Stop,
+			AbilityId::MedivacHealHeal,
+		]);
+		let target = |index, x, y| {
+			actions
+				.decode(&bot, &(1, index, Point2::new(x, y)))
+				.map(|a| a.target)
+		};
+		assert_eq!(target(0, 0.5, 0.25), Some(Target::Pos(Point2::new(4.0, 3.0))));
+		assert_eq!(target(0, 2.0, -1.0), Some(Target::Pos(Point2::new(6.0, 2.0))));
+		assert_eq!(target(1, 0.5, 0.5), Some(Target::None));
+		assert_eq!(target(2, 0.9, 0.9), Some(Target::Tag(3)));
+		assert_eq!(target(3, 0.5, 0.5), None);
+	}
+
+	#[test]
+	fn unit_encoder_features() {
+		let bot = bot();
+		let units = UnitEncoder::new(2).encode(&bot);
+		assert_eq!(units.len(), 2);
+		let marine = UnitTypeId::Marine as u32 as f32;
+		assert_eq!(units[0], [marine, 1.0, 2.5, 2.5, 0.75, 0.0, 50.0, 1.0]);
+		assert_eq!(units[1], [marine, 3.0, 4.0, 5.0, 0.75, 0.0, 50.0, 1.0]);
+		assert_eq!(UnitEncoder::new(64).encode(&bot)[2][1], 4.0);
+	}
+
+	#[test]
+	fn grid_encoder_layers() {
+		let mut bot = bot();
+		let mut pathing = PixelMap::from_elem((8, 8), Pixel::Set);
+		pathing[(1, 2)] = Pixel::Empty;
+		let mut placement = PixelMap::from_elem((8, 8), Pixel::Set);
+		placement[(3, 4)] = Pixel::Empty;
+		let mut height = ByteMap::from_elem((8, 8), 0);
+		height[(5, 6)] = 255;
+		let mut creep = PixelMap::from_elem((8, 8), Pixel::Set);
+		creep[(7, 0)] = Pixel::Empty;
+		let mut visibility = VisibilityMap::from_elem((8, 8), Visibility::Hidden);
+		visibility[(0, 0)] = Visibility::Visible;
+		visibility[(0, 1)] = Visibility::Fogged;
+		bot.pathing_grid = pathing;
+		bot.placement_grid = placement;
+		bot.game_info.terrain_height = Rs::new(height);
+		bot.state.observation.raw.creep = Rw::new(Rl::new(creep));
+		bot.state.observation.raw.visibility = visibility;
+
+		let layers = vec![
+			GridLayer::Pathing,
+			GridLayer::Placement,
+			GridLayer::TerrainHeight,
+			GridLayer::Creep,
+			GridLayer::Visibility,
+		];
+		let grid = GridEncoder::new(layers).encode(&bot);
+		assert_eq!(grid.shape(), &[5, 8, 8]);
+		for (layer, pos, value) in [
+			(0, (1, 2), 1.0),
+			(1, (3, 4), 1.0),
+			(2, (5, 6), 1.0),
+			(3, (7, 0), 1.0),
+			(4, (0, 0), 1.0),
+			(4, (0, 1), 0.5),
+		] {
+			assert_eq!(grid[(layer, pos.0, pos.1)], value, "layer {} at {:?}", layer, pos);
+		}
+		assert_eq!(grid.sum(), 5.5);
+	}
+}
@@ -0,0 +1,132 @@
+//! Error type returned by the library.
+
+use protobuf::ProtobufError;
+use std::{error::Error as StdError, fmt, io};
+
+/// Error returned in [`SC2Result`](crate::SC2Result).
+///
+/// Errors from bot's own code can be returned as [`Other`](Error::Other),
+/// using [`Error::other`] or conversions from strings.
+#[derive(Debug)]
+pub enum Error {
+	/// SC2 API responded with an error.
+	Proto(ProtoError),
+	/// Connection with SC2 failed.
+	Transport(Box<tungstenite::Error>),
+	/// Protobuf message can't be encoded or decoded.
+	Protobuf(ProtobufError),
+	/// Input/output error (e.g. when saving replay).
+	Io(io::Error),
+	/// SC2 process can't be launched.
+	Launch(io::Error),
+	/// Id received from the game doesn't exist in the library,
+	/// returned by [`DecodeId::decode`](crate::ids::DecodeId::decode).
+	UnknownId {
+		/// Name of the id type (e.g. `"AbilityId"`).
+		kind: &'static str,
+		/// Received value.
+		value: u64,
+	},
+	/// Request can't be made, because the game has already ended.
+	GameEnded,
+	/// Request made before bot was connected to the game.
+	ApiNotInitialized,
+	/// Any other error, e.g. returned from bot's code.
+	Other(Box<dyn StdError + Send + Sync>),
+}
+impl Error {
+	/// Wraps any error into [`Other`](Error::Other).
+	pub fn other<E: Into<Box<dyn StdError + Send + Sync>>>(error: E) -> Self {
+		Error::Other(error.into())
+	}
+}
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Error::Proto(e) => write!(f, "SC2 API error: {}", e),
+			Error::Transport(e) => write!(f, "Connection error: {}", e),
+			Error::Protobuf(e) => write!(f, "Protobuf error: {}", e),
+			Error::Io(e) => write!(f, "IO error: {}", e),
+			Error::Launch(e) => write!(f, "Can't launch SC2 process: {}", e),
+			Error::UnknownId { kind, value } => write!(f, "There's no `{}` with value {}", kind, value),
+			Error::GameEnded => write!(f, "Game has ended"),
+			Error::ApiNotInitialized => write!(f, "API is not initialized"),
+			Error::Other(e) => write!(f, "{}", e),
+		}
+	}
+}
+impl StdError for Error {
+	fn source(&self) -> Option<&(dyn StdError + 'static)> {
+		match self {
+			Error::Proto(e) => Some(e),
+			Error::Transport(e) => Some(e.as_ref()),
+			Error::Protobuf(e) => Some(e),
+			Error::Io(e) | Error::Launch(e) => Some(e),
+			Error::Other(e) => Some(e.as_ref()),
+			_ => None,
+		}
+	}
+}
+
+impl From<ProtoError> for Error {
+	fn from(e: ProtoError) -> Self {
+		Error::Proto(e)
+	}
+}
+impl From<tungstenite::Error> for Error {
+	fn from(e: tungstenite::Error) -> Self {
+		Error::Transport(Box::new(e))
+	}
+}
+impl From<ProtobufError> for Error {
+	fn from(e: ProtobufError) -> Self {
+		Error::Protobuf(e)
+	}
+}
+impl From<io::Error> for Error {
+	fn from(e: io::Error) -> Self {
+		Error::Io(e)
+	}
+}
+impl From<Box<dyn StdError + Send + Sync>> for Error {
+	fn from(e: Box<dyn StdError + Send + Sync>) -> Self {
+		Error::Other(e)
+	}
+}
+impl From<&str> for Error {
+	fn from(e: &str) -> Self {
+		Error::Other(e.into())
+	}
+}
+impl From<String> for Error {
+	fn from(e: String) -> Self {
+		Error::Other(e.into())
+	}
+}
+
+/// Error returned by SC2 API in response on request.
+#[derive(Debug, Clone)]
+pub struct ProtoError {
+	/// Error code (e.g. `"MissingMap"`), or error message if response has no code.
+	pub error: String,
+	/// Additional details given by SC2.
+	pub details: String,
+}
+impl ProtoError {
+	pub(crate) fn new<E: fmt::Debug>(error: E, details: &str) -> Self {
+		Self {
+			error: format!("{:?}", error),
+			details: details.to_string(),
+		}
+	}
+}
+impl fmt::Display for ProtoError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if self.details.is_empty() {
+			write!(f, "{}", self.error)
+		} else {
+			write!(f, "{}: {}", self.error, self.details)
+		}
+	}
+}
+impl StdError for ProtoError {}
@@ -0,0 +1,227 @@
+//! Feature layer and rendered interfaces of the game, disabled by default.
+//!
+//! Enable them in [`PlayerSettings`](crate::PlayerSettings),
+//! then decoded layers will be available in [`state.observation`](crate::game_state::Observation):
+//! ```
+//! use rust_sc2::{feature_layer::SpatialSettings, geometry::Size, prelude::*};
+//!
+//! let settings = PlayerSettings::new(Race::Terran)
+//!     .with_feature_layer(SpatialSettings::new(Size::new(84, 84), Size::new(64, 64)));
+//! ```
+//! Layers are indexed by (x, y) the same way as other maps, e.g.
+//! `self.state.observation.feature_layer.as_ref().unwrap().screen.unit_type[(x, y)]`.
+
+use crate::{
+	geometry::Size,
+	pixel_map::{LayerMap, RgbMap},
+	FromProto, IntoProto,
+};
+use sc2_proto::{
+	common::Size2DI,
+	sc2api::SpatialCameraSetup,
+	spatial::{
+		FeatureLayers as ProtoFeatureLayers, FeatureLayersMinimap as ProtoFeatureLayersMinimap,
+		ObservationFeatureLayer, ObservationRender,
+	},
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Resolutions and camera settings of feature layer or rendered interface.
+#[derive(Debug, Clone, Copy)]
+pub struct SpatialSettings {
+	/// Resolution of screen layers.
+	pub screen: Size,
+	/// Resolution of minimap layers.
+	pub minimap: Size,
+	/// Width of the camera in world units.
+	pub width: f32,
+	/// Crop minimap to playable area of the map.
+	pub crop_to_playable_area: bool,
+	/// Return layers with information, which is unavailable for human players.
+	pub allow_cheating_layers: bool,
+}
+impl SpatialSettings {
+	/// Constructs new settings with given resolutions of screen and minimap.
+	pub fn new(screen: Size, minimap: Size) -> Self {
+		Self {
+			screen,
+			minimap,
+			width: 24.0,
+			crop_to_playable_area: false,
+			allow_cheating_layers: false,
+		}
+	}
+	/// Sets width of the camera in world units.
+	pub fn with_width(mut self, width: f32) -> Self {
+		self.width = width;
+		self
+	}
+	/// Sets `crop_to_playable_area` to a given value.
+	pub fn crop_to_playable_area(mut self, val: bool) -> Self {
+		self.crop_to_playable_area = val;
+		self
+	}
+	/// Sets `allow_cheating_layers` to a given value.
+	pub fn allow_cheating_layers(mut self, val: bool) -> Self {
+		self.allow_cheating_layers = val;
+		self
+	}
+}
+impl IntoProto<SpatialCameraSetup> for SpatialSettings {
+	fn into_proto(self) -> SpatialCameraSetup {
+		let size_into_proto = |size: Size| {
+			let mut res = Size2DI::new();
+			res.set_x(size.x as i32);
+			res.set_y(size.y as i32);
+			res
+		};
+		let mut setup = SpatialCameraSetup::new();
+		setup.set_resolution(size_into_proto(self.screen));
+		setup.set_minimap_resolution(size_into_proto(self.minimap));
+		setup.set_width(self.width);
+		setup.set_crop_to_playable_area(self.crop_to_playable_area);
+		setup.set_allow_cheating_layers(self.allow_cheating_layers);
+		setup
+	}
+}
+
+/// Decoded layers of feature layer interface.
+///
+/// Layers which weren't sent by the game are empty.
+#[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FeatureLayers {
+	/// Layers of the screen.
+	pub screen: ScreenLayers,
+	/// Layers of the minimap.
+	pub minimap: MinimapLayers,
+}
+impl FromProto<&ObservationFeatureLayer> for FeatureLayers {
+	fn from_proto(layers: &ObservationFeatureLayer) -> Self {
+		Self {
+			screen: ScreenLayers::from_proto(layers.get_renders()),
+			minimap: MinimapLayers::from_proto(layers.get_minimap_renders()),
+		}
+	}
+}
+
+/// Screen layers of feature layer interface.
+#[allow(missing_docs)]
+#[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScreenLayers {
+	pub height_map: LayerMap,
+	pub visibility_map: LayerMap,
+	pub creep: LayerMap,
+	pub power: LayerMap,
+	pub player_id: LayerMap,
+	pub unit_type: LayerMap,
+	pub selected: LayerMap,
+	pub unit_hit_points: LayerMap,
+	pub unit_hit_points_ratio: LayerMap,
+	pub unit_energy: LayerMap,
+	pub unit_energy_ratio: LayerMap,
+	pub unit_shields: LayerMap,
+	pub unit_shields_ratio: LayerMap,
+	pub player_relative: LayerMap,
+	pub unit_density_aa: LayerMap,
+	pub unit_density: LayerMap,
+	pub effects: LayerMap,
+	pub hallucinations: LayerMap,
+	pub cloaked: LayerMap,
+	pub blip: LayerMap,
+	pub buffs: LayerMap,
+	pub buff_duration: LayerMap,
+	pub active: LayerMap,
+	pub build_progress: LayerMap,
+	pub buildable: LayerMap,
+	pub pathable: LayerMap,
+	pub placeholder: LayerMap,
+}
+impl FromProto<&ProtoFeatureLayers> for ScreenLayers {
+	fn from_proto(layers: &ProtoFeatureLayers) -> Self {
+		Self {
+			height_map: LayerMap::from_proto(layers.get_height_map()),
+			visibility_map: LayerMap::from_proto(layers.get_visibility_map()),
+			creep: LayerMap::from_proto(layers.get_creep()),
+			power: LayerMap::from_proto(layers.get_power()),
+			player_id: LayerMap::from_proto(layers.get_player_id()),
+			unit_type: LayerMap::from_proto(layers.get_unit_type()),
+			selected: LayerMap::from_proto(layers.get_selected()),
+			unit_hit_points: LayerMap::from_proto(layers.get_unit_hit_points()),
+			unit_hit_points_ratio: LayerMap::from_proto(layers.get_unit_hit_points_ratio()),
+			unit_energy: LayerMap::from_proto(layers.get_unit_energy()),
+			unit_energy_ratio: LayerMap::from_proto(layers.get_unit_energy_ratio()),
+			unit_shields: LayerMap::from_proto(layers.get_unit_shields()),
+			unit_shields_ratio: LayerMap::from_proto(layers.get_unit_shields_ratio()),
+			player_relative: LayerMap::from_proto(layers.get_player_relative()),
+			unit_density_aa: LayerMap::from_proto(layers.get_unit_density_aa()),
+			unit_density: LayerMap::from_proto(layers.get_unit_density()),
+			effects: LayerMap::from_proto(layers.get_effects()),
+			hallucinations: LayerMap::from_proto(layers.get_hallucinations()),
+			cloaked: LayerMap::from_proto(layers.get_cloaked()),
+			blip: LayerMap::from_proto(layers.get_blip()),
+			buffs: LayerMap::from_proto(layers.get_buffs()),
+			buff_duration: LayerMap::from_proto(layers.get_buff_duration()),
+			active: LayerMap::from_proto(layers.get_active()),
+			build_progress: LayerMap::from_proto(layers.get_build_progress()),
+			buildable: LayerMap::from_proto(layers.get_buildable()),
+			pathable: LayerMap::from_proto(layers.get_pathable()),
+			placeholder: LayerMap::from_proto(layers.get_placeholder()),
+		}
+	}
+}
+
+/// Minimap layers of feature layer interface.
+#[allow(missing_docs)]
+#[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MinimapLayers {
+	pub height_map: LayerMap,
+	pub visibility_map: LayerMap,
+	pub creep: LayerMap,
+	pub camera: LayerMap,
+	pub player_id: LayerMap,
+	pub player_relative: LayerMap,
+	pub selected: LayerMap,
+	pub alerts: LayerMap,
+	pub buildable: LayerMap,
+	pub pathable: LayerMap,
+	pub unit_type: LayerMap,
+}
+impl FromProto<&ProtoFeatureLayersMinimap> for MinimapLayers {
+	fn from_proto(layers: &ProtoFeatureLayersMinimap) -> Self {
+		Self {
+			height_map: LayerMap::from_proto(layers.get_height_map()),
+			visibility_map: LayerMap::from_proto(layers.get_visibility_map()),
+			creep: LayerMap::from_proto(layers.get_creep()),
+			camera: LayerMap::from_proto(layers.get_camera()),
+			player_id: LayerMap::from_proto(layers.get_player_id()),
+			player_relative: LayerMap::from_proto(layers.get_player_relative()),
+			selected: LayerMap::from_proto(layers.get_selected()),
+			alerts: LayerMap::from_proto(layers.get_alerts()),
+			buildable: LayerMap::from_proto(layers.get_buildable()),
+			pathable: LayerMap::from_proto(layers.get_pathable()),
+			unit_type: LayerMap::from_proto(layers.get_unit_type()),
+		}
+	}
+}
+
+/// Decoded images of rendered interface.
+#[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RenderedLayers {
+	/// Image of the screen.
+	pub map: RgbMap,
+	/// Image of the minimap.
+	pub minimap: RgbMap,
+}
+impl FromProto<&ObservationRender> for RenderedLayers {
+	fn from_proto(render: &ObservationRender) -> Self {
+		Self {
+			map: RgbMap::from_proto(render.get_map()),
+			minimap: RgbMap::from_proto(render.get_minimap()),
+		}
+	}
+}
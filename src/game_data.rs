@@ -2,11 +2,11 @@
 #![allow(missing_docs)]
 
 use crate::{
-	ids::{AbilityId, BuffId, EffectId, UnitTypeId, UpgradeId},
+	ids::{AbilityId, BuffId, DecodeId, EffectId, UnitTypeId, UpgradeId},
 	player::Race,
+	version::VersionData,
 	FromProto, TryFromProto,
 };
-use num_traits::FromPrimitive;
 use rustc_hash::FxHashMap;
 use sc2_proto::{
 	data::{
@@ -35,40 +35,103 @@ pub struct GameData {
 	/// Information about effects mapped to `EffectId`s.
 	pub effects: FxHashMap<EffectId, EffectData>,
 }
-impl FromProto<ResponseData> for GameData {
-	fn from_proto(data: ResponseData) -> Self {
+impl GameData {
+	/// Returns full cost of building given unit type, without any corrections.
+	pub fn unit_api_cost(&self, unit: UnitTypeId) -> Cost {
+		self.units
+			.get(&unit)
+			.map_or_else(Cost::default, |data| data.cost())
+	}
+	/// Returns correct cost of building given unit type,
+	/// i.e. without cost of the unit it's morphed from.
+	pub fn unit_cost(&self, unit: UnitTypeId) -> Cost {
+		let mut cost = self.unit_api_cost(unit);
+		match unit {
+			UnitTypeId::OverlordTransport => {
+				cost.minerals = 25;
+				cost.vespene = 25;
+			}
+			UnitTypeId::Zergling | UnitTypeId::ZerglingBurrowed => {
+				cost.minerals *= 2;
+				cost.supply *= 2.0;
+			}
+			_ => {
+				let pred = self.unit_api_cost(match unit {
+					UnitTypeId::Baneling | UnitTypeId::BanelingBurrowed => UnitTypeId::Zergling,
+					UnitTypeId::Ravager | UnitTypeId::RavagerBurrowed => UnitTypeId::Roach,
+					UnitTypeId::LurkerMP | UnitTypeId::LurkerMPBurrowed => UnitTypeId::Hydralisk,
+					UnitTypeId::Overseer | UnitTypeId::OverseerSiegeMode => UnitTypeId::Overlord,
+					UnitTypeId::BroodLord => UnitTypeId::Corruptor,
+					UnitTypeId::OrbitalCommand
+					| UnitTypeId::OrbitalCommandFlying
+					| UnitTypeId::PlanetaryFortress => UnitTypeId::CommandCenter,
+					UnitTypeId::Lair => UnitTypeId::Hatchery,
+					UnitTypeId::Hive => UnitTypeId::Lair,
+					UnitTypeId::GreaterSpire => UnitTypeId::Spire,
+					UnitTypeId::Hatchery
+					| UnitTypeId::SpineCrawler
+					| UnitTypeId::SporeCrawler
+					| UnitTypeId::Extractor
+					| UnitTypeId::SpawningPool
+					| UnitTypeId::EvolutionChamber
+					| UnitTypeId::RoachWarren
+					| UnitTypeId::BanelingNest
+					| UnitTypeId::HydraliskDen
+					| UnitTypeId::LurkerDenMP
+					| UnitTypeId::InfestationPit
+					| UnitTypeId::Spire
+					| UnitTypeId::NydusNetwork
+					| UnitTypeId::UltraliskCavern => UnitTypeId::Drone,
+					_ => return cost,
+				});
+				cost.minerals -= pred.minerals;
+				cost.vespene -= pred.vespene;
+				cost.supply = (cost.supply - pred.supply).max(0.0);
+			}
+		}
+		cost
+	}
+	/// Returns cost of researching given upgrade.
+	pub fn upgrade_cost(&self, upgrade: UpgradeId) -> Cost {
+		self.upgrades
+			.get(&upgrade)
+			.map_or_else(Cost::default, |data| data.cost())
+	}
+}
+impl FromProto<(ResponseData, &VersionData)> for GameData {
+	fn from_proto((data, version): (ResponseData, &VersionData)) -> Self {
 		Self {
 			abilities: data
 				.get_abilities()
 				.iter()
-				.filter_map(|a| AbilityData::try_from_proto(a).map(|data| (data.id, data)))
+				.filter_map(|a| AbilityData::try_from_proto((a, version)).map(|data| (data.id, data)))
 				.collect(),
 			units: data
 				.get_units()
 				.iter()
-				.filter_map(|u| UnitTypeData::try_from_proto(u).map(|data| (data.id, data)))
+				.filter_map(|u| UnitTypeData::try_from_proto((u, version)).map(|data| (data.id, data)))
 				.collect(),
 			upgrades: data
 				.get_upgrades()
 				.iter()
-				.filter_map(|u| UpgradeData::try_from_proto(u).map(|data| (data.id, data)))
+				.filter_map(|u| UpgradeData::try_from_proto((u, version)).map(|data| (data.id, data)))
 				.collect(),
 			buffs: data
 				.get_buffs()
 				.iter()
-				.filter_map(|b| BuffData::try_from_proto(b).map(|data| (data.id, data)))
+				.filter_map(|b| BuffData::try_from_proto((b, version)).map(|data| (data.id, data)))
 				.collect(),
 			effects: data
 				.get_effects()
 				.iter()
-				.filter_map(|e| EffectData::try_from_proto(e).map(|data| (data.id, data)))
+				.filter_map(|e| EffectData::try_from_proto((e, version)).map(|data| (data.id, data)))
 				.collect(),
 		}
 	}
 }
 
 /// Cost of an item (`UnitTypeId` or `UpgradeId`) in resources, supply and time.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Copy, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Cost {
 	pub minerals: u32,
@@ -214,16 +277,18 @@ pub struct AbilityData {
 	/// Maximum range to target of the ability.
 	pub cast_range: Option<f32>,
 }
-impl TryFromProto<&ProtoAbilityData> for AbilityData {
-	fn try_from_proto(a: &ProtoAbilityData) -> Option<Self> {
+impl TryFromProto<(&ProtoAbilityData, &VersionData)> for AbilityData {
+	fn try_from_proto((a, version): (&ProtoAbilityData, &VersionData)) -> Option<Self> {
 		Some(Self {
-			id: AbilityId::from_u32(a.get_ability_id())?,
+			id: AbilityId::from_game(a.get_ability_id() as u64, version)?,
 			link_name: a.get_link_name().to_string(),
 			link_index: a.get_link_index(),
 			button_name: a.button_name.as_ref().cloned(),
 			friendly_name: a.friendly_name.as_ref().cloned(),
 			hotkey: a.hotkey.as_ref().cloned(),
-			remaps_to_ability_id: a.remaps_to_ability_id.and_then(AbilityId::from_u32),
+			remaps_to_ability_id: a
+				.remaps_to_ability_id
+				.and_then(|id| AbilityId::from_game(id as u64, version)),
 			available: a.get_available(),
 			target: AbilityTarget::from_proto(a.get_target()),
 			allow_minimap: a.get_allow_minimap(),
@@ -279,10 +344,10 @@ impl UnitTypeData {
 		}
 	}
 }
-impl TryFromProto<&ProtoUnitTypeData> for UnitTypeData {
-	fn try_from_proto(u: &ProtoUnitTypeData) -> Option<Self> {
+impl TryFromProto<(&ProtoUnitTypeData, &VersionData)> for UnitTypeData {
+	fn try_from_proto((u, version): (&ProtoUnitTypeData, &VersionData)) -> Option<Self> {
 		Some(Self {
-			id: UnitTypeId::from_u32(u.get_unit_id())?,
+			id: UnitTypeId::from_game(u.get_unit_id() as u64, version)?,
 			name: u.get_name().to_string(),
 			available: u.get_available(),
 			cargo_size: u.get_cargo_size(),
@@ -290,7 +355,9 @@ impl TryFromProto<&ProtoUnitTypeData> for UnitTypeData {
 			vespene_cost: u.get_vespene_cost(),
 			food_required: u.get_food_required(),
 			food_provided: u.get_food_provided(),
-			ability: u.ability_id.and_then(AbilityId::from_u32),
+			ability: u
+				.ability_id
+				.and_then(|id| AbilityId::from_game(id as u64, version)),
 			race: Race::from_proto(u.get_race()),
 			build_time: u.get_build_time(),
 			has_vespene: u.get_has_vespene(),
@@ -299,10 +366,14 @@ impl TryFromProto<&ProtoUnitTypeData> for UnitTypeData {
 			tech_alias: u
 				.get_tech_alias()
 				.iter()
-				.filter_map(|a| UnitTypeId::from_u32(*a))
+				.filter_map(|a| UnitTypeId::from_game(*a as u64, version))
 				.collect(),
-			unit_alias: u.unit_alias.and_then(UnitTypeId::from_u32),
-			tech_requirement: u.tech_requirement.and_then(UnitTypeId::from_u32),
+			unit_alias: u
+				.unit_alias
+				.and_then(|id| UnitTypeId::from_game(id as u64, version)),
+			tech_requirement: u
+				.tech_requirement
+				.and_then(|id| UnitTypeId::from_game(id as u64, version)),
 			require_attached: u.get_require_attached(),
 			attributes: u
 				.get_attributes()
@@ -338,11 +409,11 @@ impl UpgradeData {
 		}
 	}
 }
-impl TryFromProto<&ProtoUpgradeData> for UpgradeData {
-	fn try_from_proto(u: &ProtoUpgradeData) -> Option<Self> {
+impl TryFromProto<(&ProtoUpgradeData, &VersionData)> for UpgradeData {
+	fn try_from_proto((u, version): (&ProtoUpgradeData, &VersionData)) -> Option<Self> {
 		Some(Self {
-			id: UpgradeId::from_u32(u.get_upgrade_id())?,
-			ability: AbilityId::from_u32(u.get_ability_id())?,
+			id: UpgradeId::from_game(u.get_upgrade_id() as u64, version)?,
+			ability: AbilityId::from_game(u.get_ability_id() as u64, version)?,
 			name: u.get_name().to_string(),
 			mineral_cost: u.get_mineral_cost(),
 			vespene_cost: u.get_vespene_cost(),
@@ -358,10 +429,10 @@ pub struct BuffData {
 	pub id: BuffId,
 	pub name: String,
 }
-impl TryFromProto<&ProtoBuffData> for BuffData {
-	fn try_from_proto(b: &ProtoBuffData) -> Option<Self> {
+impl TryFromProto<(&ProtoBuffData, &VersionData)> for BuffData {
+	fn try_from_proto((b, version): (&ProtoBuffData, &VersionData)) -> Option<Self> {
 		Some(Self {
-			id: BuffId::from_u32(b.get_buff_id())?,
+			id: BuffId::from_game(b.get_buff_id() as u64, version)?,
 			name: b.get_name().to_string(),
 		})
 	}
@@ -380,9 +451,9 @@ pub struct EffectData {
 	/// `true` if effect affects allied units.
 	pub friendly_fire: bool,
 }
-impl TryFromProto<&ProtoEffectData> for EffectData {
-	fn try_from_proto(e: &ProtoEffectData) -> Option<Self> {
-		EffectId::from_u32(e.get_effect_id()).map(|id| Self {
+impl TryFromProto<(&ProtoEffectData, &VersionData)> for EffectData {
+	fn try_from_proto((e, version): (&ProtoEffectData, &VersionData)) -> Option<Self> {
+		EffectId::from_game(e.get_effect_id() as u64, version).map(|id| Self {
 			id,
 			name: e.get_name().to_string(),
 			friendly_name: e.get_friendly_name().to_string(),
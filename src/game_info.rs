@@ -8,11 +8,14 @@ use crate::{
 	FromProto,
 };
 use rustc_hash::FxHashMap;
-use sc2_proto::sc2api::ResponseGameInfo;
+use sc2_proto::sc2api::{PlayerInfo as ProtoPlayerInfo, ResponseGameInfo};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 
 /// Structure where all map information stored.
 #[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct GameInfo {
 	/// Map name bot playing on, which depends on sc2 localization language.
 	pub map_name: String,
@@ -65,21 +68,7 @@ impl FromProto<ResponseGameInfo> for GameInfo {
 			players: game_info
 				.get_player_info()
 				.iter()
-				.map(|i| {
-					let id = i.get_player_id();
-					(
-						id,
-						PlayerInfo {
-							id,
-							player_type: PlayerType::from_proto(i.get_field_type()),
-							race_requested: Race::from_proto(i.get_race_requested()),
-							race_actual: i.race_actual.map(Race::from_proto),
-							difficulty: i.difficulty.map(Difficulty::from_proto),
-							ai_build: i.ai_build.map(AIBuild::from_proto),
-							player_name: i.player_name.as_ref().cloned(),
-						},
-					)
-				})
+				.map(|i| (i.get_player_id(), PlayerInfo::from_proto(i)))
 				.collect(),
 			map_size: Size::new(map_size.get_x() as usize, map_size.get_y() as usize),
 			pathing_grid: PixelMap::from_proto(start_raw.get_pathing_grid()),
@@ -105,7 +94,8 @@ impl FromProto<ResponseGameInfo> for GameInfo {
 }
 
 /// Information about player.
-#[derive(Clone)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PlayerInfo {
 	/// Player id.
 	pub id: u32,
@@ -122,3 +112,16 @@ pub struct PlayerInfo {
 	/// In-game name of player.
 	pub player_name: Option<String>,
 }
+impl FromProto<&ProtoPlayerInfo> for PlayerInfo {
+	fn from_proto(info: &ProtoPlayerInfo) -> Self {
+		Self {
+			id: info.get_player_id(),
+			player_type: PlayerType::from_proto(info.get_field_type()),
+			race_requested: Race::from_proto(info.get_race_requested()),
+			race_actual: info.race_actual.map(Race::from_proto),
+			difficulty: info.difficulty.map(Difficulty::from_proto),
+			ai_build: info.ai_build.map(AIBuild::from_proto),
+			player_name: info.player_name.as_ref().cloned(),
+		}
+	}
+}
@@ -3,27 +3,29 @@
 use crate::{
 	action::{Action, ActionError},
 	bot::{Bot, LockOwned, LockU32, Locked, Rs, Rw},
+	feature_layer::{FeatureLayers, RenderedLayers},
 	geometry::Point2,
 	ids::*,
 	pixel_map::{PixelMap, VisibilityMap},
 	score::Score,
 	unit::Unit,
 	units::Units,
-	Event, FromProto, Player, SC2Result,
+	Event, FromProto, SC2Result,
 };
-use num_traits::FromPrimitive;
 use rustc_hash::FxHashSet;
 use sc2_proto::{
 	query::RequestQueryAvailableAbilities,
 	raw::{Alliance as ProtoAlliance, PowerSource as ProtoPowerSource},
-	sc2api::{Alert as ProtoAlert, Request, ResponseObservation},
+	sc2api::{Alert as ProtoAlert, Request, Response, ResponseObservation},
 };
-use std::ops::{Deref, DerefMut};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Information about current state on current step.
 ///
 /// Can be accessed through [`state`](crate::bot::Bot::state) field.
 #[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct GameState {
 	/// Actions executed on previous step.
 	pub actions: Vec<Action>,
@@ -36,26 +38,69 @@ pub struct GameState {
 	pub chat: Vec<ChatMessage>,
 }
 
-pub(crate) fn update_state<B>(
-	bot: &mut B,
+pub(crate) fn update_state(
+	bot: &mut Bot,
 	response_observation: &ResponseObservation,
-) -> SC2Result<Vec<Event>>
-where
-	B: Player + DerefMut<Target = Bot> + Deref<Target = Bot>,
-{
+) -> SC2Result<Vec<Event>> {
+	// Abilities are queried while observation is processed
+	bot.api()?.send_only(abilities_request(response_observation))?;
+	let events = update_observation(bot, response_observation)?;
+	let res = bot.api()?.wait_response()?;
+	set_abilities(bot, &res);
+	Ok(events)
+}
+
+/// Query for available abilities of all own units in observation.
+pub(crate) fn abilities_request(response_observation: &ResponseObservation) -> Request {
+	let mut req = Request::new();
+	let req_query_abilities = req.mut_query().mut_abilities();
+	for u in response_observation.get_observation().get_raw_data().get_units() {
+		if matches!(u.get_alliance(), ProtoAlliance::value_Self) {
+			let mut req_unit = RequestQueryAvailableAbilities::new();
+			req_unit.set_unit_tag(u.get_tag());
+			req_query_abilities.push(req_unit);
+		}
+	}
+	req
+}
+
+/// Saves answer on [`abilities_request`].
+pub(crate) fn set_abilities(bot: &Bot, res: &Response) {
+	*bot.abilities_units.write_lock() = res
+		.get_query()
+		.get_abilities()
+		.iter()
+		.map(|a| {
+			(
+				a.get_unit_tag(),
+				a.get_abilities()
+					.iter()
+					.filter_map(|ab| AbilityId::decode_lossy(ab.get_ability_id() as u64, &bot.version))
+					.collect(),
+			)
+		})
+		.collect();
+}
+
+/// Updates state of the bot with observation and returns events happened since last one.
+pub(crate) fn update_observation(
+	bot: &mut Bot,
+	response_observation: &ResponseObservation,
+) -> SC2Result<Vec<Event>> {
 	// Game state
+	let version = Rs::clone(&bot.version);
 	let state = &mut bot.state;
 
 	// let player_result = response_observation.get_player_result();
 	state.actions = response_observation
 		.get_actions()
 		.iter()
-		.filter_map(Option::<Action>::from_proto)
+		.filter_map(|a| Option::<Action>::from_proto((a, &*version)))
 		.collect();
 	state.action_errors = response_observation
 		.get_action_errors()
 		.iter()
-		.map(ActionError::from_proto)
+		.map(|e| ActionError::from_proto((e, &*version)))
 		.collect();
 	state.chat = response_observation
 		.get_chat()
@@ -79,16 +124,19 @@ where
 	obs.abilities = res_obs
 		.get_abilities()
 		.iter()
-		.map(|a| AvailableAbility {
-			id: {
-				let id = a.get_ability_id();
-				AbilityId::from_i32(id).unwrap_or_else(|| panic!("There's no `AbilityId` with value {}", id))
-			},
-			requires_point: a.get_requires_point(),
+		.filter_map(|a| {
+			Some(AvailableAbility {
+				id: AbilityId::decode_lossy(a.get_ability_id() as u64, &version)?,
+				requires_point: a.get_requires_point(),
+			})
 		})
 		.collect();
 	obs.score = Score::from_proto(res_obs.get_score());
 
+	// Feature layer and render
+	obs.feature_layer = res_obs.feature_layer_data.as_ref().map(FeatureLayers::from_proto);
+	obs.render = res_obs.render_data.as_ref().map(RenderedLayers::from_proto);
+
 	// Common
 	let common = res_obs.get_player_common();
 	obs.common = Common {
@@ -119,15 +167,14 @@ where
 	raw.effects = res_raw
 		.get_effects()
 		.iter()
-		.map(|e| Effect {
-			id: {
-				let id = e.get_effect_id();
-				EffectId::from_u32(id).unwrap_or_else(|| panic!("There's no `EffectId` with value {}", id))
-			},
-			positions: e.get_pos().iter().map(Point2::from_proto).collect(),
-			alliance: Alliance::from_proto(e.get_alliance()),
-			owner: e.get_owner() as u32,
-			radius: e.get_radius(),
+		.filter_map(|e| {
+			Some(Effect {
+				id: EffectId::decode_lossy(e.get_effect_id() as u64, &version)?,
+				positions: e.get_pos().iter().map(Point2::from_proto).collect(),
+				alliance: Alliance::from_proto(e.get_alliance()),
+				owner: e.get_owner() as u32,
+				radius: e.get_radius(),
+			})
 		})
 		.collect();
 	raw.radars = res_raw
@@ -187,7 +234,7 @@ where
 	*raw.upgrades.write_lock() = raw_player
 		.get_upgrade_ids()
 		.iter()
-		.map(|u| UpgradeId::from_u32(*u).unwrap_or_else(|| panic!("There's no `UpgradeId` with value {}", u)))
+		.filter_map(|u| UpgradeId::decode_lossy(*u as u64, &version))
 		.collect::<FxHashSet<_>>();
 
 	// Map
@@ -195,33 +242,6 @@ where
 	// Creep
 	*raw.creep.write_lock() = PixelMap::from_proto(map_state.get_creep());
 
-	// Available abilities
-	let mut req = Request::new();
-	let req_query_abilities = req.mut_query().mut_abilities();
-	for u in res_raw.get_units() {
-		if matches!(u.get_alliance(), ProtoAlliance::value_Self) {
-			let mut req_unit = RequestQueryAvailableAbilities::new();
-			req_unit.set_unit_tag(u.get_tag());
-			req_query_abilities.push(req_unit);
-		}
-	}
-
-	let res = bot.api().send(req)?;
-	*bot.abilities_units.write_lock() = res
-		.get_query()
-		.get_abilities()
-		.iter()
-		.map(|a| {
-			(
-				a.get_unit_tag(),
-				a.get_abilities()
-					.iter()
-					.filter_map(|ab| AbilityId::from_i32(ab.get_ability_id()))
-					.collect(),
-			)
-		})
-		.collect();
-
 	// Get visiblity
 	let visibility = VisibilityMap::from_proto(map_state.get_visibility());
 	// Get units
@@ -290,6 +310,7 @@ where
 
 /// Messege in game chat.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ChatMessage {
 	/// Id of player who sent that message.
 	pub player_id: u32,
@@ -300,6 +321,7 @@ pub struct ChatMessage {
 /// Bot's observation stored here.
 /// Can be accessed through [`state.observation`](GameState::observation).
 #[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Observation {
 	pub(crate) game_loop: Rs<LockU32>,
 	/// Common information from the observation.
@@ -311,6 +333,10 @@ pub struct Observation {
 	pub score: Score,
 	/// Data of raw interface.
 	pub raw: RawData,
+	/// Layers of feature layer interface, if it's enabled in [`PlayerSettings`](crate::PlayerSettings).
+	pub feature_layer: Option<FeatureLayers>,
+	/// Images of rendered interface, if it's enabled in [`PlayerSettings`](crate::PlayerSettings).
+	pub render: Option<RenderedLayers>,
 }
 impl Observation {
 	/// Current game tick (frame).
@@ -322,6 +348,7 @@ impl Observation {
 /// Bot's observation stored here.
 /// Can be accessed through [`state.observation.raw`](Observation::raw).
 #[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RawData {
 	/// Protoss power from pylons.
 	pub psionic_matrix: Vec<PsionicMatrix>,
@@ -343,6 +370,7 @@ pub struct RawData {
 
 /// Power matrix from the pylon or warp prism, used to give power to buildings and warp units on it.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PsionicMatrix {
 	/// Position of psionic matrix source.
 	pub pos: Point2,
@@ -366,6 +394,7 @@ impl FromProto<&ProtoPowerSource> for PsionicMatrix {
 ///
 /// All effects stored in [state.observation.raw.effects](RawData::effects).
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Effect {
 	/// Type of the effect.
 	pub id: EffectId,
@@ -381,6 +410,7 @@ pub struct Effect {
 
 /// The alliance of unit or effect to your bot.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Alliance {
 	/// Your own objects.
 	Own,
@@ -418,6 +448,7 @@ impl FromProto<ProtoAlliance> for Alliance {
 
 /// Radar point on the map.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Radar {
 	/// Position where radar is.
 	pub pos: Point2,
@@ -427,6 +458,7 @@ pub struct Radar {
 
 /// Common information of player.
 #[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Common {
 	/// In-game player id.
 	pub player_id: u32,
@@ -457,6 +489,7 @@ pub struct Common {
 #[allow(missing_docs)]
 #[allow(clippy::enum_variant_names)]
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Alert {
 	AlertError,
 	AddOnComplete,
@@ -511,6 +544,7 @@ impl FromProto<ProtoAlert> for Alert {
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct AvailableAbility {
 	pub id: AbilityId,
 	pub requires_point: bool,
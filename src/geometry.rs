@@ -4,6 +4,8 @@
 
 use crate::{distance::Distance, unit::Radius, FromProto, IntoProto};
 use sc2_proto::common::{Point, Point2D};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::{
 	hash::{Hash, Hasher},
 	iter::Sum,
@@ -13,6 +15,7 @@ use std::{
 /// Size of 2D rectangle.
 #[allow(missing_docs)]
 #[derive(Debug, Default, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Size {
 	pub x: usize,
 	pub y: usize,
@@ -26,7 +29,8 @@ impl Size {
 
 /// Rectangle from (x0, y0) to (x1, y1).
 #[allow(missing_docs)]
-#[derive(Debug, Default, Copy, Clone)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Rect {
 	pub x0: usize,
 	pub y0: usize,
@@ -38,11 +42,16 @@ impl Rect {
 	pub fn new(x0: usize, y0: usize, x1: usize, y1: usize) -> Self {
 		Self { x0, y0, x1, y1 }
 	}
+	/// Checks if rectangles have common cells.
+	pub fn intersects(&self, other: &Rect) -> bool {
+		self.x0 < other.x1 && other.x0 < self.x1 && self.y0 < other.y1 && other.y0 < self.y1
+	}
 }
 
 /// Point on 2D grid, the most frequently used geometric primitive.
 #[allow(missing_docs)]
 #[derive(Debug, Default, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Point2 {
 	pub x: f32,
 	pub y: f32,
@@ -435,7 +444,8 @@ impl IntoProto<Point2D> for Point2 {
 
 /// Point in 3D game world.
 #[allow(missing_docs)]
-#[derive(Debug, Default, Copy, Clone)]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Point3 {
 	pub x: f32,
 	pub y: f32,
@@ -0,0 +1,84 @@
+//! Decoding of ids received from the game.
+//!
+//! Game updates may add ids which don't exist in generated enums yet,
+//! such ids are logged once and replaced with a fallback value or skipped,
+//! so bots keep working until ids are regenerated.
+//!
+//! Values of ids which differ in the game version being played are remapped
+//! according to the given [`VersionData`], usually taken from [`Bot::version`](crate::bot::Bot::version).
+
+use super::{AbilityId, BuffId, EffectId, UnitTypeId, UpgradeId};
+use crate::{version::VersionData, Error, SC2Result};
+use num_traits::{FromPrimitive, ToPrimitive};
+use once_cell::sync::Lazy;
+use rustc_hash::FxHashSet;
+use std::sync::Mutex;
+
+static UNKNOWN_IDS: Lazy<Mutex<FxHashSet<(&'static str, u64)>>> = Lazy::new(Default::default);
+
+/// Ids which can be decoded from values sent by the game and encoded back.
+pub trait DecodeId: FromPrimitive + ToPrimitive {
+	/// Name of the id type.
+	const KIND: &'static str;
+	/// Value used in place of unknown ids, where an id can't be skipped.
+	const FALLBACK: Self;
+
+	/// Decodes id without reporting unknown ones.
+	fn from_game(value: u64, version: &VersionData) -> Option<Self> {
+		Self::from_u64(version.to_library(Self::KIND, value))
+	}
+	/// Encodes id into the value used by the game.
+	fn encode(&self, version: &VersionData) -> u32 {
+		let value = self.to_u64().unwrap();
+		version.to_game(Self::KIND, value) as u32
+	}
+	/// Decodes id, returns [`Error::UnknownId`] if it doesn't exist.
+	fn decode(value: u64, version: &VersionData) -> SC2Result<Self> {
+		Self::from_game(value, version).ok_or(Error::UnknownId {
+			kind: Self::KIND,
+			value,
+		})
+	}
+	/// Decodes id, unknown ones are reported in [`unknown_ids`] and returned as `None`.
+	fn decode_lossy(value: u64, version: &VersionData) -> Option<Self> {
+		let id = Self::from_game(value, version);
+		if id.is_none() && UNKNOWN_IDS.lock().unwrap().insert((Self::KIND, value)) {
+			warn!(
+				"There's no `{}` with value {}, it will be ignored (ids might be outdated for this game version)",
+				Self::KIND,
+				value
+			);
+		}
+		id
+	}
+	/// Decodes id, unknown ones are reported in [`unknown_ids`] and replaced with [`FALLBACK`](Self::FALLBACK).
+	fn decode_or_fallback(value: u64, version: &VersionData) -> Self {
+		Self::decode_lossy(value, version).unwrap_or(Self::FALLBACK)
+	}
+}
+
+impl DecodeId for UnitTypeId {
+	const KIND: &'static str = "UnitTypeId";
+	const FALLBACK: Self = UnitTypeId::NotAUnit;
+}
+impl DecodeId for AbilityId {
+	const KIND: &'static str = "AbilityId";
+	const FALLBACK: Self = AbilityId::NullNull;
+}
+impl DecodeId for UpgradeId {
+	const KIND: &'static str = "UpgradeId";
+	const FALLBACK: Self = UpgradeId::Null;
+}
+impl DecodeId for BuffId {
+	const KIND: &'static str = "BuffId";
+	const FALLBACK: Self = BuffId::Null;
+}
+impl DecodeId for EffectId {
+	const KIND: &'static str = "EffectId";
+	const FALLBACK: Self = EffectId::Null;
+}
+
+/// Returns all unknown ids received from the game so far, stored in (kind, value) pairs.
+pub fn unknown_ids() -> Vec<(&'static str, u64)> {
+	UNKNOWN_IDS.lock().unwrap().iter().copied().collect()
+}
@@ -1,4 +1,4 @@
-//! Auto generated with `generate_ids.py` script from `stableid.json`
+//! Auto generated with `codegen` module from game data
 //! ids of units, ablities, upgrades, buffs and effects.
 #![allow(missing_docs)]
 
@@ -15,3 +15,6 @@ pub use buff_id::BuffId;
 pub use effect_id::EffectId;
 
 mod impls;
+
+mod decode;
+pub use decode::{unknown_ids, DecodeId};
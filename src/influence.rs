@@ -0,0 +1,317 @@
+//! Influence maps, which show how dangerous every point of the map is for bot's units.
+//!
+//! ```no_run
+//! # use rust_sc2::prelude::*;
+//! use rust_sc2::{bot::Bot, pathfinding::PathMode};
+//! # fn f(bot: &Bot, unit: &Unit, target: Point2) {
+//! let influence = bot.influence_map();
+//!
+//! // Retreat from danger
+//! if influence.threat(unit.position(), PathMode::Ground) > 0.0 {
+//!     if let Some(pos) = influence.safest_point(unit.position(), 8.0, PathMode::Ground) {
+//!         unit.move_to(Target::Pos(pos), false);
+//!     }
+//! }
+//!
+//! // Or route around it
+//! let mut grid = bot.path_grid(PathMode::Ground);
+//! grid.add_costs(&influence.costs(PathMode::Ground, 0.1));
+//! let path = grid.find_path(unit.position(), target);
+//! # }
+//! ```
+
+use crate::{
+	bot::Bot, consts::INHIBITOR_ZONE_RADIUS, game_state::Alliance, geometry::Point2, ids::EffectId,
+	pathfinding::PathMode, unit::Unit,
+};
+use ndarray::{s, Array2};
+
+/// Threat added by inhibitor zones, which don't deal damage, but slow down units.
+pub const INHIBITOR_ZONE_THREAT: f32 = 5.0;
+/// Detection range used for detectors, which range is unknown (e.g. snapshots of buildings).
+const DEFAULT_DETECT_RANGE: f32 = 11.0;
+
+/// Weighted grids of threat and detection over the map.
+///
+/// Threat of a cell is the sum of damage per second enemies can deal to a unit in this cell.
+#[derive(Clone)]
+pub struct InfluenceMap {
+	/// Threat to ground units.
+	pub ground: Array2<f32>,
+	/// Threat to air units.
+	pub air: Array2<f32>,
+	/// Number of enemy detectors covering each cell.
+	pub detection: Array2<f32>,
+	/// Extra distance around attack ranges, where threat linearly decreases to zero.
+	pub margin: f32,
+	pathable: Array2<bool>,
+}
+impl InfluenceMap {
+	/// Constructs empty map of given size, where all cells are pathable.
+	pub fn new(dim: (usize, usize)) -> Self {
+		Self {
+			ground: Array2::zeros(dim),
+			air: Array2::zeros(dim),
+			detection: Array2::zeros(dim),
+			margin: 1.0,
+			pathable: Array2::from_elem(dim, true),
+		}
+	}
+	/// Constructs map from the current state of the bot.
+	///
+	/// Takes into account enemy units and structures, damaging effects
+	/// (including your own ones, which hurt your units as well), inhibitor zones,
+	/// enemy detectors and scans.
+	pub fn from_bot(bot: &Bot) -> Self {
+		let mut map = Self::new(bot.pathing_grid.dim());
+		map.pathable = bot.pathing_grid.map(|p| p.is_empty());
+
+		for u in bot.units.enemy.all.iter() {
+			map.add_unit(u);
+		}
+		for zone in bot.units.inhibitor_zones.iter() {
+			if let Some(radius) = INHIBITOR_ZONE_RADIUS.get(&zone.type_id()) {
+				let pos = zone.position();
+				add_circle(&mut map.ground, pos, *radius, 0.0, INHIBITOR_ZONE_THREAT);
+				add_circle(&mut map.air, pos, *radius, 0.0, INHIBITOR_ZONE_THREAT);
+			}
+		}
+		for effect in &bot.state.observation.raw.effects {
+			if effect.id == EffectId::ScannerSweep {
+				if effect.alliance == Alliance::Enemy {
+					for pos in &effect.positions {
+						add_circle(&mut map.detection, *pos, effect.radius, 0.0, 1.0);
+					}
+				}
+				continue;
+			}
+			if let Some((ground, air)) = effect_threat(effect.id, effect.alliance) {
+				for pos in &effect.positions {
+					map.add_threat(*pos, effect.radius, ground, air);
+				}
+			}
+		}
+		map
+	}
+
+	/// Adds threat and detection of given enemy unit.
+	pub fn add_unit(&mut self, unit: &Unit) {
+		if unit.is_structure() && !unit.is_ready() {
+			return;
+		}
+		let pos = unit.position();
+		let radius = unit.radius();
+
+		let ground_dps = unit.ground_dps();
+		if ground_dps > 0.0 {
+			let range = unit.real_ground_range() + radius;
+			add_circle(&mut self.ground, pos, range, self.margin, ground_dps);
+		}
+		let air_dps = unit.air_dps();
+		if air_dps > 0.0 {
+			let range = unit.real_air_range() + radius;
+			add_circle(&mut self.air, pos, range, self.margin, air_dps);
+		}
+		if unit.is_detector() {
+			let range = match unit.detect_range() {
+				range if range > 0.0 => range,
+				_ => DEFAULT_DETECT_RANGE,
+			};
+			add_circle(&mut self.detection, pos, range, 0.0, 1.0);
+		}
+	}
+	/// Adds given threat to ground and air layers in the circle.
+	pub fn add_threat(&mut self, center: Point2, radius: f32, ground: f32, air: f32) {
+		if ground > 0.0 {
+			add_circle(&mut self.ground, center, radius, self.margin, ground);
+		}
+		if air > 0.0 {
+			add_circle(&mut self.air, center, radius, self.margin, air);
+		}
+	}
+
+	/// Returns layer of threat for given kind of units.
+	pub fn layer(&self, mode: PathMode) -> &Array2<f32> {
+		match mode {
+			PathMode::Ground => &self.ground,
+			PathMode::Air => &self.air,
+		}
+	}
+	/// Returns threat in given position for given kind of units.
+	pub fn threat(&self, pos: Point2, mode: PathMode) -> f32 {
+		self.layer(mode)
+			.get(<(usize, usize)>::from(pos))
+			.copied()
+			.unwrap_or(0.0)
+	}
+	/// Checks if there's no threat in given position for given kind of units.
+	pub fn is_safe(&self, pos: Point2, mode: PathMode) -> bool {
+		self.threat(pos, mode) <= 0.0
+	}
+	/// Checks if given position is covered by enemy detection.
+	pub fn is_detected(&self, pos: Point2) -> bool {
+		self.detection
+			.get(<(usize, usize)>::from(pos))
+			.map_or(false, |d| *d > 0.0)
+	}
+
+	/// Returns position with the lowest threat in given radius around `from`,
+	/// the closest one if there're several.
+	///
+	/// Only pathable positions are considered for ground units.
+	pub fn safest_point(&self, from: Point2, radius: f32, mode: PathMode) -> Option<Point2> {
+		let layer = self.layer(mode);
+		let (w, h) = layer.dim();
+		let x0 = (from.x - radius).floor().max(0.0) as usize;
+		let y0 = (from.y - radius).floor().max(0.0) as usize;
+		let x1 = ((from.x + radius).ceil().max(0.0) as usize).min(w);
+		let y1 = ((from.y + radius).ceil().max(0.0) as usize).min(h);
+		let radius_squared = radius * radius;
+
+		iproduct!(x0..x1, y0..y1)
+			.filter(|pos| mode == PathMode::Air || self.pathable[*pos])
+			.filter_map(|(x, y)| {
+				let pos = Point2::new(x as f32 + 0.5, y as f32 + 0.5);
+				let dx = pos.x - from.x;
+				let dy = pos.y - from.y;
+				let distance_squared = dx * dx + dy * dy;
+				if distance_squared > radius_squared {
+					None
+				} else {
+					Some((pos, layer[(x, y)], distance_squared))
+				}
+			})
+			.min_by(|(_, t1, d1), (_, t2, d2)| t1.partial_cmp(t2).unwrap().then(d1.partial_cmp(d2).unwrap()))
+			.map(|(pos, _, _)| pos)
+	}
+	/// Returns threat for given kind of units multiplied by `scale`,
+	/// which can be used as extra costs of [`PathGrid`](crate::pathfinding::PathGrid).
+	pub fn costs(&self, mode: PathMode, scale: f32) -> Array2<f32> {
+		self.layer(mode) * scale
+	}
+}
+
+/// Damage per second to ground and air units in the area of the effect.
+fn effect_threat(id: EffectId, alliance: Alliance) -> Option<(f32, f32)> {
+	let enemy = alliance != Alliance::Own;
+	match id {
+		EffectId::PsiStormPersistent => Some((28.0, 28.0)),
+		EffectId::RavagerCorrosiveBileCP => Some((60.0, 60.0)),
+		EffectId::NukePersistent => Some((300.0, 0.0)),
+		EffectId::LiberatorTargetMorphDelayPersistent | EffectId::LiberatorTargetMorphPersistent if enemy => {
+			Some((65.8, 0.0))
+		}
+		EffectId::LurkerMP if enemy => Some((20.0, 0.0)),
+		_ => None,
+	}
+}
+
+/// Adds value to cells in the circle, value linearly decreases to zero in `falloff` distance outside of it.
+pub(crate) fn add_circle(map: &mut Array2<f32>, center: Point2, radius: f32, falloff: f32, value: f32) {
+	let outer = radius + falloff;
+	let (w, h) = map.dim();
+	let x0 = (center.x - outer).floor().max(0.0) as usize;
+	let y0 = (center.y - outer).floor().max(0.0) as usize;
+	let x1 = ((center.x + outer).ceil().max(0.0) as usize).min(w);
+	let y1 = ((center.y + outer).ceil().max(0.0) as usize).min(h);
+	if x0 >= x1 || y0 >= y1 {
+		return;
+	}
+
+	for ((x, y), cell) in map.slice_mut(s![x0..x1, y0..y1]).indexed_iter_mut() {
+		let dx = (x0 + x) as f32 + 0.5 - center.x;
+		let dy = (y0 + y) as f32 + 0.5 - center.y;
+		let distance = (dx * dx + dy * dy).sqrt();
+		if distance <= radius {
+			*cell += value;
+		} else if distance < outer {
+			*cell += value * (outer - distance) / falloff;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn circle_falls_off_outside_radius() {
+		let mut map = Array2::zeros((8, 8));
+		add_circle(&mut map, Point2::new(4.0, 4.0), 1.0, 1.0, 10.0);
+		assert_eq!(map[(3, 3)], 10.0);
+		assert_eq!(map[(4, 4)], 10.0);
+		// Center of cell is sqrt(2.5) away, so it's in the falloff
+		let falloff = 10.0 * (2.0 - 2.5f32.sqrt());
+		assert!((map[(5, 3)] - falloff).abs() < 1e-5);
+		assert_eq!(map[(5, 3)], map[(2, 4)]);
+		assert_eq!(map[(6, 4)], 0.0);
+		assert_eq!(map[(0, 0)], 0.0);
+
+		// Values are summed and circles are clipped by the map
+		add_circle(&mut map, Point2::new(0.0, 0.0), 5.0, 0.0, 1.0);
+		add_circle(&mut map, Point2::new(-5.0, -5.0), 2.0, 1.0, 1.0);
+		assert_eq!(map[(0, 0)], 1.0);
+		assert_eq!(map[(3, 3)], 11.0);
+		assert_eq!(map[(4, 4)], 10.0);
+	}
+
+	#[test]
+	fn safest_point_is_the_closest_without_threat() {
+		let mut map = InfluenceMap::new((8, 8));
+		map.add_threat(Point2::new(1.0, 4.0), 2.0, 10.0, 0.0);
+		let from = Point2::new(2.1, 4.2);
+		assert!(!map.is_safe(from, PathMode::Ground));
+		assert!(map.is_safe(from, PathMode::Air));
+
+		assert_eq!(
+			map.safest_point(from, 5.0, PathMode::Ground),
+			Some(Point2::new(4.5, 4.5))
+		);
+		// Unpathable cells are skipped only for ground units
+		map.pathable[(4, 4)] = false;
+		assert_eq!(
+			map.safest_point(from, 5.0, PathMode::Ground),
+			Some(Point2::new(4.5, 3.5))
+		);
+		assert_eq!(
+			map.safest_point(from, 5.0, PathMode::Air),
+			Some(Point2::new(2.5, 4.5))
+		);
+
+		// Cells with the least threat are chosen when there's no safe one
+		let pos = map
+			.safest_point(Point2::new(1.0, 4.0), 1.0, PathMode::Ground)
+			.unwrap();
+		assert!(map.threat(pos, PathMode::Ground) > 0.0);
+		assert_eq!(
+			map.safest_point(Point2::new(-5.0, -5.0), 1.0, PathMode::Ground),
+			None
+		);
+	}
+
+	#[test]
+	fn own_effects_threaten_only_if_they_hurt_own_units() {
+		assert_eq!(
+			effect_threat(EffectId::PsiStormPersistent, Alliance::Own),
+			Some((28.0, 28.0))
+		);
+		assert_eq!(
+			effect_threat(EffectId::NukePersistent, Alliance::Enemy),
+			Some((300.0, 0.0))
+		);
+		assert_eq!(effect_threat(EffectId::LurkerMP, Alliance::Own), None);
+		assert_eq!(
+			effect_threat(EffectId::LurkerMP, Alliance::Enemy),
+			Some((20.0, 0.0))
+		);
+		assert_eq!(
+			effect_threat(EffectId::LiberatorTargetMorphPersistent, Alliance::Own),
+			None
+		);
+		assert_eq!(
+			effect_threat(EffectId::LiberatorTargetMorphPersistent, Alliance::Enemy),
+			Some((65.8, 0.0))
+		);
+		assert_eq!(effect_threat(EffectId::ScannerSweep, Alliance::Enemy), None);
+	}
+}
@@ -54,13 +54,14 @@ fn main() -> SC2Result<()> {
         &mut MyBot::default(),
         // Opponent configuration.
         Computer::new(Race::Random, Difficulty::VeryEasy, None),
-        // Map name. Panics if map doesn't exists in "StarCraft II/Maps" folder.
+        // Map name. Returns error if map doesn't exist in "StarCraft II/Maps" folder.
         "EternalEmpireLE",
         // Additional settings:
         // LaunchOptions {
         //     sc2_version: Option<&str>, // Default: None - Latest available patch.
         //     save_replay_as: Option<&str>, // Default: None - Doesn't save replay.
         //     realtime: bool, // Default: false
+        //     record_as: Option<&str>, // Default: None - Doesn't record requests and responses.
         // }
         LaunchOptions::default(),
     )
@@ -342,12 +343,14 @@ pub mod prelude {
 	pub use crate::distance::rayon::{ParCenter, ParDistanceIterator, ParDistanceSlice};
 	#[cfg(feature = "rayon")]
 	pub use crate::units::rayon::ParUnitsIterator;
+	#[cfg(feature = "async")]
+	pub use crate::AsyncPlayer;
 	pub use crate::{
 		action::Target,
 		bot::PlacementOptions,
 		client::{
-			run_ladder_game, run_vs_computer, run_vs_human, LaunchOptions, RunnerMulti, RunnerSingle,
-			SC2Result,
+			run_bot_vs_bot, run_ladder_game, run_vs_computer, run_vs_human, LaunchOptions, ReplayRunner,
+			RunnerBotVsBot, RunnerMulti, RunnerReplayStream, RunnerSingle, SC2Result,
 		},
 		consts::{ALL_PRODUCERS, PRODUCERS, RESEARCHERS, TECH_REQUIREMENTS},
 		distance::{Center, Distance, DistanceIterator, DistanceSlice},
@@ -357,7 +360,7 @@ pub mod prelude {
 		player::{AIBuild, Computer, Difficulty, GameResult, Race},
 		unit::Unit,
 		units::{iter::UnitsIterator, Units},
-		Event, Player, PlayerSettings,
+		Event, Player, PlayerSettings, ReplayObserver,
 	};
 	#[doc(no_inline)]
 	pub use sc2_macro::{bot, bot_new};
@@ -367,26 +370,50 @@ mod paths;
 
 pub mod action;
 pub mod api;
+#[cfg(feature = "async")]
+pub mod async_client;
 pub mod bot;
+pub mod build_order;
 pub mod client;
+pub mod codegen;
+pub mod combat;
 pub mod consts;
 pub mod debug;
 pub mod distance;
+pub mod env;
+pub mod error;
+pub mod feature_layer;
 pub mod game_data;
 pub mod game_info;
 pub mod game_state;
 pub mod geometry;
 pub mod ids;
+pub mod influence;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+pub mod pathfinding;
 pub mod pixel_map;
+pub mod planner;
 pub mod player;
+pub mod production;
 pub mod ramp;
+pub mod replay;
 pub mod score;
+#[cfg(feature = "serde")]
+pub mod snapshot;
+pub mod terrain;
+pub mod tournament;
 pub mod unit;
 pub mod units;
 pub mod utils;
+pub mod version;
+pub mod workers;
 
+use feature_layer::SpatialSettings;
 use game_state::Alliance;
 use player::{GameResult, Race};
+#[cfg(feature = "async")]
+use std::future::Future;
 
 /**
 `#[bot]` macro implements [`Deref`]`<Target = `[`Bot`]`>` and [`DerefMut`]`<Target = `[`Bot`]`>` for your struct.
@@ -612,6 +639,7 @@ pub use sc2_macro::bot_new;
 
 #[doc(inline)]
 pub use client::SC2Result;
+pub use error::Error;
 /**
 Request to the SC2 API.
 
@@ -621,7 +649,7 @@ let mut request = Request::new();
 
 /* modify request through it's methods */
 
-let response = self.api().send(request)?;
+let response = self.api()?.send(request)?;
 ```
 */
 pub use sc2_proto::sc2api::Request;
@@ -635,15 +663,28 @@ pub use sc2_proto::sc2api::Request;
 /// if `raw_crop_to_playable_area` is `true`, maps will be crooped to the size of
 /// [`self.game_info.playable_area`](game_info::GameInfo::playable_area).
 ///
+/// if `pipelined` is `true`, actions, debug commands, step and request for the next observation
+/// will be sent at once, without waiting for responses in between.
+/// Time spent on each step can be checked in [`self.step_latency`](bot::Bot::step_latency).
+///
+/// if `feature_layer` or `render` is set, game will send layers of these interfaces
+/// with given resolutions, see [`feature_layer`] module.
+///
 /// Defaults:
 /// `name`: `None`
 /// `raw_affects_selection`: `false`
 /// `raw_crop_to_playable_area`: `false`
+/// `pipelined`: `false`
+/// `feature_layer`: `None`
+/// `render`: `None`
 pub struct PlayerSettings<'a> {
 	pub race: Race,
 	pub name: Option<&'a str>,
 	pub raw_affects_selection: bool,
 	pub raw_crop_to_playable_area: bool,
+	pub pipelined: bool,
+	pub feature_layer: Option<SpatialSettings>,
+	pub render: Option<SpatialSettings>,
 }
 impl<'a> PlayerSettings<'a> {
 	/// Constructs new settings with given `Race`.
@@ -653,6 +694,9 @@ impl<'a> PlayerSettings<'a> {
 			name: None,
 			raw_affects_selection: false,
 			raw_crop_to_playable_area: false,
+			pipelined: false,
+			feature_layer: None,
+			render: None,
 		}
 	}
 	/// Sets name of the player.
@@ -670,6 +714,21 @@ impl<'a> PlayerSettings<'a> {
 		self.raw_crop_to_playable_area = val;
 		self
 	}
+	/// Sets `pipelined` to a given value.
+	pub fn pipelined(mut self, val: bool) -> Self {
+		self.pipelined = val;
+		self
+	}
+	/// Enables feature layer interface with given settings.
+	pub fn with_feature_layer(mut self, settings: SpatialSettings) -> Self {
+		self.feature_layer = Some(settings);
+		self
+	}
+	/// Enables rendered interface with given settings.
+	pub fn with_render(mut self, settings: SpatialSettings) -> Self {
+		self.render = Some(settings);
+		self
+	}
 }
 impl Default for PlayerSettings<'_> {
 	fn default() -> Self {
@@ -678,12 +737,16 @@ impl Default for PlayerSettings<'_> {
 			name: None,
 			raw_affects_selection: false,
 			raw_crop_to_playable_area: false,
+			pipelined: false,
+			feature_layer: None,
+			render: None,
 		}
 	}
 }
 
 /// Events that happen in game.
 /// Passed to [`on_event`](Player::on_event).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Event {
 	/// Unit died or structure destroyed (all units: your, enemy, neutral).
 	UnitDestroyed(u64, Option<Alliance>),
@@ -719,6 +782,57 @@ pub trait Player {
 	}
 }
 
+/// Trait for observers of replays, which are played by [`ReplayRunner`](client::ReplayRunner).
+///
+/// State of the observed player is available in [`Bot`](bot::Bot) the same way as in games,
+/// but commands can't be given, so any actions made by observer are ignored.
+pub trait ReplayObserver {
+	/// Called once on first step of replay.
+	fn on_start(&mut self) -> SC2Result<()> {
+		Ok(())
+	}
+	/// Called on every step of replay.
+	fn on_step(&mut self, _iteration: usize) -> SC2Result<()> {
+		Ok(())
+	}
+	/// Called once on last step with a result of the observed player.
+	fn on_end(&self, _result: GameResult) -> SC2Result<()> {
+		Ok(())
+	}
+	/// Called when different events happen.
+	fn on_event(&mut self, _event: Event) -> SC2Result<()> {
+		Ok(())
+	}
+}
+
+/// Async version of [`Player`], available with `"async"` feature.
+///
+/// Methods can be implemented as `async fn`,
+/// bots implementing this trait are run with runners from [`async_client`] module.
+///
+/// Returned futures are `Send`, so games can be played in tasks spawned with `tokio::spawn`.
+#[cfg(feature = "async")]
+pub trait AsyncPlayer {
+	/// Returns settings used to connect bot to the game.
+	fn get_player_settings(&self) -> PlayerSettings;
+	/// Called once on first step (i.e on game start).
+	fn on_start(&mut self) -> impl Future<Output = SC2Result<()>> + Send {
+		async { Ok(()) }
+	}
+	/// Called on every game step. (Main logic of the bot should be here)
+	fn on_step(&mut self, _iteration: usize) -> impl Future<Output = SC2Result<()>> + Send {
+		async { Ok(()) }
+	}
+	/// Called once on last step with a result for your bot.
+	fn on_end(&self, _result: GameResult) -> impl Future<Output = SC2Result<()>> + Send {
+		async { Ok(()) }
+	}
+	/// Called when different events happen.
+	fn on_event(&mut self, _event: Event) -> impl Future<Output = SC2Result<()>> + Send {
+		async { Ok(()) }
+	}
+}
+
 trait FromProto<T>
 where
 	Self: Sized,
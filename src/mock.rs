@@ -0,0 +1,309 @@
+//! Scriptable in-process SC2 API server, used to run bots without the game.
+//! Available with `"mock"` feature.
+//!
+//! [`MockServer`] speaks the same websocket protocol as SC2 client and answers requests
+//! from fixtures stored in [`MockGame`]. Run bot on it with [`run_mock_game`],
+//! or replay observer with [`run_mock_replay`].
+//! Game recorded with [`record_mock_game`] can be played again with [`RunnerReplayStream`].
+//!
+//! [`run_mock_game`]: crate::client::run_mock_game
+//! [`run_mock_replay`]: crate::client::run_mock_replay
+//! [`record_mock_game`]: crate::client::record_mock_game
+//! [`RunnerReplayStream`]: crate::client::RunnerReplayStream
+
+use crate::{client::SC2Result, player::GameResult, Error};
+use protobuf::Message;
+use sc2_proto::{
+	error::ActionResult as ProtoActionResult,
+	query::{
+		RequestQuery, ResponseQuery, ResponseQueryAvailableAbilities, ResponseQueryBuildingPlacement,
+		ResponseQueryPathing,
+	},
+	sc2api::{
+		PlayerResult, Request, Request_oneof_request, Response, ResponseData, ResponseGameInfo,
+		ResponseObservation, Result as ProtoGameResult, Status,
+	},
+};
+use std::{
+	collections::VecDeque,
+	net::{TcpListener, TcpStream},
+	sync::{Arc, Mutex},
+	thread::{self, JoinHandle},
+};
+use tungstenite::{accept, Message::Binary, WebSocket};
+
+/// Protobuf messages of SC2 API, used to write fixtures.
+pub use sc2_proto as proto;
+
+type QueryHandler = Box<dyn FnMut(&RequestQuery) -> ResponseQuery + Send>;
+
+/// Fixtures used by [`MockServer`] to answer bot's requests.
+///
+/// Observations are given to the bot one by one on each `RequestObservation`,
+/// when they're over, server reports that game ended with [`result`](Self::result).
+pub struct MockGame {
+	/// Answer on `RequestGameInfo`.
+	/// Must contain info about bot's player with `race_actual` set.
+	pub game_info: ResponseGameInfo,
+	/// Answer on `RequestData`.
+	pub data: ResponseData,
+	/// Answers on `RequestObservation` in order of requests.
+	pub observations: VecDeque<ResponseObservation>,
+	/// Player id given to the bot on join, replaced with observed player id on replay start.
+	pub player_id: u32,
+	/// Result of the game for bot, reported when observations are over.
+	pub result: GameResult,
+	/// Base build reported on `RequestPing`, used to select [`version`](crate::version) data.
+	pub base_build: u32,
+	query: Option<QueryHandler>,
+}
+impl MockGame {
+	/// Constructs new game with given static data and no observations.
+	pub fn new(game_info: ResponseGameInfo, data: ResponseData) -> Self {
+		Self {
+			game_info,
+			data,
+			observations: VecDeque::new(),
+			player_id: 1,
+			result: GameResult::Victory,
+			base_build: 0,
+			query: None,
+		}
+	}
+	/// Adds observations which will be returned to the bot in given order.
+	pub fn with_observations<I: IntoIterator<Item = ResponseObservation>>(mut self, observations: I) -> Self {
+		self.observations.extend(observations);
+		self
+	}
+	/// Sets player id of the bot.
+	pub fn with_player_id(mut self, player_id: u32) -> Self {
+		self.player_id = player_id;
+		self
+	}
+	/// Sets result of the game for bot.
+	pub fn with_result(mut self, result: GameResult) -> Self {
+		self.result = result;
+		self
+	}
+	/// Sets base build of the game.
+	pub fn with_base_build(mut self, base_build: u32) -> Self {
+		self.base_build = base_build;
+		self
+	}
+	/// Sets custom handler for `RequestQuery`.
+	///
+	/// By default pathing distances are straight lines (or `None` when started from unit),
+	/// all placements are successful and units have no available abilities.
+	pub fn with_query<F>(mut self, handler: F) -> Self
+	where
+		F: FnMut(&RequestQuery) -> ResponseQuery + Send + 'static,
+	{
+		self.query = Some(Box::new(handler));
+		self
+	}
+
+	fn answer(&mut self, req: &Request) -> Response {
+		let mut res = Response::new();
+		res.set_status(Status::in_game);
+		match &req.request {
+			Some(Request_oneof_request::create_game(_)) => {
+				res.mut_create_game();
+				res.set_status(Status::init_game);
+			}
+			Some(Request_oneof_request::join_game(_)) => {
+				res.mut_join_game().set_player_id(self.player_id);
+			}
+			Some(Request_oneof_request::start_replay(req_start_replay)) => {
+				res.mut_start_replay();
+				res.set_status(Status::in_replay);
+				self.player_id = req_start_replay.get_observed_player_id() as u32;
+			}
+			Some(Request_oneof_request::replay_info(_)) => {
+				res.mut_replay_info().set_base_build(self.base_build);
+			}
+			Some(Request_oneof_request::game_info(_)) => res.set_game_info(self.game_info.clone()),
+			Some(Request_oneof_request::data(_)) => res.set_data(self.data.clone()),
+			Some(Request_oneof_request::observation(_)) => match self.observations.pop_front() {
+				Some(obs) => res.set_observation(obs),
+				None => {
+					res.set_status(Status::ended);
+					let result = self.result;
+					let player_id = self.player_id;
+					let player_results = res.mut_observation().mut_player_result();
+					for info in self.game_info.get_player_info() {
+						let id = info.get_player_id();
+						let mut player_result = PlayerResult::new();
+						player_result.set_player_id(id);
+						player_result.set_result(if id == player_id {
+							result_into_proto(result)
+						} else {
+							result_into_proto(opposite_result(result))
+						});
+						player_results.push(player_result);
+					}
+				}
+			},
+			Some(Request_oneof_request::action(req_action)) => {
+				res.mut_action()
+					.set_result(vec![ProtoActionResult::Success; req_action.get_actions().len()]);
+			}
+			Some(Request_oneof_request::step(_)) => {
+				let game_loop = self
+					.observations
+					.front()
+					.map_or(0, |obs| obs.get_observation().get_game_loop());
+				res.mut_step().set_simulation_loop(game_loop);
+			}
+			Some(Request_oneof_request::query(req_query)) => {
+				let res_query = match &mut self.query {
+					Some(handler) => handler(req_query),
+					None => default_query(req_query),
+				};
+				res.set_query(res_query);
+			}
+			Some(Request_oneof_request::debug(_)) => {
+				res.mut_debug();
+			}
+			Some(Request_oneof_request::save_replay(_)) => {
+				res.mut_save_replay();
+			}
+			Some(Request_oneof_request::ping(_)) => res.mut_ping().set_base_build(self.base_build),
+			Some(Request_oneof_request::leave_game(_)) => {
+				res.mut_leave_game();
+				res.set_status(Status::launched);
+			}
+			Some(Request_oneof_request::quit(_)) => {
+				res.mut_quit();
+				res.set_status(Status::quit);
+			}
+			_ => res
+				.mut_error()
+				.push("Request is not supported by mock server".to_string()),
+		}
+		res
+	}
+}
+
+fn default_query(req: &RequestQuery) -> ResponseQuery {
+	let mut res = ResponseQuery::new();
+	let pathing = res.mut_pathing();
+	for p in req.get_pathing() {
+		let mut result = ResponseQueryPathing::new();
+		if p.has_start_pos() {
+			let (start, end) = (p.get_start_pos(), p.get_end_pos());
+			let (dx, dy) = (end.get_x() - start.get_x(), end.get_y() - start.get_y());
+			result.set_distance((dx * dx + dy * dy).sqrt());
+		}
+		pathing.push(result);
+	}
+	let abilities = res.mut_abilities();
+	for a in req.get_abilities() {
+		let mut result = ResponseQueryAvailableAbilities::new();
+		result.set_unit_tag(a.get_unit_tag());
+		abilities.push(result);
+	}
+	let placements = res.mut_placements();
+	for _ in req.get_placements() {
+		let mut result = ResponseQueryBuildingPlacement::new();
+		result.set_result(ProtoActionResult::Success);
+		placements.push(result);
+	}
+	res
+}
+
+fn result_into_proto(result: GameResult) -> ProtoGameResult {
+	match result {
+		GameResult::Victory => ProtoGameResult::Victory,
+		GameResult::Defeat => ProtoGameResult::Defeat,
+		GameResult::Tie => ProtoGameResult::Tie,
+		GameResult::Undecided => ProtoGameResult::Undecided,
+	}
+}
+fn opposite_result(result: GameResult) -> GameResult {
+	match result {
+		GameResult::Victory => GameResult::Defeat,
+		GameResult::Defeat => GameResult::Victory,
+		r => r,
+	}
+}
+
+/// Websocket server which plays [`MockGame`] for a single connected bot.
+///
+/// Server is running in separate thread and stops after `RequestQuit` or when bot disconnects.
+pub struct MockServer {
+	port: u16,
+	requests: Arc<Mutex<Vec<Request>>>,
+	handle: Option<JoinHandle<()>>,
+}
+impl MockServer {
+	/// Starts server on a free local port.
+	pub fn start(game: MockGame) -> SC2Result<Self> {
+		let listener = TcpListener::bind(("127.0.0.1", 0))?;
+		let port = listener.local_addr()?.port();
+		let requests = Arc::new(Mutex::new(Vec::new()));
+
+		let log = Arc::clone(&requests);
+		let handle = thread::spawn(move || match listener.accept() {
+			Ok((stream, _)) => {
+				if let Err(e) = serve(stream, game, &log) {
+					error!("Mock server failed: {}", e);
+				}
+			}
+			Err(e) => error!("Mock server can't accept connection: {}", e),
+		});
+
+		Ok(Self {
+			port,
+			requests,
+			handle: Some(handle),
+		})
+	}
+	/// Port the server is listening on.
+	pub fn port(&self) -> u16 {
+		self.port
+	}
+	/// Returns all requests received from the bot so far.
+	pub fn requests(&self) -> Vec<Request> {
+		self.requests.lock().unwrap().clone()
+	}
+	/// Waits until server stops (i.e. bot quits) and returns all received requests.
+	pub fn join(mut self) -> Vec<Request> {
+		if let Some(handle) = self.handle.take() {
+			if handle.join().is_err() {
+				error!("Mock server thread panicked");
+			}
+		}
+		self.requests()
+	}
+}
+
+fn serve(stream: TcpStream, mut game: MockGame, log: &Mutex<Vec<Request>>) -> SC2Result<()> {
+	stream.set_nodelay(true)?;
+	let mut ws: WebSocket<TcpStream> = accept(stream).map_err(Error::other)?;
+	loop {
+		let msg = match ws.read_message() {
+			Ok(msg) => msg,
+			Err(tungstenite::Error::ConnectionClosed) | Err(tungstenite::Error::AlreadyClosed) => {
+				return Ok(())
+			}
+			Err(e) => return Err(e.into()),
+		};
+		if msg.is_close() {
+			return Ok(());
+		}
+		if !msg.is_binary() {
+			continue;
+		}
+
+		let mut req = Request::new();
+		req.merge_from_bytes(msg.into_data().as_slice())?;
+		let res = game.answer(&req);
+		let is_quit = req.has_quit();
+		log.lock().unwrap().push(req);
+
+		ws.write_message(Binary(res.write_to_bytes()?))?;
+		if is_quit {
+			return Ok(());
+		}
+	}
+}
@@ -0,0 +1,635 @@
+//! Local pathfinding on the pathing grid, which doesn't need requests to the game.
+//!
+//! Grid is constructed from the bot once and can be reused for any number of searches:
+//! ```no_run
+//! # use rust_sc2::prelude::*;
+//! use rust_sc2::{bot::Bot, pathfinding::PathMode};
+//! # fn f(bot: &Bot, unit: &Unit, target: Point2, bunker: &Unit) {
+//! let mut grid = bot.path_grid(PathMode::Ground);
+//! // Avoid walking in range of enemy bunker
+//! grid.add_danger(bunker.position(), 7.0, 10.0);
+//!
+//! if let Some(path) = grid.find_path(unit.position(), target) {
+//!     unit.move_to(Target::Pos(path.points[1]), false);
+//! }
+//! # }
+//! ```
+//!
+//! Searches on grids without danger zones use Jump Point Search,
+//! otherwise A* is used, since JPS works only on grids with uniform costs.
+//! Units never cut corners of obstacles when moving diagonally.
+
+use crate::{
+	bot::Bot,
+	distance::*,
+	geometry::{Point2, Rect},
+	ids::UnitTypeId,
+	influence::add_circle,
+	unit::Unit,
+};
+use ndarray::{s, Array2};
+use std::{cmp::Ordering, collections::BinaryHeap, f32::consts::SQRT_2};
+
+type Pos = (usize, usize);
+
+const DIRECTIONS: [(isize, isize); 8] = [
+	(1, 0),
+	(-1, 0),
+	(0, 1),
+	(0, -1),
+	(1, 1),
+	(-1, -1),
+	(1, -1),
+	(-1, 1),
+];
+
+/// Kind of units path is searched for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathMode {
+	/// Ground units, which can walk only on pathable cells and are blocked by buildings.
+	Ground,
+	/// Air units, which can fly anywhere in the playable area.
+	Air,
+}
+
+/// Path found by [`PathGrid`].
+#[derive(Debug, Clone)]
+pub struct Path {
+	/// Waypoints of the path, starting with start and ending with goal.
+	///
+	/// Only points where path changes direction are included,
+	/// so units can be moved to them in straight lines.
+	pub points: Vec<Point2>,
+	/// Length of the path.
+	pub distance: f32,
+	/// Cost of the path, which is length with danger zones taken into account.
+	pub cost: f32,
+}
+
+/// Grid of pathable cells with additional movement costs.
+#[derive(Clone)]
+pub struct PathGrid {
+	pathable: Array2<bool>,
+	costs: Array2<f32>,
+	has_costs: bool,
+}
+impl PathGrid {
+	/// Constructs new grid with given pathable cells and no danger zones.
+	pub fn new(pathable: Array2<bool>) -> Self {
+		Self {
+			costs: Array2::zeros(pathable.dim()),
+			pathable,
+			has_costs: false,
+		}
+	}
+	/// Constructs grid for given kind of units from the current state of the bot.
+	///
+	/// For ground units [`pathing_grid`] of the bot is used,
+	/// which has current buildings, minerals, geysers and rocks marked as obstacles.
+	/// For air units the whole playable area is pathable.
+	///
+	/// [`pathing_grid`]: crate::bot::Bot::pathing_grid
+	pub fn from_bot(bot: &Bot, mode: PathMode) -> Self {
+		match mode {
+			PathMode::Ground => Self::new(bot.pathing_grid.map(|p| p.is_empty())),
+			PathMode::Air => {
+				let area = bot.game_info.playable_area;
+				let mut pathable = Array2::from_elem(bot.game_info.pathing_grid.dim(), false);
+				pathable
+					.slice_mut(s![area.x0..area.x1, area.y0..area.y1])
+					.fill(true);
+				Self::new(pathable)
+			}
+		}
+	}
+
+	/// Size of the grid.
+	pub fn dim(&self) -> Pos {
+		self.pathable.dim()
+	}
+	/// Checks if given cell can be walked through.
+	pub fn is_pathable<P: Into<Pos>>(&self, pos: P) -> bool {
+		self.pathable.get(pos.into()).copied().unwrap_or(false)
+	}
+	/// Sets cell pathable or not.
+	pub fn set_pathable<P: Into<Pos>>(&mut self, pos: P, val: bool) {
+		if let Some(cell) = self.pathable.get_mut(pos.into()) {
+			*cell = val;
+		}
+	}
+	/// Marks cells occupied by given unit as not pathable, if it's an obstacle for ground units.
+	pub fn add_obstacle(&mut self, unit: &Unit) {
+		if let Some(area) = footprint(unit) {
+			let (w, h) = self.dim();
+			self.pathable
+				.slice_mut(s![area.x0.min(w)..area.x1.min(w), area.y0.min(h)..area.y1.min(h)])
+				.fill(false);
+		}
+	}
+
+	/// Adds extra cost of moving through the circle with given center and radius.
+	///
+	/// Cost is added per unit of length, i.e. moving through the cell with cost `1`
+	/// is the same as moving through 2 cells without cost.
+	pub fn add_danger(&mut self, center: Point2, radius: f32, cost: f32) {
+		add_circle(&mut self.costs, center, radius, 0.0, cost);
+		self.has_costs = true;
+	}
+	/// Adds given extra costs of cells to the grid. Map must have the same size as the grid.
+	pub fn add_costs(&mut self, costs: &Array2<f32>) {
+		self.costs += costs;
+		self.has_costs = true;
+	}
+	/// Removes all danger zones from the grid.
+	pub fn clear_costs(&mut self) {
+		self.costs.fill(0.0);
+		self.has_costs = false;
+	}
+	/// Returns extra cost of moving through given cell.
+	pub fn cost<P: Into<Pos>>(&self, pos: P) -> f32 {
+		self.costs.get(pos.into()).copied().unwrap_or(0.0)
+	}
+
+	/// Finds the cheapest path from `start` to `goal`.
+	///
+	/// Start cell can be not pathable (e.g. when unit stands near the building),
+	/// but returns `None` if goal is not pathable or can't be reached.
+	pub fn find_path(&self, start: Point2, goal: Point2) -> Option<Path> {
+		let start_cell = self.cell(start)?;
+		let goal_cell = self.cell(goal)?;
+		if !self.is_pathable(goal_cell) {
+			return None;
+		}
+
+		let (cells, cost) = if start_cell == goal_cell {
+			(vec![start_cell], 0.0)
+		} else if self.has_costs {
+			self.astar(start_cell, goal_cell)?
+		} else {
+			self.jps(start_cell, goal_cell)?
+		};
+
+		// Start and goal cells are replaced with exact positions
+		let mut points = Vec::with_capacity(cells.len().max(2));
+		points.push(start);
+		points.extend(
+			cells
+				.iter()
+				.skip(1)
+				.take(cells.len().saturating_sub(2))
+				.map(|&(x, y)| Point2::new(x as f32 + 0.5, y as f32 + 0.5)),
+		);
+		points.push(goal);
+
+		let distance = points.windows(2).map(|p| p[0].distance(p[1])).sum::<f32>();
+		Some(Path {
+			points,
+			distance,
+			cost: if self.has_costs { cost } else { distance },
+		})
+	}
+	/// Returns length of the cheapest path from `start` to `goal`,
+	/// or `None` if there's no path.
+	pub fn distance(&self, start: Point2, goal: Point2) -> Option<f32> {
+		self.find_path(start, goal).map(|path| path.distance)
+	}
+
+	fn cell(&self, pos: Point2) -> Option<Pos> {
+		let (w, h) = self.dim();
+		if pos.x < 0.0 || pos.y < 0.0 || pos.x as usize >= w || pos.y as usize >= h {
+			None
+		} else {
+			Some(pos.into())
+		}
+	}
+	#[inline]
+	fn walkable(&self, x: isize, y: isize) -> bool {
+		x >= 0 && y >= 0 && self.is_pathable((x as usize, y as usize))
+	}
+	#[inline]
+	fn can_move(&self, x: isize, y: isize, dx: isize, dy: isize) -> bool {
+		self.walkable(x + dx, y + dy)
+			&& (dx == 0 || dy == 0 || self.walkable(x + dx, y) && self.walkable(x, y + dy))
+	}
+
+	/// A* on 8-connected grid with costs of cells.
+	fn astar(&self, start: Pos, goal: Pos) -> Option<(Vec<Pos>, f32)> {
+		let mut search = Search::new(self.dim(), start, goal);
+		while let Some(current) = search.pop() {
+			if current == goal {
+				return Some((search.key_points(goal), search.g(goal)));
+			}
+			let (x, y) = (current.0 as isize, current.1 as isize);
+			for &(dx, dy) in &DIRECTIONS {
+				if !self.can_move(x, y, dx, dy) {
+					continue;
+				}
+				let next = ((x + dx) as usize, (y + dy) as usize);
+				let length = if dx != 0 && dy != 0 { SQRT_2 } else { 1.0 };
+				search.push(current, next, length * (1.0 + self.costs[next]));
+			}
+		}
+		None
+	}
+
+	/// Jump Point Search without corner cutting.
+	fn jps(&self, start: Pos, goal: Pos) -> Option<(Vec<Pos>, f32)> {
+		let mut search = Search::new(self.dim(), start, goal);
+		let goal_i = (goal.0 as isize, goal.1 as isize);
+		while let Some(current) = search.pop() {
+			if current == goal {
+				return Some((search.key_points(goal), search.g(goal)));
+			}
+			let (x, y) = (current.0 as isize, current.1 as isize);
+			for (dx, dy) in self.jps_directions(current, search.parent(current)) {
+				if let Some((jx, jy)) = self.jump(x + dx, y + dy, dx, dy, goal_i) {
+					let next = (jx as usize, jy as usize);
+					search.push(current, next, octile(current, next));
+				}
+			}
+		}
+		None
+	}
+	/// Directions which should be checked from the cell, given direction it was reached from.
+	fn jps_directions(&self, pos: Pos, parent: Option<Pos>) -> Vec<(isize, isize)> {
+		let (x, y) = (pos.0 as isize, pos.1 as isize);
+		let (dx, dy) = match parent {
+			Some(parent) => ((x - parent.0 as isize).signum(), (y - parent.1 as isize).signum()),
+			None => {
+				return DIRECTIONS
+					.iter()
+					.copied()
+					.filter(|&(dx, dy)| self.can_move(x, y, dx, dy))
+					.collect();
+			}
+		};
+
+		let mut directions = Vec::with_capacity(5);
+		if dx != 0 && dy != 0 {
+			let vertical = self.walkable(x, y + dy);
+			let horizontal = self.walkable(x + dx, y);
+			if vertical {
+				directions.push((0, dy));
+			}
+			if horizontal {
+				directions.push((dx, 0));
+			}
+			if vertical && horizontal && self.walkable(x + dx, y + dy) {
+				directions.push((dx, dy));
+			}
+		} else {
+			// Sides of the movement direction
+			let (sx, sy) = (dy, dx);
+			let next = self.walkable(x + dx, y + dy);
+			for side in [1, -1] {
+				let (ox, oy) = (sx * side, sy * side);
+				if self.walkable(x + ox, y + oy) {
+					if next && self.walkable(x + dx + ox, y + dy + oy) {
+						directions.push((dx + ox, dy + oy));
+					}
+					directions.push((ox, oy));
+				}
+			}
+			if next {
+				directions.push((dx, dy));
+			}
+		}
+		directions
+	}
+	/// Moves from the cell in given direction until jump point is found.
+	fn jump(
+		&self,
+		mut x: isize,
+		mut y: isize,
+		dx: isize,
+		dy: isize,
+		goal: (isize, isize),
+	) -> Option<(isize, isize)> {
+		loop {
+			if !self.walkable(x, y) {
+				return None;
+			}
+			if (x, y) == goal {
+				return Some((x, y));
+			}
+
+			if dx != 0 && dy != 0 {
+				if self.jump(x + dx, y, dx, 0, goal).is_some() || self.jump(x, y + dy, 0, dy, goal).is_some()
+				{
+					return Some((x, y));
+				}
+				if !(self.walkable(x + dx, y) && self.walkable(x, y + dy)) {
+					return None;
+				}
+			} else {
+				// Forced neighbors appear when obstacle behind the side cell ends
+				let (sx, sy) = (dy, dx);
+				if (self.walkable(x + sx, y + sy) && !self.walkable(x - dx + sx, y - dy + sy))
+					|| (self.walkable(x - sx, y - sy) && !self.walkable(x - dx - sx, y - dy - sy))
+				{
+					return Some((x, y));
+				}
+			}
+
+			x += dx;
+			y += dy;
+		}
+	}
+}
+
+/// Open set and scores of A* and JPS.
+struct Search {
+	width: usize,
+	goal: Pos,
+	g: Vec<f32>,
+	parents: Vec<usize>,
+	closed: Vec<bool>,
+	open: BinaryHeap<Node>,
+}
+impl Search {
+	fn new((w, h): Pos, start: Pos, goal: Pos) -> Self {
+		let mut search = Self {
+			width: w,
+			goal,
+			g: vec![f32::INFINITY; w * h],
+			parents: vec![usize::MAX; w * h],
+			closed: vec![false; w * h],
+			open: BinaryHeap::new(),
+		};
+		let i = search.index(start);
+		search.g[i] = 0.0;
+		search.open.push(Node {
+			f: octile(start, goal),
+			pos: start,
+		});
+		search
+	}
+	#[inline]
+	fn index(&self, pos: Pos) -> usize {
+		pos.1 * self.width + pos.0
+	}
+	fn g(&self, pos: Pos) -> f32 {
+		self.g[self.index(pos)]
+	}
+	fn parent(&self, pos: Pos) -> Option<Pos> {
+		let parent = self.parents[self.index(pos)];
+		if parent == usize::MAX {
+			None
+		} else {
+			Some((parent % self.width, parent / self.width))
+		}
+	}
+	/// Returns the best cell from the open set, which wasn't visited yet.
+	fn pop(&mut self) -> Option<Pos> {
+		while let Some(node) = self.open.pop() {
+			let i = self.index(node.pos);
+			if !self.closed[i] {
+				self.closed[i] = true;
+				return Some(node.pos);
+			}
+		}
+		None
+	}
+	fn push(&mut self, from: Pos, to: Pos, cost: f32) {
+		let i = self.index(to);
+		if self.closed[i] {
+			return;
+		}
+		let g = self.g(from) + cost;
+		if g < self.g[i] {
+			self.g[i] = g;
+			self.parents[i] = self.index(from);
+			self.open.push(Node {
+				f: g + octile(to, self.goal),
+				pos: to,
+			});
+		}
+	}
+	/// Returns path to given cell without cells in the middle of straight segments.
+	fn key_points(&self, goal: Pos) -> Vec<Pos> {
+		let direction = |a: Pos, b: Pos| {
+			(
+				(b.0 as isize - a.0 as isize).signum(),
+				(b.1 as isize - a.1 as isize).signum(),
+			)
+		};
+
+		let mut cells = vec![goal];
+		let mut current = goal;
+		while let Some(parent) = self.parent(current) {
+			let len = cells.len();
+			if len > 1 && direction(parent, current) == direction(current, cells[len - 2]) {
+				cells[len - 1] = parent;
+			} else {
+				cells.push(parent);
+			}
+			current = parent;
+		}
+		cells.reverse();
+		cells
+	}
+}
+
+struct Node {
+	f: f32,
+	pos: Pos,
+}
+impl PartialEq for Node {
+	fn eq(&self, other: &Self) -> bool {
+		self.f == other.f
+	}
+}
+impl Eq for Node {}
+impl PartialOrd for Node {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+impl Ord for Node {
+	// Reversed, so the heap pops node with the lowest score
+	fn cmp(&self, other: &Self) -> Ordering {
+		other.f.partial_cmp(&self.f).unwrap_or(Ordering::Equal)
+	}
+}
+
+fn octile(a: Pos, b: Pos) -> f32 {
+	let dx = (a.0 as f32 - b.0 as f32).abs();
+	let dy = (a.1 as f32 - b.1 as f32).abs();
+	dx.max(dy) + (SQRT_2 - 1.0) * dx.min(dy)
+}
+
+/// Returns cells occupied by given unit, if it's an obstacle for ground units.
+pub(crate) fn footprint(unit: &Unit) -> Option<Rect> {
+	if matches!(
+		unit.type_id(),
+		UnitTypeId::SupplyDepotLowered
+			| UnitTypeId::CreepTumor
+			| UnitTypeId::CreepTumorBurrowed
+			| UnitTypeId::CreepTumorQueen
+	) {
+		return None;
+	}
+	placement_footprint(unit)
+}
+/// Returns cells occupied by given unit, if buildings can't be placed there.
+///
+/// Unlike [`footprint`], lowered supply depots and creep tumors are included.
+pub(crate) fn placement_footprint(unit: &Unit) -> Option<Rect> {
+	if unit.is_flying() || unit.is_placeholder() {
+		return None;
+	}
+	let (w, h) = if unit.is_mineral() {
+		(2.0, 1.0)
+	} else if unit.is_geyser() {
+		(3.0, 3.0)
+	} else if unit.is_structure() {
+		let size = unit.building_size()? as f32;
+		(size, size)
+	} else if unit.is_neutral() && !unit.is_inhibitor_zone() {
+		// Rocks and other neutral obstacles
+		let size = (unit.radius() * 2.0).round().max(1.0);
+		(size, size)
+	} else {
+		return None;
+	};
+
+	let pos = unit.position();
+	let x0 = (pos.x - w / 2.0).round().max(0.0) as usize;
+	let y0 = (pos.y - h / 2.0).round().max(0.0) as usize;
+	Some(Rect::new(x0, y0, x0 + w as usize, y0 + h as usize))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const EPS: f32 = 1e-3;
+
+	/// Grid with random obstacles and a wall with a single gap in the middle.
+	fn obstacle_grid(size: usize) -> PathGrid {
+		let mut seed = 12345_u32;
+		let mid = size / 2;
+		let pathable = Array2::from_shape_fn((size, size), |(x, y)| {
+			seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+			if y == mid && (mid - 1..=mid + 1).contains(&x) {
+				true
+			} else {
+				x != mid && (seed >> 16) % 6 != 5
+			}
+		});
+		PathGrid::new(pathable)
+	}
+
+	/// Checks that every step of the path moves to the neighbor cell without cutting corners.
+	fn assert_valid(grid: &PathGrid, cells: &[Pos]) {
+		for segment in cells.windows(2) {
+			let (mut x, mut y) = (segment[0].0 as isize, segment[0].1 as isize);
+			let (x1, y1) = (segment[1].0 as isize, segment[1].1 as isize);
+			let (dx, dy) = ((x1 - x).signum(), (y1 - y).signum());
+			assert!(
+				dx == 0 || dy == 0 || (x1 - x).abs() == (y1 - y).abs(),
+				"{:?}",
+				segment
+			);
+			while (x, y) != (x1, y1) {
+				assert!(grid.can_move(x, y, dx, dy), "Invalid step from {:?}", (x, y));
+				x += dx;
+				y += dy;
+			}
+		}
+	}
+
+	/// Returns number of found paths.
+	fn compare_searches(grid: &PathGrid, pairs: &[(Pos, Pos)]) -> usize {
+		let mut found = 0;
+		for &(start, goal) in pairs {
+			if !grid.is_pathable(start) || !grid.is_pathable(goal) {
+				continue;
+			}
+			let jps = grid.jps(start, goal);
+			let astar = grid.astar(start, goal);
+			assert_eq!(jps.is_some(), astar.is_some(), "{:?} -> {:?}", start, goal);
+			if let (Some((jps_cells, jps_cost)), Some((astar_cells, astar_cost))) = (jps, astar) {
+				assert!((jps_cost - astar_cost).abs() < EPS, "{:?} -> {:?}", start, goal);
+				assert_valid(grid, &jps_cells);
+				assert_valid(grid, &astar_cells);
+				found += 1;
+			}
+		}
+		found
+	}
+
+	fn pairs(size: usize) -> Vec<(Pos, Pos)> {
+		let cells = (0..size)
+			.step_by(3)
+			.flat_map(|x| (0..size).step_by(5).map(move |y| (x, y)));
+		cells
+			.clone()
+			.zip(cells.rev())
+			.filter(|(start, goal)| start != goal)
+			.collect()
+	}
+
+	#[test]
+	fn jps_matches_astar_on_open_grid() {
+		let grid = PathGrid::new(Array2::from_elem((40, 40), true));
+		assert_eq!(compare_searches(&grid, &pairs(40)), pairs(40).len());
+
+		let (_, cost) = grid.jps((0, 0), (10, 4)).unwrap();
+		assert!((cost - (6.0 + 4.0 * SQRT_2)).abs() < EPS);
+	}
+
+	#[test]
+	fn jps_matches_astar_with_obstacles() {
+		let grid = obstacle_grid(40);
+		let found = compare_searches(&grid, &pairs(40));
+		assert!(found > 10);
+	}
+
+	#[test]
+	fn corners_are_not_cut() {
+		let mut grid = PathGrid::new(Array2::from_elem((3, 3), true));
+		grid.set_pathable((1, 0), false);
+		let path = grid
+			.find_path(Point2::new(0.5, 0.5), Point2::new(1.5, 1.5))
+			.unwrap();
+		assert!((path.distance - 2.0).abs() < EPS, "{:?}", path.points);
+
+		// Diagonal between two obstacles is closed
+		grid.set_pathable((0, 1), false);
+		assert!(grid
+			.find_path(Point2::new(0.5, 0.5), Point2::new(1.5, 1.5))
+			.is_none());
+		grid.add_danger(Point2::new(2.0, 2.0), 1.0, 1.0);
+		assert!(grid
+			.find_path(Point2::new(0.5, 0.5), Point2::new(1.5, 1.5))
+			.is_none());
+	}
+
+	#[test]
+	fn unreachable_goal_has_no_path() {
+		let mut grid = PathGrid::new(Array2::from_elem((20, 20), true));
+		for y in 0..20 {
+			grid.set_pathable((10, y), false);
+		}
+		let (start, goal) = (Point2::new(2.5, 2.5), Point2::new(17.5, 17.5));
+		assert!(grid.find_path(start, goal).is_none());
+		assert!(grid.distance(start, goal).is_none());
+		// Goal in obstacle or outside of the grid
+		assert!(grid.find_path(start, Point2::new(10.5, 5.5)).is_none());
+		assert!(grid.find_path(start, Point2::new(25.0, 5.0)).is_none());
+
+		grid.add_danger(Point2::new(5.0, 5.0), 2.0, 1.0);
+		assert!(grid.find_path(start, goal).is_none());
+
+		grid.set_pathable((10, 19), true);
+		let path = grid.find_path(start, goal).unwrap();
+		// Path goes around the wall through the gap
+		assert!(
+			path.distance
+				> start.distance(Point2::new(10.5, 19.5)) + goal.distance(Point2::new(10.5, 19.5)) - EPS
+		);
+		assert_eq!(path.points[0], start);
+		assert_eq!(*path.points.last().unwrap(), goal);
+	}
+}
@@ -1,8 +1,9 @@
 #[cfg(windows)]
 use regex::Regex;
 
+use crate::SC2Result;
 use dirs::home_dir;
-use std::{env, fs, path::Path};
+use std::{env, fs, io, path::Path};
 
 pub fn get_path_to_sc2() -> String {
 	match env::var_os("SC2PATH") {
@@ -35,7 +36,7 @@ pub fn get_path_to_sc2() -> String {
 	}
 }
 
-pub fn get_map_path(sc2_path: &str, map_name: &str) -> String {
+pub fn get_map_path(sc2_path: &str, map_name: &str) -> SC2Result<String> {
 	let maps = {
 		let path = format!("{}/Maps", sc2_path);
 		if fs::metadata(&path).is_ok() {
@@ -45,27 +46,45 @@ pub fn get_map_path(sc2_path: &str, map_name: &str) -> String {
 			if fs::metadata(&path).is_ok() {
 				path
 			} else {
-				panic!("Can't find maps folder in: {}", sc2_path);
+				let msg = format!("Can't find maps folder in: {}", sc2_path);
+				return Err(io::Error::new(io::ErrorKind::NotFound, msg).into());
 			}
 		}
 	};
 	let map_path = format!("{}/{}.SC2Map", maps, map_name);
-	fs::metadata(&map_path).unwrap_or_else(|_| panic!("Map doesn't exists: {}", map_path));
+	fs::metadata(&map_path)
+		.map_err(|e| io::Error::new(e.kind(), format!("Map doesn't exist: {}: {}", map_path, e)))?;
+	Ok(to_sc2_path(map_path))
+}
+
+pub fn get_replay_path(replay: &str) -> SC2Result<String> {
+	fs::metadata(replay)
+		.map_err(|e| io::Error::new(e.kind(), format!("Replay doesn't exist: {}: {}", replay, e)))?;
+	// SC2 resolves relative paths from its own working directory
+	let replay_path = env::current_dir()?.join(replay);
+	Ok(to_sc2_path(replay_path.to_string_lossy().into_owned()))
+}
+
+fn to_sc2_path(path: String) -> String {
 	if cfg!(feature = "wine_sc2") {
 		// Normalize the path using winepath
 		let mut path_cmd = std::process::Command::new("winepath");
 		path_cmd
 			// Specify that we have a windows path
 			.arg("-w")
-			.arg(map_path);
+			.arg(path);
 		let output = path_cmd.output().expect("Failed to run winepath");
 		assert!(output.status.success());
 		std::str::from_utf8(&output.stdout).unwrap().trim().to_string()
 	} else {
-		map_path
+		path
 	}
 }
 
+pub fn has_base_version(sc2_path: &str, base_version: u32) -> bool {
+	Path::new(&format!("{}/Versions/Base{}", sc2_path, base_version)).is_dir()
+}
+
 pub fn get_latest_base_version(sc2_path: &str) -> u32 {
 	Path::new(&format!("{}/Versions", sc2_path))
 		.read_dir()
@@ -2,9 +2,11 @@
 #![allow(missing_docs)]
 
 use crate::{geometry::Point2, FromProto};
-use ndarray::Array2;
+use ndarray::{Array2, Array3};
 use num_traits::FromPrimitive;
 use sc2_proto::common::ImageData;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::{
 	fmt,
 	ops::{Index, IndexMut},
@@ -16,6 +18,10 @@ pub type PixelMap = Array2<Pixel>;
 pub type ByteMap = Array2<u8>;
 /// 2-Dimensional Array that represents visibility.
 pub type VisibilityMap = Array2<Visibility>;
+/// 2-Dimensional Array of feature layer values, decoded from images with any number of bits per pixel.
+pub type LayerMap = Array2<i32>;
+/// 3-Dimensional Array of RGB pixels, where the last axis is color channel.
+pub type RgbMap = Array3<u8>;
 
 impl<T> Index<Point2> for Array2<T> {
 	type Output = T;
@@ -76,9 +82,66 @@ impl FromProto<&ImageData> for VisibilityMap {
 	}
 }
 
+impl FromProto<&ImageData> for LayerMap {
+	/// Decodes layer with 1, 8, 16 or 32 bits per pixel.
+	/// Empty map is returned if layer isn't available or can't be decoded.
+	fn from_proto(grid: &ImageData) -> Self {
+		let size = grid.get_size();
+		let (w, h) = (size.get_x() as usize, size.get_y() as usize);
+		let data = grid.get_data();
+		// Layer isn't available
+		if data.is_empty() {
+			return Array2::default((0, 0));
+		}
+		let values: Vec<i32> = match grid.get_bits_per_pixel() {
+			// Last byte is padded when number of pixels isn't a multiple of 8
+			1 => data
+				.iter()
+				.flat_map(|n| (0..8).rev().map(move |x| ((n >> x) & 1) as i32))
+				.take(w * h)
+				.collect(),
+			8 => data.iter().map(|n| *n as i32).collect(),
+			16 => data
+				.chunks_exact(2)
+				.map(|n| u16::from_le_bytes([n[0], n[1]]) as i32)
+				.collect(),
+			32 => data
+				.chunks_exact(4)
+				.map(|n| i32::from_le_bytes([n[0], n[1], n[2], n[3]]))
+				.collect(),
+			bits => {
+				error!("Can't decode LayerMap with {} bits per pixel", bits);
+				return Array2::default((0, 0));
+			}
+		};
+		match Array2::from_shape_vec((h, w), values) {
+			Ok(map) => map.reversed_axes(),
+			Err(e) => {
+				error!("Can't create LayerMap of size {}x{}: {}", w, h, e);
+				Array2::default((0, 0))
+			}
+		}
+	}
+}
+impl FromProto<&ImageData> for RgbMap {
+	fn from_proto(grid: &ImageData) -> Self {
+		let size = grid.get_size();
+		if grid.get_data().is_empty() {
+			return Array3::default((0, 0, 3));
+		}
+		Array3::from_shape_vec(
+			(size.get_y() as usize, size.get_x() as usize, 3),
+			grid.get_data().to_vec(),
+		)
+		.expect("Can't create RgbMap")
+		.permuted_axes([1, 0, 2])
+	}
+}
+
 /// Base for the most 2d maps.
 #[variant_checkers]
 #[derive(FromPrimitive, ToPrimitive, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Pixel {
 	/// When pixel is set, this tile is obstacle (e.g. not pathable | not placeable)
 	/// or has something on it (e.g. has creep).
@@ -103,6 +166,7 @@ impl fmt::Debug for Pixel {
 /// Base for visibility maps.
 #[variant_checkers]
 #[derive(Debug, FromPrimitive, ToPrimitive, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Visibility {
 	/// Position is hidden (i.e. weren't explored before)
 	Hidden,
@@ -123,3 +187,63 @@ impl Default for Visibility {
 		Visibility::Hidden
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc2_proto::common::Size2DI;
+
+	fn image(w: i32, h: i32, bits: i32, data: Vec<u8>) -> ImageData {
+		let mut size = Size2DI::new();
+		size.set_x(w);
+		size.set_y(h);
+		let mut image = ImageData::new();
+		image.set_size(size);
+		image.set_bits_per_pixel(bits);
+		image.set_data(data);
+		image
+	}
+
+	#[test]
+	fn decodes_1_bit_layer() {
+		// 3x3 = 9 bits, last 7 bits of the second byte are padding
+		let map = LayerMap::from_proto(&image(3, 3, 1, vec![0b1010_0011, 0b1000_0000]));
+		assert_eq!(map.dim(), (3, 3));
+		// Map is indexed by (x, y), so rows of image are rows of transposed map
+		let rows = map.t();
+		assert_eq!(rows.row(0).to_vec(), vec![1, 0, 1]);
+		assert_eq!(rows.row(1).to_vec(), vec![0, 0, 0]);
+		assert_eq!(rows.row(2).to_vec(), vec![1, 1, 1]);
+	}
+
+	#[test]
+	fn decodes_8_bit_layer() {
+		let map = LayerMap::from_proto(&image(3, 2, 8, vec![0, 1, 2, 253, 254, 255]));
+		assert_eq!(map.dim(), (3, 2));
+		assert_eq!(map[(0, 0)], 0);
+		assert_eq!(map[(2, 0)], 2);
+		assert_eq!(map[(0, 1)], 253);
+		assert_eq!(map[(2, 1)], 255);
+	}
+
+	#[test]
+	fn decodes_16_bit_layer_as_unsigned() {
+		let map = LayerMap::from_proto(&image(2, 1, 16, vec![0x34, 0x12, 0xff, 0xff]));
+		assert_eq!(map.dim(), (2, 1));
+		assert_eq!(map[(0, 0)], 0x1234);
+		assert_eq!(map[(1, 0)], 65535);
+	}
+
+	#[test]
+	fn decodes_32_bit_layer() {
+		let map = LayerMap::from_proto(&image(1, 1, 32, (-2i32).to_le_bytes().to_vec()));
+		assert_eq!(map[(0, 0)], -2);
+	}
+
+	#[test]
+	fn invalid_layer_is_empty() {
+		assert!(LayerMap::from_proto(&image(2, 2, 0, vec![])).is_empty());
+		assert!(LayerMap::from_proto(&image(2, 2, 4, vec![0, 0])).is_empty());
+		assert!(LayerMap::from_proto(&image(2, 2, 8, vec![0, 0, 0])).is_empty());
+	}
+}
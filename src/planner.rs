@@ -0,0 +1,1119 @@
+//! Offline economy simulator, used to find the fastest build order for a given goal.
+//!
+//! Planner needs only [`GameData`], so openers can be tuned without the game,
+//! e.g. with game data saved in a [`Snapshot`](crate::snapshot::Snapshot):
+//! ```no_run
+//! use rust_sc2::{
+//!     bot::Bot,
+//!     planner::{Goal, Planner},
+//!     player::Race,
+//! };
+//! # fn f(bot: &Bot) {
+//! let goal: Goal = "2 Barracks + Stimpack by 4:00".parse().unwrap();
+//! let plan = Planner::new(Race::Terran, &bot.game_data)
+//!     .with_version(&bot.version)
+//!     .optimize(&goal)
+//!     .unwrap();
+//! assert!(plan.meets(&goal));
+//!
+//! // Plan can be executed as usual build order
+//! let build_order = plan.build_order();
+//! # }
+//! ```
+//!
+//! Economy is approximated: workers mine with constant rates and don't need time
+//! to move between bases, builders spend fixed time to reach construction site,
+//! supply providers are added automatically when free supply gets low.
+//! Chrono boost, MULEs, injects and reactors aren't taken into account.
+
+use crate::{
+	build_order::{BuildItem, BuildOrder, BuildStep},
+	consts::{ALL_PRODUCERS, FRAMES_PER_SECOND, PRODUCERS, RACE_VALUES, RESEARCHERS, TECH_ALIAS},
+	game_data::{Attribute, GameData},
+	ids::{UnitTypeId, UpgradeId},
+	player::Race,
+	version::{self, VersionData},
+	Error,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::str::FromStr;
+
+/// Minerals brought by worker in one trip.
+const MINERALS_PER_TRIP: f32 = 5.0;
+/// Game loops spent by worker on one trip to mineral field and back.
+const MINERAL_TRIP_LOOPS: f32 = 120.0;
+/// Part of the full income gathered by the third worker on mineral field.
+const THIRD_WORKER_EFFICIENCY: f32 = 0.5;
+/// Vespene brought by worker in one trip.
+const VESPENE_PER_TRIP: f32 = 4.0;
+/// Game loops spent by worker on one trip to gas building and back.
+const VESPENE_TRIP_LOOPS: f32 = 96.0;
+/// Number of mineral fields on every base.
+const MINERAL_FIELDS: usize = 8;
+/// Number of workers saturating one gas building.
+const WORKERS_PER_GAS: usize = 3;
+/// Game loops spent by builder to get to construction site.
+const BUILDER_TRAVEL_LOOPS: u32 = 90;
+/// Game loops between spawns of larva.
+const LARVA_LOOPS: u32 = 246;
+/// Maximum larva spawned by hatchery itself.
+const MAX_LARVA: usize = 3;
+/// Number of workers on start of the game.
+const START_WORKERS: usize = 12;
+/// Minerals on start of the game.
+const START_MINERALS: f32 = 50.0;
+/// Simulation step in game loops.
+const STEP: u32 = 8;
+/// Free supply, at which next supply provider is started, tried by [`Planner::optimize`].
+const SUPPLY_MARGINS: [u32; 2] = [2, 5];
+
+/// What should be made and when, parsed from string like `"2 Barracks + Stimpack by 4:00"`.
+#[derive(Debug, Clone, Default)]
+pub struct Goal {
+	/// Items and their total count (including units bot starts the game with).
+	pub items: Vec<(BuildItem, usize)>,
+	/// Time in seconds, when all items should be complete.
+	pub deadline: Option<f32>,
+}
+impl Goal {
+	/// Constructs empty goal.
+	pub fn new() -> Self {
+		Self::default()
+	}
+	/// Adds item with given count to the goal.
+	pub fn with<I: Into<BuildItem>>(mut self, item: I, count: usize) -> Self {
+		self.items.push((item.into(), count));
+		self
+	}
+	/// Sets deadline of the goal in seconds.
+	pub fn by(mut self, seconds: f32) -> Self {
+		self.deadline = Some(seconds);
+		self
+	}
+}
+/// Parses items separated by `+` or `,`, each with optional count,
+/// and optional deadline in format `by m:ss` or `by seconds`.
+impl FromStr for Goal {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (items, deadline) = match s.rsplit_once(" by ") {
+			Some((items, deadline)) => (items, Some(parse_time(deadline.trim())?)),
+			None => (s, None),
+		};
+
+		let mut goal = Goal {
+			items: Vec::new(),
+			deadline,
+		};
+		for item in items.split(&['+', ','][..]).map(|item| item.trim()) {
+			let invalid = || Error::other(format!("Invalid goal item `{}`", item));
+
+			let mut words = item.split_whitespace();
+			let first = words.next().ok_or_else(invalid)?;
+			let (count, name) = match first.parse::<usize>() {
+				Ok(count) => (count, words.next().ok_or_else(invalid)?),
+				Err(_) => (1, first),
+			};
+			if words.next().is_some() {
+				return Err(invalid());
+			}
+			goal.items.push((name.parse()?, count));
+		}
+		Ok(goal)
+	}
+}
+
+fn parse_time(s: &str) -> Result<f32, Error> {
+	let invalid = || Error::other(format!("Invalid time `{}`", s));
+	match s.split_once(':') {
+		Some((minutes, seconds)) => {
+			let minutes = minutes.parse::<u32>().map_err(|_| invalid())?;
+			let seconds = seconds.parse::<u32>().map_err(|_| invalid())?;
+			Ok((minutes * 60 + seconds) as f32)
+		}
+		None => s.parse().map_err(|_| invalid()),
+	}
+}
+
+/// Step of the plan with its timings.
+#[derive(Debug, Copy, Clone)]
+pub struct PlannedStep {
+	/// Step with supply used at its start.
+	pub step: BuildStep,
+	/// Time in seconds, when the step is started.
+	pub start: f32,
+	/// Time in seconds, when the step is complete.
+	pub finish: f32,
+}
+
+/// Result of [`Planner`].
+#[derive(Debug, Clone)]
+pub struct Plan {
+	/// Steps in order of their start, without workers trained between them.
+	pub steps: Vec<PlannedStep>,
+	/// Number of workers, which are trained until plan is complete.
+	pub workers: usize,
+	/// Time in seconds, when all steps are complete.
+	pub time: f32,
+}
+impl Plan {
+	/// Converts plan to [`BuildOrder`], which trains workers between steps.
+	pub fn build_order(&self) -> BuildOrder {
+		BuildOrder::new(self.steps.iter().map(|s| s.step).collect())
+	}
+	/// Checks if plan is complete before deadline of given goal.
+	pub fn meets(&self, goal: &Goal) -> bool {
+		goal.deadline.map_or(true, |deadline| self.time <= deadline)
+	}
+}
+
+/// Simulator of economy, which searches for the fastest order of production.
+///
+/// Search goes over orders of required items (including tech, producers and gas buildings),
+/// number of trained workers and timings of supply providers.
+#[derive(Clone)]
+pub struct Planner<'a> {
+	data: &'a GameData,
+	version: &'a VersionData,
+	race: Race,
+	/// Maximum number of workers, tried by [`optimize`](Self::optimize). [Default: `22`]
+	pub max_workers: usize,
+	/// Maximum time of simulation in seconds. [Default: `900`]
+	pub time_limit: f32,
+	/// Maximum number of different orders of items, tried by [`optimize`](Self::optimize).
+	/// [Default: `100`]
+	pub max_orders: usize,
+}
+impl<'a> Planner<'a> {
+	/// Constructs planner for given race with default settings.
+	pub fn new(race: Race, data: &'a GameData) -> Self {
+		Self {
+			data,
+			version: version::latest(),
+			race,
+			max_workers: 22,
+			time_limit: 900.0,
+			max_orders: 100,
+		}
+	}
+	/// Sets game version data, used for tech requirements. [Default: the latest version]
+	///
+	/// Should match the version of game data, e.g. [`version`](crate::bot::Bot::version) of bot.
+	pub fn with_version(mut self, version: &'a VersionData) -> Self {
+		self.version = version;
+		self
+	}
+	/// Sets maximum number of workers.
+	pub fn with_max_workers(mut self, workers: usize) -> Self {
+		self.max_workers = workers;
+		self
+	}
+	/// Sets maximum time of simulation in seconds.
+	pub fn with_time_limit(mut self, seconds: f32) -> Self {
+		self.time_limit = seconds;
+		self
+	}
+	/// Sets maximum number of different orders of items.
+	pub fn with_max_orders(mut self, orders: usize) -> Self {
+		self.max_orders = orders;
+		self
+	}
+
+	/// Searches for the fastest plan, which makes all items of the goal.
+	///
+	/// Returns `None` if some item can't be made or goal can't be reached in the time limit.
+	pub fn optimize(&self, goal: &Goal) -> Option<Plan> {
+		let items = self.requirements(goal)?;
+		let gas = RACE_VALUES[&self.race].gas;
+		let mut variants = vec![items.clone()];
+		if let Some(first_gas) = items.iter().position(|item| *item == BuildItem::Unit(gas)) {
+			let mut items = items;
+			items.insert(first_gas + 1, BuildItem::Unit(gas));
+			variants.push(items);
+		}
+
+		let mut best: Option<Plan> = None;
+		for items in &variants {
+			for order in self.orders(items) {
+				for workers in START_WORKERS..=self.max_workers.max(START_WORKERS) {
+					for margin in SUPPLY_MARGINS {
+						let limit = best.as_ref().map_or(self.time_limit, |plan| plan.time);
+						if let Some(plan) = self.run(&order, workers, margin, limit) {
+							if best.as_ref().map_or(true, |best| plan.time < best.time) {
+								best = Some(plan);
+							}
+						}
+					}
+				}
+			}
+		}
+		best
+	}
+	/// Simulates given order of items, while training workers up to given number.
+	///
+	/// Items are made in given order, every item is skipped if bot already has enough of them
+	/// (e.g. second `SupplyDepot` in the order is skipped if bot has 2 depots).
+	/// Returns `None` if some item can't be made or order can't be finished in the time limit.
+	pub fn simulate(&self, order: &[BuildItem], workers: usize) -> Option<Plan> {
+		self.run(order, workers, SUPPLY_MARGINS[0], self.time_limit)
+	}
+
+	fn run(&self, order: &[BuildItem], workers: usize, margin: u32, limit: f32) -> Option<Plan> {
+		let mut sim = Simulation::new(self, workers, margin, order);
+		let limit = (limit * FRAMES_PER_SECOND) as u32;
+		while !sim.is_complete() {
+			if sim.now > limit {
+				return None;
+			}
+			sim.step()?;
+		}
+		Some(Plan {
+			steps: sim.steps,
+			workers,
+			time: sim.now as f32 / FRAMES_PER_SECOND,
+		})
+	}
+
+	/// Items, which bot has on start of the game.
+	fn start_units(&self) -> FxHashMap<UnitTypeId, usize> {
+		let race_values = &RACE_VALUES[&self.race];
+		let mut units = FxHashMap::default();
+		units.insert(race_values.start_townhall, 1);
+		units.insert(race_values.worker, START_WORKERS);
+		if self.race == Race::Zerg {
+			units.insert(UnitTypeId::Overlord, 1);
+		}
+		units
+	}
+	/// Items, which must exist before given item can be started.
+	fn prerequisites(&self, item: BuildItem) -> Option<Vec<UnitTypeId>> {
+		let race_values = &RACE_VALUES[&self.race];
+		let mut prerequisites = Vec::new();
+		let vespene = match item {
+			BuildItem::Unit(unit) => {
+				let data = self.data.units.get(&unit)?;
+				let producer = *PRODUCERS.get(&unit)?;
+				prerequisites.extend(self.version.tech_requirements.get(&unit));
+				if producer == race_values.worker {
+					// Protoss structures need power
+					if self.race == Race::Protoss
+						&& !matches!(
+							unit,
+							UnitTypeId::Pylon | UnitTypeId::Nexus | UnitTypeId::Assimilator
+						) {
+						prerequisites.push(UnitTypeId::Pylon);
+					}
+				} else if producer != UnitTypeId::Larva {
+					prerequisites.push(producer);
+				}
+				if data.require_attached {
+					prerequisites.push(techlab(producer)?);
+				}
+				self.data.unit_cost(unit).vespene
+			}
+			BuildItem::Upgrade(upgrade) => {
+				prerequisites.push(*RESEARCHERS.get(&upgrade)?);
+				self.data.upgrades.get(&upgrade)?.vespene_cost
+			}
+		};
+		if vespene > 0 {
+			prerequisites.push(race_values.gas);
+		}
+		Some(prerequisites)
+	}
+	/// Expands goal into list of items, including all prerequisites.
+	fn requirements(&self, goal: &Goal) -> Option<Vec<BuildItem>> {
+		let mut counts = self
+			.start_units()
+			.into_iter()
+			.map(|(unit, count)| (BuildItem::Unit(unit), count))
+			.collect::<FxHashMap<_, _>>();
+		let mut items = Vec::new();
+		for (item, count) in &goal.items {
+			self.require(*item, *count, false, &mut counts, &mut items)?;
+		}
+		Some(items)
+	}
+	/// Adds item to the list until there's given count of it,
+	/// tech alias is counted for prerequisites (e.g. Lair instead of Hatchery).
+	fn require(
+		&self,
+		item: BuildItem,
+		count: usize,
+		alias: bool,
+		counts: &mut FxHashMap<BuildItem, usize>,
+		items: &mut Vec<BuildItem>,
+	) -> Option<()> {
+		let has = |counts: &FxHashMap<BuildItem, usize>| match item {
+			BuildItem::Unit(unit) if alias => {
+				counts.get(&item).copied().unwrap_or(0)
+					+ TECH_ALIAS.get(&unit).map_or(0, |alias| {
+						alias
+							.iter()
+							.map(|a| counts.get(&BuildItem::Unit(*a)).copied().unwrap_or(0))
+							.sum()
+					})
+			}
+			_ => counts.get(&item).copied().unwrap_or(0),
+		};
+		if has(counts) >= count {
+			return Some(());
+		}
+		let needed = count - has(counts);
+		let source = match item {
+			BuildItem::Unit(unit) => self.morph_source(unit),
+			BuildItem::Upgrade(_) => None,
+		};
+		for prerequisite in self.prerequisites(item)? {
+			if Some(prerequisite) != source {
+				self.require(BuildItem::Unit(prerequisite), 1, true, counts, items)?;
+			}
+		}
+		// Every unit morphing from other unit consumes it
+		if let Some(source) = source {
+			let source = BuildItem::Unit(source);
+			let has_source = counts.get(&source).copied().unwrap_or(0);
+			self.require(source, has_source + needed, false, counts, items)?;
+			*counts.get_mut(&source)? -= needed;
+		}
+		let per_item = match item {
+			BuildItem::Unit(unit) => units_per_item(unit),
+			BuildItem::Upgrade(_) => 1,
+		};
+		let new_items = needed.div_ceil(per_item);
+		for _ in 0..new_items {
+			items.push(item);
+		}
+		*counts.entry(item).or_default() += new_items * per_item;
+		Some(())
+	}
+	/// Unit, which is consumed to make given unit (e.g. Zergling for Baneling).
+	fn morph_source(&self, unit: UnitTypeId) -> Option<UnitTypeId> {
+		let producer = *PRODUCERS.get(&unit)?;
+		if producer == RACE_VALUES[&self.race].worker || producer == UnitTypeId::Larva {
+			return None;
+		}
+		let data = self.data.units.get(&producer)?;
+		(!data.attributes.contains(&Attribute::Structure)).then_some(producer)
+	}
+	/// Enumerates orders of items, where each item goes after its prerequisites.
+	fn orders(&self, items: &[BuildItem]) -> Vec<Vec<BuildItem>> {
+		// Item depends on the first occurrences of its prerequisites, previous item of the same type
+		// and all units it morphs from
+		let dependencies = items
+			.iter()
+			.enumerate()
+			.map(|(i, item)| {
+				let prerequisites = self.prerequisites(*item).unwrap_or_default();
+				let source = match item {
+					BuildItem::Unit(unit) => self.morph_source(*unit),
+					BuildItem::Upgrade(_) => None,
+				};
+				items[..i]
+					.iter()
+					.enumerate()
+					.filter(|(j, other)| {
+						*other == item
+							|| match other {
+								BuildItem::Unit(unit) => {
+									Some(*unit) == source
+										|| (prerequisites.contains(unit) && !items[..*j].contains(other))
+								}
+								BuildItem::Upgrade(_) => false,
+							}
+					})
+					.map(|(j, _)| j)
+					.collect::<Vec<_>>()
+			})
+			.collect::<Vec<_>>();
+
+		let mut orders = Vec::new();
+		let mut placed = vec![false; items.len()];
+		let mut order = Vec::with_capacity(items.len());
+		self.collect_orders(items, &dependencies, &mut placed, &mut order, &mut orders);
+		orders
+	}
+	fn collect_orders(
+		&self,
+		items: &[BuildItem],
+		dependencies: &[Vec<usize>],
+		placed: &mut [bool],
+		order: &mut Vec<BuildItem>,
+		orders: &mut Vec<Vec<BuildItem>>,
+	) {
+		if orders.len() >= self.max_orders {
+			return;
+		}
+		if order.len() == items.len() {
+			orders.push(order.clone());
+			return;
+		}
+		let mut tried = FxHashSet::default();
+		for i in 0..items.len() {
+			if placed[i] || !dependencies[i].iter().all(|j| placed[*j]) || !tried.insert(items[i]) {
+				continue;
+			}
+			placed[i] = true;
+			order.push(items[i]);
+			self.collect_orders(items, dependencies, placed, order, orders);
+			order.pop();
+			placed[i] = false;
+		}
+	}
+}
+
+fn techlab(producer: UnitTypeId) -> Option<UnitTypeId> {
+	match producer {
+		UnitTypeId::Barracks => Some(UnitTypeId::BarracksTechLab),
+		UnitTypeId::Factory => Some(UnitTypeId::FactoryTechLab),
+		UnitTypeId::Starport => Some(UnitTypeId::StarportTechLab),
+		_ => None,
+	}
+}
+
+struct Producer {
+	kind: UnitTypeId,
+	busy_until: u32,
+	addon: Option<UnitTypeId>,
+	larva: usize,
+	next_larva: u32,
+}
+impl Producer {
+	fn new(kind: UnitTypeId, now: u32) -> Self {
+		Self {
+			kind,
+			busy_until: now,
+			addon: None,
+			larva: if kind == UnitTypeId::Hatchery { 1 } else { 0 },
+			next_larva: now + LARVA_LOOPS,
+		}
+	}
+}
+
+enum Done {
+	Unit {
+		unit: UnitTypeId,
+		producer: Option<usize>,
+		builder: bool,
+		step: Option<usize>,
+	},
+	Upgrade {
+		upgrade: UpgradeId,
+		step: usize,
+	},
+	Builder,
+}
+
+struct Simulation<'a> {
+	planner: &'a Planner<'a>,
+	race: Race,
+	worker: UnitTypeId,
+	gas: UnitTypeId,
+	max_workers: usize,
+	margin: u32,
+	now: u32,
+	minerals: f32,
+	vespene: f32,
+	mineral_workers: usize,
+	gas_workers: usize,
+	workers: usize,
+	supply_used: f32,
+	supply_cap: f32,
+	/// Complete units.
+	units: FxHashMap<UnitTypeId, usize>,
+	/// Complete units, including consumed by morphs.
+	made: FxHashMap<UnitTypeId, usize>,
+	/// Complete units, units in progress and started upgrades.
+	started: FxHashMap<BuildItem, usize>,
+	upgrades: FxHashSet<UpgradeId>,
+	gas_buildings: usize,
+	townhalls: usize,
+	producers: Vec<Producer>,
+	events: Vec<(u32, Done)>,
+	/// Items of the order with count of them, which should be made.
+	targets: Vec<(BuildItem, usize)>,
+	next: usize,
+	steps: Vec<PlannedStep>,
+}
+impl<'a> Simulation<'a> {
+	fn new(planner: &'a Planner<'a>, max_workers: usize, margin: u32, order: &[BuildItem]) -> Self {
+		let race_values = &RACE_VALUES[&planner.race];
+		let units = planner.start_units();
+		let started = units
+			.iter()
+			.map(|(unit, count)| (BuildItem::Unit(*unit), *count))
+			.collect::<FxHashMap<_, _>>();
+		let mut counts = started.clone();
+		let targets = order
+			.iter()
+			.map(|item| {
+				let count = counts.entry(*item).or_default();
+				*count += match item {
+					BuildItem::Unit(unit) => units_per_item(*unit),
+					BuildItem::Upgrade(_) => 1,
+				};
+				(*item, *count)
+			})
+			.collect();
+
+		let townhall = race_values.start_townhall;
+		let mut producer = Producer::new(townhall, 0);
+		if townhall == UnitTypeId::Hatchery {
+			producer.larva = MAX_LARVA;
+		}
+		let supply_cap = units
+			.iter()
+			.map(|(unit, count)| {
+				planner
+					.data
+					.units
+					.get(unit)
+					.map_or(0.0, |data| data.food_provided)
+					* *count as f32
+			})
+			.sum();
+
+		Self {
+			planner,
+			race: planner.race,
+			worker: race_values.worker,
+			gas: race_values.gas,
+			max_workers,
+			margin,
+			now: 0,
+			minerals: START_MINERALS,
+			vespene: 0.0,
+			mineral_workers: START_WORKERS,
+			gas_workers: 0,
+			workers: START_WORKERS,
+			supply_used: START_WORKERS as f32,
+			supply_cap,
+			made: units.clone(),
+			units,
+			started,
+			upgrades: Default::default(),
+			gas_buildings: 0,
+			townhalls: 1,
+			producers: vec![producer],
+			events: Vec::new(),
+			targets,
+			next: 0,
+			steps: Vec::new(),
+		}
+	}
+
+	fn count(&self, unit: UnitTypeId) -> usize {
+		// Drones are consumed by structures
+		if unit == self.worker {
+			return self.workers;
+		}
+		self.units.get(&unit).copied().unwrap_or(0)
+			+ TECH_ALIAS.get(&unit).map_or(0, |alias| {
+				alias
+					.iter()
+					.map(|a| self.units.get(a).copied().unwrap_or(0))
+					.sum()
+			})
+	}
+	fn started(&self, item: BuildItem) -> usize {
+		self.started.get(&item).copied().unwrap_or(0)
+	}
+	fn is_complete(&self) -> bool {
+		self.next >= self.targets.len()
+			&& self.targets.iter().all(|(item, count)| match item {
+				BuildItem::Unit(unit) if *unit == self.worker => self.workers >= *count,
+				BuildItem::Unit(unit) => self.made.get(unit).map_or(false, |c| c >= count),
+				BuildItem::Upgrade(upgrade) => self.upgrades.contains(upgrade),
+			})
+	}
+
+	/// Advances simulation by one step, returns `None` if the next item can't be ever made.
+	fn step(&mut self) -> Option<()> {
+		self.now += STEP;
+		self.mine();
+		self.spawn_larva();
+		self.complete_events();
+
+		if !self.provide_supply() {
+			return Some(());
+		}
+		while let Some((item, count)) = self.targets.get(self.next).copied() {
+			if self.started(item) >= count {
+				self.next += 1;
+				continue;
+			}
+			if !self.start(item, true)? {
+				break;
+			}
+			self.next += 1;
+		}
+		self.train_workers();
+		Some(())
+	}
+
+	fn mine(&mut self) {
+		let fields = MINERAL_FIELDS * self.townhalls;
+		let full = self.mineral_workers.min(fields * 2);
+		let extra = (self.mineral_workers - full).min(fields);
+		let minerals = full as f32 + extra as f32 * THIRD_WORKER_EFFICIENCY;
+		self.minerals += minerals * MINERALS_PER_TRIP / MINERAL_TRIP_LOOPS * STEP as f32;
+
+		let gas_workers = self.gas_workers.min(self.gas_buildings * WORKERS_PER_GAS);
+		self.vespene += gas_workers as f32 * VESPENE_PER_TRIP / VESPENE_TRIP_LOOPS * STEP as f32;
+	}
+	fn spawn_larva(&mut self) {
+		let now = self.now;
+		for hatchery in self
+			.producers
+			.iter_mut()
+			.filter(|p| matches!(p.kind, UnitTypeId::Hatchery | UnitTypeId::Lair | UnitTypeId::Hive))
+		{
+			if hatchery.larva >= MAX_LARVA {
+				hatchery.next_larva = now + LARVA_LOOPS;
+			} else if now >= hatchery.next_larva {
+				hatchery.larva += 1;
+				hatchery.next_larva += LARVA_LOOPS;
+			}
+		}
+	}
+	fn complete_events(&mut self) {
+		let now = self.now;
+		if self.events.iter().all(|(at, _)| *at > now) {
+			return;
+		}
+		let (done, pending) = self.events.drain(..).partition::<Vec<_>, _>(|(at, _)| *at <= now);
+		self.events = pending;
+		for (_, done) in done {
+			match done {
+				Done::Unit {
+					unit,
+					producer,
+					builder,
+					step,
+				} => self.complete_unit(unit, producer, builder, step),
+				Done::Upgrade { upgrade, step } => {
+					self.upgrades.insert(upgrade);
+					self.steps[step].finish = now as f32 / FRAMES_PER_SECOND;
+				}
+				Done::Builder => self.mineral_workers += 1,
+			}
+		}
+	}
+	fn complete_unit(
+		&mut self,
+		unit: UnitTypeId,
+		producer: Option<usize>,
+		builder: bool,
+		step: Option<usize>,
+	) {
+		let data = &self.planner.data.units[&unit];
+		*self.units.entry(unit).or_default() += units_per_item(unit);
+		*self.made.entry(unit).or_default() += units_per_item(unit);
+		self.supply_cap = (self.supply_cap + data.food_provided).min(200.0);
+		if let Some(step) = step {
+			self.steps[step].finish = self.now as f32 / FRAMES_PER_SECOND;
+		}
+		if builder {
+			self.mineral_workers += 1;
+		}
+
+		if unit == self.worker {
+			self.workers += 1;
+			self.mineral_workers += 1;
+		} else if unit == self.gas {
+			self.gas_buildings += 1;
+			let moved = self.mineral_workers.min(WORKERS_PER_GAS);
+			self.mineral_workers -= moved;
+			self.gas_workers += moved;
+		} else if data.attributes.contains(&Attribute::Structure) {
+			if RACE_VALUES[&self.race].townhalls.contains(&unit) && producer.is_none() {
+				self.townhalls += 1;
+			}
+			match producer {
+				// Addon
+				Some(p) if techlab(self.producers[p].kind) == Some(unit) || is_reactor(unit) => {
+					self.producers[p].addon = Some(unit);
+					self.producers.push(Producer::new(unit, self.now));
+				}
+				// Morph
+				Some(p) => {
+					let producer = &mut self.producers[p];
+					if let Some(count) = self.units.get_mut(&producer.kind) {
+						*count = count.saturating_sub(1);
+					}
+					producer.kind = unit;
+				}
+				None => self.producers.push(Producer::new(unit, self.now)),
+			}
+		}
+	}
+
+	/// Starts supply provider if free supply is low, returns `false` if it can't be afforded.
+	fn provide_supply(&mut self) -> bool {
+		let data = &self.planner.data.units;
+		let supply = RACE_VALUES[&self.race].supply;
+		let in_progress = self
+			.events
+			.iter()
+			.filter_map(|(_, done)| match done {
+				Done::Unit { unit, .. } => data.get(unit).map(|data| data.food_provided),
+				_ => None,
+			})
+			.sum::<f32>();
+		let cap = self.supply_cap + in_progress;
+		if cap >= 200.0 || cap - self.supply_used > self.margin as f32 {
+			return true;
+		}
+		self.start(BuildItem::Unit(supply), false) == Some(true)
+	}
+	fn train_workers(&mut self) {
+		while self.workers + self.workers_in_progress() < self.max_workers
+			&& self.start(BuildItem::Unit(self.worker), false) == Some(true)
+		{}
+	}
+	fn workers_in_progress(&self) -> usize {
+		self.events
+			.iter()
+			.filter(|(_, done)| matches!(done, Done::Unit { unit, .. } if *unit == self.worker))
+			.count()
+	}
+
+	/// Tries to start given item, returns `None` if it can't be ever made
+	/// and `false` if it can't be made now.
+	fn start(&mut self, item: BuildItem, record: bool) -> Option<bool> {
+		match item {
+			BuildItem::Unit(unit) => self.start_unit(unit, record),
+			BuildItem::Upgrade(upgrade) => self.start_upgrade(upgrade),
+		}
+	}
+	fn start_unit(&mut self, unit: UnitTypeId, record: bool) -> Option<bool> {
+		let planner = self.planner;
+		let data = planner.data.units.get(&unit)?;
+		let producers = ALL_PRODUCERS.get(&unit)?;
+		let cost = planner.data.unit_cost(unit);
+		if self.minerals < cost.minerals as f32
+			|| self.vespene < cost.vespene as f32
+			|| self.supply_cap - self.supply_used < cost.supply
+		{
+			return Some(false);
+		}
+		let has_prerequisites = planner
+			.prerequisites(BuildItem::Unit(unit))?
+			.into_iter()
+			.filter(|prerequisite| *prerequisite != unit && !producers.contains(prerequisite))
+			.all(|prerequisite| prerequisite == self.gas || self.count(prerequisite) > 0);
+		if !has_prerequisites {
+			return Some(false);
+		}
+
+		let build_time = data.build_time as u32;
+		let is_structure = data.attributes.contains(&Attribute::Structure);
+		let mut producer = None;
+		let mut builder = false;
+		let finish;
+		if producers.contains(&self.worker) {
+			if self.mineral_workers == 0 {
+				return Some(false);
+			}
+			self.mineral_workers -= 1;
+			finish = self.now + BUILDER_TRAVEL_LOOPS + build_time;
+			match self.race {
+				Race::Terran => builder = true,
+				Race::Zerg => {
+					self.workers -= 1;
+					self.supply_used -= 1.0;
+				}
+				_ => self
+					.events
+					.push((self.now + BUILDER_TRAVEL_LOOPS * 2, Done::Builder)),
+			}
+		} else if producers.contains(&UnitTypeId::Larva) {
+			let hatchery = self.producers.iter_mut().find(|p| p.larva > 0);
+			match hatchery {
+				Some(hatchery) => hatchery.larva -= 1,
+				None => return Some(false),
+			}
+			finish = self.now + build_time;
+		} else {
+			let now = self.now;
+			let is_addon = techlab(producers[0]) == Some(unit) || is_reactor(unit);
+			let index = self.producers.iter().position(|p| {
+				producers.contains(&p.kind)
+					&& p.busy_until <= now
+					&& if is_addon {
+						p.addon.is_none()
+					} else {
+						!data.require_attached || p.addon.map_or(false, |addon| !is_reactor(addon))
+					}
+			});
+			match index {
+				Some(index) => {
+					self.producers[index].busy_until = now + build_time;
+					if is_structure {
+						producer = Some(index);
+					}
+				}
+				None => {
+					// Units morphing from other units (e.g. Baneling) consume them
+					let unit_producer = producers.iter().find(|p| {
+						self.units.get(p).map_or(false, |count| *count > 0)
+							&& planner
+								.data
+								.units
+								.get(p)
+								.map_or(false, |data| !data.attributes.contains(&Attribute::Structure))
+					});
+					match unit_producer {
+						Some(p) => *self.units.get_mut(p).unwrap() -= 1,
+						None => return Some(false),
+					}
+				}
+			}
+			finish = now + build_time;
+		}
+
+		// Workers trained between steps aren't recorded
+		let step = if record || unit != self.worker {
+			self.steps.push(PlannedStep {
+				step: BuildStep::new(self.supply_used as u32, unit),
+				start: self.now as f32 / FRAMES_PER_SECOND,
+				finish: finish as f32 / FRAMES_PER_SECOND,
+			});
+			Some(self.steps.len() - 1)
+		} else {
+			None
+		};
+		self.minerals -= cost.minerals as f32;
+		self.vespene -= cost.vespene as f32;
+		self.supply_used += cost.supply;
+		*self.started.entry(BuildItem::Unit(unit)).or_default() += units_per_item(unit);
+		self.events.push((
+			finish,
+			Done::Unit {
+				unit,
+				producer,
+				builder,
+				step,
+			},
+		));
+		Some(true)
+	}
+	fn start_upgrade(&mut self, upgrade: UpgradeId) -> Option<bool> {
+		let data = self.planner.data.upgrades.get(&upgrade)?;
+		let researcher = *RESEARCHERS.get(&upgrade)?;
+		if self.minerals < data.mineral_cost as f32 || self.vespene < data.vespene_cost as f32 {
+			return Some(false);
+		}
+		let now = self.now;
+		let producer = match self
+			.producers
+			.iter_mut()
+			.find(|p| p.kind == researcher && p.busy_until <= now)
+		{
+			Some(producer) => producer,
+			None => return Some(false),
+		};
+		let finish = now + data.research_time as u32;
+		producer.busy_until = finish;
+
+		self.steps.push(PlannedStep {
+			step: BuildStep::new(self.supply_used as u32, upgrade),
+			start: now as f32 / FRAMES_PER_SECOND,
+			finish: finish as f32 / FRAMES_PER_SECOND,
+		});
+		self.minerals -= data.mineral_cost as f32;
+		self.vespene -= data.vespene_cost as f32;
+		*self.started.entry(BuildItem::Upgrade(upgrade)).or_default() += 1;
+		self.events.push((
+			finish,
+			Done::Upgrade {
+				upgrade,
+				step: self.steps.len() - 1,
+			},
+		));
+		Some(true)
+	}
+}
+
+/// Number of units made by one order (Zerglings hatch in pairs).
+fn units_per_item(unit: UnitTypeId) -> usize {
+	if unit == UnitTypeId::Zergling {
+		2
+	} else {
+		1
+	}
+}
+
+fn is_reactor(unit: UnitTypeId) -> bool {
+	matches!(
+		unit,
+		UnitTypeId::BarracksReactor | UnitTypeId::FactoryReactor | UnitTypeId::StarportReactor
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{ids::AbilityId, version, FromProto};
+	use sc2_proto::{
+		data::{Attribute as ProtoAttribute, UnitTypeData, UpgradeData},
+		sc2api::ResponseData,
+	};
+
+	/// Terran data needed to research Stimpack, build times are in game loops.
+	fn data() -> GameData {
+		let mut res = ResponseData::new();
+		for (unit, minerals, vespene, food, provided, time) in [
+			(UnitTypeId::CommandCenter, 400, 0, 0.0, 15.0, 1590.0),
+			(UnitTypeId::SCV, 50, 0, 1.0, 0.0, 272.0),
+			(UnitTypeId::SupplyDepot, 100, 0, 0.0, 8.0, 470.0),
+			(UnitTypeId::Refinery, 75, 0, 0.0, 0.0, 470.0),
+			(UnitTypeId::Barracks, 150, 0, 0.0, 0.0, 1030.0),
+			(UnitTypeId::BarracksTechLab, 50, 25, 0.0, 0.0, 560.0),
+		] {
+			let mut data = UnitTypeData::new();
+			data.set_unit_id(unit as u32);
+			data.set_available(true);
+			data.set_mineral_cost(minerals);
+			data.set_vespene_cost(vespene);
+			data.set_food_required(food);
+			data.set_food_provided(provided);
+			data.set_build_time(time);
+			data.set_has_vespene(unit == UnitTypeId::Refinery);
+			if unit != UnitTypeId::SCV {
+				data.mut_attributes().push(ProtoAttribute::Structure);
+			}
+			res.mut_units().push(data);
+		}
+		let mut stimpack = UpgradeData::new();
+		stimpack.set_upgrade_id(UpgradeId::Stimpack as u32);
+		stimpack.set_ability_id(AbilityId::BarracksTechLabResearchStimpack as u32);
+		stimpack.set_mineral_cost(100);
+		stimpack.set_vespene_cost(100);
+		stimpack.set_research_time(2240.0);
+		res.mut_upgrades().push(stimpack);
+		GameData::from_proto((res, version::latest()))
+	}
+
+	#[test]
+	fn parses_goal() {
+		let goal = "2 Barracks + Stimpack by 4:00".parse::<Goal>().unwrap();
+		assert_eq!(
+			goal.items,
+			vec![
+				(BuildItem::Unit(UnitTypeId::Barracks), 2),
+				(BuildItem::Upgrade(UpgradeId::Stimpack), 1),
+			]
+		);
+		assert_eq!(goal.deadline, Some(240.0));
+
+		let goal = "Marine, 3 SCV by 90".parse::<Goal>().unwrap();
+		assert_eq!(
+			goal.items,
+			vec![
+				(BuildItem::Unit(UnitTypeId::Marine), 1),
+				(BuildItem::Unit(UnitTypeId::SCV), 3),
+			]
+		);
+		assert_eq!(goal.deadline, Some(90.0));
+		assert_eq!("Barracks".parse::<Goal>().unwrap().deadline, None);
+	}
+
+	#[test]
+	fn rejects_invalid_goal() {
+		for goal in [
+			"",
+			"2",
+			"Barracks +",
+			"2 Barracks Stimpack",
+			"2 Barrack",
+			"Barracks by soon",
+			"Barracks by 4:xx",
+		] {
+			assert!(goal.parse::<Goal>().is_err(), "`{}` is parsed", goal);
+		}
+	}
+
+	#[test]
+	fn expands_requirements() {
+		let data = data();
+		let planner = Planner::new(Race::Terran, &data);
+		let goal = Goal::new().with(UpgradeId::Stimpack, 1);
+		let items = planner.requirements(&goal).unwrap();
+
+		let position = |item: BuildItem| items.iter().position(|i| *i == item).unwrap();
+		let depot = position(BuildItem::Unit(UnitTypeId::SupplyDepot));
+		let barracks = position(BuildItem::Unit(UnitTypeId::Barracks));
+		let refinery = position(BuildItem::Unit(UnitTypeId::Refinery));
+		let techlab = position(BuildItem::Unit(UnitTypeId::BarracksTechLab));
+		let stimpack = position(BuildItem::Upgrade(UpgradeId::Stimpack));
+		assert_eq!(items.len(), 5, "{:?}", items);
+		assert!(depot < barracks && barracks < techlab && refinery < techlab && techlab < stimpack);
+
+		// Units bot starts with aren't required again
+		let goal = Goal::new()
+			.with(UnitTypeId::SCV, 12)
+			.with(UnitTypeId::Barracks, 2);
+		let items = planner.requirements(&goal).unwrap();
+		assert_eq!(
+			items,
+			vec![
+				BuildItem::Unit(UnitTypeId::SupplyDepot),
+				BuildItem::Unit(UnitTypeId::Barracks),
+				BuildItem::Unit(UnitTypeId::Barracks),
+			]
+		);
+
+		// Items without data can't be made
+		let goal = Goal::new().with(UnitTypeId::Marine, 1);
+		assert!(planner.requirements(&goal).is_none());
+	}
+
+	#[test]
+	fn requirements_follow_version() {
+		let data = data();
+		let goal = Goal::new().with(UnitTypeId::Barracks, 1);
+		let items = Planner::new(Race::Terran, &data).requirements(&goal).unwrap();
+		assert_eq!(
+			items,
+			vec![
+				BuildItem::Unit(UnitTypeId::SupplyDepot),
+				BuildItem::Unit(UnitTypeId::Barracks),
+			]
+		);
+
+		let mut version = version::latest().clone();
+		version.tech_requirements.remove(&UnitTypeId::Barracks);
+		let items = Planner::new(Race::Terran, &data)
+			.with_version(&version)
+			.requirements(&goal)
+			.unwrap();
+		assert_eq!(items, vec![BuildItem::Unit(UnitTypeId::Barracks)]);
+	}
+
+	#[test]
+	fn more_workers_make_plan_faster() {
+		let data = data();
+		let goal = "2 Barracks + Stimpack".parse::<Goal>().unwrap();
+		let mut previous = f32::INFINITY;
+		for max_workers in [12, 16, 20] {
+			let plan = Planner::new(Race::Terran, &data)
+				.with_max_workers(max_workers)
+				.with_max_orders(10)
+				.optimize(&goal)
+				.unwrap();
+			assert!(plan.workers <= max_workers);
+			assert!(plan.time <= previous, "{} > {}", plan.time, previous);
+			previous = plan.time;
+
+			let made = plan.steps.iter().map(|s| s.step.item).collect::<Vec<_>>();
+			assert!(made.contains(&BuildItem::Upgrade(UpgradeId::Stimpack)));
+			let barracks = made
+				.iter()
+				.filter(|item| **item == BuildItem::Unit(UnitTypeId::Barracks))
+				.count();
+			assert_eq!(barracks, 2);
+		}
+	}
+}
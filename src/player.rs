@@ -145,7 +145,8 @@ impl Default for AIBuild {
 }
 
 /// Type of the player, used when joining a game.
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PlayerType {
 	/// Bot or Human.
 	Participant,
@@ -174,6 +175,7 @@ impl IntoProto<ProtoPlayerType> for PlayerType {
 }
 
 /// Computer opponent configuration used in [`run_vs_computer`](crate::client::run_vs_computer).
+#[derive(Debug, Copy, Clone)]
 pub struct Computer {
 	pub race: Race,
 	pub difficulty: Difficulty,
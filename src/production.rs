@@ -0,0 +1,263 @@
+//! Queue of production requests, which persists across steps and reserves resources by priority.
+//!
+//! ```no_run
+//! use rust_sc2::{
+//!     bot::Bot,
+//!     ids::UnitTypeId,
+//!     production::{ProductionManager, Request},
+//! };
+//! # fn f(bot: &mut Bot, production: &mut ProductionManager) {
+//! // Probes are made first, Zealots don't spend minerals saved for Nexus
+//! production.add(Request::new(UnitTypeId::Probe, 2).with_priority(2));
+//! production.add(Request::new(UnitTypeId::Nexus, 1).with_priority(1));
+//! production.add(Request::new(UnitTypeId::Zealot, 5));
+//!
+//! for blocked in production.execute(bot) {
+//!     println!("{}", blocked);
+//! }
+//! println!("Saving {} minerals", production.reserved().minerals);
+//! # }
+//! ```
+//!
+//! Request is considered done when all its orders are given,
+//! orders which fail later (e.g. builder gets killed) aren't repeated.
+
+use crate::{
+	bot::Bot,
+	build_order::{start_unit, start_upgrade, BlockReason, BuildItem},
+	game_data::Cost,
+};
+use rustc_hash::FxHashMap;
+use std::fmt;
+
+/// Identifier of request, returned by [`ProductionManager::add`].
+pub type RequestId = usize;
+
+/// Units or upgrade to make with given priority.
+#[derive(Debug, Clone)]
+pub struct Request {
+	id: RequestId,
+	/// Unit or upgrade to make.
+	pub item: BuildItem,
+	/// Number of orders left.
+	pub count: usize,
+	/// Requests with greater priority are started first. [Default: `0`]
+	pub priority: i32,
+	/// Save resources for this request while it can't be started,
+	/// so requests with lower priority don't spend them. [Default: `true`]
+	pub reserve: bool,
+}
+impl Request {
+	/// Constructs request to make given number of units or upgrade.
+	pub fn new<I: Into<BuildItem>>(item: I, count: usize) -> Self {
+		Self {
+			id: 0,
+			item: item.into(),
+			count,
+			priority: 0,
+			reserve: true,
+		}
+	}
+	/// Sets priority of request.
+	pub fn with_priority(mut self, priority: i32) -> Self {
+		self.priority = priority;
+		self
+	}
+	/// Sets `reserve` to a given value.
+	pub fn reserve(mut self, val: bool) -> Self {
+		self.reserve = val;
+		self
+	}
+	/// Identifier of request, assigned when it's added to the manager.
+	pub fn id(&self) -> RequestId {
+		self.id
+	}
+}
+
+/// Request, which can't be started on this step, returned by [`ProductionManager::execute`].
+#[derive(Debug, Clone)]
+pub struct BlockedRequest {
+	/// Identifier of request.
+	pub id: RequestId,
+	/// Unit or upgrade of request.
+	pub item: BuildItem,
+	/// Why request can't be started.
+	pub reason: BlockReason,
+}
+impl fmt::Display for BlockedRequest {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"Request {} `{}` is blocked: {}",
+			self.id, self.item, self.reason
+		)
+	}
+}
+
+/// Manager of production requests, sorted by priority.
+#[derive(Debug, Default, Clone)]
+pub struct ProductionManager {
+	requests: Vec<Request>,
+	next_id: RequestId,
+	reserved: Cost,
+}
+impl ProductionManager {
+	/// Constructs empty manager.
+	pub fn new() -> Self {
+		Self::default()
+	}
+	/// Adds request to the queue after requests with the same or greater priority.
+	pub fn add(&mut self, mut request: Request) -> RequestId {
+		let id = self.next_id;
+		self.next_id += 1;
+		request.id = id;
+		let index = self
+			.requests
+			.iter()
+			.position(|r| r.priority < request.priority)
+			.unwrap_or(self.requests.len());
+		self.requests.insert(index, request);
+		id
+	}
+	/// Removes request from the queue and returns it.
+	pub fn cancel(&mut self, id: RequestId) -> Option<Request> {
+		let index = self.requests.iter().position(|r| r.id == id)?;
+		Some(self.requests.remove(index))
+	}
+	/// Removes all requests.
+	pub fn clear(&mut self) {
+		self.requests.clear();
+		self.reserved = Cost::default();
+	}
+	/// Returns request with given id if it's still in the queue.
+	pub fn get(&self, id: RequestId) -> Option<&Request> {
+		self.requests.iter().find(|r| r.id == id)
+	}
+	/// Requests in order of priority.
+	pub fn requests(&self) -> &[Request] {
+		&self.requests
+	}
+	/// Checks if all requests are done.
+	pub fn is_empty(&self) -> bool {
+		self.requests.is_empty()
+	}
+
+	/// Resources saved for blocked requests on the last [`execute`](Self::execute).
+	pub fn reserved(&self) -> &Cost {
+		&self.reserved
+	}
+	/// Total cost of all orders left in the queue.
+	pub fn planned_spending(&self, bot: &Bot) -> Cost {
+		let mut total = Cost::default();
+		for request in &self.requests {
+			let cost = item_cost(bot, request.item);
+			total.minerals += cost.minerals * request.count as u32;
+			total.vespene += cost.vespene * request.count as u32;
+			total.supply += cost.supply * request.count as f32;
+		}
+		total
+	}
+
+	/// Gives orders for requests in order of priority and subtracts their cost from bot's resources.
+	///
+	/// Requests which can't be started because of missing resources or supply
+	/// reserve resources for one order, the rest of blocked requests don't.
+	/// Returns all requests which can't be started on this step.
+	pub fn execute(&mut self, bot: &mut Bot) -> Vec<BlockedRequest> {
+		let mut reserved = Cost::default();
+		let mut queued = FxHashMap::default();
+		let mut blocked = Vec::new();
+
+		for request in &mut self.requests {
+			while request.count > 0 {
+				match start_reserved(bot, request.item, &reserved, &mut queued) {
+					Ok(()) => request.count -= 1,
+					Err(reason) => {
+						let waits_resources = matches!(
+							reason,
+							BlockReason::NotEnoughResources | BlockReason::SupplyBlocked
+						);
+						if request.reserve && waits_resources {
+							let cost = item_cost(bot, request.item);
+							reserved.minerals += cost.minerals;
+							reserved.vespene += cost.vespene;
+							reserved.supply += cost.supply;
+						}
+						blocked.push(BlockedRequest {
+							id: request.id,
+							item: request.item,
+							reason,
+						});
+						break;
+					}
+				}
+			}
+		}
+
+		self.requests.retain(|r| r.count > 0);
+		self.reserved = reserved;
+		blocked
+	}
+}
+
+fn item_cost(bot: &Bot, item: BuildItem) -> Cost {
+	match item {
+		BuildItem::Unit(unit) => bot.get_unit_cost(unit),
+		BuildItem::Upgrade(upgrade) => bot.get_upgrade_cost(upgrade),
+	}
+}
+
+/// Starts item with reserved resources hidden from the bot.
+fn start_reserved(
+	bot: &mut Bot,
+	item: BuildItem,
+	reserved: &Cost,
+	queued: &mut FxHashMap<u64, usize>,
+) -> Result<(), BlockReason> {
+	let (minerals, vespene, supply_left) = (bot.minerals, bot.vespene, bot.supply_left);
+	bot.minerals = minerals.saturating_sub(reserved.minerals);
+	bot.vespene = vespene.saturating_sub(reserved.vespene);
+	bot.supply_left = supply_left.saturating_sub(reserved.supply as u32);
+	let available = (bot.minerals, bot.vespene, bot.supply_left);
+
+	let result = match item {
+		BuildItem::Unit(unit) => start_unit(bot, unit, queued),
+		BuildItem::Upgrade(upgrade) => start_upgrade(bot, upgrade),
+	};
+
+	bot.minerals = minerals - (available.0 - bot.minerals);
+	bot.vespene = vespene - (available.1 - bot.vespene);
+	bot.supply_left = supply_left - (available.2 - bot.supply_left);
+	result
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ids::UnitTypeId;
+
+	#[test]
+	fn requests_are_sorted_by_priority() {
+		let mut production = ProductionManager::new();
+		let marine = production.add(Request::new(UnitTypeId::Marine, 1));
+		let scv = production.add(Request::new(UnitTypeId::SCV, 1).with_priority(2));
+		let depot = production.add(Request::new(UnitTypeId::SupplyDepot, 1).with_priority(1));
+		let reaper = production.add(Request::new(UnitTypeId::Reaper, 1));
+		let orbital = production.add(Request::new(UnitTypeId::OrbitalCommand, 1).with_priority(2));
+
+		// Requests with the same priority keep order they were added in
+		let ids = production.requests().iter().map(|r| r.id()).collect::<Vec<_>>();
+		assert_eq!(ids, [scv, orbital, depot, marine, reaper]);
+		assert_eq!(
+			production.get(depot).unwrap().item,
+			BuildItem::Unit(UnitTypeId::SupplyDepot)
+		);
+
+		assert_eq!(production.cancel(depot).unwrap().id(), depot);
+		assert!(production.get(depot).is_none());
+		assert!(production.cancel(depot).is_none());
+		assert_eq!(production.requests().len(), 4);
+		production.clear();
+		assert!(production.is_empty());
+	}
+}
@@ -2,6 +2,8 @@
 //! with methods for extracting useful info from them.
 
 use crate::{bot::Rs, distance::*, geometry::Point2, pixel_map::ByteMap};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::{
 	cmp::{Ordering, Reverse},
 	convert::TryInto,
@@ -9,7 +11,8 @@ use std::{
 };
 
 /// Structured collection of ramps.
-#[derive(Default)]
+#[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Ramps {
 	/// All ramps on the map.
 	pub all: Vec<Ramp>,
@@ -24,9 +27,11 @@ type Pos = (usize, usize);
 /// Ramp data structure with some helpful methods.
 /// All ramps stored in [`Ramps`] in [`ramps`](crate::bot::Bot::ramps) field of bot.
 #[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Ramp {
 	/// All points which belong to this ramp.
 	pub points: Vec<Pos>,
+	#[cfg_attr(feature = "serde", serde(skip))]
 	height: Rs<ByteMap>,
 	start_location: Point2,
 }
@@ -38,6 +43,10 @@ impl Ramp {
 			start_location,
 		}
 	}
+	#[cfg(feature = "serde")]
+	pub(crate) fn set_height(&mut self, height: &Rs<ByteMap>) {
+		self.height = Rs::clone(height);
+	}
 	/// Returns only upper points of the ramp.
 	pub fn upper(&self) -> Vec<Pos> {
 		let mut max = u8::MIN;
@@ -0,0 +1,518 @@
+//! Reading information and actions of players from replays without running a bot.
+//!
+//! ```no_run
+//! use rust_sc2::{prelude::*, replay::ReplayReader};
+//!
+//! let mut reader = ReplayReader::new();
+//! let info = reader.info("path/to/replay.SC2Replay")?;
+//! println!("{} ({} loops)", info.map_name, info.duration_loops);
+//! for player in &info.players {
+//!     println!("{:?}: {:?} {:?}", player.info.player_name, player.info.race_requested, player.result);
+//! }
+//!
+//! for action in reader.actions("path/to/replay.SC2Replay", 1)? {
+//!     let action = action?;
+//!     println!("{}: {:?}", action.game_loop, action.action);
+//! }
+//! # Ok::<(), rust_sc2::Error>(())
+//! ```
+
+use crate::{
+	action::Action,
+	api::API,
+	client::{
+		connect_to_websocket, get_unused_port, interface_options, launch_client_build, replay_info,
+		start_replay, SC2Result, HOST,
+	},
+	game_info::PlayerInfo,
+	paths::*,
+	player::GameResult,
+	version::{self, VersionData},
+	Error, FromProto, IntoSC2,
+};
+use sc2_proto::sc2api::{PlayerInfoExtra, Request, ResponseReplayInfo, Status};
+use std::{collections::VecDeque, process::Child};
+
+/// Information about replay.
+#[derive(Debug, Clone)]
+pub struct ReplayInfo {
+	/// Name of the map replay was played on.
+	pub map_name: String,
+	/// Path to the map on current computer.
+	pub local_map_path: String,
+	/// Players of the game.
+	pub players: Vec<ReplayPlayer>,
+	/// Length of the game in game loops.
+	pub duration_loops: u32,
+	/// Length of the game in seconds.
+	pub duration_seconds: f32,
+	/// Version of the game replay was played on (e.g. `"4.10.1.75800"`).
+	pub game_version: String,
+	/// Data version of the game replay was played on.
+	pub data_version: String,
+	/// Data build of the game replay was played on.
+	pub data_build: u32,
+	/// Base build of the game replay was played on.
+	pub base_build: u32,
+}
+impl ReplayInfo {
+	/// Returns player with given id.
+	pub fn player(&self, id: u32) -> Option<&ReplayPlayer> {
+		self.players.iter().find(|p| p.info.id == id)
+	}
+}
+impl FromProto<&ResponseReplayInfo> for ReplayInfo {
+	fn from_proto(info: &ResponseReplayInfo) -> Self {
+		Self {
+			map_name: info.get_map_name().to_string(),
+			local_map_path: info.get_local_map_path().to_string(),
+			players: info
+				.get_player_info()
+				.iter()
+				.map(ReplayPlayer::from_proto)
+				.collect(),
+			duration_loops: info.get_game_duration_loops(),
+			duration_seconds: info.get_game_duration_seconds(),
+			game_version: info.get_game_version().to_string(),
+			data_version: info.get_data_version().to_string(),
+			data_build: info.get_data_build(),
+			base_build: info.get_base_build(),
+		}
+	}
+}
+
+/// Information about player in replay.
+#[derive(Debug, Clone)]
+pub struct ReplayPlayer {
+	/// General information about player.
+	pub info: PlayerInfo,
+	/// Result of the game for player.
+	pub result: Option<GameResult>,
+	/// Matchmaking rating of player.
+	pub mmr: Option<i32>,
+	/// Actions per minute of player.
+	pub apm: Option<i32>,
+}
+impl FromProto<&PlayerInfoExtra> for ReplayPlayer {
+	fn from_proto(player: &PlayerInfoExtra) -> Self {
+		Self {
+			info: PlayerInfo::from_proto(player.get_player_info()),
+			result: player
+				.player_result
+				.as_ref()
+				.map(|result| result.get_result().into_sc2()),
+			mmr: player.player_mmr,
+			apm: player.player_apm,
+		}
+	}
+}
+
+/// Action made by player in replay.
+#[derive(Debug, Clone)]
+pub struct ReplayAction {
+	/// Id of player who made the action.
+	pub player_id: u32,
+	/// Game loop the action was made on.
+	pub game_loop: u32,
+	/// The action itself.
+	pub action: Action,
+}
+
+/// Reader of replays, which launches SC2 client on demand and reuses it for all replays.
+///
+/// Client is closed when reader is dropped.
+pub struct ReplayReader {
+	sc2_path: String,
+	process: Option<Child>,
+	api: Option<API>,
+	/// Base build of running SC2 client.
+	base_build: Option<u32>,
+	/// Number of game loops replay is stepped by while actions are collected.
+	/// Doesn't affect actions, since all actions made between observations are reported.
+	pub step_size: u32,
+}
+impl Default for ReplayReader {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+impl ReplayReader {
+	/// Constructs new replay reader.
+	pub fn new() -> Self {
+		Self {
+			sc2_path: get_path_to_sc2(),
+			process: None,
+			api: None,
+			base_build: None,
+			step_size: 64,
+		}
+	}
+
+	fn launch(&mut self, base_build: u32, data_hash: &str) -> SC2Result<()> {
+		self.close();
+		let port = get_unused_port()?;
+		debug!("Launching SC2 process with base build {}", base_build);
+		self.process = Some(launch_client_build(&self.sc2_path, port, base_build, data_hash)?);
+		debug!("Connecting to websocket");
+		self.api = Some(API::new(connect_to_websocket(HOST, port)?));
+		self.base_build = Some(base_build);
+		Ok(())
+	}
+	fn api(&self) -> SC2Result<&API> {
+		self.api.as_ref().ok_or(Error::ApiNotInitialized)
+	}
+
+	/// Returns information about replay in given path.
+	///
+	/// Replay isn't played, so any installed SC2 version can be used to read it.
+	///
+	/// Returns [`Error::Io`] if the replay doesn't exist.
+	pub fn info(&mut self, path: &str) -> SC2Result<ReplayInfo> {
+		let replay_path = get_replay_path(path)?;
+		if self.base_build.is_none() {
+			self.launch(get_latest_base_version(&self.sc2_path), "")?;
+		}
+		Ok(ReplayInfo::from_proto(&replay_info(self.api()?, &replay_path)?))
+	}
+
+	/// Returns iterator over actions of given player in replay, in order they were made.
+	///
+	/// Replay is played in background while iterator is consumed,
+	/// on SC2 version it was recorded on if it's installed, otherwise on the latest one.
+	///
+	/// Returns [`Error::Io`] if the replay doesn't exist.
+	pub fn actions(&mut self, path: &str, player_id: u32) -> SC2Result<ReplayActions<'_>> {
+		let replay_path = get_replay_path(path)?;
+		if self.base_build.is_none() {
+			self.launch(get_latest_base_version(&self.sc2_path), "")?;
+		}
+
+		let res_replay_info = replay_info(self.api()?, &replay_path)?;
+		let base_build = res_replay_info.get_base_build();
+		if self.base_build != Some(base_build) {
+			if has_base_version(&self.sc2_path, base_build) {
+				self.launch(base_build, res_replay_info.get_data_version())?;
+			} else {
+				warn!(
+					"SC2 version with base build {} isn't installed, replay may fail to play",
+					base_build
+				);
+			}
+		}
+		let version = version::select(base_build);
+
+		debug!("Sending StartReplay request");
+		let mut req = Request::new();
+		let req_start_replay = req.mut_start_replay();
+		req_start_replay.set_replay_path(replay_path);
+		req_start_replay.set_observed_player_id(player_id as i32);
+		req_start_replay.set_options(interface_options(false, false));
+		start_replay(self.api()?, req)?;
+
+		Ok(ReplayActions {
+			api: self.api()?,
+			version,
+			player_id,
+			step_size: self.step_size,
+			actions: VecDeque::new(),
+			started: false,
+			ended: false,
+		})
+	}
+
+	/// Manually closes SC2 client.
+	pub fn close(&mut self) {
+		if let Some(api) = &self.api {
+			let mut req = Request::new();
+			req.mut_quit();
+			if let Err(e) = api.send_request(req) {
+				error!("Request QuitGame failed: {}", e);
+			}
+		}
+
+		if let Some(process) = &mut self.process {
+			if let Err(e) = process.kill() {
+				error!("Can't kill SC2 process: {}", e);
+			}
+		}
+		self.process = None;
+		self.api = None;
+		self.base_build = None;
+	}
+}
+impl Drop for ReplayReader {
+	fn drop(&mut self) {
+		self.close();
+	}
+}
+
+/// Iterator over actions of a player in replay, returned by [`ReplayReader::actions`].
+///
+/// Yields error and stops if request to SC2 fails.
+pub struct ReplayActions<'a> {
+	api: &'a API,
+	version: &'static VersionData,
+	player_id: u32,
+	step_size: u32,
+	actions: VecDeque<ReplayAction>,
+	started: bool,
+	ended: bool,
+}
+impl ReplayActions<'_> {
+	/// Steps replay and collects actions made since the last observation.
+	fn fetch(&mut self) -> SC2Result<()> {
+		if self.started {
+			let mut req = Request::new();
+			req.mut_step().set_count(self.step_size);
+			self.api.send_request(req)?;
+		}
+		self.started = true;
+
+		let mut req = Request::new();
+		req.mut_observation();
+		let res = self.api.send(req)?;
+		if matches!(res.get_status(), Status::ended) {
+			self.ended = true;
+		}
+
+		let player_id = self.player_id;
+		let version = self.version;
+		self.actions
+			.extend(res.get_observation().get_actions().iter().filter_map(|action| {
+				Some(ReplayAction {
+					player_id,
+					game_loop: action.get_game_loop(),
+					action: Option::<Action>::from_proto((action, version))?,
+				})
+			}));
+		Ok(())
+	}
+}
+impl Iterator for ReplayActions<'_> {
+	type Item = SC2Result<ReplayAction>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			if let Some(action) = self.actions.pop_front() {
+				return Some(Ok(action));
+			}
+			if self.ended {
+				return None;
+			}
+			if let Err(e) = self.fetch() {
+				self.ended = true;
+				return Some(Err(e));
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		action::{Target, UIAction},
+		ids::AbilityId,
+		mock::{MockGame, MockServer},
+	};
+	use protobuf::Message;
+	use sc2_proto::sc2api::{
+		Action as ProtoAction, Response, ResponseData, ResponseGameInfo, ResponseObservation,
+	};
+	use std::{
+		env::temp_dir,
+		fs,
+		net::TcpListener,
+		thread::{self, JoinHandle},
+	};
+	use tungstenite::{accept, Message::Binary};
+
+	fn replay_file(name: &str) -> String {
+		let path = temp_dir().join(name);
+		fs::write(&path, []).unwrap();
+		path.to_string_lossy().into_owned()
+	}
+
+	/// Reader connected to server on given port, which reports base build `0`.
+	fn reader(port: u16) -> ReplayReader {
+		ReplayReader {
+			sc2_path: String::new(),
+			process: None,
+			api: Some(API::new(connect_to_websocket(HOST, port as i32).unwrap())),
+			base_build: Some(0),
+			step_size: 16,
+		}
+	}
+
+	fn observation(game_loop: u32, actions: Vec<ProtoAction>) -> ResponseObservation {
+		let mut res = ResponseObservation::new();
+		res.mut_observation().set_game_loop(game_loop);
+		res.set_actions(actions.into());
+		res
+	}
+	fn action(game_loop: u32, f: impl FnOnce(&mut ProtoAction)) -> ProtoAction {
+		let mut action = ProtoAction::new();
+		action.set_game_loop(game_loop);
+		f(&mut action);
+		action
+	}
+
+	/// Serves raw responses given by `answer` until client disconnects.
+	fn serve<F>(mut answer: F) -> (u16, JoinHandle<()>)
+	where
+		F: FnMut(&Request) -> Vec<u8> + Send + 'static,
+	{
+		let listener = TcpListener::bind((HOST, 0)).unwrap();
+		let port = listener.local_addr().unwrap().port();
+		let handle = thread::spawn(move || {
+			let mut ws = accept(listener.accept().unwrap().0).unwrap();
+			while let Ok(msg) = ws.read_message() {
+				if !msg.is_binary() {
+					break;
+				}
+				let req = Request::parse_from_bytes(&msg.into_data()).unwrap();
+				if ws.write_message(Binary(answer(&req))).is_err() {
+					break;
+				}
+			}
+		});
+		(port, handle)
+	}
+	/// Answers replay requests correctly and observation requests with given bytes.
+	fn corrupt_observations(bytes: Vec<u8>) -> impl FnMut(&Request) -> Vec<u8> {
+		move |req| {
+			let mut res = Response::new();
+			if req.has_replay_info() {
+				res.mut_replay_info();
+			} else if req.has_start_replay() {
+				res.mut_start_replay();
+			} else if req.has_observation() {
+				return bytes.clone();
+			} else {
+				res.mut_quit();
+			}
+			res.write_to_bytes().unwrap()
+		}
+	}
+
+	#[test]
+	fn actions_are_decoded_until_end() {
+		let observations = vec![
+			observation(
+				16,
+				vec![
+					action(3, |a| {
+						let command = a.mut_action_raw().mut_unit_command();
+						command.set_ability_id(AbilityId::MoveMove as i32);
+						command.set_target_unit_tag(5);
+						command.set_unit_tags(vec![1, 2]);
+					}),
+					action(5, |a| a.mut_action_chat().set_message("gl hf".to_string())),
+				],
+			),
+			observation(
+				32,
+				vec![
+					// Actions with unknown abilities are skipped
+					action(20, |a| {
+						a.mut_action_raw().mut_unit_command().set_ability_id(999_999)
+					}),
+					action(30, |a| {
+						a.mut_action_ui().mut_select_army().set_selection_add(true)
+					}),
+				],
+			),
+		];
+		let game =
+			MockGame::new(ResponseGameInfo::new(), ResponseData::new()).with_observations(observations);
+		let server = MockServer::start(game).unwrap();
+		let path = replay_file("rust-sc2-replay-actions.SC2Replay");
+
+		let mut reader = reader(server.port());
+		let actions = reader
+			.actions(&path, 2)
+			.unwrap()
+			.collect::<SC2Result<Vec<_>>>()
+			.unwrap();
+		reader.close();
+		let requests = server.join();
+		fs::remove_file(path).unwrap();
+
+		let actions = actions
+			.into_iter()
+			.map(|a| (a.player_id, a.game_loop, a.action))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			actions,
+			[
+				(
+					2,
+					3,
+					Action::UnitCommand(AbilityId::MoveMove, Target::Tag(5), vec![1, 2], false)
+				),
+				(2, 5, Action::Chat("gl hf".to_string(), false)),
+				(2, 30, Action::UI(UIAction::SelectArmy(true))),
+			]
+		);
+		assert_eq!(requests[1].get_start_replay().get_observed_player_id(), 2);
+		let steps = requests.iter().filter(|req| req.has_step()).collect::<Vec<_>>();
+		assert_eq!(steps.len(), 2);
+		assert!(steps.iter().all(|req| req.get_step().get_count() == 16));
+	}
+
+	#[test]
+	fn missing_replay_is_error() {
+		// Path is checked before connecting to SC2
+		let mut reader = ReplayReader {
+			sc2_path: String::new(),
+			process: None,
+			api: None,
+			base_build: None,
+			step_size: 16,
+		};
+		let path = temp_dir().join("rust-sc2-missing.SC2Replay");
+		assert!(matches!(reader.info(path.to_str().unwrap()), Err(Error::Io(_))));
+		assert!(matches!(
+			reader.actions(path.to_str().unwrap(), 1),
+			Err(Error::Io(_))
+		));
+	}
+
+	#[test]
+	fn truncated_observation_is_error() {
+		let mut res = Response::new();
+		let obs = observation(
+			16,
+			vec![action(5, |a| {
+				a.mut_action_chat().set_message("truncated message".to_string())
+			})],
+		);
+		res.set_observation(obs);
+		let mut bytes = res.write_to_bytes().unwrap();
+		bytes.truncate(bytes.len() / 2);
+
+		let (port, handle) = serve(corrupt_observations(bytes));
+		let path = replay_file("rust-sc2-replay-truncated.SC2Replay");
+		let mut reader = reader(port);
+		let mut actions = reader.actions(&path, 1).unwrap();
+		let next = actions.next();
+		assert!(matches!(next, Some(Err(_))), "{:?}", next);
+		// Iterator stops after error
+		assert!(actions.next().is_none());
+		drop(reader);
+		handle.join().unwrap();
+		fs::remove_file(path).unwrap();
+	}
+
+	#[test]
+	fn corrupt_response_is_error() {
+		let (port, handle) = serve(|_| vec![0xFF; 8]);
+		let path = replay_file("rust-sc2-replay-corrupt.SC2Replay");
+		let mut reader = reader(port);
+		assert!(reader.info(&path).is_err());
+		assert!(reader.actions(&path, 1).is_err());
+		drop(reader);
+		handle.join().unwrap();
+		fs::remove_file(path).unwrap();
+	}
+}
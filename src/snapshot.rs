@@ -0,0 +1,221 @@
+//! Self-contained frames of the bot state, which can be saved and loaded back without the game.
+//!
+//! Available with `"serde"` feature. Useful to attach a frame to bug reports
+//! and to test decision functions on it:
+//! ```no_run
+//! use rust_sc2::{bot::Bot, snapshot::Snapshot, SC2Result};
+//! # fn f(bot: &Bot) -> SC2Result<()> {
+//! // Dump the current frame
+//! bot.snapshot().save("frame.json")?;
+//!
+//! // Load it later, e.g. in tests
+//! let bot = Bot::from_snapshot(Snapshot::load("frame.json")?);
+//! assert!(!bot.units.my.workers.is_empty());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+	bot::{Bot, Expansion, Locked, Rs},
+	client::SC2Result,
+	game_data::GameData,
+	game_info::GameInfo,
+	game_state::GameState,
+	geometry::Point2,
+	ids::{AbilityId, UpgradeId},
+	pixel_map::PixelMap,
+	player::Race,
+	ramp::Ramps,
+	terrain::Terrain,
+	units::Units,
+	version, Error,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+/// State of the bot on a single step, created by [`Bot::snapshot`].
+///
+/// Contains everything needed to restore the bot with [`Bot::from_snapshot`],
+/// except connection to the game.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+	/// Actual race of the bot.
+	pub race: Race,
+	/// Requested race of the opponent.
+	pub enemy_race: Race,
+	/// Bot's in-game id.
+	pub player_id: u32,
+	/// Opponent's in-game id.
+	pub enemy_player_id: u32,
+	/// Opponent id on ladder.
+	pub opponent_id: String,
+	/// Game step of the bot.
+	pub game_step: u32,
+	/// Information about map.
+	pub game_info: GameInfo,
+	/// Information about abilities, unit types, upgrades, buffs and effects.
+	pub game_data: Rs<GameData>,
+	/// Name of the game version data, the latest one is used if it's unknown.
+	#[serde(default)]
+	pub version: String,
+	/// State of the game.
+	pub state: GameState,
+	/// All units visible to the bot.
+	pub units: Units,
+	/// Abilities available to owned units.
+	pub abilities_units: FxHashMap<u64, FxHashSet<AbilityId>>,
+	/// Predicted opponent's upgrades.
+	pub enemy_upgrades: FxHashSet<UpgradeId>,
+	/// Bot's starting location.
+	pub start_location: Point2,
+	/// Opponent's starting location.
+	pub enemy_start: Point2,
+	/// Bot's resource center on start location.
+	pub start_center: Point2,
+	/// Opponents's resource center on start location.
+	pub enemy_start_center: Point2,
+	/// All expansions.
+	pub expansions: Vec<Expansion>,
+	/// Ramps on map.
+	pub ramps: Ramps,
+	/// Regions of the map and chokes between them.
+	pub terrain: Terrain,
+	/// Obstacles on map which block vision of ground units.
+	pub vision_blockers: Vec<Point2>,
+	/// Pathing grid with footprints of buildings, minerals, geysers and rocks.
+	pub pathing_grid: PixelMap,
+	/// Placement grid with footprints of buildings, minerals, geysers and rocks.
+	/// The static one is used if it's empty.
+	#[serde(default)]
+	pub placement_grid: PixelMap,
+}
+impl Snapshot {
+	/// Serializes snapshot to JSON.
+	pub fn to_json(&self) -> SC2Result<String> {
+		serde_json::to_string(self).map_err(Error::other)
+	}
+	/// Deserializes snapshot from JSON.
+	pub fn from_json(json: &str) -> SC2Result<Self> {
+		serde_json::from_str(json).map_err(Error::other)
+	}
+	/// Serializes snapshot to compact binary format.
+	pub fn to_bincode(&self) -> SC2Result<Vec<u8>> {
+		bincode::serialize(self).map_err(Error::other)
+	}
+	/// Deserializes snapshot from compact binary format.
+	pub fn from_bincode(bytes: &[u8]) -> SC2Result<Self> {
+		bincode::deserialize(bytes).map_err(Error::other)
+	}
+	/// Saves snapshot to a file.
+	/// Format is JSON if file has `.json` extension, bincode otherwise.
+	pub fn save<P: AsRef<Path>>(&self, path: P) -> SC2Result<()> {
+		let path = path.as_ref();
+		if is_json(path) {
+			fs::write(path, self.to_json()?)?;
+		} else {
+			fs::write(path, self.to_bincode()?)?;
+		}
+		Ok(())
+	}
+	/// Loads snapshot from a file saved with [`save`](Self::save).
+	pub fn load<P: AsRef<Path>>(path: P) -> SC2Result<Self> {
+		let path = path.as_ref();
+		if is_json(path) {
+			Self::from_json(&fs::read_to_string(path)?)
+		} else {
+			Self::from_bincode(&fs::read(path)?)
+		}
+	}
+}
+
+fn is_json(path: &Path) -> bool {
+	path.extension()
+		.map_or(false, |ext| ext.eq_ignore_ascii_case("json"))
+}
+
+impl Bot {
+	/// Dumps the current state of the bot into a self-contained [`Snapshot`].
+	pub fn snapshot(&self) -> Snapshot {
+		Snapshot {
+			race: self.race,
+			enemy_race: self.enemy_race,
+			player_id: self.player_id,
+			enemy_player_id: self.enemy_player_id,
+			opponent_id: self.opponent_id.clone(),
+			game_step: self.game_step(),
+			game_info: self.game_info.clone(),
+			game_data: Rs::clone(&self.game_data),
+			version: self.version.name.to_string(),
+			state: self.state.clone(),
+			units: self.units.all.clone(),
+			abilities_units: self.abilities_units.read_lock().clone(),
+			enemy_upgrades: self.enemy_upgrades().clone(),
+			start_location: self.start_location,
+			enemy_start: self.enemy_start,
+			start_center: self.start_center,
+			enemy_start_center: self.enemy_start_center,
+			expansions: self.expansions.clone(),
+			ramps: self.ramps.clone(),
+			terrain: self.terrain.clone(),
+			vision_blockers: self.vision_blockers.clone(),
+			pathing_grid: self.pathing_grid.clone(),
+			placement_grid: self.placement_grid.clone(),
+		}
+	}
+	/// Restores bot from [`Snapshot`].
+	///
+	/// Restored bot isn't connected to the game, so it can't send requests,
+	/// but all the state and methods of units are available.
+	pub fn from_snapshot(snapshot: Snapshot) -> Self {
+		let mut bot = Bot::default();
+		bot.opponent_id = snapshot.opponent_id;
+		bot.player_id = snapshot.player_id;
+		bot.game_info = snapshot.game_info;
+		bot.game_data = snapshot.game_data;
+		bot.version = Rs::new(version::by_name(&snapshot.version).clone());
+		bot.state = snapshot.state;
+		bot.start_location = snapshot.start_location;
+		bot.enemy_start = snapshot.enemy_start;
+		bot.start_center = snapshot.start_center;
+		bot.enemy_start_center = snapshot.enemy_start_center;
+		bot.expansions = snapshot.expansions;
+		bot.ramps = snapshot.ramps;
+		bot.terrain = snapshot.terrain;
+		bot.vision_blockers = snapshot.vision_blockers;
+		bot.set_game_step(snapshot.game_step);
+		if let Err(e) = bot.init_data_for_unit() {
+			error!("{}", e);
+		}
+		bot.race = snapshot.race;
+		bot.enemy_race = snapshot.enemy_race;
+		bot.enemy_player_id = snapshot.enemy_player_id;
+		*bot.abilities_units.write_lock() = snapshot.abilities_units;
+		*bot.enemy_upgrades() = snapshot.enemy_upgrades;
+
+		// Ramps share terrain height with game info, which isn't serialized for every ramp
+		let height = &bot.game_info.terrain_height;
+		let ramps = &mut bot.ramps;
+		for ramp in ramps.all.iter_mut().chain([&mut ramps.my, &mut ramps.enemy]) {
+			ramp.set_height(height);
+		}
+
+		let units = snapshot
+			.units
+			.into_iter()
+			.map(|mut u| {
+				u.set_data(Rs::clone(&bot.data_for_unit));
+				u
+			})
+			.collect();
+		bot.update_units(units);
+		let placement_grid = if snapshot.placement_grid.is_empty() {
+			bot.game_info.placement_grid.clone()
+		} else {
+			snapshot.placement_grid
+		};
+		bot.reset_grids(snapshot.pathing_grid, placement_grid);
+		bot.prepare_step();
+		bot
+	}
+}
@@ -0,0 +1,596 @@
+//! Terrain analysis, which splits the map into regions connected by chokes and ramps.
+//!
+//! Analysis is made once on game start and stored in [`terrain`](crate::bot::Bot::terrain) field of bot:
+//! ```no_run
+//! # use rust_sc2::{bot::Bot, geometry::Point2};
+//! # fn f(bot: &Bot, pos: Point2) {
+//! let terrain = &bot.terrain;
+//! if let Some(region) = terrain.region_at(pos) {
+//!     for (neighbor, choke) in terrain.neighbors(region) {
+//!         println!("{} -> {}: {:?} (width {})", region, neighbor, choke.center, choke.width);
+//!     }
+//! }
+//!
+//! // Regions to walk through from bot's main base to the enemy's one
+//! let main = terrain.closest_region(bot.start_location);
+//! let enemy = terrain.closest_region(bot.enemy_start);
+//! if let (Some(main), Some(enemy)) = (main, enemy) {
+//!     let route = terrain.route(main, enemy);
+//! }
+//! # }
+//! ```
+//!
+//! Regions are found by watershed on distances to the closest obstacle,
+//! so basins of open space are split where passages between them are narrow.
+
+use crate::{
+	bot::Expansion,
+	distance::*,
+	geometry::Point2,
+	pixel_map::{ByteMap, PixelMap},
+	ramp::Ramp,
+};
+use ndarray::Array2;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{
+	cmp::{Ordering, Reverse},
+	collections::BinaryHeap,
+	f32::consts::SQRT_2,
+};
+
+type Pos = (usize, usize);
+
+const NONE: usize = usize::MAX;
+/// Basins are merged if passage between them is at least this part of the smaller basin's size.
+const MERGE_RATIO: f32 = 0.75;
+/// Basins with less cells are always merged into neighbors.
+const MIN_REGION_AREA: usize = 100;
+/// Basins which are narrower than this are always merged into neighbors.
+const MIN_REGION_DEPTH: f32 = 3.0;
+
+/// Result of terrain analysis.
+#[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Terrain {
+	/// All regions of the map.
+	pub regions: Vec<Region>,
+	/// Chokes and ramps between regions.
+	pub chokes: Vec<Choke>,
+	/// Distance from every pathable cell to the closest obstacle, `0` for obstacles.
+	pub clearance: Array2<f32>,
+	region_map: Array2<usize>,
+}
+
+/// Area of open space on the map.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Region {
+	/// All points which belong to this region.
+	pub points: Vec<Pos>,
+	/// The most open point of the region (i.e. the farthest from obstacles).
+	pub center: Point2,
+	/// Terrain height in the center of region.
+	pub height: u8,
+	/// Indices of chokes in [`Terrain::chokes`] connecting this region to others.
+	pub chokes: Vec<usize>,
+	/// Indices of expansions in [`expansions`](crate::bot::Bot::expansions) located in this region.
+	pub expansions: Vec<usize>,
+}
+
+/// Narrow passage connecting two regions.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Choke {
+	/// Indices of connected regions.
+	pub regions: (usize, usize),
+	/// Points of the choke line between regions, or all points of the ramp.
+	pub points: Vec<Pos>,
+	/// The widest point of the choke.
+	pub center: Point2,
+	/// Width of the choke in cells.
+	pub width: f32,
+	/// Index of the ramp in [`ramps.all`](crate::ramp::Ramps::all) if choke is a ramp.
+	pub ramp: Option<usize>,
+}
+impl Choke {
+	/// Returns region on the other side of the choke.
+	pub fn other(&self, region: usize) -> usize {
+		if self.regions.0 == region {
+			self.regions.1
+		} else {
+			self.regions.0
+		}
+	}
+}
+
+impl Terrain {
+	pub(crate) fn new(
+		pathing: &PixelMap,
+		height: &ByteMap,
+		ramps: &[Ramp],
+		expansions: &mut [Expansion],
+	) -> Self {
+		let pathable = pathing.map(|p| p.is_empty());
+		let clearance = clearance(&pathable);
+		let (w, h) = pathable.dim();
+
+		let mut ramp_map = Array2::from_elem((w, h), NONE);
+		for (i, ramp) in ramps.iter().enumerate() {
+			for p in &ramp.points {
+				ramp_map[*p] = i;
+			}
+		}
+
+		// Flooding basins from the most open points
+		let mut cells = iproduct!(0..w, 0..h)
+			.filter(|p| pathable[*p] && ramp_map[*p] == NONE)
+			.collect::<Vec<Pos>>();
+		cells.sort_by(|a, b| clearance[*b].partial_cmp(&clearance[*a]).unwrap());
+
+		let mut basins = Basins::default();
+		let mut labels = Array2::from_elem((w, h), NONE);
+		for pos in cells {
+			let depth = clearance[pos];
+			let mut roots = neighbors4(pos, w, h)
+				.filter_map(|n| match labels[n] {
+					NONE => None,
+					label => Some(basins.find(label)),
+				})
+				.collect::<Vec<usize>>();
+			roots.sort_unstable();
+			roots.dedup();
+
+			let label = match roots.len() {
+				0 => basins.add(pos, depth),
+				_ => {
+					roots.sort_by(|a, b| basins.depth[*b].partial_cmp(&basins.depth[*a]).unwrap());
+					let main = roots[0];
+					for &other in &roots[1..] {
+						let area = basins.area[main].min(basins.area[other]);
+						let smaller = basins.depth[other];
+						if depth >= smaller * MERGE_RATIO
+							|| area < MIN_REGION_AREA
+							|| smaller < MIN_REGION_DEPTH
+						{
+							basins.union(main, other);
+						}
+					}
+					main
+				}
+			};
+			labels[pos] = label;
+			basins.area[label] += 1;
+		}
+
+		// Collecting regions
+		let mut ids = vec![NONE; basins.parent.len()];
+		let mut regions = Vec::<Region>::new();
+		let mut region_map = Array2::from_elem((w, h), NONE);
+		for (pos, label) in labels.indexed_iter() {
+			if *label == NONE {
+				continue;
+			}
+			let root = basins.find(*label);
+			if ids[root] == NONE {
+				ids[root] = regions.len();
+				let center = basins.seed[root];
+				regions.push(Region {
+					points: Vec::new(),
+					center: Point2::new(center.0 as f32 + 0.5, center.1 as f32 + 0.5),
+					height: height.get(center).copied().unwrap_or(0),
+					chokes: Vec::new(),
+					expansions: Vec::new(),
+				});
+			}
+			let id = ids[root];
+			regions[id].points.push(pos);
+			region_map[pos] = id;
+		}
+
+		let mut terrain = Self {
+			regions,
+			chokes: Vec::new(),
+			clearance,
+			region_map,
+		};
+		terrain.find_chokes();
+		terrain.connect_ramps(ramps);
+
+		for (i, choke) in terrain.chokes.iter().enumerate() {
+			terrain.regions[choke.regions.0].chokes.push(i);
+			terrain.regions[choke.regions.1].chokes.push(i);
+		}
+		for (i, exp) in expansions.iter_mut().enumerate() {
+			exp.region = terrain.closest_region(exp.loc);
+			if let Some(region) = exp.region {
+				terrain.regions[region].expansions.push(i);
+			}
+		}
+		terrain
+	}
+
+	/// Finds chokes on borders of adjacent regions.
+	fn find_chokes(&mut self) {
+		let (w, h) = self.region_map.dim();
+		let mut borders = Array2::from_elem((w, h), NONE);
+		let mut pairs = Vec::<(usize, usize)>::new();
+		for (pos, region) in self.region_map.indexed_iter() {
+			if *region == NONE {
+				continue;
+			}
+			for n in neighbors4(pos, w, h) {
+				let other = self.region_map[n];
+				if other != NONE && other != *region {
+					let pair = ((*region).min(other), (*region).max(other));
+					let index = pairs.iter().position(|p| *p == pair).unwrap_or_else(|| {
+						pairs.push(pair);
+						pairs.len() - 1
+					});
+					borders[pos] = index;
+					break;
+				}
+			}
+		}
+
+		// Border between two regions can consist of several separate chokes
+		let mut visited = Array2::from_elem((w, h), false);
+		for (start, index) in borders.indexed_iter() {
+			if *index == NONE || visited[start] {
+				continue;
+			}
+			visited[start] = true;
+			let mut points = vec![start];
+			let mut i = 0;
+			while i < points.len() {
+				for n in neighbors8(points[i], w, h) {
+					if borders[n] == *index && !visited[n] {
+						visited[n] = true;
+						points.push(n);
+					}
+				}
+				i += 1;
+			}
+			self.chokes.push(self.choke(pairs[*index], points, None));
+		}
+	}
+	/// Adds ramps as chokes between regions on their upper and lower sides.
+	fn connect_ramps(&mut self, ramps: &[Ramp]) {
+		let (w, h) = self.region_map.dim();
+		for (i, ramp) in ramps.iter().enumerate() {
+			let mut contacts = Vec::<(usize, usize)>::new();
+			for p in &ramp.points {
+				for n in neighbors8(*p, w, h) {
+					let region = self.region_map[n];
+					if region == NONE {
+						continue;
+					}
+					match contacts.iter_mut().find(|(r, _)| *r == region) {
+						Some((_, count)) => *count += 1,
+						None => contacts.push((region, 1)),
+					}
+				}
+			}
+			if contacts.len() < 2 {
+				continue;
+			}
+			contacts.sort_unstable_by_key(|(_, count)| Reverse(*count));
+			let (a, b) = (contacts[0].0, contacts[1].0);
+			self.chokes
+				.push(self.choke((a.min(b), a.max(b)), ramp.points.clone(), Some(i)));
+		}
+	}
+	fn choke(&self, regions: (usize, usize), points: Vec<Pos>, ramp: Option<usize>) -> Choke {
+		let widest = points
+			.iter()
+			.copied()
+			.max_by(|a, b| self.clearance[*a].partial_cmp(&self.clearance[*b]).unwrap())
+			.unwrap();
+		Choke {
+			regions,
+			center: Point2::new(widest.0 as f32 + 0.5, widest.1 as f32 + 0.5),
+			width: (self.clearance[widest] * 2.0 - 1.0).max(1.0),
+			points,
+			ramp,
+		}
+	}
+
+	/// Returns index of region given position belongs to,
+	/// or `None` if it's not pathable or belongs to a ramp.
+	pub fn region_at<P: Into<Pos>>(&self, pos: P) -> Option<usize> {
+		match self.region_map.get(pos.into()) {
+			Some(&NONE) | None => None,
+			Some(region) => Some(*region),
+		}
+	}
+	/// Returns index of region given position belongs to,
+	/// or region of the closest point in 8 cells radius if position isn't in any region.
+	pub fn closest_region(&self, pos: Point2) -> Option<usize> {
+		if let Some(region) = self.region_at(pos) {
+			return Some(region);
+		}
+		let (w, h) = self.region_map.dim();
+		let (x, y) = <(usize, usize)>::from(pos);
+		iproduct!(
+			x.saturating_sub(8)..(x + 9).min(w),
+			y.saturating_sub(8)..(y + 9).min(h)
+		)
+		.filter(|p| self.region_map[*p] != NONE)
+		.min_by(|a, b| {
+			let dist = |p: &Pos| pos.distance_squared(Point2::new(p.0 as f32 + 0.5, p.1 as f32 + 0.5));
+			dist(a).partial_cmp(&dist(b)).unwrap()
+		})
+		.map(|p| self.region_map[p])
+	}
+	/// Returns iterator over neighbors of given region and chokes leading to them.
+	pub fn neighbors(&self, region: usize) -> impl Iterator<Item = (usize, &Choke)> {
+		self.regions[region].chokes.iter().map(move |i| {
+			let choke = &self.chokes[*i];
+			(choke.other(region), choke)
+		})
+	}
+	/// Returns iterator over chokes directly connecting two given regions.
+	pub fn chokes_between(&self, a: usize, b: usize) -> impl Iterator<Item = &Choke> {
+		let regions = (a.min(b), a.max(b));
+		self.chokes.iter().filter(move |c| c.regions == regions)
+	}
+	/// Returns the shortest sequence of regions from one to another (including both),
+	/// where path goes through centers of regions and chokes between them.
+	pub fn route(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+		let mut distances = vec![f32::INFINITY; self.regions.len()];
+		let mut parents = vec![NONE; self.regions.len()];
+		let mut heap = BinaryHeap::new();
+		distances[from] = 0.0;
+		heap.push(Node(0.0, from));
+
+		while let Some(Node(distance, region)) = heap.pop() {
+			if region == to {
+				let mut route = vec![to];
+				let mut current = to;
+				while parents[current] != NONE {
+					current = parents[current];
+					route.push(current);
+				}
+				route.reverse();
+				return Some(route);
+			}
+			if distance > distances[region] {
+				continue;
+			}
+			let center = self.regions[region].center;
+			for (other, choke) in self.neighbors(region) {
+				let next = distance
+					+ center.distance(choke.center)
+					+ choke.center.distance(self.regions[other].center);
+				if next < distances[other] {
+					distances[other] = next;
+					parents[other] = region;
+					heap.push(Node(next, other));
+				}
+			}
+		}
+		None
+	}
+}
+
+/// Union-find of basins.
+#[derive(Default)]
+struct Basins {
+	parent: Vec<usize>,
+	/// Clearance of the seed.
+	depth: Vec<f32>,
+	area: Vec<usize>,
+	seed: Vec<Pos>,
+}
+impl Basins {
+	fn add(&mut self, seed: Pos, depth: f32) -> usize {
+		let id = self.parent.len();
+		self.parent.push(id);
+		self.depth.push(depth);
+		self.area.push(0);
+		self.seed.push(seed);
+		id
+	}
+	fn find(&mut self, mut id: usize) -> usize {
+		while self.parent[id] != id {
+			self.parent[id] = self.parent[self.parent[id]];
+			id = self.parent[id];
+		}
+		id
+	}
+	/// Merges `other` basin into `main` one.
+	fn union(&mut self, main: usize, other: usize) {
+		self.parent[other] = main;
+		self.area[main] += self.area[other];
+	}
+}
+
+struct Node(f32, usize);
+impl PartialEq for Node {
+	fn eq(&self, other: &Self) -> bool {
+		self.0 == other.0
+	}
+}
+impl Eq for Node {}
+impl PartialOrd for Node {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+impl Ord for Node {
+	// Reversed, so the heap pops node with the lowest distance
+	fn cmp(&self, other: &Self) -> Ordering {
+		other.0.partial_cmp(&self.0).unwrap_or(Ordering::Equal)
+	}
+}
+
+/// Distance from every cell to the closest not pathable one, cells outside of the map are not pathable.
+fn clearance(pathable: &Array2<bool>) -> Array2<f32> {
+	let (w, h) = pathable.dim();
+	let mut dist = pathable.map(|p| if *p { f32::INFINITY } else { 0.0 });
+	let get = |dist: &Array2<f32>, x: isize, y: isize| {
+		if x < 0 || y < 0 || x as usize >= w || y as usize >= h {
+			0.0
+		} else {
+			dist[(x as usize, y as usize)]
+		}
+	};
+
+	// Two-pass chamfer distance transform
+	for y in 0..h as isize {
+		for x in 0..w as isize {
+			let d = get(&dist, x, y)
+				.min(get(&dist, x - 1, y) + 1.0)
+				.min(get(&dist, x, y - 1) + 1.0)
+				.min(get(&dist, x - 1, y - 1) + SQRT_2)
+				.min(get(&dist, x + 1, y - 1) + SQRT_2);
+			dist[(x as usize, y as usize)] = d;
+		}
+	}
+	for y in (0..h as isize).rev() {
+		for x in (0..w as isize).rev() {
+			let d = get(&dist, x, y)
+				.min(get(&dist, x + 1, y) + 1.0)
+				.min(get(&dist, x, y + 1) + 1.0)
+				.min(get(&dist, x + 1, y + 1) + SQRT_2)
+				.min(get(&dist, x - 1, y + 1) + SQRT_2);
+			dist[(x as usize, y as usize)] = d;
+		}
+	}
+	dist
+}
+
+fn neighbors4((x, y): Pos, w: usize, h: usize) -> impl Iterator<Item = Pos> {
+	[(1, 0), (-1, 0), (0, 1), (0, -1)]
+		.into_iter()
+		.filter_map(move |(dx, dy)| offset(x, y, dx, dy, w, h))
+}
+fn neighbors8((x, y): Pos, w: usize, h: usize) -> impl Iterator<Item = Pos> {
+	[
+		(1, 0),
+		(-1, 0),
+		(0, 1),
+		(0, -1),
+		(1, 1),
+		(-1, -1),
+		(1, -1),
+		(-1, 1),
+	]
+	.into_iter()
+	.filter_map(move |(dx, dy)| offset(x, y, dx, dy, w, h))
+}
+fn offset(x: usize, y: usize, dx: isize, dy: isize, w: usize, h: usize) -> Option<Pos> {
+	let x = x.checked_add_signed(dx)?;
+	let y = y.checked_add_signed(dy)?;
+	if x < w && y < h {
+		Some((x, y))
+	} else {
+		None
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{bot::Rs, pixel_map::Pixel};
+
+	const W: usize = 48;
+	const H: usize = 24;
+	/// Cells of the 2x4 passage between two rooms.
+	const PASSAGE: [Pos; 8] = [
+		(21, 11),
+		(22, 11),
+		(23, 11),
+		(24, 11),
+		(21, 12),
+		(22, 12),
+		(23, 12),
+		(24, 12),
+	];
+
+	/// Two 20x22 rooms, connected by narrow passage.
+	fn rooms() -> PixelMap {
+		Array2::from_shape_fn((W, H), |(x, y)| {
+			let room = (1..=20).contains(&x) || (25..=44).contains(&x);
+			if (room && (1..=22).contains(&y)) || PASSAGE.contains(&(x, y)) {
+				Pixel::Empty
+			} else {
+				Pixel::Set
+			}
+		})
+	}
+
+	fn analyze(pathing: &PixelMap, ramps: &[Ramp]) -> Terrain {
+		Terrain::new(pathing, &Array2::from_elem((W, H), 100), ramps, &mut [])
+	}
+
+	#[test]
+	fn open_area_is_single_region() {
+		let pathing = Array2::from_shape_fn((W, H), |(x, y)| {
+			if (1..W - 1).contains(&x) && (1..H - 1).contains(&y) {
+				Pixel::Empty
+			} else {
+				Pixel::Set
+			}
+		});
+		let terrain = analyze(&pathing, &[]);
+
+		assert_eq!(terrain.regions.len(), 1);
+		assert!(terrain.chokes.is_empty());
+		assert_eq!(terrain.regions[0].points.len(), (W - 2) * (H - 2));
+		assert_eq!(terrain.region_at((0, 0)), None);
+		assert_eq!(terrain.closest_region(Point2::new(0.5, 0.5)), Some(0));
+	}
+
+	#[test]
+	fn rooms_are_split_by_passage() {
+		let terrain = analyze(&rooms(), &[]);
+
+		assert_eq!(terrain.regions.len(), 2);
+		let left = terrain.region_at((10, 10)).unwrap();
+		let right = terrain.region_at((35, 10)).unwrap();
+		assert_ne!(left, right);
+		assert!((1..=20).all(|x| terrain.region_at((x, 5)) == Some(left)));
+		assert!((25..=44).all(|x| terrain.region_at((x, 5)) == Some(right)));
+		assert_eq!(terrain.region_at((22, 5)), None);
+
+		assert_eq!(terrain.chokes.len(), 1);
+		let choke = &terrain.chokes[0];
+		assert_eq!(choke.regions, (left.min(right), left.max(right)));
+		assert_eq!(choke.ramp, None);
+		assert!(choke.width <= 2.0, "{}", choke.width);
+		// Border lies in the passage or at its mouths
+		assert!(
+			choke
+				.points
+				.iter()
+				.all(|(x, y)| (20..=25).contains(x) && (11..=12).contains(y)),
+			"{:?}",
+			choke.points
+		);
+
+		assert_eq!(
+			terrain.neighbors(left).map(|(r, _)| r).collect::<Vec<_>>(),
+			vec![right]
+		);
+		assert_eq!(terrain.chokes_between(right, left).count(), 1);
+		assert_eq!(terrain.route(left, right), Some(vec![left, right]));
+		assert_eq!(terrain.route(left, left), Some(vec![left]));
+	}
+
+	#[test]
+	fn ramp_connects_regions() {
+		let height = Rs::new(Array2::from_elem((W, H), 100));
+		let ramp = Ramp::new(PASSAGE.to_vec(), &height, Point2::new(10.0, 10.0));
+		let terrain = analyze(&rooms(), &[ramp]);
+
+		assert_eq!(terrain.regions.len(), 2);
+		assert!(PASSAGE.iter().all(|p| terrain.region_at(*p).is_none()));
+		assert_eq!(terrain.chokes.len(), 1);
+		let choke = &terrain.chokes[0];
+		assert_eq!(choke.ramp, Some(0));
+		assert_eq!(choke.points, PASSAGE.to_vec());
+		let left = terrain.region_at((10, 10)).unwrap();
+		assert_eq!(terrain.route(left, choke.other(left)).map(|r| r.len()), Some(2));
+	}
+}
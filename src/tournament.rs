@@ -0,0 +1,445 @@
+//! Harness for playing series of games vs built-in AI and collecting their results.
+//!
+//! ```no_run
+//! use rust_sc2::{prelude::*, tournament::{write_summary, Tournament}};
+//!
+//! #[bot]
+//! #[derive(Default)]
+//! struct MyBot;
+//! impl Player for MyBot {
+//!     fn get_player_settings(&self) -> PlayerSettings {
+//!         PlayerSettings::new(Race::Random)
+//!     }
+//! }
+//!
+//! let mut bot = MyBot::default();
+//! let mut tournament = Tournament::new(
+//!     &["EternalEmpireLE", "EverDreamLE"],
+//!     &[
+//!         Computer::new(Race::Zerg, Difficulty::Hard, None),
+//!         Computer::new(Race::Terran, Difficulty::Harder, Some(AIBuild::Rush)),
+//!     ],
+//!     3,
+//! );
+//! tournament.replays_dir = Some("replays");
+//!
+//! let records = tournament.run(&mut bot)?;
+//! write_summary(&records, "results.csv")?;
+//! # Ok::<(), rust_sc2::Error>(())
+//! ```
+
+#[cfg(feature = "serde")]
+use crate::player::{AIBuild, Difficulty, Race};
+use crate::{
+	bot::{Bot, LockOwned},
+	client::{RunnerSingle, SC2Result},
+	player::{Computer, GameResult},
+	Error, Player,
+};
+#[cfg(feature = "serde")]
+use serde::Serialize;
+use std::{
+	fs,
+	ops::{Deref, DerefMut},
+	path::Path,
+	time::{Duration, Instant},
+};
+
+/// Schedule of games vs built-in AI.
+///
+/// Every opponent is played on every map given number of times,
+/// games are played one by one on the same SC2 client.
+pub struct Tournament<'a> {
+	/// Maps to play on.
+	pub maps: Vec<String>,
+	/// Opponents to play against on every map.
+	pub computers: Vec<Computer>,
+	/// Number of games against every opponent on every map.
+	pub repeats: usize,
+	/// SC2 version to play on, otherwise latest available will be used.
+	pub sc2_version: Option<&'a str>,
+	/// Play games in real time mode or not.
+	pub realtime: bool,
+	/// Directory to save replays of all games in, replays aren't saved if `None`.
+	pub replays_dir: Option<&'a str>,
+	/// How many times failed game is played again (after restarting SC2),
+	/// before it's recorded as failed.
+	pub max_retries: usize,
+}
+
+impl<'a> Tournament<'a> {
+	/// Constructs new tournament with given maps, opponents and number of repeats.
+	pub fn new<S: AsRef<str>>(maps: &[S], computers: &[Computer], repeats: usize) -> Self {
+		Self {
+			maps: maps.iter().map(|map| map.as_ref().to_string()).collect(),
+			computers: computers.to_vec(),
+			repeats,
+			sc2_version: None,
+			realtime: false,
+			replays_dir: None,
+			max_retries: 1,
+		}
+	}
+
+	/// Returns all games in order they will be played, stored in (map, opponent, repeat) tuples.
+	pub fn schedule(&self) -> Vec<(&str, Computer, usize)> {
+		let mut games = Vec::with_capacity(self.maps.len() * self.computers.len() * self.repeats);
+		for map in &self.maps {
+			for computer in &self.computers {
+				for repeat in 0..self.repeats {
+					games.push((map.as_str(), *computer, repeat));
+				}
+			}
+		}
+		games
+	}
+
+	/// Plays all scheduled games and returns their records.
+	///
+	/// When a game fails (e.g. SC2 crashed or bot returned an error), SC2 is restarted and
+	/// the game is played again up to [`max_retries`](Self::max_retries) times.
+	/// If SC2 can't be restarted, the game is recorded as failed and next one is tried to be launched again.
+	/// Returns error only if SC2 can't be launched for the first game.
+	pub fn run<B>(&self, bot: &mut B) -> SC2Result<Vec<GameRecord>>
+	where
+		B: Player + DerefMut<Target = Bot> + Deref<Target = Bot>,
+	{
+		let schedule = self.schedule();
+		let first = match schedule.first() {
+			Some(game) => game,
+			None => return Ok(Vec::new()),
+		};
+
+		let replays = schedule
+			.iter()
+			.enumerate()
+			.map(|(index, (map, computer, repeat))| {
+				self.replays_dir.map(|dir| {
+					let name = format!(
+						"{}_{}_{:?}_{:?}_{}.SC2Replay",
+						index, map, computer.race, computer.difficulty, repeat
+					);
+					Path::new(dir).join(name).to_string_lossy().into_owned()
+				})
+			})
+			.collect::<Vec<_>>();
+		if let Some(dir) = self.replays_dir {
+			fs::create_dir_all(dir)?;
+		}
+
+		let mut runner = RunnerSingle::new(bot, first.1, first.0, self.sc2_version);
+		runner.realtime = self.realtime;
+		runner.launch()?;
+		let mut launched = true;
+
+		let mut records = Vec::with_capacity(schedule.len());
+		for (index, ((map, computer, repeat), replay)) in schedule.into_iter().zip(&replays).enumerate() {
+			debug!("Starting game {} on {} vs {:?}", index, map, computer);
+			runner.set_map(map);
+			runner.computer = computer;
+			runner.save_replay_as = replay.as_deref();
+
+			let mut record = GameRecord {
+				index,
+				map: map.to_string(),
+				computer,
+				repeat,
+				result: None,
+				error: None,
+				restarts: 0,
+				duration: Duration::ZERO,
+				game_loop: 0,
+				replay: None,
+			};
+			loop {
+				record.duration = Duration::ZERO;
+				record.game_loop = 0;
+				if !launched {
+					if let Err(e) = runner.launch() {
+						error!("Can't restart SC2 for game {}: {}", index, e);
+						record.error = Some(e.to_string());
+						break;
+					}
+					launched = true;
+				}
+
+				// Game loop of the previous game shouldn't be recorded if this one fails before the first step
+				runner.bot().state.observation.game_loop.set_locked(0);
+				let started = Instant::now();
+				let outcome = runner.run_game();
+				record.duration = started.elapsed();
+				record.game_loop = runner.bot().state.observation.game_loop();
+
+				match outcome {
+					Ok(()) => {
+						record.result = runner.last_result();
+						record.error = None;
+						record.replay = replay.clone();
+						break;
+					}
+					Err(e) => {
+						error!("Game {} failed: {}", index, e);
+						record.error = Some(e.to_string());
+
+						// Client can be crashed or still be in the failed game
+						runner.close();
+						launched = false;
+						if record.restarts >= self.max_retries {
+							break;
+						}
+						record.restarts += 1;
+					}
+				}
+			}
+			records.push(record);
+		}
+
+		if launched {
+			runner.close();
+		}
+		Ok(records)
+	}
+}
+
+/// Result of a single game played in [`Tournament`].
+#[derive(Debug, Clone)]
+pub struct GameRecord {
+	/// Position of the game in [`schedule`](Tournament::schedule).
+	pub index: usize,
+	/// Map the game was played on.
+	pub map: String,
+	/// Opponent of the bot.
+	pub computer: Computer,
+	/// Number of the game among games with the same map and opponent.
+	pub repeat: usize,
+	/// Result of the game for bot, `None` if the game failed.
+	pub result: Option<GameResult>,
+	/// Error of the last failed attempt to play the game.
+	pub error: Option<String>,
+	/// How many times SC2 was restarted to play the game again.
+	pub restarts: usize,
+	/// Real time spent on the last attempt to play the game.
+	pub duration: Duration,
+	/// Game loop the game ended on.
+	pub game_loop: u32,
+	/// Path of saved replay.
+	pub replay: Option<String>,
+}
+
+const COLUMNS: [&str; 12] = [
+	"index",
+	"map",
+	"race",
+	"difficulty",
+	"ai_build",
+	"repeat",
+	"result",
+	"error",
+	"restarts",
+	"duration",
+	"game_loop",
+	"replay",
+];
+
+impl GameRecord {
+	/// Values of the record in order of [`COLUMNS`], `None` for missing ones.
+	fn values(&self) -> [Option<String>; 12] {
+		[
+			Some(self.index.to_string()),
+			Some(self.map.clone()),
+			Some(format!("{:?}", self.computer.race)),
+			Some(format!("{:?}", self.computer.difficulty)),
+			self.computer.ai_build.map(|build| format!("{:?}", build)),
+			Some(self.repeat.to_string()),
+			self.result.map(|result| format!("{:?}", result)),
+			self.error.clone(),
+			Some(self.restarts.to_string()),
+			Some(format!("{:.3}", self.duration.as_secs_f64())),
+			Some(self.game_loop.to_string()),
+			self.replay.clone(),
+		]
+	}
+}
+
+/// Row of JSON summary, fields are named as [`COLUMNS`].
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+	index: usize,
+	map: &'a str,
+	race: Race,
+	difficulty: Difficulty,
+	ai_build: Option<AIBuild>,
+	repeat: usize,
+	result: Option<GameResult>,
+	error: Option<&'a str>,
+	restarts: usize,
+	duration: f64,
+	game_loop: u32,
+	replay: Option<&'a str>,
+}
+#[cfg(feature = "serde")]
+impl<'a> From<&'a GameRecord> for JsonRecord<'a> {
+	fn from(record: &'a GameRecord) -> Self {
+		Self {
+			index: record.index,
+			map: &record.map,
+			race: record.computer.race,
+			difficulty: record.computer.difficulty,
+			ai_build: record.computer.ai_build,
+			repeat: record.repeat,
+			result: record.result,
+			error: record.error.as_deref(),
+			restarts: record.restarts,
+			duration: record.duration.as_secs_f64(),
+			game_loop: record.game_loop,
+			replay: record.replay.as_deref(),
+		}
+	}
+}
+
+/// Writes records to a CSV file with header, duration is written in seconds.
+pub fn write_csv<P: AsRef<Path>>(records: &[GameRecord], path: P) -> SC2Result<()> {
+	let mut out = COLUMNS.join(",");
+	out.push('\n');
+	for record in records {
+		let row = record
+			.values()
+			.into_iter()
+			.map(|value| value.map_or_else(String::new, |value| csv_field(&value)))
+			.collect::<Vec<_>>();
+		out.push_str(&row.join(","));
+		out.push('\n');
+	}
+	fs::write(path, out)?;
+	Ok(())
+}
+
+/// Writes records to a JSON file as array of objects, duration is written in seconds.
+///
+/// Available with `"serde"` feature.
+#[cfg(feature = "serde")]
+pub fn write_json<P: AsRef<Path>>(records: &[GameRecord], path: P) -> SC2Result<()> {
+	let records = records.iter().map(JsonRecord::from).collect::<Vec<_>>();
+	let json = serde_json::to_string_pretty(&records).map_err(Error::other)?;
+	fs::write(path, json)?;
+	Ok(())
+}
+
+/// Writes records to a JSON file if path has `.json` extension, otherwise to a CSV file.
+///
+/// Returns error for `.json` path if `"serde"` feature isn't enabled.
+pub fn write_summary<P: AsRef<Path>>(records: &[GameRecord], path: P) -> SC2Result<()> {
+	let path = path.as_ref();
+	if matches!(path.extension(), Some(ext) if ext.eq_ignore_ascii_case("json")) {
+		#[cfg(feature = "serde")]
+		return write_json(records, path);
+		#[cfg(not(feature = "serde"))]
+		return Err(Error::other("JSON summary requires \"serde\" feature"));
+	}
+	write_csv(records, path)
+}
+
+fn csv_field(value: &str) -> String {
+	if value.contains([',', '"', '\n', '\r']) {
+		format!("\"{}\"", value.replace('"', "\"\""))
+	} else {
+		value.to_string()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::player::{Difficulty, Race};
+	use std::env::temp_dir;
+
+	fn record(index: usize, error: Option<&str>) -> GameRecord {
+		GameRecord {
+			index,
+			map: "EternalEmpireLE".to_string(),
+			computer: Computer::new(Race::Zerg, Difficulty::Easy, None),
+			repeat: 0,
+			result: error.is_none().then_some(GameResult::Victory),
+			error: error.map(str::to_string),
+			restarts: error.map_or(0, |_| 1),
+			duration: Duration::from_millis(1500),
+			game_loop: 2240,
+			replay: None,
+		}
+	}
+
+	#[test]
+	fn schedule_repeats_games_on_every_map() {
+		let computers = [
+			Computer::new(Race::Zerg, Difficulty::Easy, None),
+			Computer::new(Race::Terran, Difficulty::Hard, None),
+		];
+		let tournament = Tournament::new(&["A", "B"], &computers, 2);
+		let schedule = tournament
+			.schedule()
+			.into_iter()
+			.map(|(map, computer, repeat)| (map, computer.race, repeat))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			schedule,
+			[
+				("A", Race::Zerg, 0),
+				("A", Race::Zerg, 1),
+				("A", Race::Terran, 0),
+				("A", Race::Terran, 1),
+				("B", Race::Zerg, 0),
+				("B", Race::Zerg, 1),
+				("B", Race::Terran, 0),
+				("B", Race::Terran, 1),
+			]
+		);
+		assert!(Tournament::new(&["A"], &computers, 0).schedule().is_empty());
+	}
+
+	#[test]
+	fn csv_fields_are_quoted() {
+		assert_eq!(csv_field("plain"), "plain");
+		assert_eq!(csv_field("a,b"), "\"a,b\"");
+		assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+		assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+		assert_eq!(csv_field(""), "");
+	}
+
+	#[test]
+	fn records_are_written_to_csv() {
+		let path = temp_dir().join("rust-sc2-tournament.csv");
+		write_summary(&[record(0, None), record(1, Some("failed, again"))], &path).unwrap();
+		let csv = fs::read_to_string(&path).unwrap();
+		fs::remove_file(&path).unwrap();
+		assert_eq!(
+			csv.lines().collect::<Vec<_>>(),
+			[
+				"index,map,race,difficulty,ai_build,repeat,result,error,restarts,duration,game_loop,replay",
+				"0,EternalEmpireLE,Zerg,Easy,,0,Victory,,0,1.500,2240,",
+				"1,EternalEmpireLE,Zerg,Easy,,0,,\"failed, again\",1,1.500,2240,",
+			]
+		);
+	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	fn records_are_written_to_json() {
+		let path = temp_dir().join("rust-sc2-tournament.json");
+		write_summary(&[record(0, None)], &path).unwrap();
+		let json = fs::read_to_string(&path).unwrap();
+		fs::remove_file(&path).unwrap();
+		let value = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+		assert_eq!(value[0]["map"], "EternalEmpireLE");
+		assert_eq!(value[0]["result"], "Victory");
+		assert_eq!(value[0]["duration"], 1.5);
+	}
+
+	#[cfg(not(feature = "serde"))]
+	#[test]
+	fn json_summary_requires_serde() {
+		let path = temp_dir().join("rust-sc2-tournament-no-serde.json");
+		assert!(write_summary(&[record(0, None)], &path).is_err());
+		assert!(!path.exists());
+	}
+}
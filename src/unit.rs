@@ -4,34 +4,34 @@
 use crate::{
 	action::{Commander, Target},
 	bot::{LockBool, LockOwned, LockU32, Locked, Reader, Rl, Rs, Rw},
-	consts::{
-		RaceValues, ANTI_ARMOR_BUFF, DAMAGE_BONUS_PER_UPGRADE, FRAMES_PER_SECOND, MISSED_WEAPONS,
-		OFF_CREEP_SPEED_UPGRADES, SPEED_BUFFS, SPEED_ON_CREEP, SPEED_UPGRADES, WARPGATE_ABILITIES,
-	},
+	consts::{RaceValues, FRAMES_PER_SECOND, MIN_DAMAGE, SPEED_BUFFS, SPEED_ON_CREEP, WARPGATE_ABILITIES},
 	distance::Distance,
 	game_data::{Attribute, Cost, GameData, TargetType, UnitTypeData, Weapon},
 	game_state::Alliance,
 	geometry::{Point2, Point3},
-	ids::{AbilityId, BuffId, UnitTypeId, UpgradeId},
+	ids::{AbilityId, BuffId, DecodeId, UnitTypeId, UpgradeId},
 	pixel_map::{PixelMap, VisibilityMap},
 	player::Race,
 	units::Container,
 	utils::CacheMap,
+	version::VersionData,
 	FromProto,
 };
 use lazy_init::Lazy as LazyInit;
-use num_traits::FromPrimitive;
 use once_cell::sync::Lazy;
 use rustc_hash::{FxHashMap, FxHashSet};
 use sc2_proto::raw::{
 	CloakState as ProtoCloakState, DisplayType as ProtoDisplayType, Unit as ProtoUnit,
 	UnitOrder_oneof_target as ProtoTarget,
 };
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 #[derive(Default, Clone)]
 pub(crate) struct DataForUnit {
 	pub commander: Rw<Commander>,
 	pub game_data: Rs<GameData>,
+	pub version: Rs<VersionData>,
 	pub techlab_tags: Rw<FxHashSet<u64>>,
 	pub reactor_tags: Rw<FxHashSet<u64>>,
 	pub race_values: Rs<RaceValues>,
@@ -46,6 +46,7 @@ pub(crate) struct DataForUnit {
 	pub available_frames: Rw<FxHashMap<u64, u32>>,
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub(crate) struct UnitBase {
 	pub display_type: Rl<DisplayType>,
 	pub alliance: Alliance,
@@ -95,7 +96,9 @@ pub(crate) struct UnitBase {
 	pub rally_targets: Vec<RallyTarget>,
 
 	// cache
+	#[cfg_attr(feature = "serde", serde(skip))]
 	real_speed: LazyInit<f32>,
+	#[cfg_attr(feature = "serde", serde(skip))]
 	real_weapon_vs: Lazy<CacheMap<u64, (f32, f32)>>,
 }
 
@@ -112,7 +115,9 @@ pub(crate) type SharedUnitData = Rs<DataForUnit>;
 /// Unit structure contains some raw data, helper methods for it's analysis
 /// and some methods for actions execution.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Unit {
+	#[cfg_attr(feature = "serde", serde(skip))]
 	data: SharedUnitData,
 	pub(crate) base: Rs<UnitBase>,
 }
@@ -419,7 +424,7 @@ impl Unit {
 	fn type_data(&self) -> Option<&UnitTypeData> {
 		self.data.game_data.units.get(&self.type_id())
 	}
-	fn upgrades(&self) -> Reader<FxHashSet<UpgradeId>> {
+	pub(crate) fn upgrades(&self) -> Reader<FxHashSet<UpgradeId>> {
 		if self.is_mine() {
 			self.data.upgrades.read_lock()
 		} else {
@@ -454,6 +459,10 @@ impl Unit {
 	pub fn is_geyser(&self) -> bool {
 		self.type_data().map_or(false, |data| data.has_vespene)
 	}
+	/// Checks if it's inhibitor zone, which slows down units inside.
+	pub fn is_inhibitor_zone(&self) -> bool {
+		self.data.version.inhibitor_ids.contains(&self.type_id())
+	}
 	/// Checks if unit is detector.
 	#[rustfmt::skip::macros(matches)]
 	pub fn is_detector(&self) -> bool {
@@ -726,7 +735,8 @@ impl Unit {
 
 			// ---- Upgrades ----
 			let upgrades = self.upgrades();
-			if let Some((upgrade_id, increase)) = SPEED_UPGRADES.get(&unit_type) {
+			let version = &self.data.version;
+			if let Some((upgrade_id, increase)) = version.speed_upgrades.get(&unit_type) {
 				if upgrades.contains(upgrade_id) {
 					speed *= increase;
 				}
@@ -741,7 +751,7 @@ impl Unit {
 			}
 			// Off creep upgrades
 			if !upgrades.is_empty() {
-				if let Some((upgrade_id, increase)) = OFF_CREEP_SPEED_UPGRADES.get(&unit_type) {
+				if let Some((upgrade_id, increase)) = version.off_creep_speed_upgrades.get(&unit_type) {
 					if upgrades.contains(upgrade_id) {
 						speed *= increase;
 					}
@@ -854,7 +864,7 @@ impl Unit {
 			| UnitTypeId::ChangelingZerglingWings
 			| UnitTypeId::ChangelingZergling => &[],
 			UnitTypeId::Baneling | UnitTypeId::BanelingBurrowed | UnitTypeId::BanelingCocoon => {
-				&MISSED_WEAPONS[&UnitTypeId::Baneling]
+				missed_weapons(&self.data.version, UnitTypeId::Baneling)
 			}
 			UnitTypeId::RavagerCocoon => self
 				.data
@@ -866,8 +876,7 @@ impl Unit {
 				.type_data()
 				.map(|data| data.weapons.as_slice())
 				.filter(|weapons| !weapons.is_empty())
-				.or_else(|| MISSED_WEAPONS.get(&unit_type).map(|ws| ws.as_slice()))
-				.unwrap_or_default(),
+				.unwrap_or_else(|| missed_weapons(&self.data.version, unit_type)),
 		}
 	}
 	/// Targets unit can attack if it has weapon.
@@ -1221,6 +1230,42 @@ impl Unit {
 		self.calculate_weapon_stats(CalcTarget::Abstract(target_type, attributes))
 	}
 
+	/// Returns (armor, shield armor, has guardian shield) of unit including buffs and given upgrades of it's owner.
+	pub(crate) fn real_armor(&self, upgrades: &FxHashSet<UpgradeId>) -> (i32, i32, bool) {
+		let mut armor = self.armor() + self.armor_upgrade_level();
+		let mut shield_armor = self.shield_upgrade_level();
+
+		let mut has_guardian_shield = false;
+
+		for buff in self.buffs() {
+			match buff {
+				BuffId::GuardianShield => has_guardian_shield = true,
+				_ => {
+					if *buff == self.data.version.anti_armor_buff {
+						armor -= 3;
+						shield_armor -= 3;
+					}
+				}
+			}
+		}
+
+		if !upgrades.is_empty() {
+			if self.race().is_terran() {
+				if self.is_structure() && upgrades.contains(&UpgradeId::TerranBuildingArmor) {
+					armor += 2;
+				}
+			} else if matches!(
+				self.type_id(),
+				UnitTypeId::Ultralisk | UnitTypeId::UltraliskBurrowed
+			) && upgrades.contains(&UpgradeId::ChitinousPlating)
+			{
+				armor += 2;
+			}
+		}
+
+		(armor, shield_armor, has_guardian_shield)
+	}
+
 	/// Returns (dps, range) of unit's weapon vs given target (can be unit or abstract)
 	/// if unit can attack it, otherwise returs `(0, 0)`.
 	///
@@ -1244,37 +1289,8 @@ impl Unit {
 
 		let (not_target, attributes, target_unit) = match target {
 			CalcTarget::Unit(target) => {
-				let mut enemy_armor = target.armor() + target.armor_upgrade_level();
-				let mut enemy_shield_armor = target.shield_upgrade_level();
-
-				let mut target_has_guardian_shield = false;
-
-				for buff in target.buffs() {
-					match buff {
-						BuffId::GuardianShield => target_has_guardian_shield = true,
-						_ => {
-							if *buff == ANTI_ARMOR_BUFF {
-								enemy_armor -= 3;
-								enemy_shield_armor -= 3;
-							}
-						}
-					}
-				}
-
-				if !target_upgrades.is_empty() {
-					if target.race().is_terran() {
-						if target.is_structure() && target_upgrades.contains(&UpgradeId::TerranBuildingArmor)
-						{
-							enemy_armor += 2;
-						}
-					} else if matches!(
-						target.type_id(),
-						UnitTypeId::Ultralisk | UnitTypeId::UltraliskBurrowed
-					) && target_upgrades.contains(&UpgradeId::ChitinousPlating)
-					{
-						enemy_armor += 2;
-					}
-				}
+				let (enemy_armor, enemy_shield_armor, target_has_guardian_shield) =
+					target.real_armor(&target_upgrades);
 
 				(
 					if matches!(target.type_id(), UnitTypeId::Colossus) {
@@ -1304,9 +1320,73 @@ impl Unit {
 			),
 		};
 
+		let hits = self.weapon_hits(not_target, attributes, &upgrades);
+		let extract_weapon_stats = |&(damage, mut attacks, speed, range): &(u32, u32, f32, f32)| {
+			// Subtract damage
+			match target_unit {
+				Some((target, enemy_armor, enemy_shield_armor, target_has_guardian_shield)) => {
+					let mut shield_damage = 0;
+					let mut health_damage = 0;
+
+					if let Some(enemy_shield) = target.shield().filter(|shield| shield > &0) {
+						let enemy_shield_armor = if target_has_guardian_shield && range >= 2.0 {
+							enemy_shield_armor + 2
+						} else {
+							enemy_shield_armor
+						};
+						let exact_damage = MIN_DAMAGE.max(damage as i32 - enemy_shield_armor) as u32;
+
+						for _ in 0..attacks {
+							if shield_damage >= enemy_shield {
+								health_damage = shield_damage - enemy_shield;
+								break;
+							}
+							shield_damage += exact_damage;
+							attacks -= 1;
+						}
+					}
+
+					if let Some(enemy_health) = target.health().filter(|health| health > &0) {
+						let enemy_armor = if target_has_guardian_shield && range >= 2.0 {
+							enemy_armor + 2
+						} else {
+							enemy_armor
+						};
+						let exact_damage = MIN_DAMAGE.max(damage as i32 - enemy_armor) as u32;
+
+						for _ in 0..attacks {
+							if health_damage >= enemy_health {
+								break;
+							}
+							health_damage += exact_damage;
+						}
+					}
+
+					(shield_damage + health_damage, speed, range)
+				}
+				None => (damage * attacks, speed, range),
+			}
+		};
+		let (damage, speed, range) = hits
+			.iter()
+			.map(extract_weapon_stats)
+			.max_by_key(|k| k.0)
+			.unwrap_or((0, 0.0, 0.0));
+		(if speed == 0.0 { 0.0 } else { damage as f32 / speed }, range)
+	}
+
+	/// Returns (damage of single attack, number of attacks, cooldown, range) for each unit's weapon,
+	/// which can hit targets of given type and attributes.
+	/// Damage includes bonuses vs attributes and upgrades, but not armor of target.
+	pub(crate) fn weapon_hits(
+		&self,
+		not_target: TargetType,
+		attributes: &[Attribute],
+		upgrades: &FxHashSet<UpgradeId>,
+	) -> Vec<(u32, u32, f32, f32)> {
 		let weapons = self.weapons();
 		if weapons.is_empty() {
-			return (0.0, 0.0);
+			return Vec::new();
 		}
 
 		let mut speed_modifier = 1.0;
@@ -1351,8 +1431,8 @@ impl Unit {
 			}
 		}
 
-		let damage_bonus_per_upgrade = DAMAGE_BONUS_PER_UPGRADE.get(&self.type_id());
-		let extract_weapon_stats = |w: &Weapon| {
+		let damage_bonus_per_upgrade = self.data.version.damage_bonus_per_upgrade.get(&self.type_id());
+		let extract_hit = |w: &Weapon| {
 			let damage_bonus_per_upgrade = damage_bonus_per_upgrade.and_then(|bonus| bonus.get(&w.target));
 
 			let mut damage = w.damage
@@ -1401,67 +1481,13 @@ impl Unit {
 				damage += bonus;
 			}
 
-			// Subtract damage
-			match target_unit {
-				Some((target, enemy_armor, enemy_shield_armor, target_has_guardian_shield)) => {
-					let mut attacks = w.attacks;
-					let mut shield_damage = 0;
-					let mut health_damage = 0;
-
-					if let Some(enemy_shield) = target.shield().filter(|shield| shield > &0) {
-						let enemy_shield_armor = if target_has_guardian_shield && range >= 2.0 {
-							enemy_shield_armor + 2
-						} else {
-							enemy_shield_armor
-						};
-						let exact_damage = 1.max(damage as i32 - enemy_shield_armor) as u32;
-
-						for _ in 0..attacks {
-							if shield_damage >= enemy_shield {
-								health_damage = shield_damage - enemy_shield;
-								break;
-							}
-							shield_damage += exact_damage;
-							attacks -= 1;
-						}
-					}
-
-					if let Some(enemy_health) = target.health().filter(|health| health > &0) {
-						let enemy_armor = if target_has_guardian_shield && range >= 2.0 {
-							enemy_armor + 2
-						} else {
-							enemy_armor
-						};
-						let exact_damage = 1.max(damage as i32 - enemy_armor) as u32;
-
-						for _ in 0..attacks {
-							if health_damage >= enemy_health {
-								break;
-							}
-							health_damage += exact_damage;
-						}
-					}
-
-					(shield_damage + health_damage, speed, range)
-				}
-				None => (damage * w.attacks, speed, range),
-			}
-		};
-		let (damage, speed, range) = if not_target.is_any() {
-			weapons
-				.iter()
-				.map(extract_weapon_stats)
-				.max_by_key(|k| k.0)
-				.unwrap_or((0, 0.0, 0.0))
-		} else {
-			weapons
-				.iter()
-				.filter(|w| w.target != not_target)
-				.map(extract_weapon_stats)
-				.max_by_key(|k| k.0)
-				.unwrap_or((0, 0.0, 0.0))
+			(damage, w.attacks, speed, range)
 		};
-		(if speed == 0.0 { 0.0 } else { damage as f32 / speed }, range)
+		weapons
+			.iter()
+			.filter(|w| not_target.is_any() || w.target != not_target)
+			.map(extract_hit)
+			.collect()
 	}
 
 	/// Checks if unit is close enough to attack given target.
@@ -1920,10 +1946,8 @@ impl Unit {
 	pub(crate) fn from_proto(data: SharedUnitData, visibility: &VisibilityMap, u: &ProtoUnit) -> Self {
 		let pos = u.get_pos();
 		let position = Point2::from_proto(pos);
-		let type_id = {
-			let id = u.get_unit_type();
-			UnitTypeId::from_u32(id).unwrap_or_else(|| panic!("There's no `UnitTypeId` with value {}", id))
-		};
+		let version = Rs::clone(&data.version);
+		let type_id = UnitTypeId::decode_or_fallback(u.get_unit_type() as u64, &version);
 		let is_burrowed = u.get_is_burrowed();
 		let (is_cloaked, is_revealed) = if is_burrowed {
 			(true, false)
@@ -1964,9 +1988,7 @@ impl Unit {
 				buffs: u
 					.get_buff_ids()
 					.iter()
-					.map(|b| {
-						BuffId::from_u32(*b).unwrap_or_else(|| panic!("There's no `BuffId` with value {}", b))
-					})
+					.filter_map(|b| BuffId::decode_lossy(*b as u64, &version))
 					.collect(),
 				detect_range: match type_id {
 					UnitTypeId::Observer => 11.0,
@@ -1999,11 +2021,7 @@ impl Unit {
 					.get_orders()
 					.iter()
 					.map(|order| UnitOrder {
-						ability: {
-							let id = order.get_ability_id();
-							AbilityId::from_u32(id)
-								.unwrap_or_else(|| panic!("There's no `AbilityId` with value {}", id))
-						},
+						ability: AbilityId::decode_or_fallback(order.get_ability_id() as u64, &version),
 						target: match &order.target {
 							Some(ProtoTarget::target_world_space_pos(pos)) => {
 								Target::Pos(Point2::from_proto(pos))
@@ -2026,11 +2044,7 @@ impl Unit {
 						shield_max: p.get_shield_max(),
 						energy: p.get_energy(),
 						energy_max: p.get_energy_max(),
-						type_id: {
-							let id = p.get_unit_type();
-							UnitTypeId::from_u32(id)
-								.unwrap_or_else(|| panic!("There's no `UnitTypeId` with value {}", id))
-						},
+						type_id: UnitTypeId::decode_or_fallback(p.get_unit_type() as u64, &version),
 					})
 					.collect(),
 				cargo_space_taken: u.cargo_space_taken.map(|x| x as u32),
@@ -2056,12 +2070,17 @@ impl Unit {
 			}),
 		}
 	}
+	#[cfg(feature = "serde")]
+	pub(crate) fn set_data(&mut self, data: SharedUnitData) {
+		self.data = data;
+	}
 }
 
 /// The display type of [`Unit`].
 /// Can be accessed through [`display_type`](Unit::display_type) field.
 #[variant_checkers]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum DisplayType {
 	/// Fully visible.
 	Visible,
@@ -2086,6 +2105,7 @@ impl FromProto<ProtoDisplayType> for DisplayType {
 
 /// Order given to unit. All current orders of unit stored in [`orders`](Unit::orders) field.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UnitOrder {
 	/// Ability unit is using.
 	pub ability: AbilityId,
@@ -2097,6 +2117,7 @@ pub struct UnitOrder {
 
 /// Unit inside transport or bunker. All passengers stored in [`passengers`](Unit::passengers) field.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PassengerUnit {
 	pub tag: u64,
 	pub health: f32,
@@ -2111,6 +2132,7 @@ pub struct PassengerUnit {
 /// Rally point of production building.
 /// All rally points stored in [`rally_targets`](Unit::rally_targets) field.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RallyTarget {
 	/// Rally point. Position building rallied on.
 	pub point: Point2,
@@ -2136,3 +2158,10 @@ impl Radius for Unit {
 		self.radius()
 	}
 }
+
+fn missed_weapons(version: &VersionData, unit_type: UnitTypeId) -> &[Weapon] {
+	version
+		.missed_weapons
+		.get(&unit_type)
+		.map_or(&[], |weapons| weapons.as_slice())
+}
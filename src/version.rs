@@ -0,0 +1,205 @@
+//! Data which differs between game versions.
+//!
+//! Generated ids and tables in [`consts`](crate::consts) match the latest game version.
+//! When bot connects to the game, data set for its version is selected by base build
+//! received in `ResponsePing` (so it also follows `sc2_version` given in [`LaunchOptions`]),
+//! and stored in [`version`](crate::bot::Bot::version) field of bot.
+//! Ids received from the game are remapped to the library ones and back with this data,
+//! so games of different versions can run in one process at the same time.
+//!
+//! Hand-written balance tables (e.g. tech requirements, speed upgrades and weapons missing
+//! in game data) are stored per version too, and the library looks them up through this field.
+//!
+//! Version-specific constants can be accessed through the field or selected manually:
+//! ```
+//! use rust_sc2::version;
+//!
+//! let inhibitors = &version::select(75689).inhibitor_ids;
+//! assert_eq!(inhibitors.len(), 3);
+//! ```
+//!
+//! [`LaunchOptions`]: crate::client::LaunchOptions
+
+use crate::{
+	consts::{
+		BonusesForTarget, ANTI_ARMOR_BUFF, DAMAGE_BONUS_PER_UPGRADE, INHIBITOR_IDS, MISSED_WEAPONS,
+		OFF_CREEP_SPEED_UPGRADES, SPEED_UPGRADES, TECH_REQUIREMENTS,
+	},
+	game_data::Weapon,
+	ids::*,
+};
+use std::{collections::HashMap, ops::RangeInclusive};
+
+lazy_static! {
+	static ref VERSIONS: Vec<VersionData> = vec![
+		VersionData {
+			name: "4.10",
+			base_builds: 75689..=76811,
+			// Values matching the library ones are listed too,
+			// so this version stays correct when ids are regenerated
+			remaps: vec![
+				("UnitTypeId", 1980, UnitTypeId::AssimilatorRich as u32),
+				("UnitTypeId", 1981, UnitTypeId::ExtractorRich as u32),
+				("UnitTypeId", 1985, UnitTypeId::AccelerationZoneSmall as u32),
+				("UnitTypeId", 1986, UnitTypeId::AccelerationZoneMedium as u32),
+				("UnitTypeId", 1987, UnitTypeId::AccelerationZoneLarge as u32),
+				("UpgradeId", 296, UpgradeId::TempestGroundAttackUpgrade as u32),
+				("UpgradeId", 297, UpgradeId::EnhancedShockwaves as u32),
+				("AbilityId", 48, AbilityId::FleetBeaconResearchVoidRaySpeedUpgrade as u32),
+				(
+					"AbilityId",
+					49,
+					AbilityId::FleetBeaconResearchTempestResearchGroundAttackUpgrade as u32,
+				),
+				("AbilityId", 822, AbilityId::GhostAcademyResearchEnhancedShockwaves as u32),
+				("AbilityId", 3710, AbilityId::LurkerDenResearchLurkerRange as u32),
+				("AbilityId", 3815, AbilityId::BatteryOverchargeBatteryOvercharge as u32),
+				("AbilityId", 3817, AbilityId::AmorphousArmorcloudAmorphousArmorcloud as u32),
+				("BuffId", 290, BuffId::AccelerationZoneTemporalField as u32),
+				("BuffId", 296, BuffId::AmorphousArmorcloud as u32),
+				("BuffId", 298, BuffId::BatteryOvercharge as u32),
+			],
+			anti_armor_buff: BuffId::RavenShredderMissileArmorReduction,
+			inhibitor_ids: vec![
+				UnitTypeId::InhibitorZoneSmall,
+				UnitTypeId::InhibitorZoneMedium,
+				UnitTypeId::InhibitorZoneLarge,
+			],
+			// Balance tables match the latest ones for units existing in this version
+			..VersionData::latest()
+		},
+		// Must be the last one, since it matches any base build
+		VersionData::latest(),
+	];
+}
+
+/// Ids and constants for a range of game versions.
+#[derive(Clone)]
+pub struct VersionData {
+	/// Name of the data set (e.g. `"4.10"`).
+	pub name: &'static str,
+	/// Base builds of game versions this data set used for.
+	pub base_builds: RangeInclusive<u32>,
+	/// Ids which values differ from the library ones, stored in (kind, game value, library value) tuples.
+	remaps: Vec<(&'static str, u32, u32)>,
+	/// Version of [`ANTI_ARMOR_BUFF`].
+	pub anti_armor_buff: BuffId,
+	/// Types of inhibitor zones existing in this version.
+	pub inhibitor_ids: Vec<UnitTypeId>,
+	/// Version of [`TECH_REQUIREMENTS`].
+	pub tech_requirements: HashMap<UnitTypeId, UnitTypeId>,
+	/// Weapons missing in game data of units, used in damage calculations.
+	pub(crate) missed_weapons: HashMap<UnitTypeId, Vec<Weapon>>,
+	/// Upgrades which increase speed of units, with speed multipliers.
+	pub(crate) speed_upgrades: HashMap<UnitTypeId, (UpgradeId, f32)>,
+	/// Upgrades which increase speed of units off creep, with speed multipliers.
+	pub(crate) off_creep_speed_upgrades: HashMap<UnitTypeId, (UpgradeId, f32)>,
+	/// Damage bonuses given by each attack upgrade level.
+	pub(crate) damage_bonus_per_upgrade: HashMap<UnitTypeId, BonusesForTarget>,
+}
+impl VersionData {
+	fn latest() -> Self {
+		Self {
+			name: "latest",
+			base_builds: 0..=u32::MAX,
+			remaps: Vec::new(),
+			anti_armor_buff: ANTI_ARMOR_BUFF,
+			inhibitor_ids: INHIBITOR_IDS.to_vec(),
+			tech_requirements: TECH_REQUIREMENTS.clone(),
+			missed_weapons: MISSED_WEAPONS.clone(),
+			speed_upgrades: SPEED_UPGRADES.clone(),
+			off_creep_speed_upgrades: OFF_CREEP_SPEED_UPGRADES.clone(),
+			damage_bonus_per_upgrade: DAMAGE_BONUS_PER_UPGRADE.clone(),
+		}
+	}
+
+	/// Converts value of id received from the game to the library one.
+	pub(crate) fn to_library(&self, kind: &str, value: u64) -> u64 {
+		self.remaps
+			.iter()
+			.find(|(k, game, _)| *k == kind && *game as u64 == value)
+			.map_or(value, |(_, _, library)| *library as u64)
+	}
+	/// Converts value of library id to the one used by the game.
+	pub(crate) fn to_game(&self, kind: &str, value: u64) -> u64 {
+		self.remaps
+			.iter()
+			.find(|(k, _, library)| *k == kind && *library as u64 == value)
+			.map_or(value, |(_, game, _)| *game as u64)
+	}
+}
+impl Default for VersionData {
+	fn default() -> Self {
+		Self::latest()
+	}
+}
+
+/// Returns all known data sets, the last one is used for the latest version.
+pub fn versions() -> &'static [VersionData] {
+	&VERSIONS
+}
+
+/// Returns data set for the game with given base build.
+///
+/// Called automatically when bot connects to the game,
+/// but can be used to read replays or saved data of other versions.
+pub fn select(base_build: u32) -> &'static VersionData {
+	let version = VERSIONS
+		.iter()
+		.find(|v| v.base_builds.contains(&base_build))
+		.unwrap();
+	debug!(
+		"Using {} version data for base build {}",
+		version.name, base_build
+	);
+	version
+}
+/// Returns data set for the latest game version.
+pub fn latest() -> &'static VersionData {
+	VERSIONS.last().unwrap()
+}
+/// Returns data set with given name, or the latest one if there's no such set.
+pub fn by_name(name: &str) -> &'static VersionData {
+	VERSIONS.iter().find(|v| v.name == name).unwrap_or_else(latest)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn remaps_are_reversible() {
+		for version in versions() {
+			for (i, (kind, game, library)) in version.remaps.iter().enumerate() {
+				let duplicate = |(k, g, l): &&(&str, u32, u32)| k == kind && (g == game || l == library);
+				assert!(
+					!version.remaps[i + 1..].iter().any(|r| duplicate(&r)),
+					"{} {} is remapped twice in {}",
+					kind,
+					game,
+					version.name
+				);
+				assert_eq!(version.to_library(kind, *game as u64), *library as u64);
+				assert_eq!(version.to_game(kind, *library as u64), *game as u64);
+			}
+		}
+	}
+
+	#[test]
+	fn selects_version_by_base_build() {
+		assert_eq!(select(75689).name, "4.10");
+		assert_eq!(select(76811).name, "4.10");
+		assert_eq!(select(81009).name, "latest");
+		assert_eq!(select(0).name, "latest");
+		let v410 = &versions()[0];
+		assert_eq!(
+			v410.to_library("UnitTypeId", 1980),
+			UnitTypeId::AssimilatorRich as u64
+		);
+		assert_eq!(
+			v410.to_game("BuffId", BuffId::AccelerationZoneTemporalField as u64),
+			290
+		);
+		assert_eq!(v410.to_library("UnitTypeId", 100), 100);
+	}
+}
@@ -0,0 +1,521 @@
+//! Distribution of workers between mineral fields and gas buildings of owned bases.
+//!
+//! ```no_run
+//! use rust_sc2::{bot::Bot, workers::WorkerManager};
+//! # fn f(bot: &Bot, workers: &mut WorkerManager) {
+//! // Mine only minerals while there's enough gas
+//! workers.use_gas = bot.vespene < 300;
+//! workers.execute(bot);
+//!
+//! for base in bot.saturation() {
+//!     println!("{}/{}", base.mineral_workers, base.ideal_mineral_workers);
+//! }
+//! # }
+//! ```
+//!
+//! Manager takes workers, which are idle or collecting resources. Workers given other orders
+//! (e.g. builders and scouts) are dropped and taken back when they finish.
+//! Every mineral field gets 2 workers and gas building gets its ideal number of workers,
+//! workers left after that are sent to minerals up to 3 per field.
+//!
+//! Since orders appear only in the next observation, call [`execute`](WorkerManager::execute)
+//! before giving other orders to workers, or [`release`](WorkerManager::release) them.
+
+use crate::{
+	action::Target,
+	bot::{Bot, Expansion},
+	distance::{Distance, DistanceIterator},
+	geometry::Point2,
+	ids::{AbilityId, UnitTypeId},
+	unit::Unit,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// Workers on mineral field, which mine at full rate.
+const MINERAL_WORKERS: usize = 2;
+/// Maximum workers on mineral field, the third one still brings some income.
+const MAX_MINERAL_WORKERS: usize = 3;
+/// Workers on gas building, if the game doesn't report ideal number.
+const GAS_WORKERS: u32 = 3;
+/// Number of game loops, during which released worker isn't taken back.
+const RELEASE_LOOPS: u32 = 22;
+/// Number of game loops, during which worker can be hidden inside of gas building.
+const INSIDE_GAS_LOOPS: u32 = 45;
+
+/// Resource assigned to a worker by [`WorkerManager`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Assignment {
+	/// Tag of mineral field or gas building.
+	pub resource: u64,
+	/// Tag of townhall, where resources are returned.
+	pub townhall: u64,
+	/// Resource is gas building.
+	pub is_gas: bool,
+}
+
+/// Saturation of owned base, as reported by the game. Returned by [`Bot::saturation`].
+#[derive(Debug, Copy, Clone)]
+pub struct Saturation {
+	/// Tag of townhall.
+	pub townhall: u64,
+	/// Workers assigned to minerals.
+	pub mineral_workers: u32,
+	/// Workers needed to saturate minerals.
+	pub ideal_mineral_workers: u32,
+	/// Workers assigned to gas buildings.
+	pub gas_workers: u32,
+	/// Workers needed to saturate gas buildings.
+	pub ideal_gas_workers: u32,
+}
+impl Saturation {
+	/// Workers above ideal number, negative if base needs more workers.
+	pub fn surplus(&self) -> i32 {
+		(self.mineral_workers + self.gas_workers) as i32
+			- (self.ideal_mineral_workers + self.ideal_gas_workers) as i32
+	}
+}
+
+impl Bot {
+	/// Returns saturation of all owned bases with ready townhall.
+	pub fn saturation(&self) -> Vec<Saturation> {
+		self.owned_expansions()
+			.filter_map(|exp| {
+				let townhall = self.units.my.townhalls.get(exp.base?)?;
+				if !townhall.is_ready() {
+					return None;
+				}
+				let (gas_workers, ideal_gas_workers) =
+					gas_buildings(self, exp).fold((0, 0), |(assigned, ideal), gas| {
+						(
+							assigned + gas.assigned_harvesters().unwrap_or(0),
+							ideal + gas.ideal_harvesters().unwrap_or(0),
+						)
+					});
+				Some(Saturation {
+					townhall: townhall.tag(),
+					mineral_workers: townhall.assigned_harvesters().unwrap_or(0),
+					ideal_mineral_workers: townhall.ideal_harvesters().unwrap_or(0),
+					gas_workers,
+					ideal_gas_workers,
+				})
+			})
+			.collect()
+	}
+}
+
+/// Manager, which assigns workers to resources and keeps bases evenly saturated.
+#[derive(Debug, Clone)]
+pub struct WorkerManager {
+	/// Send workers to gas buildings.
+	///
+	/// When disabled, workers are moved from gas to minerals. [Default: `true`]
+	pub use_gas: bool,
+	/// Micro workers on minerals to skip their acceleration near mineral field and townhall.
+	/// [Default: `false`]
+	pub speed_mining: bool,
+	assignments: FxHashMap<u64, Assignment>,
+	released: FxHashMap<u64, u32>,
+	last_seen: FxHashMap<u64, u32>,
+}
+impl Default for WorkerManager {
+	fn default() -> Self {
+		Self {
+			use_gas: true,
+			speed_mining: false,
+			assignments: Default::default(),
+			released: Default::default(),
+			last_seen: Default::default(),
+		}
+	}
+}
+impl WorkerManager {
+	/// Constructs manager with default settings.
+	pub fn new() -> Self {
+		Self::default()
+	}
+	/// Sets `use_gas` to a given value.
+	pub fn use_gas(mut self, val: bool) -> Self {
+		self.use_gas = val;
+		self
+	}
+	/// Sets `speed_mining` to a given value.
+	pub fn speed_mining(mut self, val: bool) -> Self {
+		self.speed_mining = val;
+		self
+	}
+
+	/// Returns resource assigned to given worker.
+	pub fn assignment(&self, worker: u64) -> Option<&Assignment> {
+		self.assignments.get(&worker)
+	}
+	/// Returns all managed workers with their resources.
+	pub fn assignments(&self) -> &FxHashMap<u64, Assignment> {
+		&self.assignments
+	}
+	/// Returns number of workers assigned to given mineral field or gas building.
+	pub fn workers_on(&self, resource: u64) -> usize {
+		self.assignments
+			.values()
+			.filter(|a| a.resource == resource)
+			.count()
+	}
+	/// Removes worker from the manager, so it can be given other orders on this step.
+	pub fn release(&mut self, worker: u64, bot: &Bot) -> Option<Assignment> {
+		self.released.insert(worker, bot.state.observation.game_loop());
+		self.assignments.remove(&worker)
+	}
+
+	/// Assigns free workers, moves workers from oversaturated resources and gives mining orders.
+	pub fn execute(&mut self, bot: &Bot) {
+		let game_loop = bot.state.observation.game_loop();
+		self.released
+			.retain(|_, released| game_loop.saturating_sub(*released) < RELEASE_LOOPS);
+
+		let slots = self.slots(bot);
+		let slot_indices = slots
+			.iter()
+			.enumerate()
+			.map(|(i, slot)| (slot.resource, i))
+			.collect::<FxHashMap<_, _>>();
+
+		// Drop dead and busy workers, mined out resources and destroyed bases
+		let workers = &bot.units.my.workers;
+		for u in workers {
+			self.last_seen.insert(u.tag(), game_loop);
+		}
+		let released = &self.released;
+		let last_seen = &self.last_seen;
+		let speed_mining = self.speed_mining;
+		self.assignments.retain(|tag, assignment| {
+			let slot = match slot_indices.get(&assignment.resource) {
+				Some(i) => &slots[*i],
+				None => return false,
+			};
+			// Resource can be taken over by another townhall after its base was rebuilt
+			if slot.townhall != assignment.townhall || released.contains_key(tag) {
+				return false;
+			}
+			match workers.get(*tag) {
+				Some(u) => is_mining(u, slot, speed_mining),
+				// Workers aren't observed while they're inside of gas building
+				None => {
+					slot.is_gas
+						&& last_seen
+							.get(tag)
+							.map_or(false, |seen| game_loop.saturating_sub(*seen) < INSIDE_GAS_LOOPS)
+				}
+			}
+		});
+		let assignments = &self.assignments;
+		self.last_seen
+			.retain(|tag, seen| *seen == game_loop || assignments.contains_key(tag));
+
+		let mut counts = vec![0; slots.len()];
+		for assignment in self.assignments.values() {
+			counts[slot_indices[&assignment.resource]] += 1;
+		}
+
+		// Move workers from oversaturated resources, if there're free slots elsewhere
+		let mut free_slots = slots
+			.iter()
+			.zip(&counts)
+			.map(|(slot, count)| slot.ideal.saturating_sub(*count))
+			.sum::<usize>();
+		for (i, slot) in slots.iter().enumerate() {
+			let surplus = counts[i].saturating_sub(slot.ideal).min(free_slots);
+			if surplus == 0 {
+				continue;
+			}
+			let mut moved = self
+				.assignments
+				.iter()
+				.filter(|(_, a)| a.resource == slot.resource)
+				.map(|(tag, _)| *tag)
+				.collect::<Vec<_>>();
+			// Workers carrying resources return them first
+			moved.sort_by_key(|tag| workers.get(*tag).map_or(false, |u| u.is_carrying_resource()));
+			for tag in moved.into_iter().take(surplus) {
+				self.assignments.remove(&tag);
+			}
+			counts[i] -= surplus;
+			free_slots -= surplus;
+		}
+
+		// Fill gas buildings with workers from minerals of the same base
+		for (i, slot) in slots.iter().enumerate().filter(|(_, slot)| slot.is_gas) {
+			while counts[i] < slot.ideal {
+				let worker = self
+					.assignments
+					.iter()
+					.filter(|(_, a)| !a.is_gas && a.townhall == slot.townhall)
+					.filter_map(|(tag, _)| workers.get(*tag))
+					.filter(|u| !u.is_carrying_resource())
+					.closest(slot.position)
+					.map(|u| u.tag());
+				let tag = match worker {
+					Some(tag) => tag,
+					None => break,
+				};
+				let previous = self.assignments.remove(&tag).unwrap();
+				counts[slot_indices[&previous.resource]] -= 1;
+				self.assign(tag, slot);
+				counts[i] += 1;
+			}
+		}
+
+		// Assign free workers to the closest resources
+		let free_workers = workers
+			.iter()
+			.filter(|u| {
+				u.type_id() != UnitTypeId::MULE
+					&& !self.assignments.contains_key(&u.tag())
+					&& !self.released.contains_key(&u.tag())
+					&& !u.is_sleeping()
+					&& (u.is_idle() || u.is_collecting())
+			})
+			.collect::<Vec<_>>();
+		for u in free_workers {
+			let closest = |limit: fn(&Slot) -> usize| {
+				slots
+					.iter()
+					.enumerate()
+					.filter(|(i, slot)| counts[*i] < limit(slot))
+					.min_by(|(_, a), (_, b)| {
+						u.distance_squared(a.position)
+							.partial_cmp(&u.distance_squared(b.position))
+							.unwrap()
+					})
+					.map(|(i, _)| i)
+			};
+			let i = match closest(|slot| slot.ideal).or_else(|| closest(|slot| slot.max)) {
+				Some(i) => i,
+				None => break,
+			};
+			self.assign(u.tag(), &slots[i]);
+			counts[i] += 1;
+		}
+
+		self.give_orders(bot, &slots, &slot_indices);
+	}
+
+	/// Mineral fields and gas buildings of owned bases with ready townhall.
+	fn slots(&self, bot: &Bot) -> Vec<Slot> {
+		let mut slots = Vec::new();
+		for exp in bot.owned_expansions() {
+			let townhall = match exp.base.and_then(|base| bot.units.my.townhalls.get(base)) {
+				Some(townhall) if townhall.is_ready() => townhall,
+				_ => continue,
+			};
+			// Mined out fields disappear from observation
+			for mineral in exp
+				.minerals
+				.iter()
+				.filter_map(|m| bot.units.mineral_fields.get(*m))
+			{
+				slots.push(Slot {
+					resource: mineral.tag(),
+					townhall: townhall.tag(),
+					position: mineral.position(),
+					radius: mineral.radius(),
+					is_gas: false,
+					ideal: MINERAL_WORKERS,
+					max: MAX_MINERAL_WORKERS,
+				});
+			}
+			if !self.use_gas {
+				continue;
+			}
+			for gas in gas_buildings(bot, exp)
+				.filter(|gas| gas.is_ready() && gas.vespene_contents().map_or(false, |v| v > 0))
+			{
+				let ideal = gas.ideal_harvesters().unwrap_or(GAS_WORKERS) as usize;
+				slots.push(Slot {
+					resource: gas.tag(),
+					townhall: townhall.tag(),
+					position: gas.position(),
+					radius: gas.radius(),
+					is_gas: true,
+					ideal,
+					max: ideal,
+				});
+			}
+		}
+		slots
+	}
+
+	fn assign(&mut self, worker: u64, slot: &Slot) {
+		self.assignments.insert(
+			worker,
+			Assignment {
+				resource: slot.resource,
+				townhall: slot.townhall,
+				is_gas: slot.is_gas,
+			},
+		);
+	}
+
+	/// Sends workers to assigned resources, if they're doing something else.
+	fn give_orders(&self, bot: &Bot, slots: &[Slot], slot_indices: &FxHashMap<u64, usize>) {
+		let mut mineral_workers = FxHashMap::<u64, Vec<u64>>::default();
+		if self.speed_mining {
+			for (tag, assignment) in self.assignments.iter().filter(|(_, a)| !a.is_gas) {
+				mineral_workers.entry(assignment.resource).or_default().push(*tag);
+			}
+		}
+		let mut speed_mined = FxHashSet::default();
+
+		for (tag, assignment) in &self.assignments {
+			let u = match bot.units.my.workers.get(*tag) {
+				Some(u) => u,
+				None => continue,
+			};
+			let slot = &slots[slot_indices[&assignment.resource]];
+			let townhall = match bot.units.my.townhalls.get(assignment.townhall) {
+				Some(townhall) => townhall,
+				None => continue,
+			};
+
+			if self.speed_mining && !slot.is_gas {
+				let neighbors = &mineral_workers[&slot.resource];
+				if speed_mine(bot, u, slot, townhall, neighbors, &mut speed_mined) {
+					continue;
+				}
+			}
+
+			let is_done = match u.orders().first().map(|order| (order.ability, order.target)) {
+				Some((_, Target::Tag(target))) if u.is_gathering() => target == slot.resource,
+				Some(_) => u.is_returning(),
+				None => false,
+			};
+			if is_done {
+				continue;
+			}
+			if u.is_carrying_resource() {
+				u.return_resource(false);
+				u.gather(slot.resource, true);
+			} else {
+				u.gather(slot.resource, false);
+			}
+			speed_mined.insert(u.tag());
+		}
+	}
+}
+
+struct Slot {
+	resource: u64,
+	townhall: u64,
+	position: Point2,
+	radius: f32,
+	is_gas: bool,
+	/// Workers mining at full rate.
+	ideal: usize,
+	/// Maximum workers.
+	max: usize,
+}
+
+/// Gas buildings standing on geysers of given expansion.
+fn gas_buildings<'a>(bot: &'a Bot, exp: &'a Expansion) -> impl Iterator<Item = &'a Unit> {
+	bot.units.my.gas_buildings.iter().filter(move |gas| {
+		exp.geysers.iter().any(|geyser| {
+			bot.units
+				.vespene_geysers
+				.get(*geyser)
+				.map_or(false, |geyser| gas.is_closer(1.0, geyser))
+		})
+	})
+}
+
+/// Checks if worker is busy only with mining of its resource.
+fn is_mining(u: &Unit, slot: &Slot, speed_mining: bool) -> bool {
+	if u.is_sleeping() {
+		return false;
+	}
+	match u.orders().first().map(|order| (order.ability, order.target)) {
+		None => true,
+		// Speed mining moves workers close to resource and townhall
+		Some((AbilityId::MoveMove, Target::Pos(target))) => {
+			speed_mining
+				&& !slot.is_gas
+				&& (target.is_closer(slot.radius + 1.0, slot.position) || u.is_carrying_resource())
+		}
+		_ => u.is_collecting(),
+	}
+}
+
+/// Moves worker by points near mineral field and townhall, instead of slowing down with gather
+/// and return orders. Returns `false` if worker should be given usual order.
+fn speed_mine(
+	bot: &Bot,
+	u: &Unit,
+	slot: &Slot,
+	townhall: &Unit,
+	neighbors: &[u64],
+	speed_mined: &mut FxHashSet<u64>,
+) -> bool {
+	let is_collides = |speed_mined: &FxHashSet<u64>| {
+		let range = (u.radius() + u.distance_per_step()) * 2.0;
+		!neighbors.iter().all(|w| {
+			*w == u.tag()
+				|| speed_mined.contains(w)
+				|| bot
+					.units
+					.my
+					.workers
+					.get(*w)
+					.map_or(true, |w| u.is_further(range, w))
+		})
+	};
+
+	match u.orders().first().map(|order| (order.ability, order.target)) {
+		Some((AbilityId::MoveMove, Target::Pos(target))) => {
+			let range = slot.radius + u.distance_per_step();
+			// Moving towards mineral, gather when close enough or colliding with other workers
+			if target.is_closer(range, slot.position) {
+				if u.is_closer(u.radius() + range, slot.position) || is_collides(speed_mined) {
+					u.smart(Target::Tag(slot.resource), false);
+					speed_mined.insert(u.tag());
+				}
+				return true;
+			}
+			let range = townhall.radius() + u.distance_per_step();
+			// Moving towards townhall, return when close enough or colliding with other workers
+			if target.is_closer(range, townhall) {
+				if u.is_closer(u.radius() + range, townhall) || is_collides(speed_mined) {
+					u.smart(Target::Tag(townhall.tag()), false);
+					speed_mined.insert(u.tag());
+				}
+				return true;
+			}
+			false
+		}
+		Some((_, Target::Tag(target))) if u.is_gathering() && target == slot.resource => {
+			// Move straight to mineral while far away from it
+			if u.is_further(u.radius() + slot.radius + u.distance_per_step(), slot.position)
+				&& !is_collides(speed_mined)
+			{
+				u.move_to(
+					Target::Pos(slot.position.towards(townhall.position(), slot.radius)),
+					false,
+				);
+			} else {
+				speed_mined.insert(u.tag());
+			}
+			true
+		}
+		Some((_, Target::Tag(target))) if u.is_returning() && target == townhall.tag() => {
+			// Move straight to townhall while far away from it
+			if u.is_further(u.radius() + townhall.radius() + u.distance_per_step(), townhall)
+				&& !is_collides(speed_mined)
+			{
+				u.move_to(
+					Target::Pos(townhall.position().towards(u.position(), townhall.radius())),
+					false,
+				);
+			} else {
+				speed_mined.insert(u.tag());
+			}
+			true
+		}
+		_ => false,
+	}
+}
@@ -0,0 +1,107 @@
+#![cfg(feature = "async")]
+
+mod common;
+
+use rust_sc2::{
+	async_client::{record_mock_game, run_mock_game},
+	client::RunnerReplayStream,
+	mock::{proto::sc2api::Request, MockGame, MockServer},
+	prelude::*,
+};
+use std::{mem::discriminant, time::Duration};
+
+#[bot]
+#[derive(Default)]
+struct AsyncRecorder {
+	pipelined: bool,
+	game_loops: Vec<u32>,
+}
+impl AsyncPlayer for AsyncRecorder {
+	fn get_player_settings(&self) -> PlayerSettings {
+		PlayerSettings::new(Race::Terran).pipelined(self.pipelined)
+	}
+	async fn on_step(&mut self, _iteration: usize) -> SC2Result<()> {
+		// Task can be moved to other thread here
+		tokio::task::yield_now().await;
+		self.game_loops.push(self.state.observation.game_loop());
+		if let Some(worker) = self.units.my.workers.first() {
+			worker.move_to(Target::Pos(Point2::new(20.0, 20.0)), false);
+		}
+		Ok(())
+	}
+}
+
+/// Sync bot used to play recordings of async games again.
+#[bot]
+#[derive(Default)]
+struct Replayed {
+	game_loops: Vec<u32>,
+}
+impl Player for Replayed {
+	fn get_player_settings(&self) -> PlayerSettings {
+		PlayerSettings::new(Race::Terran)
+	}
+	fn on_step(&mut self, _iteration: usize) -> SC2Result<()> {
+		self.game_loops.push(self.state.observation.game_loop());
+		Ok(())
+	}
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn games_run_in_spawned_tasks() {
+	let games = (0..2).map(|i| {
+		let record_as = std::env::temp_dir().join(format!("rust-sc2-async-game-{}.bin", i));
+		tokio::spawn(async move {
+			let game = MockGame::new(common::game_info(), common::game_data())
+				.with_observations(common::observations(4, 2));
+			let server = MockServer::start(game).unwrap();
+			let mut bot = AsyncRecorder::default();
+			let result = record_mock_game(&mut bot, &server, record_as.to_str().unwrap()).await;
+			(result, bot.game_loops, record_as)
+		})
+	});
+	for game in games.collect::<Vec<_>>() {
+		let (result, game_loops, record_as) = game.await.unwrap();
+		assert!(result.is_ok());
+		assert_eq!(game_loops, vec![2, 4, 6]);
+		// Recorded game can be played again by sync bot
+		let mut replayed = Replayed::default();
+		RunnerReplayStream::new(&mut replayed, record_as.to_str().unwrap())
+			.run_game()
+			.unwrap();
+		assert_eq!(replayed.game_loops, game_loops);
+		std::fs::remove_file(record_as).unwrap();
+	}
+}
+
+#[tokio::test]
+async fn pipelined_mode_sends_same_requests() {
+	async fn play(pipelined: bool) -> (AsyncRecorder, Vec<Request>) {
+		let game = MockGame::new(common::game_info(), common::game_data())
+			.with_observations(common::observations(4, 2));
+		let server = MockServer::start(game).unwrap();
+		let mut bot = AsyncRecorder {
+			pipelined,
+			..Default::default()
+		};
+		run_mock_game(&mut bot, &server).await.unwrap();
+		(bot, server.join())
+	}
+	let kinds = |requests: &[Request]| {
+		requests
+			.iter()
+			.map(|req| discriminant(req.request.as_ref().unwrap()))
+			.collect::<Vec<_>>()
+	};
+
+	let (sequential, sequential_requests) = play(false).await;
+	let (pipelined, pipelined_requests) = play(true).await;
+
+	assert_eq!(kinds(&sequential_requests), kinds(&pipelined_requests));
+	assert_eq!(sequential.game_loops, pipelined.game_loops);
+	for bot in [&sequential, &pipelined] {
+		let latency = bot.step_latency;
+		assert!(latency.total > Duration::ZERO);
+		assert!(latency.total >= latency.state + latency.bot);
+	}
+}
@@ -0,0 +1,186 @@
+//! Fixtures of a small map with one Terran base, used to run bots on `MockServer`.
+#![allow(dead_code)]
+
+use rust_sc2::{
+	ids::UnitTypeId,
+	mock::proto::{
+		common::{ImageData, Point2D, Race as ProtoRace},
+		data::{Attribute, UnitTypeData},
+		raw::{Alliance as ProtoAlliance, DisplayType, Unit as RawUnit},
+		sc2api::{PlayerInfo, ResponseData, ResponseGameInfo, ResponseObservation},
+	},
+};
+
+/// Size of the map in both dimensions.
+pub const MAP_SIZE: i32 = 32;
+
+/// Game info of 32x32 map, where all cells except the border are pathable and placeable.
+pub fn game_info() -> ResponseGameInfo {
+	let mut game_info = ResponseGameInfo::new();
+	game_info.set_local_map_path("Mock.SC2Map".to_string());
+	for (id, race) in [(1, ProtoRace::Terran), (2, ProtoRace::Zerg)] {
+		let mut info = PlayerInfo::new();
+		info.set_player_id(id);
+		info.set_race_requested(race);
+		info.set_race_actual(race);
+		game_info.mut_player_info().push(info);
+	}
+
+	let start_raw = game_info.mut_start_raw();
+	start_raw.mut_map_size().set_x(MAP_SIZE);
+	start_raw.mut_map_size().set_y(MAP_SIZE);
+	let area = start_raw.mut_playable_area();
+	area.mut_p1().set_x(MAP_SIZE);
+	area.mut_p1().set_y(MAP_SIZE);
+
+	// 1 bit per cell, bits of the first and the last row and column are cleared
+	let mut grid = vec![0; 4];
+	for _ in 1..MAP_SIZE - 1 {
+		grid.extend([0x7F, 0xFF, 0xFF, 0xFE]);
+	}
+	grid.extend([0; 4]);
+	let mut image = ImageData::new();
+	image.set_bits_per_pixel(1);
+	image.mut_size().set_x(MAP_SIZE);
+	image.mut_size().set_y(MAP_SIZE);
+	image.set_data(grid);
+	start_raw.set_pathing_grid(image.clone());
+	start_raw.set_placement_grid(image);
+	let height = start_raw.mut_terrain_height();
+	height.set_bits_per_pixel(8);
+	height.mut_size().set_x(MAP_SIZE);
+	height.mut_size().set_y(MAP_SIZE);
+	height.set_data(vec![100; (MAP_SIZE * MAP_SIZE) as usize]);
+
+	let mut enemy_start = Point2D::new();
+	enemy_start.set_x(28.0);
+	enemy_start.set_y(28.0);
+	start_raw.mut_start_locations().push(enemy_start);
+	game_info
+}
+
+/// Data of units used in fixtures.
+pub fn game_data() -> ResponseData {
+	let mut data = ResponseData::new();
+	for unit in [
+		unit_type(UnitTypeId::CommandCenter, 400, 0.0, true),
+		unit_type(UnitTypeId::CommandCenterFlying, 400, 0.0, true),
+		unit_type(UnitTypeId::OrbitalCommand, 550, 0.0, true),
+		unit_type(UnitTypeId::OrbitalCommandFlying, 550, 0.0, true),
+		unit_type(UnitTypeId::PlanetaryFortress, 550, 0.0, true),
+		unit_type(UnitTypeId::SCV, 50, 1.0, false),
+		unit_type(UnitTypeId::Refinery, 75, 0.0, true),
+		unit_type(UnitTypeId::RefineryRich, 75, 0.0, true),
+		unit_type(UnitTypeId::MineralField, 0, 0.0, true),
+		unit_type(UnitTypeId::VespeneGeyser, 0, 0.0, true),
+	] {
+		data.mut_units().push(unit);
+	}
+	data
+}
+
+/// Data of unit type with given cost.
+pub fn unit_type(type_id: UnitTypeId, minerals: u32, food: f32, structure: bool) -> UnitTypeData {
+	let mut data = UnitTypeData::new();
+	data.set_unit_id(type_id as u32);
+	data.set_name(format!("{:?}", type_id));
+	data.set_available(true);
+	data.set_mineral_cost(minerals);
+	data.set_food_required(food);
+	data.set_has_vespene(matches!(
+		type_id,
+		UnitTypeId::VespeneGeyser | UnitTypeId::Refinery | UnitTypeId::RefineryRich
+	));
+	if structure {
+		data.mut_attributes().push(Attribute::Structure);
+	}
+	data
+}
+
+/// Visible unit at given position, owned by player 1 or neutral.
+pub fn unit(tag: u64, type_id: UnitTypeId, x: f32, y: f32, own: bool) -> RawUnit {
+	let mut u = RawUnit::new();
+	u.set_tag(tag);
+	u.set_unit_type(type_id as u32);
+	if own {
+		u.set_alliance(ProtoAlliance::value_Self);
+		u.set_owner(1);
+	} else {
+		u.set_alliance(ProtoAlliance::Neutral);
+		u.set_owner(16);
+	}
+	u.set_display_type(DisplayType::Visible);
+	u.mut_pos().set_x(x);
+	u.mut_pos().set_y(y);
+	u.set_health(100.0);
+	u.set_health_max(100.0);
+	u.set_build_progress(1.0);
+	u.set_radius(match type_id {
+		UnitTypeId::CommandCenter => 2.75,
+		UnitTypeId::SCV => 0.375,
+		UnitTypeId::VespeneGeyser | UnitTypeId::Refinery => 1.5,
+		_ => 1.0,
+	});
+	if matches!(type_id, UnitTypeId::VespeneGeyser | UnitTypeId::Refinery) {
+		u.set_vespene_contents(2250);
+	}
+	if matches!(type_id, UnitTypeId::MineralField) {
+		u.set_mineral_contents(1800);
+	}
+	u
+}
+
+/// Main base with Command Center, 4 mineral fields and geyser, and 12 SCVs.
+pub fn base_units() -> Vec<RawUnit> {
+	let mut units = vec![unit(1, UnitTypeId::CommandCenter, 10.5, 10.5, true)];
+	for i in 0..12 {
+		units.push(unit(100 + i, UnitTypeId::SCV, 14.0, 6.0 + i as f32 * 0.5, true));
+	}
+	for i in 0..4 {
+		units.push(unit(
+			200 + i,
+			UnitTypeId::MineralField,
+			7.0 + 2.0 * i as f32,
+			17.5,
+			false,
+		));
+	}
+	units.push(unit(300, UnitTypeId::VespeneGeyser, 17.5, 10.5, false));
+	units
+}
+
+/// Observation on given game loop with given units.
+pub fn observation(game_loop: u32, units: Vec<RawUnit>) -> ResponseObservation {
+	let mut res = ResponseObservation::new();
+	let obs = res.mut_observation();
+	obs.set_game_loop(game_loop);
+
+	let common = obs.mut_player_common();
+	common.set_player_id(1);
+	common.set_minerals(50);
+	common.set_food_cap(15);
+	common.set_food_used(12);
+	common.set_food_workers(12);
+
+	let raw = obs.mut_raw_data();
+	let map_state = raw.mut_map_state();
+	let creep = map_state.mut_creep();
+	creep.set_bits_per_pixel(1);
+	creep.mut_size().set_x(MAP_SIZE);
+	creep.mut_size().set_y(MAP_SIZE);
+	creep.set_data(vec![0; (MAP_SIZE * MAP_SIZE / 8) as usize]);
+	let visibility = map_state.mut_visibility();
+	visibility.set_bits_per_pixel(8);
+	visibility.mut_size().set_x(MAP_SIZE);
+	visibility.mut_size().set_y(MAP_SIZE);
+	visibility.set_data(vec![2; (MAP_SIZE * MAP_SIZE) as usize]);
+	raw.set_units(units.into());
+	res
+}
+
+/// Observations of the main base on consecutive steps of given size.
+pub fn observations(steps: u32, step_size: u32) -> Vec<ResponseObservation> {
+	(0..steps)
+		.map(|i| observation(i * step_size, base_units()))
+		.collect()
+}
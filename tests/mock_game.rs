@@ -0,0 +1,700 @@
+mod common;
+
+use rust_sc2::{
+	bot::Bot,
+	client::{
+		record_mock_game, recording_path, run_mock_bot_vs_bot, run_mock_game, run_mock_replay,
+		RunnerReplayStream,
+	},
+	ids::unknown_ids,
+	mock::{
+		proto::{
+			raw::ActionRawUnitCommand,
+			sc2api::{Request, Request_oneof_request, ResponseObservation},
+		},
+		MockGame, MockServer,
+	},
+	prelude::*,
+	Error,
+};
+use std::{cell::Cell, mem::discriminant};
+
+#[bot]
+#[derive(Default)]
+struct Recorder {
+	pipelined: bool,
+	started: usize,
+	steps: Vec<usize>,
+	game_loops: Vec<u32>,
+	workers: Vec<usize>,
+	result: Cell<Option<GameResult>>,
+	fail_on_step: Option<usize>,
+}
+impl Player for Recorder {
+	fn get_player_settings(&self) -> PlayerSettings {
+		PlayerSettings::new(Race::Terran).pipelined(self.pipelined)
+	}
+	fn on_start(&mut self) -> SC2Result<()> {
+		self.started += 1;
+		self.game_loops.push(self.state.observation.game_loop());
+		Ok(())
+	}
+	fn on_step(&mut self, iteration: usize) -> SC2Result<()> {
+		if self.fail_on_step == Some(iteration) {
+			return Err(Error::other("Bot failed"));
+		}
+		self.steps.push(iteration);
+		self.game_loops.push(self.state.observation.game_loop());
+		self.workers.push(self.units.my.workers.len());
+		if let Some(worker) = self.units.my.workers.first() {
+			worker.move_to(Target::Pos(Point2::new(20.0, 20.0)), false);
+		}
+		Ok(())
+	}
+	fn on_end(&self, result: GameResult) -> SC2Result<()> {
+		self.result.set(Some(result));
+		Ok(())
+	}
+}
+
+fn play(bot: &mut Recorder, observations: Vec<ResponseObservation>) -> (SC2Result<()>, Vec<Request>) {
+	let game = MockGame::new(common::game_info(), common::game_data()).with_observations(observations);
+	let server = MockServer::start(game).unwrap();
+	let result = run_mock_game(bot, &server);
+	(result, server.join())
+}
+
+fn kinds(requests: &[Request]) -> Vec<std::mem::Discriminant<Request_oneof_request>> {
+	requests
+		.iter()
+		.filter_map(|req| req.request.as_ref().map(discriminant))
+		.collect()
+}
+
+fn unit_commands(requests: &[Request]) -> Vec<ActionRawUnitCommand> {
+	requests
+		.iter()
+		.filter(|req| req.has_action())
+		.flat_map(|req| req.get_action().get_actions())
+		.map(|action| action.get_action_raw().get_unit_command().clone())
+		.collect()
+}
+
+#[test]
+fn plays_game_until_end() {
+	let mut bot = Recorder::default();
+	let (result, requests) = play(&mut bot, common::observations(3, 2));
+	result.unwrap();
+
+	assert!(requests[0].has_join_game());
+	assert!(requests.iter().any(|req| req.has_game_info()));
+	assert!(requests.iter().any(|req| req.has_data()));
+	assert!(requests.last().unwrap().has_quit());
+
+	assert_eq!(bot.started, 1);
+	assert_eq!(bot.steps, vec![0, 1]);
+	assert_eq!(bot.game_loops, vec![0, 2, 4]);
+	assert_eq!(bot.workers, vec![12, 12]);
+	assert_eq!(bot.result.get(), Some(GameResult::Victory));
+	assert_eq!(bot.player_id, 1);
+	assert_eq!(requests.iter().filter(|req| req.has_step()).count(), 3);
+
+	let commands = unit_commands(&requests);
+	assert_eq!(commands.len(), 2);
+	assert_eq!(commands[0].get_ability_id(), AbilityId::MoveMove as i32);
+	assert_eq!(commands[0].get_unit_tags(), &[100]);
+}
+
+#[test]
+fn pipelined_mode_sends_same_requests() {
+	let mut sequential = Recorder::default();
+	let (result, sequential_requests) = play(&mut sequential, common::observations(4, 2));
+	result.unwrap();
+
+	let mut pipelined = Recorder {
+		pipelined: true,
+		..Default::default()
+	};
+	let (result, pipelined_requests) = play(&mut pipelined, common::observations(4, 2));
+	result.unwrap();
+
+	assert_eq!(kinds(&sequential_requests), kinds(&pipelined_requests));
+	assert_eq!(sequential.steps, pipelined.steps);
+	assert_eq!(sequential.game_loops, pipelined.game_loops);
+	assert_eq!(
+		unit_commands(&sequential_requests),
+		unit_commands(&pipelined_requests)
+	);
+
+	for bot in [&sequential, &pipelined] {
+		let latency = bot.step_latency;
+		assert!(latency.total > std::time::Duration::ZERO);
+		assert!(latency.total >= latency.state + latency.bot);
+	}
+}
+
+#[test]
+fn bot_errors_are_returned() {
+	let mut bot = Recorder {
+		fail_on_step: Some(1),
+		..Default::default()
+	};
+	let (result, requests) = play(&mut bot, common::observations(5, 2));
+
+	match result {
+		Err(Error::Other(e)) => assert_eq!(e.to_string(), "Bot failed"),
+		r => panic!("Unexpected result: {:?}", r),
+	}
+	assert_eq!(bot.steps, vec![0]);
+	assert_eq!(bot.result.get(), None);
+	// Client is closed even when bot fails
+	assert!(requests.last().unwrap().has_quit());
+}
+
+#[test]
+fn unknown_player_is_error() {
+	// Game info has only players 1 and 2
+	let game = MockGame::new(common::game_info(), common::game_data())
+		.with_observations(common::observations(2, 2))
+		.with_player_id(3);
+	let server = MockServer::start(game).unwrap();
+	let mut bot = Recorder::default();
+	let result = run_mock_game(&mut bot, &server);
+	let requests = server.join();
+
+	match result {
+		Err(Error::Other(e)) => assert_eq!(e.to_string(), "There's no player 3 in game info"),
+		r => panic!("Unexpected result: {:?}", r),
+	}
+	assert_eq!(bot.started, 0);
+	assert!(requests.last().unwrap().has_quit());
+}
+
+#[test]
+fn replay_is_observed_until_end() {
+	#[bot]
+	#[derive(Default)]
+	struct Observer {
+		started: usize,
+		steps: Vec<usize>,
+		game_loops: Vec<u32>,
+		events: Vec<Event>,
+		result: Cell<Option<GameResult>>,
+	}
+	impl ReplayObserver for Observer {
+		fn on_start(&mut self) -> SC2Result<()> {
+			self.started += 1;
+			self.game_loops.push(self.state.observation.game_loop());
+			Ok(())
+		}
+		fn on_step(&mut self, iteration: usize) -> SC2Result<()> {
+			self.steps.push(iteration);
+			self.game_loops.push(self.state.observation.game_loop());
+			// Actions of observer are dropped
+			if let Some(worker) = self.units.my.workers.first() {
+				worker.move_to(Target::Pos(Point2::new(20.0, 20.0)), false);
+			}
+			Ok(())
+		}
+		fn on_end(&self, result: GameResult) -> SC2Result<()> {
+			self.result.set(Some(result));
+			Ok(())
+		}
+		fn on_event(&mut self, event: Event) -> SC2Result<()> {
+			self.events.push(event);
+			Ok(())
+		}
+	}
+
+	let mut observations = common::observations(3, 2);
+	let raw = observations[1].mut_observation().mut_raw_data();
+	raw.mut_units()
+		.push(common::unit(200, UnitTypeId::SCV, 14.0, 13.0, true));
+	let raw = observations[2].mut_observation().mut_raw_data();
+	raw.mut_units().retain(|u| u.get_tag() != 111);
+	raw.mut_event().mut_dead_units().push(111);
+
+	let game = MockGame::new(common::game_info(), common::game_data())
+		.with_observations(observations)
+		.with_result(GameResult::Defeat);
+	let server = MockServer::start(game).unwrap();
+	let mut observer = Observer::default();
+	let result = run_mock_replay(&mut observer, &server, 2);
+	let requests = server.join();
+
+	assert_eq!(result.unwrap(), GameResult::Defeat);
+	assert_eq!(requests[0].get_start_replay().get_observed_player_id(), 2);
+	assert!(requests.last().unwrap().has_quit());
+	assert!(unit_commands(&requests).is_empty());
+	assert_eq!(requests.iter().filter(|req| req.has_step()).count(), 3);
+
+	assert_eq!(observer.player_id, 2);
+	assert_eq!(observer.started, 1);
+	assert_eq!(observer.steps, vec![0, 1]);
+	assert_eq!(observer.game_loops, vec![0, 2, 4]);
+	// Units existing on the first step are reported too
+	let mut events = vec![Event::ConstructionComplete(1)];
+	events.extend((100..112).map(Event::UnitCreated));
+	events.extend([
+		Event::UnitCreated(200),
+		Event::UnitDestroyed(111, Some(Alliance::Own)),
+	]);
+	assert_eq!(observer.events, events);
+	assert_eq!(observer.result.get(), Some(GameResult::Defeat));
+}
+
+#[test]
+fn observer_errors_are_returned() {
+	#[bot]
+	#[derive(Default)]
+	struct Observer;
+	impl ReplayObserver for Observer {
+		fn on_step(&mut self, _iteration: usize) -> SC2Result<()> {
+			Err(Error::other("Observer failed"))
+		}
+	}
+
+	let game =
+		MockGame::new(common::game_info(), common::game_data()).with_observations(common::observations(5, 2));
+	let server = MockServer::start(game).unwrap();
+	let result = run_mock_replay(&mut Observer::default(), &server, 1);
+	let requests = server.join();
+
+	match result {
+		Err(Error::Other(e)) => assert_eq!(e.to_string(), "Observer failed"),
+		r => panic!("Unexpected result: {:?}", r),
+	}
+	// Client is closed even when observer fails
+	assert!(requests.last().unwrap().has_quit());
+}
+
+#[test]
+fn api_errors_are_typed() {
+	assert!(matches!(Bot::default().api(), Err(Error::ApiNotInitialized)));
+
+	#[bot]
+	#[derive(Default)]
+	struct Unsupported {
+		error: Option<Error>,
+		pathing_error: bool,
+		batch_error: Option<Error>,
+		in_sync_after_batch: bool,
+	}
+	impl Player for Unsupported {
+		fn get_player_settings(&self) -> PlayerSettings {
+			PlayerSettings::new(Race::Terran)
+		}
+		fn on_start(&mut self) -> SC2Result<()> {
+			let mut req = Request::new();
+			req.mut_map_command();
+			self.error = self.api()?.send(req).err();
+			self.pathing_error = self
+				.query_pathing(vec![(Target::None, Point2::new(5.0, 5.0))])
+				.is_err();
+
+			// Responses after the failed one are still received
+			let mut failed = Request::new();
+			failed.mut_map_command();
+			let mut ping = Request::new();
+			ping.mut_ping();
+			self.batch_error = self.api()?.send_batch(vec![failed, ping]).err();
+			let mut game_info = Request::new();
+			game_info.mut_game_info();
+			self.in_sync_after_batch = self.api()?.send(game_info)?.has_game_info();
+			Ok(())
+		}
+	}
+
+	let game =
+		MockGame::new(common::game_info(), common::game_data()).with_observations(common::observations(2, 2));
+	let server = MockServer::start(game).unwrap();
+	let mut bot = Unsupported::default();
+	run_mock_game(&mut bot, &server).unwrap();
+	server.join();
+
+	assert!(matches!(bot.error, Some(Error::Proto(_))));
+	assert!(bot.pathing_error);
+	assert!(matches!(bot.batch_error, Some(Error::Proto(_))));
+	assert!(bot.in_sync_after_batch);
+}
+
+#[test]
+fn unknown_ids_are_skipped() {
+	const UNKNOWN_UNIT: u32 = 99_001;
+	const UNKNOWN_ABILITY: u32 = 99_002;
+	const UNKNOWN_UPGRADE: u32 = 99_003;
+
+	let mut observations = common::observations(3, 2);
+	for res in &mut observations {
+		let obs = res.mut_observation();
+		obs.mut_raw_data()
+			.mut_player()
+			.mut_upgrade_ids()
+			.push(UNKNOWN_UPGRADE);
+		let units = obs.mut_raw_data().mut_units();
+		let mut unknown = common::unit(999, UnitTypeId::SCV, 15.0, 15.0, true);
+		unknown.set_unit_type(UNKNOWN_UNIT);
+		units.push(unknown);
+		units[1]
+			.mut_orders()
+			.push_default()
+			.set_ability_id(UNKNOWN_ABILITY);
+	}
+
+	let mut bot = Recorder::default();
+	let (result, _) = play(&mut bot, observations);
+	result.unwrap();
+
+	assert_eq!(bot.steps, vec![0, 1]);
+	assert_eq!(bot.result.get(), Some(GameResult::Victory));
+	let unknown = bot.units.my.all.get(999).unwrap();
+	assert_eq!(unknown.type_id(), UnitTypeId::NotAUnit);
+	let worker = bot.units.my.workers.get(100).unwrap();
+	assert_eq!(worker.ordered_ability(), Some(AbilityId::NullNull));
+	assert!(!bot.has_upgrade(UpgradeId::Null));
+
+	let ids = unknown_ids();
+	for (kind, value) in [
+		("UnitTypeId", UNKNOWN_UNIT),
+		("AbilityId", UNKNOWN_ABILITY),
+		("UpgradeId", UNKNOWN_UPGRADE),
+	] {
+		assert!(
+			ids.contains(&(kind, value as u64)),
+			"{} {} is not reported",
+			kind,
+			value
+		);
+	}
+}
+
+#[test]
+fn versions_are_selected_per_game() {
+	#[bot]
+	#[derive(Default)]
+	struct Versioned {
+		version_name: String,
+		rich_gas: Option<UnitTypeId>,
+	}
+	impl Player for Versioned {
+		fn get_player_settings(&self) -> PlayerSettings {
+			PlayerSettings::new(Race::Terran)
+		}
+		fn on_step(&mut self, _iteration: usize) -> SC2Result<()> {
+			self.version_name = self.version.name.to_string();
+			self.rich_gas = self.units.my.all.get(500).map(|u| u.type_id());
+			if let Some(worker) = self.units.my.workers.first() {
+				worker.use_ability(AbilityId::BatteryOverchargeBatteryOvercharge, false);
+			}
+			Ok(())
+		}
+	}
+
+	// AssimilatorRich in 4.10
+	const GAME_VALUE: u32 = 1980;
+	let play = |base_build: u32| {
+		std::thread::spawn(move || {
+			let mut observations = common::observations(3, 2);
+			for res in &mut observations {
+				let mut gas = common::unit(500, UnitTypeId::Assimilator, 17.5, 10.5, true);
+				gas.set_unit_type(GAME_VALUE);
+				res.mut_observation().mut_raw_data().mut_units().push(gas);
+			}
+			let game = MockGame::new(common::game_info(), common::game_data())
+				.with_observations(observations)
+				.with_base_build(base_build);
+			let server = MockServer::start(game).unwrap();
+			let mut bot = Versioned::default();
+			run_mock_game(&mut bot, &server).unwrap();
+			let commands = unit_commands(&server.join());
+			(bot.version_name, bot.rich_gas, commands[0].get_ability_id())
+		})
+	};
+	// Games of different versions are played at the same time
+	let old = play(75689);
+	let latest = play(0);
+	let (old, latest) = (old.join().unwrap(), latest.join().unwrap());
+
+	assert_eq!(old.0, "4.10");
+	assert_eq!(old.1, Some(UnitTypeId::AssimilatorRich));
+	assert_eq!(old.2, 3815);
+	assert_eq!(latest.0, "latest");
+	assert_ne!(latest.1, Some(UnitTypeId::AssimilatorRich));
+	assert_eq!(latest.2, AbilityId::BatteryOverchargeBatteryOvercharge as i32);
+}
+
+#[test]
+fn removed_obstacles_restore_static_grid() {
+	#[bot]
+	#[derive(Default)]
+	struct Walker {
+		pathable: Vec<Vec<bool>>,
+	}
+	impl Player for Walker {
+		fn get_player_settings(&self) -> PlayerSettings {
+			PlayerSettings::new(Race::Terran)
+		}
+		fn on_start(&mut self) -> SC2Result<()> {
+			self.on_step(0)
+		}
+		fn on_step(&mut self, _iteration: usize) -> SC2Result<()> {
+			let cells = [(0, 1), (2, 2), (19, 19), (20, 20)];
+			let pathable = cells.iter().map(|cell| self.is_pathable(*cell)).collect();
+			self.pathable.push(pathable);
+			Ok(())
+		}
+	}
+
+	// Rock on cells, which are unpathable in the static grid, and rock seen later on the map border
+	const ROCKS: [(u64, f32, f32, f32); 2] = [(400, 20.0, 20.0, 1.0), (401, 2.0, 2.0, 2.0)];
+	let mut game_info = common::game_info();
+	let data = game_info.mut_start_raw().mut_pathing_grid().mut_data();
+	data[19 * 4 + 2] = 0xE7;
+	data[20 * 4 + 2] = 0xE7;
+
+	let mut observations = common::observations(3, 2);
+	for (i, (tag, x, y, radius)) in ROCKS.into_iter().enumerate() {
+		let mut rock = common::unit(tag, UnitTypeId::DestructibleRock6x6, x, y, false);
+		rock.set_radius(radius);
+		observations[i]
+			.mut_observation()
+			.mut_raw_data()
+			.mut_units()
+			.push(rock);
+		let raw = observations[i + 1].mut_observation().mut_raw_data();
+		raw.mut_event().mut_dead_units().push(tag);
+	}
+
+	let game = MockGame::new(game_info, common::game_data()).with_observations(observations);
+	let server = MockServer::start(game).unwrap();
+	let mut bot = Walker::default();
+	run_mock_game(&mut bot, &server).unwrap();
+	server.join();
+
+	// Footprints of rocks are pathable after they are destroyed, but border stays unpathable
+	assert_eq!(bot.pathable[0], [false, true, false, false]);
+	assert_eq!(bot.pathable[1], [false, false, true, true]);
+	assert_eq!(bot.pathable[2], [false, true, true, true]);
+}
+
+#[test]
+fn removed_obstacles_are_placeable() {
+	#[bot]
+	#[derive(Default)]
+	struct Builder {
+		placeable: Vec<Vec<bool>>,
+	}
+	impl Player for Builder {
+		fn get_player_settings(&self) -> PlayerSettings {
+			PlayerSettings::new(Race::Terran)
+		}
+		fn on_step(&mut self, _iteration: usize) -> SC2Result<()> {
+			let cells = [(0, 1), (9, 9), (10, 10), (12, 12)];
+			let placeable = cells.iter().map(|cell| self.is_placeable(*cell)).collect();
+			self.placeable.push(placeable);
+			Ok(())
+		}
+	}
+
+	// Rock is on the map at start and destroyed on the second step
+	let mut observations = common::observations(4, 2);
+	let mut rock = common::unit(400, UnitTypeId::DestructibleRock6x6, 10.0, 10.0, false);
+	rock.set_radius(1.0);
+	for res in &mut observations[..2] {
+		res.mut_observation()
+			.mut_raw_data()
+			.mut_units()
+			.push(rock.clone());
+	}
+	let raw = observations[2].mut_observation().mut_raw_data();
+	raw.mut_event().mut_dead_units().push(400);
+
+	let game = MockGame::new(common::game_info(), common::game_data()).with_observations(observations);
+	let server = MockServer::start(game).unwrap();
+	let mut bot = Builder::default();
+	run_mock_game(&mut bot, &server).unwrap();
+	server.join();
+
+	// Footprint of rock is placeable after it's destroyed, but border stays unplaceable
+	assert_eq!(
+		bot.placeable,
+		[
+			[false, false, false, true],
+			[false, true, true, true],
+			[false, true, true, true],
+		]
+	);
+}
+
+#[test]
+fn recorded_game_is_played_again() {
+	#[bot]
+	#[derive(Default)]
+	struct Mover {
+		game_loops: Vec<u32>,
+		events: Vec<Event>,
+	}
+	impl Player for Mover {
+		fn get_player_settings(&self) -> PlayerSettings {
+			PlayerSettings::new(Race::Terran)
+		}
+		fn on_event(&mut self, event: Event) -> SC2Result<()> {
+			self.events.push(event);
+			Ok(())
+		}
+		fn on_step(&mut self, iteration: usize) -> SC2Result<()> {
+			self.game_loops.push(self.state.observation.game_loop());
+			for (i, worker) in self.units.my.workers.iter().take(2).enumerate() {
+				worker.move_to(
+					Target::Pos(Point2::new(10.0 + iteration as f32, 5.0 + i as f32)),
+					false,
+				);
+			}
+			Ok(())
+		}
+	}
+
+	// SCV is trained on the second step and killed on the third one
+	let mut observations = common::observations(4, 2);
+	let raw = observations[1].mut_observation().mut_raw_data();
+	raw.mut_units()
+		.push(common::unit(150, UnitTypeId::SCV, 14.0, 12.0, true));
+	let raw = observations[2].mut_observation().mut_raw_data();
+	raw.mut_event().mut_dead_units().push(150);
+
+	let path = std::env::temp_dir().join("rust-sc2-recorded-game.bin");
+	let path = path.to_str().unwrap();
+	let game = MockGame::new(common::game_info(), common::game_data()).with_observations(observations);
+	let server = MockServer::start(game).unwrap();
+	let mut recorded = Mover::default();
+	record_mock_game(&mut recorded, &server, path).unwrap();
+	server.join();
+
+	let mut played = Mover::default();
+	let mut runner = RunnerReplayStream::new(&mut played, path);
+	let actions = runner.run_game().unwrap();
+	let recorded_actions = runner.recorded_actions().unwrap();
+	std::fs::remove_file(path).unwrap();
+
+	assert_eq!(recorded.game_loops, [2, 4, 6]);
+	assert_eq!(played.game_loops, recorded.game_loops);
+	// Events of the first step are reported for all initial units
+	assert_eq!(
+		recorded.events[recorded.events.len() - 2..],
+		[
+			Event::UnitCreated(150),
+			Event::UnitDestroyed(150, Some(Alliance::Own))
+		]
+	);
+	assert_eq!(played.events, recorded.events);
+	assert_eq!(actions.len(), 3);
+	assert_eq!(actions, recorded_actions);
+}
+
+#[test]
+fn bots_are_stepped_in_lockstep() {
+	let server1 = MockServer::start(
+		MockGame::new(common::game_info(), common::game_data()).with_observations(common::observations(3, 2)),
+	)
+	.unwrap();
+	// Game of the second bot lasts longer, the first one waits for it after the end
+	let server2 = MockServer::start(
+		MockGame::new(common::game_info(), common::game_data())
+			.with_observations(common::observations(4, 2))
+			.with_player_id(2)
+			.with_result(GameResult::Defeat),
+	)
+	.unwrap();
+	let record_as = std::env::temp_dir().join("rust-sc2-bot-vs-bot.bin");
+	let record_as = record_as.to_str().unwrap();
+
+	let mut bot1 = Recorder::default();
+	let mut bot2 = Recorder {
+		pipelined: true,
+		..Default::default()
+	};
+	let results = run_mock_bot_vs_bot(&mut bot1, &mut bot2, &server1, &server2, Some(record_as)).unwrap();
+	let (requests1, requests2) = (server1.join(), server2.join());
+
+	assert_eq!(results, (GameResult::Victory, GameResult::Defeat));
+	assert_eq!((bot1.player_id, bot2.player_id), (1, 2));
+	assert_eq!(bot1.game_loops, vec![0, 2, 4]);
+	assert_eq!(bot2.game_loops, vec![0, 2, 4, 6]);
+	assert_eq!(bot1.result.get(), Some(GameResult::Victory));
+	assert_eq!(bot2.result.get(), Some(GameResult::Defeat));
+	for requests in [&requests1, &requests2] {
+		assert!(requests[0].has_join_game());
+		assert!(requests.last().unwrap().has_quit());
+	}
+	assert_eq!(unit_commands(&requests1).len(), 2);
+	assert_eq!(unit_commands(&requests2).len(), 3);
+
+	// Each bot is recorded to its own file
+	for (i, game_loops) in [(1, &bot1.game_loops), (2, &bot2.game_loops)] {
+		let path = recording_path(record_as, i);
+		let mut replayed = Recorder::default();
+		RunnerReplayStream::new(&mut replayed, &path).run_game().unwrap();
+		assert_eq!(&replayed.game_loops, game_loops);
+		assert_eq!(replayed.player_id, i);
+		std::fs::remove_file(path).unwrap();
+	}
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn snapshot_is_restored_from_json_and_bincode() {
+	use rust_sc2::{pixel_map::Pixel, snapshot::Snapshot};
+
+	#[bot]
+	#[derive(Default)]
+	struct Snapshotter {
+		snapshot: Option<Snapshot>,
+	}
+	impl Player for Snapshotter {
+		fn get_player_settings(&self) -> PlayerSettings {
+			PlayerSettings::new(Race::Terran)
+		}
+		fn on_step(&mut self, _iteration: usize) -> SC2Result<()> {
+			self.snapshot = Some(self.snapshot());
+			Ok(())
+		}
+	}
+
+	let game =
+		MockGame::new(common::game_info(), common::game_data()).with_observations(common::observations(2, 2));
+	let server = MockServer::start(game).unwrap();
+	let mut bot = Snapshotter::default();
+	run_mock_game(&mut bot, &server).unwrap();
+	server.join();
+	let mut snapshot = bot.snapshot.take().unwrap();
+	// Cell of maintained grid, which differs from the static one
+	snapshot.placement_grid[(20, 20)] = Pixel::Set;
+
+	let restored = [
+		Snapshot::from_json(&snapshot.to_json().unwrap()).unwrap(),
+		Snapshot::from_bincode(&snapshot.to_bincode().unwrap()).unwrap(),
+	];
+	for restored in restored {
+		assert_eq!(restored.player_id, snapshot.player_id);
+		assert_eq!(restored.version, snapshot.version);
+		assert_eq!(restored.state.observation.game_loop(), 2);
+		assert_eq!(restored.placement_grid, snapshot.placement_grid);
+		assert_eq!(restored.pathing_grid, snapshot.pathing_grid);
+
+		let restored = Bot::from_snapshot(restored);
+		assert_eq!(restored.race, Race::Terran);
+		assert_eq!(restored.start_location, bot.start_location);
+		assert_eq!(restored.expansions.len(), bot.expansions.len());
+		assert_eq!(
+			restored.units.all.iter().map(|u| u.tag()).collect::<Vec<_>>(),
+			bot.units.all.iter().map(|u| u.tag()).collect::<Vec<_>>()
+		);
+		assert_eq!(restored.units.my.workers.len(), 12);
+		assert_eq!(restored.minerals, bot.minerals);
+		assert_eq!(restored.pathing_grid, bot.pathing_grid);
+		assert!(!restored.is_placeable((0, 0)));
+		assert!(!restored.is_placeable((20, 20)));
+		assert!(restored.is_placeable((20, 21)));
+		assert!(restored.game_info.placement_grid[(20, 20)].is_empty());
+	}
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use rust_sc2::{
+	build_order::{BlockReason, BuildItem},
+	client::run_mock_game,
+	game_data::Cost,
+	mock::{MockGame, MockServer},
+	prelude::*,
+	production::{ProductionManager, Request},
+};
+
+#[bot]
+#[derive(Default)]
+struct Producer {
+	production: ProductionManager,
+	/// Reasons of blocked requests, in order of priority.
+	blocked: Vec<BlockReason>,
+	/// Minerals left to bot after execution.
+	minerals_left: u32,
+	reserved: Cost,
+}
+impl Player for Producer {
+	fn get_player_settings(&self) -> PlayerSettings {
+		PlayerSettings::new(Race::Terran)
+	}
+	fn on_start(&mut self) -> SC2Result<()> {
+		self.blocked = self
+			.production
+			.execute(&mut self._bot)
+			.into_iter()
+			.map(|blocked| blocked.reason)
+			.collect();
+		self.minerals_left = self.minerals;
+		self.reserved = *self.production.reserved();
+		Ok(())
+	}
+}
+
+/// Executes requests once with given minerals and number of Command Centers.
+fn execute(requests: Vec<Request>, minerals: u32, townhalls: u64) -> Producer {
+	let mut units = common::base_units();
+	for tag in 2..=townhalls {
+		units.push(common::unit(tag, UnitTypeId::CommandCenter, 20.5, 20.5, true));
+	}
+	let mut observation = common::observation(0, units);
+	observation
+		.mut_observation()
+		.mut_player_common()
+		.set_minerals(minerals);
+
+	let mut bot = Producer::default();
+	for request in requests {
+		bot.production.add(request);
+	}
+	let game = MockGame::new(common::game_info(), common::game_data()).with_observations(vec![observation]);
+	let server = MockServer::start(game).unwrap();
+	run_mock_game(&mut bot, &server).unwrap();
+	server.join();
+	bot
+}
+
+#[test]
+fn blocked_requests_reserve_resources() {
+	let bot = execute(
+		vec![
+			Request::new(UnitTypeId::SCV, 1).reserve(false),
+			Request::new(UnitTypeId::SCV, 2).with_priority(1),
+		],
+		75,
+		2,
+	);
+
+	// The first SCV of prioritized request is started, the second one waits for minerals
+	assert_eq!(
+		bot.blocked,
+		[BlockReason::NotEnoughResources, BlockReason::NotEnoughResources]
+	);
+	assert_eq!(bot.reserved.minerals, 50);
+	assert_eq!(bot.reserved.supply, 1.0);
+	// Reserved minerals are given back to bot, only spent ones are subtracted
+	assert_eq!(bot.minerals_left, 25);
+	let counts = bot
+		.production
+		.requests()
+		.iter()
+		.map(|r| r.count)
+		.collect::<Vec<_>>();
+	assert_eq!(counts, [1, 1]);
+}
+
+#[test]
+fn missing_tech_doesnt_reserve_resources() {
+	let bot = execute(
+		vec![
+			Request::new(UnitTypeId::SCV, 1),
+			Request::new(UnitTypeId::OrbitalCommand, 1).with_priority(1),
+			Request::new(UnitTypeId::SCV, 2).with_priority(2),
+		],
+		150,
+		3,
+	);
+
+	// Missing tech doesn't reserve resources, so the last SCV can be started
+	assert_eq!(bot.blocked, [BlockReason::MissingTech(UnitTypeId::Barracks)]);
+	assert_eq!(bot.reserved.minerals, 0);
+	assert_eq!(bot.minerals_left, 0);
+	let items = bot
+		.production
+		.requests()
+		.iter()
+		.map(|r| r.item)
+		.collect::<Vec<_>>();
+	assert_eq!(items, [BuildItem::Unit(UnitTypeId::OrbitalCommand)]);
+}
+
+#[test]
+fn busy_producers_dont_reserve_resources() {
+	let bot = execute(vec![Request::new(UnitTypeId::SCV, 2)], 200, 1);
+
+	assert_eq!(
+		bot.blocked,
+		[BlockReason::ProducersBusy(UnitTypeId::CommandCenter)]
+	);
+	assert_eq!(bot.reserved.minerals, 0);
+	assert_eq!(bot.reserved.supply, 0.0);
+	assert_eq!(bot.minerals_left, 150);
+}
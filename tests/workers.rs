@@ -0,0 +1,122 @@
+mod common;
+
+use rust_sc2::{
+	client::run_mock_game,
+	mock::{
+		proto::{
+			error::ActionResult as ProtoActionResult,
+			query::{RequestQuery, ResponseQuery, ResponseQueryBuildingPlacement, ResponseQueryPathing},
+			sc2api::ResponseObservation,
+		},
+		MockGame, MockServer,
+	},
+	prelude::*,
+	workers::WorkerManager,
+};
+
+const MINERALS: [u64; 4] = [200, 201, 202, 203];
+const REFINERY: u64 = 301;
+
+#[bot]
+#[derive(Default)]
+struct Miner {
+	workers: WorkerManager,
+	/// Value of `use_gas` on each step.
+	use_gas: Vec<bool>,
+	/// Workers on each mineral field and on refinery after each step.
+	saturation: Vec<(Vec<usize>, usize)>,
+	townhalls: Vec<Vec<u64>>,
+}
+impl Player for Miner {
+	fn get_player_settings(&self) -> PlayerSettings {
+		PlayerSettings::new(Race::Terran)
+	}
+	fn on_start(&mut self) -> SC2Result<()> {
+		self.on_step(0)
+	}
+	fn on_step(&mut self, _iteration: usize) -> SC2Result<()> {
+		let step = self.saturation.len();
+		self.workers.use_gas = self.use_gas.get(step).copied().unwrap_or(true);
+		self.workers.execute(&self._bot);
+
+		let minerals = MINERALS.iter().map(|m| self.workers.workers_on(*m)).collect();
+		self.saturation
+			.push((minerals, self.workers.workers_on(REFINERY)));
+		let mut townhalls = self
+			.workers
+			.assignments()
+			.values()
+			.map(|a| a.townhall)
+			.collect::<Vec<_>>();
+		townhalls.sort_unstable();
+		townhalls.dedup();
+		self.townhalls.push(townhalls);
+		Ok(())
+	}
+}
+
+fn observations(townhalls: &[u64]) -> Vec<ResponseObservation> {
+	townhalls
+		.iter()
+		.enumerate()
+		.map(|(i, townhall)| {
+			let mut units = common::base_units();
+			units[0].set_tag(*townhall);
+			units.push(common::unit(REFINERY, UnitTypeId::Refinery, 17.5, 10.5, true));
+			common::observation(i as u32 * 2, units)
+		})
+		.collect()
+}
+
+fn query(req: &RequestQuery) -> ResponseQuery {
+	let mut res = ResponseQuery::new();
+	for _ in req.get_placements() {
+		let mut result = ResponseQueryBuildingPlacement::new();
+		result.set_result(ProtoActionResult::Success);
+		res.mut_placements().push(result);
+	}
+	for _ in req.get_pathing() {
+		let mut result = ResponseQueryPathing::new();
+		result.set_distance(10.0);
+		res.mut_pathing().push(result);
+	}
+	res
+}
+
+fn play(bot: &mut Miner, observations: Vec<ResponseObservation>) {
+	let game = MockGame::new(common::game_info(), common::game_data())
+		.with_observations(observations)
+		.with_query(query);
+	let server = MockServer::start(game).unwrap();
+	run_mock_game(bot, &server).unwrap();
+	server.join();
+}
+
+#[test]
+fn moves_workers_from_oversaturated_minerals() {
+	let mut bot = Miner {
+		use_gas: vec![false, true],
+		..Default::default()
+	};
+	play(&mut bot, observations(&[1, 1, 1]));
+
+	// All 12 workers are on minerals while gas is disabled
+	assert_eq!(bot.saturation[0], (vec![3, 3, 3, 3], 0));
+	// Surplus is moved to refinery, when it's allowed to be used
+	for (minerals, gas) in &bot.saturation[1..] {
+		assert_eq!(*gas, 3);
+		assert_eq!(minerals.iter().sum::<usize>(), 9);
+		assert!(minerals.iter().all(|n| (2..=3).contains(n)), "{:?}", minerals);
+	}
+}
+
+#[test]
+fn reassigns_workers_of_rebuilt_base() {
+	let mut bot = Miner::default();
+	play(&mut bot, observations(&[1, 1, 2, 2]));
+
+	assert_eq!(bot.townhalls[1], vec![1]);
+	assert_eq!(bot.townhalls[2], vec![2]);
+	assert_eq!(bot.townhalls[3], vec![2]);
+	assert_eq!(bot.saturation[3].0.iter().sum::<usize>(), 9);
+}